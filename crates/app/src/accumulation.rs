@@ -0,0 +1,68 @@
+use crate::{RenderSettings, Scene};
+use serde::{Deserialize, Serialize};
+
+const MAGIC: &[u8; 8] = b"PORTACC1";
+
+/// Everything needed to resume accumulating a render after restarting the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedAccumulation {
+    /// Saved as is, without the import/export scale, so it traces exactly the same image
+    pub scene: Scene,
+    pub render_settings: RenderSettings,
+    pub width: u32,
+    pub height: u32,
+    pub accumulated_frames: u32,
+    pub accumulated_samples: u64,
+    /// Linear and premultiplied by alpha, bottom row first like the ray tracing texture
+    #[serde(skip)]
+    pub pixels: Vec<[f32; 4]>,
+}
+
+impl SavedAccumulation {
+    /// The magic bytes, the length of the json header as a little endian u64,
+    /// the json header and then the pixels as little endian f32s
+    pub fn encode(&self) -> Vec<u8> {
+        let header = serde_json::to_vec(self).unwrap();
+        let mut bytes = Vec::with_capacity(
+            MAGIC.len() + 8 + header.len() + std::mem::size_of_val(self.pixels.as_slice()),
+        );
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes.extend(self.pixels.iter().flatten().flat_map(|c| c.to_le_bytes()));
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let bytes = bytes
+            .strip_prefix(MAGIC)
+            .ok_or("Not a saved accumulation")?;
+        let (header_length, bytes) = bytes
+            .split_first_chunk::<8>()
+            .ok_or("Saved accumulation is truncated")?;
+        let header_length = usize::try_from(u64::from_le_bytes(*header_length))
+            .map_err(|_| "Saved accumulation is corrupted")?;
+        if bytes.len() < header_length {
+            return Err("Saved accumulation is truncated".into());
+        }
+        let (header, bytes) = bytes.split_at(header_length);
+
+        let mut saved: Self = serde_json::from_slice(header).map_err(|error| error.to_string())?;
+        let pixel_count = saved.width as usize * saved.height as usize;
+        if bytes.len() != pixel_count * std::mem::size_of::<[f32; 4]>() {
+            return Err(format!(
+                "Saved accumulation should have {pixel_count} pixels for {}x{}",
+                saved.width, saved.height
+            ));
+        }
+        saved.pixels = bytes
+            .chunks_exact(std::mem::size_of::<[f32; 4]>())
+            .map(|pixel| {
+                std::array::from_fn(|channel| {
+                    f32::from_le_bytes(pixel[channel * 4..][..4].try_into().unwrap())
+                })
+            })
+            .collect();
+        Ok(saved)
+    }
+}
@@ -0,0 +1,125 @@
+use eframe::egui;
+use ray_tracing::{HISTOGRAM_MAX_EV, HISTOGRAM_MIN_EV, InspectedPixel, ToneMapper, linear_to_srgb};
+
+const HISTOGRAM_SIZE: egui::Vec2 = egui::vec2(256.0, 96.0);
+const HISTOGRAM_MARGIN: f32 = 8.0;
+
+/// Draws the luminance histogram in the bottom left corner of `rect`,
+/// with a line where luminance starts clipping
+pub fn draw_histogram(painter: &egui::Painter, rect: egui::Rect, bins: &[u32]) {
+    let histogram_rect = egui::Rect::from_min_size(
+        egui::pos2(
+            rect.left() + HISTOGRAM_MARGIN,
+            rect.bottom() - HISTOGRAM_MARGIN - HISTOGRAM_SIZE.y,
+        ),
+        HISTOGRAM_SIZE,
+    );
+    painter.rect_filled(histogram_rect, 2.0, egui::Color32::from_black_alpha(192));
+
+    // the log makes the small bins still visible next to a huge spike
+    let max = bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let bin_width = histogram_rect.width() / bins.len() as f32;
+    for (index, &count) in bins.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        let height = (count as f32).ln_1p() / max.ln_1p() * histogram_rect.height();
+        let left = histogram_rect.left() + index as f32 * bin_width;
+        painter.rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(left, histogram_rect.bottom() - height),
+                egui::pos2(left + bin_width, histogram_rect.bottom()),
+            ),
+            0.0,
+            egui::Color32::from_gray(200),
+        );
+    }
+
+    let clip_x = histogram_rect.left()
+        + (0.0 - HISTOGRAM_MIN_EV) / (HISTOGRAM_MAX_EV - HISTOGRAM_MIN_EV) * histogram_rect.width();
+    painter.vline(
+        clip_x,
+        histogram_rect.y_range(),
+        egui::Stroke::new(1.0, egui::Color32::RED),
+    );
+}
+
+/// `height` is the height of the viewport, used to flip the pixel back into screen coordinates,
+/// `tone_mapper` is the one the pixel is displayed with
+pub fn ui_inspected_pixel(
+    ui: &mut egui::Ui,
+    pixel: InspectedPixel,
+    height: u32,
+    tone_mapper: ToneMapper,
+    accumulated_samples: u64,
+) {
+    let [r, g, b, a] = pixel.color;
+    let display = |value: f32| (linear_to_srgb(value) * 255.0).round() as u8;
+    let [display_r, display_g, display_b] = tone_mapper.apply([r, g, b]);
+    ui.label(format!(
+        "Pixel: {}, {}",
+        pixel.x,
+        height.saturating_sub(1).saturating_sub(pixel.y)
+    ));
+    ui.label(format!("Linear: {r:.5}, {g:.5}, {b:.5}"));
+    ui.label(format!(
+        "Luminance: {:.5}",
+        r * 0.2126 + g * 0.7152 + b * 0.0722
+    ));
+    ui.label(format!(
+        "Display: {}, {}, {}",
+        display(display_r),
+        display(display_g),
+        display(display_b)
+    ));
+    ui.label(format!("Alpha: {a:.3}"));
+    ui.label(format!("Samples: {accumulated_samples}"));
+}
+
+/// Draws the line between the two halves of a tone mapper comparison with their names,
+/// the line can be dragged to move the split
+pub fn ui_tone_mapper_split(
+    ui: &mut egui::Ui,
+    rect: egui::Rect,
+    split_position: &mut f32,
+    left: ToneMapper,
+    right: ToneMapper,
+) {
+    let x = rect.left() + rect.width() * *split_position;
+    let handle = egui::Rect::from_x_y_ranges(x - 4.0..=x + 4.0, rect.y_range());
+    let response = ui
+        .interact(
+            handle,
+            ui.id().with("Tone Mapper Split"),
+            egui::Sense::drag(),
+        )
+        .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+    if let Some(position) = response.interact_pointer_pos()
+        && response.dragged()
+    {
+        *split_position = ((position.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+    }
+
+    let x = rect.left() + rect.width() * *split_position;
+    let painter = ui.painter_at(rect);
+    painter.vline(
+        x,
+        rect.y_range(),
+        egui::Stroke::new(1.0, egui::Color32::WHITE),
+    );
+    let font = egui::FontId::proportional(14.0);
+    painter.text(
+        egui::pos2(x - 6.0, rect.top() + 6.0),
+        egui::Align2::RIGHT_TOP,
+        left.name(),
+        font.clone(),
+        egui::Color32::WHITE,
+    );
+    painter.text(
+        egui::pos2(x + 6.0, rect.top() + 6.0),
+        egui::Align2::LEFT_TOP,
+        right.name(),
+        font,
+        egui::Color32::WHITE,
+    );
+}
@@ -0,0 +1,442 @@
+use crate::{Camera, Plane, PlaneId, find_plane, ui_plane_id};
+use eframe::egui;
+use math::{Rotor, Vector3};
+use ray_tracing::Color;
+use serde::{Deserialize, Serialize};
+
+/// Keyframes closer together in time than this are the same keyframe
+const SAME_TIME: f32 = 0.001;
+
+/// What a track animates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnimationTarget {
+    CameraPose,
+    PlaneTransform(PlaneId),
+    PlaneColor(PlaneId),
+    PlaneEmissiveColor(PlaneId),
+}
+
+impl AnimationTarget {
+    fn name(self, planes: &[Plane]) -> String {
+        let plane_name = |id| {
+            find_plane(planes, id).map_or("Missing Plane", |index| planes[index].name.as_str())
+        };
+        match self {
+            AnimationTarget::CameraPose => "Camera".into(),
+            AnimationTarget::PlaneTransform(id) => format!("{} Transform", plane_name(id)),
+            AnimationTarget::PlaneColor(id) => format!("{} Color", plane_name(id)),
+            AnimationTarget::PlaneEmissiveColor(id) => {
+                format!("{} Emissive Color", plane_name(id))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum KeyValue {
+    Pose { position: Vector3, rotation: Rotor },
+    Color(Color),
+}
+
+impl KeyValue {
+    /// Positions and colors are interpolated linearly, rotations with [`Rotor::slerp`]
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        match (self, to) {
+            (
+                KeyValue::Pose { position, rotation },
+                KeyValue::Pose {
+                    position: to_position,
+                    rotation: to_rotation,
+                },
+            ) => KeyValue::Pose {
+                position: position + (to_position - position) * t,
+                rotation: rotation.slerp(to_rotation, t),
+            },
+            (KeyValue::Color(color), KeyValue::Color(to_color)) => {
+                let mut color = color;
+                for (channel, to_channel) in color.as_mut().iter_mut().zip(to_color.as_ref()) {
+                    *channel += (to_channel - *channel) * t;
+                }
+                KeyValue::Color(color)
+            }
+            // a track only ever gets one kind of value, but a hand edited scene could mix them
+            _ => self,
+        }
+    }
+}
+
+/// How the value changes on the way from a keyframe to the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    /// Starts and stops slowly
+    #[default]
+    Smooth,
+    /// Holds the value until the next keyframe
+    Step,
+}
+
+impl Easing {
+    const ALL: [Self; 3] = [Self::Linear, Self::Smooth, Self::Step];
+
+    fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::Smooth => "Smooth",
+            Easing::Step => "Step",
+        }
+    }
+
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::Smooth => t * t * (3.0 - 2.0 * t),
+            Easing::Step => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// In seconds from the start of the animation
+    pub time: f32,
+    pub value: KeyValue,
+    pub easing: Easing,
+}
+
+/// The keyframes of one target, sorted by time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Track {
+    pub target: AnimationTarget,
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Track {
+    /// The value at `time`, held before the first keyframe and after the last one
+    pub fn sample(&self, time: f32) -> Option<KeyValue> {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        match (
+            self.keyframes.get(next.wrapping_sub(1)),
+            self.keyframes.get(next),
+        ) {
+            (Some(from), Some(to)) => {
+                let t = (time - from.time) / (to.time - from.time).max(SAME_TIME);
+                Some(
+                    from.value
+                        .interpolate(to.value, from.easing.apply(t.clamp(0.0, 1.0))),
+                )
+            }
+            (Some(keyframe), None) | (None, Some(keyframe)) => Some(keyframe.value),
+            (None, None) => None,
+        }
+    }
+
+    /// Adds a keyframe, replacing the one at the same time if there is one
+    pub fn set_key(&mut self, time: f32, value: KeyValue) {
+        if let Some(keyframe) = self
+            .keyframes
+            .iter_mut()
+            .find(|keyframe| (keyframe.time - time).abs() < SAME_TIME)
+        {
+            keyframe.value = value;
+            return;
+        }
+        let index = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time < time);
+        self.keyframes.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                easing: Easing::default(),
+            },
+        );
+    }
+
+    fn sort(&mut self) {
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+}
+
+/// Keyframed plane transforms, plane colors and camera poses, saved with the scene
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Animation {
+    /// In seconds
+    pub duration: f32,
+    pub tracks: Vec<Track>,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            duration: 5.0,
+            tracks: vec![],
+        }
+    }
+}
+
+impl Animation {
+    /// Adds a keyframe to the track of `target`, starting the track if there isn't one yet
+    pub fn set_key(&mut self, target: AnimationTarget, time: f32, value: KeyValue) {
+        match self.tracks.iter_mut().find(|track| track.target == target) {
+            Some(track) => track.set_key(time, value),
+            None => self.tracks.push(Track {
+                target,
+                keyframes: vec![Keyframe {
+                    time,
+                    value,
+                    easing: Easing::default(),
+                }],
+            }),
+        }
+    }
+
+    /// Sets everything that is animated to how it is at `time`,
+    /// returns whether the camera moved and whether any planes changed
+    pub fn apply(&self, time: f32, camera: &mut Camera, planes: &mut [Plane]) -> (bool, bool) {
+        let mut camera_moved = false;
+        let mut planes_changed = false;
+        for track in &self.tracks {
+            let Some(value) = track.sample(time) else {
+                continue;
+            };
+            let plane = |id| find_plane(planes, id);
+            match (track.target, value) {
+                (AnimationTarget::CameraPose, KeyValue::Pose { position, rotation }) => {
+                    camera.position = position;
+                    camera.rotation = rotation;
+                    camera_moved = true;
+                }
+                (AnimationTarget::PlaneTransform(id), KeyValue::Pose { position, rotation }) => {
+                    if let Some(index) = plane(id) {
+                        planes[index].position = position;
+                        planes[index].set_rotation(rotation);
+                        planes_changed = true;
+                    }
+                }
+                (AnimationTarget::PlaneColor(id), KeyValue::Color(color)) => {
+                    if let Some(index) = plane(id) {
+                        planes[index].color = color;
+                        planes_changed = true;
+                    }
+                }
+                (AnimationTarget::PlaneEmissiveColor(id), KeyValue::Color(color)) => {
+                    if let Some(index) = plane(id) {
+                        planes[index].emissive_color = color;
+                        planes_changed = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        (camera_moved, planes_changed)
+    }
+
+    pub fn scale_lengths(&mut self, factor: f32) {
+        for track in &mut self.tracks {
+            for keyframe in &mut track.keyframes {
+                if let KeyValue::Pose { position, .. } = &mut keyframe.value {
+                    *position *= factor;
+                }
+            }
+        }
+    }
+}
+
+/// Where the animation is being played or scrubbed to, not saved with the scene
+pub struct Timeline {
+    /// In seconds from the start of the animation
+    pub time: f32,
+    pub playing: bool,
+    pub looping: bool,
+    /// The plane that the key buttons add keyframes for
+    selected_plane: Option<PlaneId>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            playing: false,
+            looping: true,
+            selected_plane: None,
+        }
+    }
+}
+
+impl Timeline {
+    /// Moves the time forward while playing, returns whether it moved
+    pub fn advance(&mut self, dt: f32, duration: f32) -> bool {
+        if !self.playing {
+            return false;
+        }
+        self.time += dt;
+        if self.time >= duration {
+            if self.looping && duration > 0.0 {
+                self.time = self.time.rem_euclid(duration);
+            } else {
+                self.time = duration;
+                self.playing = false;
+            }
+        }
+        true
+    }
+
+    /// Returns whether the time or the keyframes changed, so the animation has to be applied again
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        animation: &mut Animation,
+        camera: &Camera,
+        planes: &[Plane],
+    ) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Duration:");
+            ui.add(
+                egui::DragValue::new(&mut animation.duration)
+                    .range(0.1..=f32::INFINITY)
+                    .speed(0.1)
+                    .suffix(" s"),
+            );
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button(if self.playing { "Pause" } else { "Play" })
+                .clicked()
+            {
+                if !self.playing && self.time >= animation.duration {
+                    self.time = 0.0;
+                }
+                self.playing = !self.playing;
+            }
+            ui.checkbox(&mut self.looping, "Loop");
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut self.time, 0.0..=animation.duration)
+                        .suffix(" s")
+                        .clamping(egui::SliderClamping::Always),
+                )
+                .changed();
+        });
+
+        ui.separator();
+        if ui
+            .button("Key Camera")
+            .on_hover_text("Adds a keyframe of where the camera is at the current time")
+            .clicked()
+        {
+            animation.set_key(
+                AnimationTarget::CameraPose,
+                self.time,
+                KeyValue::Pose {
+                    position: camera.position,
+                    rotation: camera.rotation,
+                },
+            );
+        }
+        ui_plane_id(ui, "Plane:", &mut self.selected_plane, planes);
+        if let Some(plane) = self
+            .selected_plane
+            .and_then(|id| find_plane(planes, id))
+            .map(|index| &planes[index])
+        {
+            ui.horizontal(|ui| {
+                if ui.button("Key Transform").clicked() {
+                    animation.set_key(
+                        AnimationTarget::PlaneTransform(plane.id),
+                        self.time,
+                        KeyValue::Pose {
+                            position: plane.position,
+                            rotation: plane.rotation(),
+                        },
+                    );
+                }
+                if ui.button("Key Color").clicked() {
+                    animation.set_key(
+                        AnimationTarget::PlaneColor(plane.id),
+                        self.time,
+                        KeyValue::Color(plane.color),
+                    );
+                }
+                if ui.button("Key Emissive Color").clicked() {
+                    animation.set_key(
+                        AnimationTarget::PlaneEmissiveColor(plane.id),
+                        self.time,
+                        KeyValue::Color(plane.emissive_color),
+                    );
+                }
+            });
+        }
+
+        let mut track_to_delete = None;
+        for (track_index, track) in animation.tracks.iter_mut().enumerate() {
+            ui.separator();
+            ui.push_id(track_index, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong(track.target.name(planes));
+                    if ui.button("Delete Track").clicked() {
+                        track_to_delete = Some(track_index);
+                    }
+                });
+                let mut to_delete = None;
+                let mut retimed = false;
+                for (index, keyframe) in track.keyframes.iter_mut().enumerate() {
+                    ui.push_id(index, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("Go To").clicked() {
+                                self.time = keyframe.time;
+                                changed = true;
+                            }
+                            retimed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut keyframe.time)
+                                        .range(0.0..=f32::INFINITY)
+                                        .speed(0.01)
+                                        .suffix(" s"),
+                                )
+                                .changed();
+                            egui::ComboBox::new("Easing", "")
+                                .selected_text(keyframe.easing.name())
+                                .show_ui(ui, |ui| {
+                                    for easing in Easing::ALL {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut keyframe.easing,
+                                                easing,
+                                                easing.name(),
+                                            )
+                                            .changed();
+                                    }
+                                })
+                                .response
+                                .on_hover_text(
+                                    "How the value changes on the way to the next keyframe",
+                                );
+                            if ui.button("Delete").clicked() {
+                                to_delete = Some(index);
+                            }
+                        });
+                    });
+                }
+                if let Some(index) = to_delete {
+                    track.keyframes.remove(index);
+                    changed = true;
+                }
+                if retimed {
+                    track.sort();
+                    changed = true;
+                }
+            });
+        }
+        if let Some(index) = track_to_delete {
+            animation.tracks.remove(index);
+        }
+        animation.tracks.retain(|track| !track.keyframes.is_empty());
+        changed
+    }
+}
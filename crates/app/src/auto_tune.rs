@@ -0,0 +1,322 @@
+use crate::{QualityPreset, RenderSettings, Scene};
+use eframe::{egui, egui_wgpu::RenderState, wgpu};
+use ray_tracing::{RayTracingRenderer, ShaderError};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// Traced before a candidate is timed, the first frames compile pipelines and upload the scene
+const WARMUP_FRAMES: u32 = 2;
+const TIMED_FRAMES: u32 = 8;
+/// How many frames the renderer's gpu timestamps are read back after the frame they measured,
+/// with only one frame in flight at a time
+const TIMESTAMP_LAG: u32 = 2;
+
+const SAMPLES_PER_PIXEL: [u32; 3] = [1, 2, 4];
+const MAX_BOUNCES: [u32; 3] = [1, 2, 4];
+const RECURSIVE_PORTAL_COUNTS: [u32; 3] = [3, 6, 10];
+
+#[derive(Debug, Clone, Copy)]
+struct Measurement {
+    preset: QualityPreset,
+    frames_per_second: f64,
+}
+
+/// A submitted frame the gpu hasn't finished yet
+struct InFlight {
+    submitted: Instant,
+    /// Set once the queue has finished the frame, which is only noticed when the device is polled
+    done: Arc<AtomicBool>,
+}
+
+/// A benchmark in progress, at most one frame is in flight so the ui keeps running
+struct Run {
+    /// Snapshots of the scene and settings when the run started
+    scene: Scene,
+    render_settings: RenderSettings,
+    width: u32,
+    height: u32,
+    candidates: Vec<QualityPreset>,
+    candidate: usize,
+    frame: u32,
+    /// Gpu time of the frames measured with timestamp queries, without them the time from submitting a frame
+    /// until it was noticed to be done, which is only as precise as the ui's frame rate
+    timed: Duration,
+    timed_frames: u32,
+    in_flight: Option<InFlight>,
+}
+
+/// Benchmarks combinations of samples per pixel, bounces and portal recursion on the current scene
+/// and recommends the best looking one that still reaches a target frame rate
+pub struct AutoTuner {
+    pub target_fps: f32,
+    run: Option<Run>,
+    measurements: Vec<Measurement>,
+    /// Separate from the viewport's renderer so the benchmark doesn't disturb what is being accumulated there
+    renderer: Option<RayTracingRenderer>,
+}
+
+impl Default for AutoTuner {
+    fn default() -> Self {
+        Self {
+            target_fps: 60.0,
+            run: None,
+            measurements: vec![],
+            renderer: None,
+        }
+    }
+}
+
+impl AutoTuner {
+    pub fn running(&self) -> bool {
+        self.run.is_some()
+    }
+
+    /// The best looking preset that reaches the target frame rate, or the fastest one if none of them do,
+    /// portal recursion matters the most for how a scene looks, then bounces, more samples only converge faster
+    fn recommended(&self) -> Option<Measurement> {
+        let target = self.target_fps as f64;
+        let quality = |measurement: &&Measurement| {
+            let preset = measurement.preset;
+            (
+                preset.recursive_portal_count,
+                preset.max_bounces,
+                preset.samples_per_pixel,
+            )
+        };
+        self.measurements
+            .iter()
+            .filter(|measurement| measurement.frames_per_second >= target)
+            .max_by_key(quality)
+            .or_else(|| {
+                self.measurements
+                    .iter()
+                    .max_by(|a, b| a.frames_per_second.total_cmp(&b.frames_per_second))
+            })
+            .copied()
+    }
+
+    /// Returns whether the recommended settings were applied to `render_settings`
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        scene: &Scene,
+        render_settings: &mut RenderSettings,
+        (width, height): (u32, u32),
+    ) -> bool {
+        ui.horizontal(|ui| {
+            ui.label("Target Frame Rate:");
+            ui.add(
+                egui::DragValue::new(&mut self.target_fps)
+                    .range(1.0..=1000.0)
+                    .suffix(" fps"),
+            );
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !self.running() && width > 0 && height > 0,
+                    egui::Button::new("Auto Tune"),
+                )
+                .on_hover_text(format!(
+                    "Times every combination of settings on the current scene at the viewport's resolution of {width}x{height}"
+                ))
+                .clicked()
+            {
+                let mut candidates = vec![];
+                for recursive_portal_count in RECURSIVE_PORTAL_COUNTS {
+                    for max_bounces in MAX_BOUNCES {
+                        for samples_per_pixel in SAMPLES_PER_PIXEL {
+                            candidates.push(QualityPreset {
+                                samples_per_pixel,
+                                max_bounces,
+                                recursive_portal_count,
+                            });
+                        }
+                    }
+                }
+                self.measurements.clear();
+                self.run = Some(Run {
+                    scene: scene.clone(),
+                    render_settings: render_settings.clone(),
+                    width,
+                    height,
+                    candidates,
+                    candidate: 0,
+                    frame: 0,
+                    timed: Duration::ZERO,
+                    timed_frames: 0,
+                    in_flight: None,
+                });
+            }
+            if self.running() && ui.button("Cancel").clicked() {
+                self.run = None;
+                self.renderer = None;
+            }
+        });
+        if let Some(run) = &self.run {
+            ui.add(
+                egui::ProgressBar::new(run.candidate as f32 / run.candidates.len() as f32)
+                    .show_percentage(),
+            );
+        }
+
+        let recommended = self.recommended();
+        let mut applied = false;
+        if !self.measurements.is_empty() {
+            ui.separator();
+            egui::Grid::new("Auto Tune Measurements")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Samples Per Pixel");
+                    ui.strong("Max Bounces");
+                    ui.strong("Portal Recursion");
+                    ui.strong("Frame Rate");
+                    ui.end_row();
+                    for measurement in &self.measurements {
+                        let preset = measurement.preset;
+                        let is_recommended =
+                            recommended.is_some_and(|recommended| recommended.preset == preset);
+                        let text = |text: String| {
+                            let text = egui::RichText::new(text);
+                            if is_recommended { text.strong() } else { text }
+                        };
+                        ui.label(text(preset.samples_per_pixel.to_string()));
+                        ui.label(text(preset.max_bounces.to_string()));
+                        ui.label(text(preset.recursive_portal_count.to_string()));
+                        let frames_per_second =
+                            text(format!("{:.1} fps", measurement.frames_per_second));
+                        if measurement.frames_per_second >= self.target_fps as f64 {
+                            ui.label(frames_per_second);
+                        } else {
+                            ui.colored_label(ui.visuals().warn_fg_color, frames_per_second);
+                        }
+                        ui.end_row();
+                    }
+                });
+        }
+        if let Some(recommended) = recommended
+            && !self.running()
+        {
+            let preset = recommended.preset;
+            if recommended.frames_per_second < self.target_fps as f64 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "Nothing reached the target frame rate, the fastest settings are recommended",
+                );
+            }
+            if ui
+                .button(format!(
+                    "Apply {} spp, {} bounces, {} recursion",
+                    preset.samples_per_pixel, preset.max_bounces, preset.recursive_portal_count
+                ))
+                .clicked()
+            {
+                render_settings.samples_per_pixel = preset.samples_per_pixel;
+                render_settings.max_bounces = preset.max_bounces;
+                render_settings.recursive_portal_count = preset.recursive_portal_count;
+                applied = true;
+            }
+        }
+        applied
+    }
+
+    /// Times the frame in flight once the gpu has finished it and submits the next one, without ever waiting on the gpu
+    pub fn update(&mut self, render_state: &RenderState) -> Result<(), ShaderError> {
+        let Some(run) = &mut self.run else {
+            return Ok(());
+        };
+        let renderer = match self.renderer.take().map_or_else(
+            || {
+                RayTracingRenderer::new(
+                    &render_state.device,
+                    &render_state.queue,
+                    render_state.target_format,
+                )
+            },
+            Ok,
+        ) {
+            Ok(renderer) => self.renderer.insert(renderer),
+            Err(error) => {
+                self.run = None;
+                return Err(error);
+            }
+        };
+
+        if let Some(in_flight) = &run.in_flight {
+            _ = render_state.device.poll(wgpu::PollType::Poll);
+            if !in_flight.done.load(Ordering::Acquire) {
+                return Ok(());
+            }
+            let elapsed = in_flight.submitted.elapsed();
+            run.in_flight = None;
+
+            let preset = run.candidates[run.candidate];
+            if render_state
+                .device
+                .features()
+                .contains(wgpu::Features::TIMESTAMP_QUERY)
+            {
+                if let Some(gpu_trace) = renderer.take_prepare_timings().gpu_trace
+                    && run.frame >= WARMUP_FRAMES + TIMESTAMP_LAG
+                {
+                    run.timed += gpu_trace;
+                    run.timed_frames += 1;
+                }
+            } else if run.frame >= WARMUP_FRAMES {
+                run.timed += elapsed;
+                run.timed_frames += 1;
+            }
+            run.frame += 1;
+
+            if run.timed_frames == TIMED_FRAMES {
+                self.measurements.push(Measurement {
+                    preset,
+                    frames_per_second: TIMED_FRAMES as f64 / run.timed.as_secs_f64().max(1e-9),
+                });
+                run.candidate += 1;
+                run.frame = 0;
+                run.timed = Duration::ZERO;
+                run.timed_frames = 0;
+                if run.candidate == run.candidates.len() {
+                    self.run = None;
+                    self.renderer = None;
+                    return Ok(());
+                }
+            }
+        }
+
+        let preset = run.candidates[run.candidate];
+        run.render_settings.samples_per_pixel = preset.samples_per_pixel;
+        run.render_settings.max_bounces = preset.max_bounces;
+        run.render_settings.recursive_portal_count = preset.recursive_portal_count;
+        let frame = run.render_settings.accumulation_frame(
+            &run.scene,
+            run.width,
+            run.height,
+            run.frame,
+            run.frame as u64 * preset.samples_per_pixel as u64,
+            preset.samples_per_pixel,
+        );
+
+        render_state.queue.submit([renderer.prepare_frame(
+            &render_state.device,
+            &render_state.queue,
+            &frame,
+        )]);
+        let done = Arc::new(AtomicBool::new(false));
+        render_state.queue.on_submitted_work_done({
+            let done = done.clone();
+            move || done.store(true, Ordering::Release)
+        });
+        run.in_flight = Some(InFlight {
+            submitted: Instant::now(),
+            done,
+        });
+        Ok(())
+    }
+}
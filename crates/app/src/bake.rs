@@ -0,0 +1,136 @@
+use eframe::egui;
+use ray_tracing::{BakeFrame, RayTracingPaintCallback};
+
+/// The most cells that get baked, planes that don't fit anymore are left unbaked
+const MAX_BAKED_CELLS: u32 = 1 << 20;
+
+/// Bakes the lighting of every checker cell into the planes over a number of frames,
+/// Unlit mode then shows the planes lit by it so static scenes can be looked around at interactive framerates
+pub struct LightBake {
+    pub enabled: bool,
+    /// How many rays every side of a cell traces per frame
+    pub samples_per_cell: u32,
+    /// How many frames are baked before the bake stops
+    pub frames: u32,
+    accumulated_frames: u32,
+    /// Where the baked lighting of every plane starts in the last frame, empty if nothing was baked
+    offsets: Vec<u32>,
+}
+
+impl Default for LightBake {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            samples_per_cell: 16,
+            frames: 64,
+            accumulated_frames: 0,
+            offsets: vec![],
+        }
+    }
+}
+
+impl LightBake {
+    /// Returns whether the settings changed, in which case the bake has started over
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Bake Lighting:");
+            changed |= ui
+                .checkbox(&mut self.enabled, "")
+                .on_hover_text(
+                    "Traces the light reaching every checker cell of the planes, \
+                    Unlit mode then shows the planes lit by it",
+                )
+                .changed();
+            if ui
+                .add_enabled(self.enabled, egui::Button::new("Restart"))
+                .clicked()
+            {
+                changed = true;
+            }
+        });
+        if self.enabled {
+            ui.indent("Bake Lighting", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Samples Per Cell:");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.samples_per_cell).range(1..=1024))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Frames:");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.frames).range(1..=u32::MAX))
+                        .changed();
+                });
+                ui.add(
+                    egui::ProgressBar::new(self.accumulated_frames as f32 / self.frames as f32)
+                        .text(format!(
+                            "{}/{} frames",
+                            self.accumulated_frames, self.frames
+                        )),
+                );
+            });
+        }
+        if changed {
+            self.restart();
+        }
+        changed
+    }
+
+    /// Starts baking over, for when the scene changes
+    pub fn restart(&mut self) {
+        self.accumulated_frames = 0;
+    }
+
+    /// Whether there are frames left to bake
+    pub fn baking(&self) -> bool {
+        self.enabled && self.accumulated_frames < self.frames
+    }
+
+    /// Gives the planes of `frame` their baked lighting, and bakes another frame if `bake` is set,
+    /// has to be called every frame even when nothing is baked so the planes stop using it when turned off
+    pub fn apply(&mut self, frame: &mut RayTracingPaintCallback, bake: bool) {
+        if !self.enabled {
+            // the uploaded planes still point at the baked lighting
+            if !self.offsets.is_empty() {
+                self.offsets.clear();
+                frame.dirty_planes = None;
+            }
+            return;
+        }
+
+        let mut offsets = Vec::with_capacity(frame.planes.len());
+        let mut cell_count = 0u32;
+        for plane in &mut frame.planes {
+            // both sides of every cell
+            let cells = plane
+                .checker_count_x
+                .max(1)
+                .saturating_mul(plane.checker_count_z.max(1))
+                .saturating_mul(2);
+            if cell_count.saturating_add(cells) > MAX_BAKED_CELLS {
+                offsets.push(u32::MAX);
+                continue;
+            }
+            plane.baked_offset = cell_count;
+            offsets.push(cell_count);
+            cell_count += cells;
+        }
+        if offsets != self.offsets {
+            self.offsets = offsets;
+            self.restart();
+            frame.dirty_planes = None;
+        }
+
+        let bake = bake && self.baking();
+        frame.bake = Some(BakeFrame {
+            cell_count,
+            accumulated_frames: self.accumulated_frames,
+            samples_per_cell: if bake { self.samples_per_cell } else { 0 },
+        });
+        if bake {
+            self.accumulated_frames += 1;
+        }
+    }
+}
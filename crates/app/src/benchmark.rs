@@ -0,0 +1,170 @@
+use crate::{Headless, RenderSettings, RenderType, load_scene, synthetic_planes};
+use std::{
+    ffi::OsString,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+const USAGE: &str = "usage: portals benchmark <file.scene> [--seconds <n>] [--width <n>] [--height <n>] \
+    [--samples <samples per dispatch>] [--settings <render settings.json>] [--synthetic-planes <n>]";
+
+/// What a benchmark is run with, everything but the scene has a default so runs are comparable
+struct BenchmarkOptions {
+    scene_path: PathBuf,
+    seconds: f64,
+    width: u32,
+    height: u32,
+    samples_per_dispatch: u32,
+    settings_path: Option<PathBuf>,
+    /// Added to the scene to see how it scales with more planes, see [`synthetic_planes`]
+    synthetic_planes: u32,
+}
+
+impl BenchmarkOptions {
+    fn parse(args: &[OsString]) -> Result<Self, String> {
+        let mut options = Self {
+            scene_path: PathBuf::new(),
+            seconds: 10.0,
+            width: 1280,
+            height: 720,
+            samples_per_dispatch: 4,
+            settings_path: None,
+            synthetic_planes: 0,
+        };
+        let mut scene_path = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.to_str().unwrap_or_default() {
+                "--seconds" => options.seconds = number(arg, args.next())?,
+                "--width" => options.width = number(arg, args.next())?,
+                "--height" => options.height = number(arg, args.next())?,
+                "--samples" => options.samples_per_dispatch = number(arg, args.next())?,
+                "--synthetic-planes" => options.synthetic_planes = number(arg, args.next())?,
+                "--settings" => {
+                    options.settings_path =
+                        Some(args.next().ok_or("--settings needs a value")?.into());
+                }
+                option if option.starts_with("--") => {
+                    return Err(format!("unknown option {option}"));
+                }
+                _ if scene_path.is_none() => scene_path = Some(arg.into()),
+                _ => return Err(format!("unexpected argument {}", arg.display())),
+            }
+        }
+        options.scene_path = scene_path.ok_or("no scene given")?;
+        if options.seconds.is_nan()
+            || options.seconds <= 0.0
+            || options.width == 0
+            || options.height == 0
+            || options.samples_per_dispatch == 0
+        {
+            return Err("the duration, resolution and samples have to be positive".into());
+        }
+        Ok(options)
+    }
+}
+
+fn number<T: std::str::FromStr>(option: &OsString, value: Option<&OsString>) -> Result<T, String> {
+    value
+        .and_then(|value| value.to_str()?.parse().ok())
+        .ok_or_else(|| format!("{} needs a number", option.display()))
+}
+
+/// Runs `portals benchmark`, rendering a scene lit for a fixed time and printing how far it got,
+/// returns the exit code, which is nonzero if the scene couldn't be rendered
+pub fn benchmark(args: &[OsString]) -> i32 {
+    let options = match BenchmarkOptions::parse(args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}\n{USAGE}");
+            return 2;
+        }
+    };
+    match run_benchmark(&options) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("error: {error}");
+            1
+        }
+    }
+}
+
+fn run_benchmark(options: &BenchmarkOptions) -> Result<(), String> {
+    let mut scene = load_scene(&options.scene_path)?;
+    scene
+        .planes
+        .extend(synthetic_planes(options.synthetic_planes));
+    let mut render_settings = match &options.settings_path {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|s| {
+                serde_json::from_str::<RenderSettings>(&s).map_err(|error| error.to_string())
+            })?,
+        None => RenderSettings::default(),
+    };
+    // the unlit preview would only measure primary visibility
+    render_settings.render_type = RenderType::Lit;
+
+    let mut headless = Headless::new()?;
+    println!(
+        "adapter: {} ({:?})",
+        headless.adapter_info.name, headless.adapter_info.backend
+    );
+    println!("scene: {}", options.scene_path.display());
+    println!("resolution: {}x{}", options.width, options.height);
+    println!("planes: {}", scene.planes.len());
+
+    // the first frame compiles pipelines and allocates the image, so it isn't timed
+    headless.render_frame(&render_settings.accumulation_frame(
+        &scene,
+        options.width,
+        options.height,
+        0,
+        0,
+        1,
+    ))?;
+
+    let duration = Duration::from_secs_f64(options.seconds);
+    let start = Instant::now();
+    let mut accumulated_frames = 0;
+    let mut accumulated_samples = 0u64;
+    // a frame is never started once the time is up, so it only overruns by the last frame
+    while start.elapsed() < duration {
+        headless.render_frame(&render_settings.accumulation_frame(
+            &scene,
+            options.width,
+            options.height,
+            accumulated_frames,
+            accumulated_samples,
+            options.samples_per_dispatch,
+        ))?;
+        accumulated_frames += 1;
+        accumulated_samples += options.samples_per_dispatch as u64;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let pixels = headless
+        .renderer
+        .read_texture(&headless.device, &headless.queue);
+    let camera_rays = accumulated_samples * options.width as u64 * options.height as u64;
+    println!("seconds: {elapsed:.3}");
+    println!("frames: {accumulated_frames}");
+    println!("samples per pixel: {accumulated_samples}");
+    println!(
+        "camera rays per second: {:.2} million",
+        camera_rays as f64 / elapsed / 1e6
+    );
+    println!("checksum: {:016x}", image_checksum(&pixels));
+    Ok(())
+}
+
+/// FNV-1a over the accumulated values of the image, only the same between runs with the same samples per pixel
+pub fn image_checksum(pixels: &[[f32; 4]]) -> u64 {
+    pixels
+        .iter()
+        .flatten()
+        .flat_map(|value| value.to_le_bytes())
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
@@ -1,27 +1,111 @@
-use crate::{ui_transform, ui_vector3};
+use crate::{GamepadInput, Units, ui_transform, ui_vector3, ui_vector3_with_suffix};
 use eframe::egui;
-use math::{Rotor, Transform, Vector3};
+use math::{ParseError, Rotor, Transform, Vector3};
 use serde::{Deserialize, Serialize};
-use std::f32::consts::TAU;
+use std::f32::consts::{FRAC_PI_2, TAU};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Camera {
     pub position: Vector3,
     pub rotation: Rotor,
     pub speed: f32,
     pub rotation_speed: f32,
+    /// The vertical field of view in radians
+    #[serde(default = "default_fov")]
+    pub fov: f32,
+    /// The radius of the lens, 0 is a pinhole where everything is in focus
+    #[serde(default)]
+    pub aperture: f32,
+    /// How far in front of the camera things are in focus when the aperture isn't 0
+    #[serde(default = "default_focus_distance")]
+    pub focus_distance: f32,
+}
+
+fn default_fov() -> f32 {
+    FRAC_PI_2
+}
+
+fn default_focus_distance() -> f32 {
+    5.0
+}
+
+/// How dragging the mouse turns the camera, a preference of whoever is looking around so it isn't saved with the scene
+#[derive(Debug, Clone, Copy)]
+pub struct MouseLook {
+    /// How far the camera turns per point the mouse is dragged, in radians
+    pub sensitivity: f32,
+    /// Dragging the mouse up looks down instead
+    pub invert_y: bool,
+}
+
+impl MouseLook {
+    /// How far a mouse drag of `delta` points yaws and pitches the camera, in radians
+    pub fn angles(self, delta: egui::Vec2) -> (f32, f32) {
+        let vertical = if self.invert_y { delta.y } else { -delta.y };
+        (delta.x * self.sensitivity, vertical * self.sensitivity)
+    }
+}
+
+/// A saved camera pose that can be returned to or rendered from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub position: Vector3,
+    pub rotation: Rotor,
 }
 
 impl Camera {
+    pub fn bookmark(&self, name: String) -> CameraBookmark {
+        CameraBookmark {
+            name,
+            position: self.position,
+            rotation: self.rotation,
+        }
+    }
+
+    /// Where the camera is and how it is turned as text, see [`Transform`]'s `Display` impl
+    pub fn pose(&self) -> String {
+        self.transform().to_string()
+    }
+
+    /// Moves the camera to a pose written by [`Camera::pose`]
+    pub fn set_pose(&mut self, pose: &str) -> Result<(), ParseError> {
+        let transform = pose.parse::<Transform>()?;
+        self.position = transform.translation_part();
+        self.rotation = transform.rotor_part().normalised();
+        Ok(())
+    }
+
+    pub fn go_to(&mut self, bookmark: &CameraBookmark) {
+        self.position = bookmark.position;
+        self.rotation = bookmark.rotation;
+    }
+
+    /// Whether both cameras see the scene from exactly the same place, direction, field of view and focus
+    pub fn same_view(&self, other: &Camera) -> bool {
+        let view = |camera: &Camera| {
+            let Camera {
+                position: Vector3 { x, y, z },
+                rotation: Rotor { s, e12, e13, e23 },
+                fov,
+                aperture,
+                focus_distance,
+                ..
+            } = *camera;
+            [x, y, z, s, e12, e13, e23, fov, aperture, focus_distance]
+        };
+        view(self) == view(other)
+    }
+
     pub fn transform(&self) -> Transform {
         Transform::translation(self.position).then(Transform::from_rotor(self.rotation))
     }
 
-    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+    pub fn ui(&mut self, ui: &mut egui::Ui, units: Units) -> bool {
         let mut changed = false;
         ui.horizontal(|ui| {
             ui.label("Position:");
-            changed |= ui_vector3(ui, &mut self.position).changed();
+            changed |= ui_vector3_with_suffix(ui, &mut self.position, units.suffix()).changed();
         });
         ui.add_enabled_ui(false, |ui| {
             ui.horizontal(|ui| {
@@ -40,6 +124,36 @@ impl Camera {
                 ui_vector3(ui, &mut right);
             });
         });
+        ui.horizontal(|ui| {
+            ui.label("Field Of View:");
+            changed |= ui.drag_angle(&mut self.fov).changed();
+            self.fov = self.fov.clamp(1f32.to_radians(), 179f32.to_radians());
+        });
+        ui.horizontal(|ui| {
+            ui.label("Aperture:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.aperture)
+                        .speed(0.001)
+                        .range(0.0..=f32::INFINITY)
+                        .suffix(units.suffix()),
+                )
+                .on_hover_text("The radius of the lens, 0 keeps everything in focus")
+                .changed();
+        });
+        ui.add_enabled_ui(self.aperture > 0.0, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Focus Distance:");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.focus_distance)
+                            .speed(0.05)
+                            .range(0.001..=f32::INFINITY)
+                            .suffix(units.suffix()),
+                    )
+                    .changed();
+            });
+        });
         ui.collapsing("Transform", |ui| {
             ui.add_enabled_ui(false, |ui| {
                 ui_transform(ui, &mut self.transform());
@@ -47,7 +161,11 @@ impl Camera {
         });
         ui.horizontal(|ui| {
             ui.label("Camera Speed:");
-            ui.add(egui::DragValue::new(&mut self.speed).speed(0.1));
+            ui.add(
+                egui::DragValue::new(&mut self.speed)
+                    .speed(0.1)
+                    .suffix(format!("{}/s", units.suffix())),
+            );
         });
         ui.horizontal(|ui| {
             ui.label("Camera Rotation Speed:");
@@ -56,6 +174,46 @@ impl Camera {
         changed
     }
 
+    /// Flies the camera like the keyboard does, but as fast as the sticks are pushed,
+    /// `sensitivity` scales how fast the right stick turns it, returns whether it moved
+    pub fn fly(&mut self, input: &GamepadInput, sensitivity: f32, ts: f32) -> bool {
+        if input.is_none() {
+            return false;
+        }
+
+        let boost = input.boost as u8 as f32 + 1.0;
+        let movement = if input.movement.magnitude() > 1.0 {
+            input.movement.normalised()
+        } else {
+            input.movement
+        };
+        self.position += self.rotation.rotate(movement) * self.speed * boost * ts;
+
+        let turn = self.rotation_speed * sensitivity * TAU * ts;
+        self.rotation = self
+            .rotation
+            .then(Rotor::rotation_xy(input.pitch * turn))
+            .then(Rotor::rotation_xz(input.yaw * turn))
+            .then(Rotor::rotation_yz(input.roll * turn))
+            .normalised();
+        true
+    }
+
+    /// Turns the camera by a mouse drag of `delta` points, yawing and pitching around its own axes like the arrow keys,
+    /// so any roll is kept, returns whether it turned
+    pub fn look(&mut self, delta: egui::Vec2, mouse: MouseLook) -> bool {
+        if delta == egui::Vec2::ZERO {
+            return false;
+        }
+        let (yaw, pitch) = mouse.angles(delta);
+        self.rotation = self
+            .rotation
+            .then(Rotor::rotation_xy(pitch))
+            .then(Rotor::rotation_xz(yaw))
+            .normalised();
+        true
+    }
+
     pub fn update(&mut self, i: &egui::InputState, ts: f32) -> bool {
         let mut changed = false;
 
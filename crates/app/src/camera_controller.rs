@@ -0,0 +1,261 @@
+use crate::{Camera, MouseLook, Plane, PlaneId, Units, find_plane, ui_plane_id};
+use eframe::egui;
+use math::{Rotor, Vector3};
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+/// How quickly the camera catches up to where it should be behind a followed plane, per second
+const FOLLOW_STIFFNESS: f32 = 8.0;
+
+/// How the camera is moved every simulation step
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CameraController {
+    /// Moved with WASD/QE and turned with the arrow keys or by dragging the viewport with the right mouse button
+    #[default]
+    FreeFly,
+    /// Circles around a plane, the arrow keys and right dragging orbit and scrolling over the viewport zooms,
+    /// the yaw goes around the scene's up
+    Orbit {
+        plane: Option<PlaneId>,
+        distance: f32,
+        yaw: f32,
+        pitch: f32,
+    },
+    /// Stays behind a plane looking the way the plane faces, so it moves along with it
+    Follow {
+        plane: Option<PlaneId>,
+        distance: f32,
+        height: f32,
+    },
+}
+
+impl CameraController {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraController::FreeFly => "Free Fly",
+            CameraController::Orbit { .. } => "Orbit",
+            CameraController::Follow { .. } => "Follow",
+        }
+    }
+
+    /// Whether the camera moves by itself and can collide with and teleport through planes
+    pub fn is_free_fly(&self) -> bool {
+        matches!(self, CameraController::FreeFly)
+    }
+
+    /// `from_y_up` turns y up into the scene's up, returns whether the camera changed
+    pub fn update(
+        &mut self,
+        camera: &mut Camera,
+        planes: &[Plane],
+        from_y_up: Rotor,
+        i: &egui::InputState,
+        ts: f32,
+    ) -> bool {
+        match self {
+            CameraController::FreeFly => camera.update(i, ts),
+            CameraController::Orbit {
+                plane,
+                distance,
+                yaw,
+                pitch,
+            } => {
+                let Some(plane) = plane.and_then(|id| find_plane(planes, id)) else {
+                    return false;
+                };
+                let plane = &planes[plane];
+
+                let up = i.key_down(egui::Key::ArrowUp) as u8 as f32;
+                let down = i.key_down(egui::Key::ArrowDown) as u8 as f32;
+                let left = i.key_down(egui::Key::ArrowLeft) as u8 as f32;
+                let right = i.key_down(egui::Key::ArrowRight) as u8 as f32;
+                *yaw += (right - left) * camera.rotation_speed * TAU * ts;
+                // looking straight up or down would flip the camera over
+                *pitch = (*pitch + (up - down) * camera.rotation_speed * TAU * ts)
+                    .clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+
+                let rotation =
+                    from_y_up.then(Rotor::rotation_xz(*yaw).then(Rotor::rotation_xy(*pitch)));
+                let position = plane.position - rotation.rotate(Vector3::FORWARD) * *distance;
+                set_pose(camera, position, rotation)
+            }
+            CameraController::Follow {
+                plane,
+                distance,
+                height,
+            } => {
+                let Some(plane) = plane.and_then(|id| find_plane(planes, id)) else {
+                    return false;
+                };
+                let plane = &planes[plane];
+
+                let rotation = plane.transform().rotor_part();
+                let target_position = plane.position
+                    - rotation.rotate(Vector3::FORWARD) * *distance
+                    + rotation.rotate(Vector3::UP) * *height;
+
+                // eases towards the pose behind the plane so sudden movements of the plane are smoothed out
+                let t = 1.0 - (-FOLLOW_STIFFNESS * ts).exp();
+                let position = camera.position + (target_position - camera.position) * t;
+                let rotation = nlerp(camera.rotation, rotation, t);
+                set_pose(camera, position, rotation)
+            }
+        }
+    }
+
+    /// Turns a free flying camera or orbits an orbiting one by a right mouse drag of `delta` points,
+    /// returns whether it changed
+    pub fn look(&mut self, camera: &mut Camera, delta: egui::Vec2, mouse: MouseLook) -> bool {
+        match self {
+            CameraController::FreeFly => camera.look(delta, mouse),
+            CameraController::Orbit { yaw, pitch, .. } if delta != egui::Vec2::ZERO => {
+                let (yaw_delta, pitch_delta) = mouse.angles(delta);
+                *yaw += yaw_delta;
+                *pitch = (*pitch + pitch_delta).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Zooms an orbiting camera in or out by `scroll` points, returns whether it changed
+    pub fn zoom(&mut self, scroll: f32) -> bool {
+        match self {
+            CameraController::Orbit { distance, .. } if scroll != 0.0 => {
+                *distance = (*distance * (-scroll * 0.002).exp()).max(0.01);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        camera: &Camera,
+        planes: &[Plane],
+        from_y_up: Rotor,
+        units: Units,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Camera Mode:");
+            egui::ComboBox::new("Camera Mode", "")
+                .selected_text(self.name())
+                .show_ui(ui, |ui| {
+                    let free_fly = CameraController::FreeFly;
+                    ui.selectable_value(self, free_fly, free_fly.name());
+                    // starts from where the camera already is, so switching doesn't jump
+                    let orbit = CameraController::orbiting(camera, planes, from_y_up);
+                    if ui
+                        .selectable_label(
+                            matches!(self, CameraController::Orbit { .. }),
+                            orbit.name(),
+                        )
+                        .clicked()
+                        && !matches!(self, CameraController::Orbit { .. })
+                    {
+                        *self = orbit;
+                    }
+                    let follow = CameraController::Follow {
+                        plane: planes.first().map(|plane| plane.id),
+                        distance: 3.0,
+                        height: 1.0,
+                    };
+                    if ui
+                        .selectable_label(
+                            matches!(self, CameraController::Follow { .. }),
+                            follow.name(),
+                        )
+                        .clicked()
+                        && !matches!(self, CameraController::Follow { .. })
+                    {
+                        *self = follow;
+                    }
+                });
+        });
+
+        match self {
+            CameraController::FreeFly => {}
+            CameraController::Orbit {
+                plane, distance, ..
+            } => {
+                ui_plane_id(ui, "Orbit Plane:", plane, planes);
+                ui.horizontal(|ui| {
+                    ui.label("Orbit Distance:");
+                    ui.add(
+                        egui::DragValue::new(distance)
+                            .range(0.01..=f32::INFINITY)
+                            .speed(0.1)
+                            .suffix(units.suffix()),
+                    );
+                });
+            }
+            CameraController::Follow {
+                plane,
+                distance,
+                height,
+            } => {
+                ui_plane_id(ui, "Follow Plane:", plane, planes);
+                ui.horizontal(|ui| {
+                    ui.label("Follow Distance:");
+                    ui.add(
+                        egui::DragValue::new(distance)
+                            .speed(0.1)
+                            .suffix(units.suffix()),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Follow Height:");
+                    ui.add(
+                        egui::DragValue::new(height)
+                            .speed(0.1)
+                            .suffix(units.suffix()),
+                    );
+                });
+            }
+        }
+    }
+
+    /// Orbits the plane closest to the camera, keeping the camera's direction
+    fn orbiting(camera: &Camera, planes: &[Plane], from_y_up: Rotor) -> Self {
+        let closest = planes
+            .iter()
+            .map(|plane| (plane.id, (plane.position - camera.position).magnitude()))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        let (plane, distance) = closest.map_or((None, 5.0), |(id, distance)| (Some(id), distance));
+
+        let forward = from_y_up
+            .reverse()
+            .rotate(camera.rotation.rotate(Vector3::FORWARD));
+        CameraController::Orbit {
+            plane,
+            distance: distance.max(0.01),
+            yaw: forward.z.atan2(forward.x),
+            pitch: forward.y.clamp(-1.0, 1.0).asin(),
+        }
+    }
+}
+
+/// Moves the camera, returns whether it moved
+fn set_pose(camera: &mut Camera, position: Vector3, rotation: Rotor) -> bool {
+    let moved = (position - camera.position).magnitude() > 1e-6
+        || (rotation.rotate(Vector3::FORWARD) - camera.rotation.rotate(Vector3::FORWARD))
+            .magnitude()
+            > 1e-6
+        || (rotation.rotate(Vector3::UP) - camera.rotation.rotate(Vector3::UP)).magnitude() > 1e-6;
+    camera.position = position;
+    camera.rotation = rotation;
+    moved
+}
+
+/// Interpolates between two rotations along the shorter way around
+fn nlerp(from: Rotor, to: Rotor, t: f32) -> Rotor {
+    let dot = from.s * to.s + from.e12 * to.e12 + from.e13 * to.e13 + from.e23 * to.e23;
+    let sign = if dot < 0.0 { -1.0 } else { 1.0 };
+    Rotor {
+        s: from.s + (to.s * sign - from.s) * t,
+        e12: from.e12 + (to.e12 * sign - from.e12) * t,
+        e13: from.e13 + (to.e13 * sign - from.e13) * t,
+        e23: from.e23 + (to.e23 * sign - from.e23) * t,
+    }
+    .normalised()
+}
@@ -0,0 +1,98 @@
+use std::{ffi::OsString, path::PathBuf};
+
+pub const USAGE: &str = "usage: portals [file.scene]
+       portals lint <file.scene>...
+       portals benchmark <file.scene> [options]";
+
+/// What the command line asks for, without the program name
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Opens the window, with the scene at `scene_path` instead of the last session's scene if there is one,
+    /// which is also how opening a scene file with the app from a file manager starts it
+    Open {
+        scene_path: Option<PathBuf>,
+    },
+    /// The scene files after `lint`
+    Lint(Vec<PathBuf>),
+    /// The arguments after `benchmark`
+    Benchmark(Vec<OsString>),
+    Help,
+}
+
+/// Takes the arguments as [`OsString`]s so paths that aren't valid unicode can still be opened
+pub fn parse_command(args: &[OsString]) -> Result<Command, String> {
+    match args {
+        [] => Ok(Command::Open { scene_path: None }),
+        [command, rest @ ..] if command == "lint" => {
+            Ok(Command::Lint(rest.iter().map(PathBuf::from).collect()))
+        }
+        [command, rest @ ..] if command == "benchmark" => Ok(Command::Benchmark(rest.to_vec())),
+        [help] if help == "--help" || help == "-h" => Ok(Command::Help),
+        [option] if option.as_encoded_bytes().starts_with(b"-") => {
+            Err(format!("unknown option {}\n{USAGE}", option.display()))
+        }
+        [scene_path] => Ok(Command::Open {
+            scene_path: Some(scene_path.into()),
+        }),
+        [_, extra, ..] => Err(format!("unexpected argument {}\n{USAGE}", extra.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands() {
+        let cases: &[(&[&str], Result<Command, &str>)] = &[
+            (&[], Ok(Command::Open { scene_path: None })),
+            (
+                &["level.scene"],
+                Ok(Command::Open {
+                    scene_path: Some("level.scene".into()),
+                }),
+            ),
+            (&["--help"], Ok(Command::Help)),
+            (&["-h"], Ok(Command::Help)),
+            (&["--fullscreen"], Err("unknown option --fullscreen")),
+            (&["a.scene", "b.scene"], Err("unexpected argument b.scene")),
+            (&["lint"], Ok(Command::Lint(vec![]))),
+            (
+                &["lint", "a.scene", "b.scene"],
+                Ok(Command::Lint(vec!["a.scene".into(), "b.scene".into()])),
+            ),
+            (
+                &["benchmark", "a.scene", "--seconds", "5"],
+                Ok(Command::Benchmark(vec![
+                    "a.scene".into(),
+                    "--seconds".into(),
+                    "5".into(),
+                ])),
+            ),
+        ];
+        for (args, expected) in cases {
+            let args = args.iter().map(OsString::from).collect::<Vec<_>>();
+            match (parse_command(&args), expected) {
+                (Ok(command), Ok(expected)) => assert_eq!(&command, expected, "{args:?}"),
+                (Err(error), Err(expected)) => {
+                    assert!(error.starts_with(expected), "{args:?}: {error}")
+                }
+                (result, expected) => panic!("{args:?}: got {result:?}, expected {expected:?}"),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_unicode_path() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let path = OsString::from_vec(b"level\xff.scene".to_vec());
+        assert_eq!(
+            parse_command(std::slice::from_ref(&path)),
+            Ok(Command::Open {
+                scene_path: Some(path.into()),
+            })
+        );
+    }
+}
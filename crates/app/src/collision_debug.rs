@@ -0,0 +1,88 @@
+use crate::Camera;
+use eframe::egui;
+use math::Vector3;
+use std::collections::VecDeque;
+
+const MAX_MOVEMENTS: usize = 128;
+
+#[derive(Debug, Clone)]
+pub struct CameraMovement {
+    pub from: Vector3,
+    pub to: Vector3,
+    /// Every collidable plane hit along the movement, and whether the camera teleported through it
+    pub hits: Vec<(Vector3, bool)>,
+}
+
+#[derive(Debug, Default)]
+pub struct CollisionDebug {
+    pub enabled: bool,
+    movements: VecDeque<CameraMovement>,
+}
+
+impl CollisionDebug {
+    pub fn record(&mut self, movement: CameraMovement) {
+        if !self.enabled {
+            return;
+        }
+        if self.movements.len() >= MAX_MOVEMENTS {
+            self.movements.pop_front();
+        }
+        self.movements.push_back(movement);
+    }
+
+    pub fn clear(&mut self) {
+        self.movements.clear();
+    }
+
+    /// `fov` is the vertical field of view of the viewport
+    pub fn draw(&self, painter: &egui::Painter, rect: egui::Rect, camera: &Camera, fov: f32) {
+        if !self.enabled {
+            return;
+        }
+
+        let scale = rect.height() * 0.5 / (fov * 0.5).tan();
+        let to_local = |point: Vector3| camera.rotation.reverse().rotate(point - camera.position);
+        let project = |local: Vector3| {
+            egui::pos2(
+                rect.center().x + local.z / local.x * scale,
+                rect.center().y - local.y / local.x * scale,
+            )
+        };
+
+        const NEAR: f32 = 0.001;
+        let last_index = self.movements.len().saturating_sub(1);
+        for (index, movement) in self.movements.iter().enumerate() {
+            let color = if index == last_index {
+                egui::Color32::WHITE
+            } else {
+                egui::Color32::from_white_alpha(64)
+            };
+
+            let mut from = to_local(movement.from);
+            let mut to = to_local(movement.to);
+            if from.x < NEAR && to.x < NEAR {
+                continue;
+            }
+            // clip the segment against the near plane so it doesn't wrap around behind the camera
+            if from.x < NEAR {
+                from = to + (from - to) * ((to.x - NEAR) / (to.x - from.x));
+            } else if to.x < NEAR {
+                to = from + (to - from) * ((from.x - NEAR) / (from.x - to.x));
+            }
+            painter.line_segment([project(from), project(to)], egui::Stroke::new(2.0, color));
+
+            for &(position, teleported) in &movement.hits {
+                let local = to_local(position);
+                if local.x < NEAR {
+                    continue;
+                }
+                let color = if teleported {
+                    egui::Color32::RED
+                } else {
+                    egui::Color32::YELLOW
+                };
+                painter.circle_filled(project(local), 4.0, color);
+            }
+        }
+    }
+}
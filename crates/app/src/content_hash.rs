@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+/// A 64 bit FNV-1a hash of some content, the same for the same bytes on every platform and in every version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentHash(u64);
+
+impl ContentHash {
+    const OFFSET_BASIS: u64 = 0xCBF29CE484222325;
+    const PRIME: u64 = 0x100000001B3;
+
+    pub fn of(bytes: &[u8]) -> Self {
+        Self(bytes.iter().fold(Self::OFFSET_BASIS, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(Self::PRIME)
+        }))
+    }
+
+    /// Hashes the json `value` is saved as, so the hash of a saved scene can be checked against the file
+    pub fn of_json(value: &impl Serialize) -> Self {
+        Self::of(&serde_json::to_vec(value).unwrap())
+    }
+}
+
+impl std::fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{Plane, PlaneMaterial, Units, ui_pick_material};
+use eframe::egui;
+use math::Vector3;
+use ray_tracing::Color;
+use std::f32::consts::FRAC_PI_2;
+
+/// Generates a closed corridor or room of a floor, a ceiling and four walls along z,
+/// the walls at both ends can be connected to each other so walking out of one end comes back in the other
+#[derive(Debug, Clone)]
+pub struct CorridorGenerator {
+    /// Where the middle of the floor goes
+    pub position: Vector3,
+    /// How long the corridor is along z
+    pub length: f32,
+    /// How wide the corridor is along x
+    pub width: f32,
+    pub height: f32,
+    /// Used by the walls and the ceiling, the floor is always checkered
+    pub wall_material: PlaneMaterial,
+    pub loop_back: bool,
+}
+
+impl Default for CorridorGenerator {
+    fn default() -> Self {
+        Self {
+            position: Vector3::ZERO,
+            length: 10.0,
+            width: 2.0,
+            height: 2.5,
+            wall_material: PlaneMaterial {
+                color: Color {
+                    r: 0.9,
+                    g: 0.9,
+                    b: 0.9,
+                },
+                checker_darkness: 0.1,
+                ..PlaneMaterial::default()
+            },
+            loop_back: true,
+        }
+    }
+}
+
+impl CorridorGenerator {
+    /// New planes to add to the scene, with new ids so they can be generated more than once
+    pub fn generate(&self) -> Vec<Plane> {
+        let half_height = self.height * 0.5;
+        let floor = Plane {
+            name: "Corridor Floor".into(),
+            position: self.position,
+            width: self.width,
+            height: self.length,
+            checker_count_x: (self.width.round() as u32).max(1),
+            checker_count_z: (self.length.round() as u32).max(1),
+            color: Color {
+                r: 0.6,
+                g: 0.6,
+                b: 0.6,
+            },
+            ..Plane::default()
+        };
+        let wall = |name: &str, position: Vector3| {
+            let mut plane = Plane {
+                name: name.into(),
+                position: self.position + position,
+                ..Plane::default()
+            };
+            self.wall_material.apply(&mut plane);
+            plane
+        };
+        let ceiling = Plane {
+            width: self.width,
+            height: self.length,
+            ..wall("Corridor Ceiling", Vector3::UP * self.height)
+        };
+        // the plane's own xz is turned upright, along x by a yz rotation and along z by an xy rotation
+        let side = |name: &str, x: f32| Plane {
+            xy_rotation: FRAC_PI_2,
+            width: self.height,
+            height: self.length,
+            ..wall(
+                name,
+                Vector3 {
+                    x,
+                    y: half_height,
+                    z: 0.0,
+                },
+            )
+        };
+        let end = |name: &str, z: f32| Plane {
+            yz_rotation: FRAC_PI_2,
+            width: self.width,
+            height: self.height,
+            ..wall(
+                name,
+                Vector3 {
+                    x: 0.0,
+                    y: half_height,
+                    z,
+                },
+            )
+        };
+        let mut front_end = end("Corridor Front End", self.length * 0.5);
+        let mut back_end = end("Corridor Back End", self.length * -0.5);
+
+        if self.loop_back {
+            // both ends face the same way, so going out of either one keeps going the same way back into the corridor
+            let (front_id, back_id) = (front_end.id, back_end.id);
+            for (plane, other) in [(&mut front_end, back_id), (&mut back_end, front_id)] {
+                plane.front_portal.other = Some(other);
+                plane.back_portal.other = Some(other);
+            }
+        }
+
+        vec![
+            floor,
+            ceiling,
+            side("Corridor Left Wall", self.width * -0.5),
+            side("Corridor Right Wall", self.width * 0.5),
+            front_end,
+            back_end,
+        ]
+    }
+
+    /// Returns whether a corridor should be generated
+    pub fn ui(&mut self, ui: &mut egui::Ui, units: Units, planes: &[Plane]) -> bool {
+        ui.horizontal(|ui| {
+            ui.label("Position:");
+            ui.add(
+                egui::DragValue::new(&mut self.position.x)
+                    .prefix("x:")
+                    .speed(0.1)
+                    .suffix(units.suffix()),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.position.y)
+                    .prefix("y:")
+                    .speed(0.1)
+                    .suffix(units.suffix()),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.position.z)
+                    .prefix("z:")
+                    .speed(0.1)
+                    .suffix(units.suffix()),
+            );
+        });
+        for (label, value) in [
+            ("Length:", &mut self.length),
+            ("Width:", &mut self.width),
+            ("Height:", &mut self.height),
+        ] {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                ui.add(
+                    egui::DragValue::new(value)
+                        .speed(0.1)
+                        .range(0.1..=f32::INFINITY)
+                        .suffix(units.suffix()),
+                );
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Wall Color:");
+            ui.color_edit_button_rgb(self.wall_material.color.as_mut());
+            ui_pick_material(ui, "Wall Material From", &mut self.wall_material, planes);
+        });
+        ui.checkbox(&mut self.loop_back, "Loop Back Through Portal At End")
+            .on_hover_text("Connects the walls at both ends, so the corridor goes on forever");
+        ui.button("Generate Corridor")
+            .on_hover_text("Adds the corridor to the scene")
+            .clicked()
+    }
+}
@@ -0,0 +1,111 @@
+use eframe::egui;
+use ray_tracing::CropRect;
+
+/// A rectangle of the viewport that is the only part being traced,
+/// so a small detail can converge without waiting on the whole frame
+#[derive(Debug, Default)]
+pub struct CropRegion {
+    /// Whether dragging over the viewport draws a new region
+    pub drawing: bool,
+    /// The start and current position of the drag while drawing
+    drag: Option<(egui::Pos2, egui::Pos2)>,
+    /// Relative to the top left of the viewport
+    region: Option<egui::Rect>,
+}
+
+impl CropRegion {
+    /// Returns whether the region changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Crop Region:");
+            ui.toggle_value(&mut self.drawing, "Draw")
+                .on_hover_text("Drag over the viewport to only trace inside a rectangle");
+            if ui
+                .add_enabled(self.region.is_some(), egui::Button::new("Clear"))
+                .clicked()
+            {
+                self.region = None;
+                changed = true;
+            }
+        });
+        changed
+    }
+
+    /// Handles drawing a new region by dragging over the viewport, returns whether the region changed
+    pub fn interact(&mut self, response: &egui::Response, rect: egui::Rect) -> bool {
+        if !self.drawing {
+            self.drag = None;
+            return false;
+        }
+
+        // the right mouse button looks around instead
+        if response.drag_started_by(egui::PointerButton::Primary)
+            && let Some(position) = response.interact_pointer_pos()
+        {
+            self.drag = Some((position, position));
+        }
+        if response.dragged_by(egui::PointerButton::Primary)
+            && let Some((_, end)) = &mut self.drag
+            && let Some(position) = response.interact_pointer_pos()
+        {
+            *end = position;
+        }
+        if response.drag_stopped_by(egui::PointerButton::Primary)
+            && let Some((start, end)) = self.drag.take()
+        {
+            let region = egui::Rect::from_two_pos(start, end)
+                .intersect(rect)
+                .translate(-rect.min.to_vec2());
+            if region.width() >= 1.0 && region.height() >= 1.0 {
+                self.region = Some(region);
+                self.drawing = false;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.region.is_some()
+    }
+
+    /// The region in texture pixels of a `width` by `height` viewport, `pixel_scale` is how many pixels a point covers
+    pub fn crop_rect(&self, width: u32, height: u32, pixel_scale: f32) -> Option<CropRect> {
+        let region = self.region?;
+        let region = egui::Rect::from_min_max(
+            (region.min.to_vec2() * pixel_scale).to_pos2(),
+            (region.max.to_vec2() * pixel_scale).to_pos2(),
+        );
+        let left = (region.min.x.floor() as u32).min(width);
+        let right = (region.max.x.ceil() as u32).min(width);
+        let top = (region.min.y.floor() as u32).min(height);
+        let bottom = (region.max.y.ceil() as u32).min(height);
+        // the texture's first row is the bottom of the viewport
+        Some(CropRect {
+            x: left,
+            y: height - bottom,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+
+    pub fn draw(&self, painter: &egui::Painter, rect: egui::Rect) {
+        if let Some(region) = self.region {
+            painter.rect_stroke(
+                region.translate(rect.min.to_vec2()),
+                0.0,
+                egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                egui::StrokeKind::Outside,
+            );
+        }
+        if let Some((start, end)) = self.drag {
+            painter.rect_stroke(
+                egui::Rect::from_two_pos(start, end),
+                0.0,
+                egui::Stroke::new(1.0, egui::Color32::WHITE),
+                egui::StrokeKind::Outside,
+            );
+        }
+    }
+}
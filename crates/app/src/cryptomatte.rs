@@ -0,0 +1,124 @@
+use crate::top_first;
+use exr::prelude::{AnyChannel, AttributeValue, FlatSamples, Text};
+use ray_tracing::{Aovs, MATTE_RANKS};
+use std::{collections::BTreeMap, fmt::Write};
+
+/// The name of the cryptomatte layer the planes are put in
+const CRYPTOMATTE_LAYER: &str = "CryptoObject";
+
+/// MurmurHash3_x86_32, what cryptomatte hashes names with
+pub fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut hash = seed;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        hash ^= mix(u32::from_le_bytes(chunk.try_into().unwrap()));
+        hash = hash
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe6546b64);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .enumerate()
+            .fold(0, |k, (index, &byte)| k | (byte as u32) << (index * 8));
+        hash ^= mix(k);
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85ebca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2ae35);
+    hash ^= hash >> 16;
+    hash
+}
+
+/// The id cryptomatte gives an object called `name`, the hash of the name as the bits of a float,
+/// with the exponent nudged so it is never a nan, infinity or denormal
+pub fn cryptomatte_id(name: &str) -> f32 {
+    let hash = murmur3_32(name.as_bytes(), 0);
+    let exponent = hash >> 23 & 0xff;
+    if exponent == 0 || exponent == 0xff {
+        f32::from_bits(hash ^ 1 << 23)
+    } else {
+        f32::from_bits(hash)
+    }
+}
+
+/// The channels of a cryptomatte layer with the [`Aovs::mattes`] and the header attributes describing it,
+/// `object_names` are the names of the planes by index, planes with the same name share an id
+pub fn cryptomatte_channels(
+    aovs: &Aovs,
+    object_names: &[&str],
+) -> (Vec<AnyChannel<FlatSamples>>, Vec<(Text, AttributeValue)>) {
+    let ids = object_names
+        .iter()
+        .map(|name| cryptomatte_id(name))
+        .collect::<Vec<_>>();
+    let mattes = top_first(aovs.width, &aovs.mattes);
+
+    // every rgba channel set holds two ranks as (id, coverage) pairs
+    let mut channels = vec![];
+    for pair in 0..MATTE_RANKS / 2 {
+        for (channel, name) in ["R", "G", "B", "A"].into_iter().enumerate() {
+            let rank = pair * 2 + channel / 2;
+            let values = mattes
+                .iter()
+                .map(|samples| {
+                    let sample = samples[rank];
+                    // empty slots are NO_OBJECT, so they aren't a plane either
+                    match ids.get(sample.object_id as usize) {
+                        Some(&id) if channel % 2 == 0 => id,
+                        Some(_) => sample.coverage,
+                        None => 0.0,
+                    }
+                })
+                .collect();
+            channels.push(AnyChannel::new(
+                format!("{CRYPTOMATTE_LAYER}{pair:02}.{name}").as_str(),
+                FlatSamples::F32(values),
+            ));
+        }
+    }
+
+    let manifest = object_names
+        .iter()
+        .zip(&ids)
+        .map(|(&name, id)| (name, format!("{:08x}", id.to_bits())))
+        .collect::<BTreeMap<_, _>>();
+    // exr text can only hold latin-1, everything else is escaped, which is only ever inside the json's strings
+    let mut escaped_manifest = String::new();
+    for c in serde_json::to_string(&manifest).unwrap().chars() {
+        if c.is_ascii() {
+            escaped_manifest.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                write!(escaped_manifest, "\\u{unit:04x}").unwrap();
+            }
+        }
+    }
+
+    let key = &format!("{:08x}", murmur3_32(CRYPTOMATTE_LAYER.as_bytes(), 0))[..7];
+    let attributes = [
+        ("name", CRYPTOMATTE_LAYER.to_string()),
+        ("hash", "MurmurHash3_32".to_string()),
+        ("conversion", "uint32_to_float32".to_string()),
+        ("manifest", escaped_manifest),
+    ]
+    .into_iter()
+    .map(|(name, value)| {
+        (
+            Text::new_or_panic(format!("cryptomatte/{key}/{name}")),
+            AttributeValue::Text(Text::new_or_panic(value)),
+        )
+    })
+    .collect();
+
+    (channels, attributes)
+}
@@ -0,0 +1,81 @@
+use ray_tracing::{Aovs, NO_OBJECT};
+
+/// How many pixels away from a pixel its neighbours are still averaged with it
+const DENOISE_RADIUS: i32 = 4;
+/// How quickly neighbours stop counting as their normals turn away
+const NORMAL_SHARPNESS: i32 = 32;
+/// How different the depth of a neighbour can be relative to the pixel's before it stops counting
+const DEPTH_TOLERANCE: f32 = 0.05;
+
+/// Smooths out the noise of `pixels` with a bilateral filter guided by the [`Aovs`],
+/// neighbours only count if they hit the same plane facing the same way at about the same depth,
+/// the albedo is divided out first so the checkers stay sharp,
+/// `pixels` are premultiplied by alpha and bottom row first like the aovs
+pub fn denoise(pixels: &[[f32; 4]], aovs: &Aovs) -> Vec<[f32; 4]> {
+    let width = aovs.width as i32;
+    let height = aovs.height as i32;
+    assert_eq!(pixels.len(), (width * height) as usize);
+
+    let demodulate = |index: usize| {
+        let [r, g, b, _] = pixels[index];
+        let [albedo_r, albedo_g, albedo_b] = aovs.albedo[index];
+        [
+            r / albedo_r.max(1e-3),
+            g / albedo_g.max(1e-3),
+            b / albedo_b.max(1e-3),
+        ]
+    };
+
+    let mut denoised = pixels.to_vec();
+    for y in 0..height {
+        for x in 0..width {
+            let index = (x + y * width) as usize;
+            let object_id = aovs.object_id[index];
+            // the background has no noise to remove
+            if object_id == NO_OBJECT {
+                continue;
+            }
+            let normal = aovs.normal[index];
+            let depth = aovs.depth[index];
+
+            let mut total = [0.0; 3];
+            let mut total_weight = 0.0;
+            for neighbour_y in (y - DENOISE_RADIUS).max(0)..=(y + DENOISE_RADIUS).min(height - 1) {
+                for neighbour_x in (x - DENOISE_RADIUS).max(0)..=(x + DENOISE_RADIUS).min(width - 1)
+                {
+                    let neighbour = (neighbour_x + neighbour_y * width) as usize;
+                    if aovs.object_id[neighbour] != object_id {
+                        continue;
+                    }
+                    let [nx, ny, nz] = aovs.normal[neighbour];
+                    let facing = (normal[0] * nx + normal[1] * ny + normal[2] * nz).max(0.0);
+                    let depth_difference = (aovs.depth[neighbour] - depth).abs() / depth.max(1e-3);
+
+                    let distance_squared =
+                        ((neighbour_x - x).pow(2) + (neighbour_y - y).pow(2)) as f32;
+                    let sigma = DENOISE_RADIUS as f32 * 0.5;
+                    let weight = (-distance_squared / (2.0 * sigma * sigma)).exp()
+                        * facing.powi(NORMAL_SHARPNESS)
+                        * (-depth_difference / DEPTH_TOLERANCE).exp();
+                    if weight <= 0.0 {
+                        continue;
+                    }
+
+                    let irradiance = demodulate(neighbour);
+                    for channel in 0..3 {
+                        total[channel] += irradiance[channel] * weight;
+                    }
+                    total_weight += weight;
+                }
+            }
+
+            // the pixel itself always has a weight of 1, so this never divides by zero
+            let albedo = aovs.albedo[index];
+            for channel in 0..3 {
+                denoised[index][channel] =
+                    total[channel] / total_weight * albedo[channel].max(1e-3);
+            }
+        }
+    }
+    denoised
+}
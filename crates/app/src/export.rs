@@ -0,0 +1,366 @@
+use crate::{ContentHash, cryptomatte_channels};
+use ray_tracing::{Aovs, Color, ToneMapper, linear_to_srgb};
+
+/// Everything needed to reproduce an exported image
+#[derive(Debug, Clone)]
+pub struct ExportMetadata {
+    pub scene_name: String,
+    pub samples_per_pixel: u64,
+    pub seed: u32,
+    pub render_settings_json: String,
+    pub scene_json: String,
+}
+
+impl ExportMetadata {
+    fn add_text_chunks<W: std::io::Write>(
+        &self,
+        encoder: &mut png::Encoder<'_, W>,
+    ) -> Result<(), png::EncodingError> {
+        encoder.add_text_chunk("Software".into(), "Portals".into())?;
+        encoder.add_text_chunk(
+            "Portals Samples Per Pixel".into(),
+            self.samples_per_pixel.to_string(),
+        )?;
+        encoder.add_text_chunk("Portals Seed".into(), self.seed.to_string())?;
+        encoder.add_text_chunk("Portals Scene Hash".into(), self.scene_hash().to_string())?;
+        // these can contain any unicode so they need iTXt instead of tEXt chunks
+        encoder.add_itxt_chunk("Title".into(), self.scene_name.clone())?;
+        encoder.add_itxt_chunk(
+            "Portals Render Settings".into(),
+            self.render_settings_json.clone(),
+        )?;
+        encoder.add_itxt_chunk("Portals Scene".into(), self.scene_json.clone())?;
+        Ok(())
+    }
+
+    /// The same as the hash of the scene shown in the app when it was rendered
+    pub fn scene_hash(&self) -> ContentHash {
+        ContentHash::of(self.scene_json.as_bytes())
+    }
+
+    fn footer_text(&self) -> String {
+        format!(
+            "{}  {} spp  seed {}",
+            self.scene_name, self.samples_per_pixel, self.seed
+        )
+    }
+}
+
+/// The settings an image was rendered with, written in a strip below exported pngs
+/// so grids of images comparing settings say what they compare
+#[derive(Debug, Clone, Copy)]
+pub struct SettingsCaption {
+    pub samples_per_pixel: u64,
+    pub max_bounces: u32,
+    pub denoised: bool,
+    pub tone_mapper: ToneMapper,
+}
+
+impl SettingsCaption {
+    fn text(&self) -> String {
+        format!(
+            "{} spp  {} bounces  denoiser {}  tone mapper {}",
+            self.samples_per_pixel,
+            self.max_bounces,
+            if self.denoised { "on" } else { "off" },
+            self.tone_mapper.name(),
+        )
+    }
+}
+
+/// Composites premultiplied `pixels` over `background`, so exported images look like the viewport shows them
+pub fn flatten_background(pixels: &[[f32; 4]], background: Color) -> Vec<[f32; 4]> {
+    pixels
+        .iter()
+        .map(|&[r, g, b, a]| {
+            let uncovered = 1.0 - a.clamp(0.0, 1.0);
+            [
+                r + background.r * uncovered,
+                g + background.g * uncovered,
+                b + background.b * uncovered,
+                1.0,
+            ]
+        })
+        .collect()
+}
+
+/// Encodes the accumulated image as an 8 bit sRGB png with straight alpha, tone mapped like it is displayed,
+/// `pixels` are linear, premultiplied by alpha and bottom row first like the ray tracing texture
+pub fn encode_png(
+    width: u32,
+    height: u32,
+    pixels: &[[f32; 4]],
+    tone_mapper: ToneMapper,
+    metadata: Option<&ExportMetadata>,
+    footer: bool,
+    caption: Option<SettingsCaption>,
+) -> Result<Vec<u8>, png::EncodingError> {
+    let mut data = top_first(width, pixels)
+        .into_iter()
+        .flat_map(|[r, g, b, a]| {
+            let unpremultiply = if a > 0.0 { a.recip() } else { 0.0 };
+            let to_byte = |value: f32| (value * 255.0).round() as u8;
+            let [r, g, b] =
+                tone_mapper.apply([r * unpremultiply, g * unpremultiply, b * unpremultiply]);
+            [
+                to_byte(linear_to_srgb(r)),
+                to_byte(linear_to_srgb(g)),
+                to_byte(linear_to_srgb(b)),
+                to_byte(a.clamp(0.0, 1.0)),
+            ]
+        })
+        .collect::<Vec<u8>>();
+
+    let mut height = height;
+    if let Some(caption) = caption {
+        height += draw_footer(&mut data, width, &caption.text());
+    }
+    if footer && let Some(metadata) = metadata {
+        height += draw_footer(&mut data, width, &metadata.footer_text());
+    }
+
+    let mut bytes = vec![];
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        if let Some(metadata) = metadata {
+            metadata.add_text_chunks(&mut encoder)?;
+        }
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+    }
+    Ok(bytes)
+}
+
+/// Encodes the accumulated image as a linear 32 bit float exr with premultiplied alpha, which isn't tone mapped,
+/// the [`Aovs`] and the denoised image are stored as extra `layer.channel` channels in the same part,
+/// along with cryptomatte mattes of the planes named `object_names`,
+/// `pixels` and everything in `aovs` are bottom row first like the ray tracing texture
+pub fn encode_exr(
+    width: u32,
+    height: u32,
+    pixels: &[[f32; 4]],
+    aovs: Option<&Aovs>,
+    object_names: &[&str],
+    denoised: Option<&[[f32; 4]]>,
+    metadata: Option<&ExportMetadata>,
+) -> Result<Vec<u8>, exr::error::Error> {
+    use exr::prelude::*;
+
+    fn vector_channels<const N: usize>(
+        layer: &str,
+        names: [&str; N],
+        width: u32,
+        values: &[[f32; N]],
+    ) -> impl Iterator<Item = AnyChannel<FlatSamples>> {
+        let values = top_first(width, values);
+        names.into_iter().enumerate().map(move |(index, name)| {
+            AnyChannel::new(
+                format!("{layer}{name}").as_str(),
+                FlatSamples::F32(values.iter().map(|value| value[index]).collect()),
+            )
+        })
+    }
+
+    let mut channels = vector_channels("", ["R", "G", "B", "A"], width, pixels).collect::<Vec<_>>();
+    if let Some(aovs) = aovs {
+        channels.extend(vector_channels(
+            "albedo.",
+            ["R", "G", "B"],
+            width,
+            &aovs.albedo,
+        ));
+        channels.extend(vector_channels(
+            "normal.",
+            ["X", "Y", "Z"],
+            width,
+            &aovs.normal,
+        ));
+        channels.push(AnyChannel::new(
+            "depth.Z",
+            FlatSamples::F32(top_first(width, &aovs.depth)),
+        ));
+        channels.push(AnyChannel::new(
+            "object_id.id",
+            FlatSamples::U32(top_first(width, &aovs.object_id)),
+        ));
+        channels.push(AnyChannel::new(
+            "portal_count.count",
+            FlatSamples::U32(top_first(width, &aovs.portal_count)),
+        ));
+    }
+    let mut cryptomatte_attributes = vec![];
+    if let Some(aovs) = aovs {
+        let (matte_channels, attributes) = cryptomatte_channels(aovs, object_names);
+        channels.extend(matte_channels);
+        cryptomatte_attributes = attributes;
+    }
+    if let Some(denoised) = denoised {
+        channels.extend(vector_channels(
+            "denoised.",
+            ["R", "G", "B", "A"],
+            width,
+            denoised,
+        ));
+    }
+
+    let mut attributes = LayerAttributes {
+        software_name: Text::new_or_none("Portals"),
+        ..Default::default()
+    };
+    attributes.other.extend(cryptomatte_attributes);
+    if let Some(metadata) = metadata {
+        // exr text can only hold latin-1, anything else is left out
+        attributes.layer_name = Text::new_or_none(&metadata.scene_name);
+        attributes.comments = Text::new_or_none(metadata.footer_text());
+        for (name, value) in [
+            (
+                "Portals Samples Per Pixel",
+                metadata.samples_per_pixel.to_string(),
+            ),
+            ("Portals Seed", metadata.seed.to_string()),
+            ("Portals Scene Hash", metadata.scene_hash().to_string()),
+            (
+                "Portals Render Settings",
+                metadata.render_settings_json.clone(),
+            ),
+            ("Portals Scene", metadata.scene_json.clone()),
+        ] {
+            if let Some(value) = Text::new_or_none(value) {
+                attributes
+                    .other
+                    .insert(Text::new_or_panic(name), AttributeValue::Text(value));
+            }
+        }
+    }
+
+    let layer = Layer::new(
+        (width as usize, height as usize),
+        attributes,
+        Encoding::FAST_LOSSLESS,
+        AnyChannels::sort(channels.into()),
+    );
+    let mut bytes = std::io::Cursor::new(vec![]);
+    Image::from_layer(layer).write().to_buffered(&mut bytes)?;
+    Ok(bytes.into_inner())
+}
+
+/// Flips an image between the bottom row first order of the ray tracing texture
+/// and the top row first order of image files
+pub fn top_first<T: Copy>(width: u32, values: &[T]) -> Vec<T> {
+    values
+        .chunks_exact(width as usize)
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+const FOOTER_SCALE: u32 = 2;
+const FOOTER_PADDING: u32 = 4;
+const FOOTER_BACKGROUND: [u8; 4] = [24, 24, 24, 255];
+const FOOTER_FOREGROUND: [u8; 4] = [160, 160, 160, 255];
+
+/// Appends a strip below the image with `text` written in it, returning the height of the strip
+fn draw_footer(data: &mut Vec<u8>, width: u32, text: &str) -> u32 {
+    let glyph_width = 3 * FOOTER_SCALE;
+    let glyph_height = 5 * FOOTER_SCALE;
+    let footer_height = glyph_height + FOOTER_PADDING * 2;
+
+    let start = data.len();
+    data.extend(std::iter::repeat_n(FOOTER_BACKGROUND, (width * footer_height) as usize).flatten());
+    let footer = &mut data[start..];
+
+    for (index, c) in text.chars().enumerate() {
+        let left = FOOTER_PADDING + index as u32 * (glyph_width + FOOTER_SCALE);
+        if left + glyph_width > width {
+            break;
+        }
+        let rows = glyph(c);
+        for y in 0..glyph_height {
+            for x in 0..glyph_width {
+                let row = rows[(y / FOOTER_SCALE) as usize];
+                if row & (0b100 >> (x / FOOTER_SCALE)) == 0 {
+                    continue;
+                }
+                let pixel = ((FOOTER_PADDING + y) * width + left + x) as usize * 4;
+                footer[pixel..pixel + 4].copy_from_slice(&FOOTER_FOREGROUND);
+            }
+        }
+    }
+
+    footer_height
+}
+
+/// A tiny 3x5 font, each row is 3 bits with the most significant bit on the left
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_lowercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'a' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'b' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'c' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'd' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'e' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'f' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'g' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'h' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'i' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'j' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'k' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'l' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'm' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'n' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'o' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'p' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'r' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        's' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        't' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'u' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'v' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'w' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'x' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        _ => [0b000; 5],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The texture stores the bottom row first but png files start at the top row
+    #[test]
+    fn png_top_row_first_with_alpha() {
+        let bottom = [1.0, 0.0, 0.0, 1.0];
+        let top = [0.0, 0.0, 0.5, 0.5];
+        let png = encode_png(1, 2, &[bottom, top], ToneMapper::None, None, false, None).unwrap();
+
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png))
+            .read_info()
+            .unwrap();
+        let mut data = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut data).unwrap();
+        // the alpha is unpremultiplied, so the top pixel is fully blue
+        assert_eq!(data, [0, 0, 255, 128, 255, 0, 0, 255]);
+    }
+}
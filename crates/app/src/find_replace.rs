@@ -0,0 +1,344 @@
+use crate::{EmissionProfile, EmissiveSides, Plane, PlaneField, PlaneUndo};
+use eframe::egui;
+use ray_tracing::{Color, LightGroup};
+
+/// What of a plane gets compared and replaced
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ReplaceTarget {
+    #[default]
+    Color,
+    EmissiveColor,
+    /// Everything about how the plane looks, not where it is or what it is connected to
+    Material,
+}
+
+impl ReplaceTarget {
+    pub const ALL: [Self; 3] = [Self::Color, Self::EmissiveColor, Self::Material];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReplaceTarget::Color => "Color",
+            ReplaceTarget::EmissiveColor => "Emissive Color",
+            ReplaceTarget::Material => "Material",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaneMaterial {
+    pub color: Color,
+    pub checker_darkness: f32,
+    pub emissive_color: Color,
+    pub emission_intensity: f32,
+    pub emissive_checker_darkness: f32,
+    pub emissive_sides: EmissiveSides,
+    pub emission_profile: EmissionProfile,
+    pub transmission: f32,
+    pub ior: f32,
+    pub dispersion: f32,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub specular: f32,
+    pub light_group: LightGroup,
+}
+
+impl PlaneMaterial {
+    pub fn of(plane: &Plane) -> Self {
+        Self {
+            color: plane.color,
+            checker_darkness: plane.checker_darkness,
+            emissive_color: plane.emissive_color,
+            emission_intensity: plane.emission_intensity,
+            emissive_checker_darkness: plane.emissive_checker_darkness,
+            emissive_sides: plane.emissive_sides,
+            emission_profile: plane.emission_profile,
+            transmission: plane.transmission,
+            ior: plane.ior,
+            dispersion: plane.dispersion,
+            metallic: plane.metallic,
+            roughness: plane.roughness,
+            specular: plane.specular,
+            light_group: plane.light_group,
+        }
+    }
+
+    pub fn apply(&self, plane: &mut Plane) {
+        plane.color = self.color;
+        plane.checker_darkness = self.checker_darkness;
+        plane.emissive_color = self.emissive_color;
+        plane.emission_intensity = self.emission_intensity;
+        plane.emissive_checker_darkness = self.emissive_checker_darkness;
+        plane.emissive_sides = self.emissive_sides;
+        plane.emission_profile = self.emission_profile;
+        plane.transmission = self.transmission;
+        plane.ior = self.ior;
+        plane.dispersion = self.dispersion;
+        plane.metallic = self.metallic;
+        plane.roughness = self.roughness;
+        plane.specular = self.specular;
+        plane.light_group = self.light_group;
+    }
+
+    /// Whether `target` of both materials differs by at most `tolerance` in every value
+    fn matches(&self, other: &Self, target: ReplaceTarget, tolerance: f32) -> bool {
+        let close = |a: f32, b: f32| (a - b).abs() <= tolerance;
+        let close_colors =
+            |a: Color, b: Color| close(a.r, b.r) && close(a.g, b.g) && close(a.b, b.b);
+        match target {
+            ReplaceTarget::Color => close_colors(self.color, other.color),
+            ReplaceTarget::EmissiveColor => close_colors(self.emissive_color, other.emissive_color),
+            ReplaceTarget::Material => {
+                close_colors(self.color, other.color)
+                    && close(self.checker_darkness, other.checker_darkness)
+                    && close_colors(self.emissive_color, other.emissive_color)
+                    && close(self.emission_intensity, other.emission_intensity)
+                    && close(
+                        self.emissive_checker_darkness,
+                        other.emissive_checker_darkness,
+                    )
+                    && self.emissive_sides == other.emissive_sides
+                    && close(
+                        self.emission_profile.exponent(),
+                        other.emission_profile.exponent(),
+                    )
+                    && close(self.transmission, other.transmission)
+                    && close(self.ior, other.ior)
+                    && close(self.dispersion, other.dispersion)
+                    && close(self.metallic, other.metallic)
+                    && close(self.roughness, other.roughness)
+                    && close(self.specular, other.specular)
+                    && self.light_group == other.light_group
+            }
+        }
+    }
+
+    /// Replaces `target` of `plane` with this material's
+    fn replace(&self, plane: &mut Plane, target: ReplaceTarget) {
+        match target {
+            ReplaceTarget::Color => plane.color = self.color,
+            ReplaceTarget::EmissiveColor => plane.emissive_color = self.emissive_color,
+            ReplaceTarget::Material => self.apply(plane),
+        }
+    }
+}
+
+/// Only what the target replaces is read from the plane, the rest of the material is left at its default,
+/// so undoing a replacement doesn't count later edits to the rest of the material
+impl PlaneField for ReplaceTarget {
+    type Value = PlaneMaterial;
+
+    fn get(self, plane: &Plane) -> PlaneMaterial {
+        match self {
+            ReplaceTarget::Color => PlaneMaterial {
+                color: plane.color,
+                ..PlaneMaterial::default()
+            },
+            ReplaceTarget::EmissiveColor => PlaneMaterial {
+                emissive_color: plane.emissive_color,
+                ..PlaneMaterial::default()
+            },
+            ReplaceTarget::Material => PlaneMaterial::of(plane),
+        }
+    }
+
+    fn set(self, plane: &mut Plane, material: PlaneMaterial) {
+        material.replace(plane, self);
+    }
+}
+
+impl Default for PlaneMaterial {
+    fn default() -> Self {
+        Self::of(&Plane::default())
+    }
+}
+
+/// Finds every plane with the same color or material and replaces it on all of them at once,
+/// the last replacement can be undone with [`PlaneUndo`]
+#[derive(Debug, Default)]
+pub struct FindReplace {
+    pub target: ReplaceTarget,
+    pub find: PlaneMaterial,
+    pub replace: PlaneMaterial,
+    pub tolerance: f32,
+    undo: PlaneUndo<ReplaceTarget>,
+}
+
+impl FindReplace {
+    /// The indices of the planes matching what is being found
+    pub fn matches(&self, planes: &[Plane]) -> Vec<usize> {
+        planes
+            .iter()
+            .enumerate()
+            .filter(|(_, plane)| {
+                PlaneMaterial::of(plane).matches(&self.find, self.target, self.tolerance)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns whether any planes changed
+    pub fn ui(&mut self, ui: &mut egui::Ui, planes: &mut [Plane]) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Find:");
+            egui::ComboBox::new("Find Target", "")
+                .selected_text(self.target.name())
+                .show_ui(ui, |ui| {
+                    for target in ReplaceTarget::ALL {
+                        ui.selectable_value(&mut self.target, target, target.name());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Tolerance:");
+            ui.add(
+                egui::DragValue::new(&mut self.tolerance)
+                    .speed(0.001)
+                    .range(0.0..=1.0),
+            );
+        });
+
+        egui::CollapsingHeader::new("Find")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui_pick_material(ui, "Find From", &mut self.find, planes);
+                ui_material(ui, &mut self.find, self.target);
+            });
+        egui::CollapsingHeader::new("Replace With")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui_pick_material(ui, "Replace From", &mut self.replace, planes);
+                ui_material(ui, &mut self.replace, self.target);
+            });
+
+        let matches = self.matches(planes);
+        ui.label(format!("Matching Planes: {}", matches.len()))
+            .on_hover_ui(|ui| {
+                for &index in &matches {
+                    ui.label(&planes[index].name);
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!matches.is_empty(), egui::Button::new("Replace All"))
+                .clicked()
+            {
+                self.undo.apply(
+                    planes,
+                    self.target,
+                    matches.iter().map(|&index| (index, self.replace)),
+                );
+                changed = true;
+            }
+            changed |= self.undo.ui(ui, "Undo Replace", planes);
+        });
+        changed
+    }
+}
+
+/// Copies the material of a plane into `material`
+pub fn ui_pick_material(
+    ui: &mut egui::Ui,
+    label: &str,
+    material: &mut PlaneMaterial,
+    planes: &[Plane],
+) {
+    egui::ComboBox::new(label, "")
+        .selected_text("Pick From Plane")
+        .show_ui(ui, |ui| {
+            for plane in planes {
+                if ui.selectable_label(false, &plane.name).clicked() {
+                    *material = PlaneMaterial::of(plane);
+                }
+            }
+        });
+}
+
+/// Edits the parts of `material` that `target` compares
+fn ui_material(ui: &mut egui::Ui, material: &mut PlaneMaterial, target: ReplaceTarget) {
+    if matches!(target, ReplaceTarget::Color | ReplaceTarget::Material) {
+        ui.horizontal(|ui| {
+            ui.label("Color:");
+            ui.color_edit_button_rgb(material.color.as_mut());
+        });
+    }
+    if matches!(
+        target,
+        ReplaceTarget::EmissiveColor | ReplaceTarget::Material
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Emissive Color:");
+            ui.color_edit_button_rgb(material.emissive_color.as_mut());
+        });
+    }
+    if target == ReplaceTarget::Material {
+        ui.horizontal(|ui| {
+            ui.label("Checker Darkness:");
+            ui.add(egui::Slider::new(&mut material.checker_darkness, 0.0..=1.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Emission Intensity:");
+            ui.add(egui::DragValue::new(&mut material.emission_intensity).speed(0.1));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Emissive Checker Darkness:");
+            ui.add(egui::Slider::new(
+                &mut material.emissive_checker_darkness,
+                0.0..=1.0,
+            ));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Emissive Sides:");
+            material.emissive_sides.ui(ui);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Emission Profile:");
+            ui.vertical(|ui| {
+                material.emission_profile.ui(ui);
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Metallic:");
+            ui.add(egui::Slider::new(&mut material.metallic, 0.0..=1.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Roughness:");
+            ui.add(egui::Slider::new(&mut material.roughness, 0.0..=1.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Specular:");
+            ui.add(egui::Slider::new(&mut material.specular, 0.0..=1.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Transmission:");
+            ui.add(egui::Slider::new(&mut material.transmission, 0.0..=1.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("IOR:");
+            ui.add(
+                egui::DragValue::new(&mut material.ior)
+                    .speed(0.01)
+                    .range(1.0..=4.0),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Dispersion:");
+            ui.add(
+                egui::DragValue::new(&mut material.dispersion)
+                    .speed(0.001)
+                    .range(0.0..=0.2)
+                    .suffix("µm²"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Light Group:");
+            egui::ComboBox::new(ui.id().with("Light Group"), "")
+                .selected_text(material.light_group.name())
+                .show_ui(ui, |ui| {
+                    for group in LightGroup::ALL {
+                        ui.selectable_value(&mut material.light_group, group, group.name());
+                    }
+                });
+        });
+    }
+}
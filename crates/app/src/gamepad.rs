@@ -0,0 +1,202 @@
+use crate::{Rumble, Traversal, TraversalListener};
+use eframe::egui;
+use gilrs::{
+    Axis, Button, GamepadId, Gilrs,
+    ff::{BaseEffect, BaseEffectType, EffectBuilder, Repeat, Replay, Ticks},
+};
+use math::Vector3;
+
+/// What the sticks and triggers of the gamepad ask the camera to do, after the dead zone is taken out
+#[derive(Debug, Clone, Copy)]
+pub struct GamepadInput {
+    /// Relative to the camera, from the left stick and the triggers, each axis from -1 to 1
+    pub movement: Vector3,
+    /// From the right stick, from -1 to 1
+    pub yaw: f32,
+    pub pitch: f32,
+    /// From the bumpers, from -1 to 1
+    pub roll: f32,
+    /// Pressing in the left stick flies faster, like holding shift
+    pub boost: bool,
+}
+
+impl GamepadInput {
+    pub const NONE: Self = Self {
+        movement: Vector3::ZERO,
+        yaw: 0.0,
+        pitch: 0.0,
+        roll: 0.0,
+        boost: false,
+    };
+
+    pub fn is_none(&self) -> bool {
+        self.movement.sqr_magnitude() == 0.0
+            && self.yaw == 0.0
+            && self.pitch == 0.0
+            && self.roll == 0.0
+    }
+}
+
+/// Reads the gamepads through gilrs, the one that was used last flies the camera
+pub struct Gamepads {
+    /// None if gilrs failed to start, `error` says why
+    gilrs: Option<Gilrs>,
+    error: Option<String>,
+    active: Option<GamepadId>,
+    /// The rumble that is playing, dropping it stops it
+    rumble: Option<gilrs::ff::Effect>,
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        match Gilrs::new() {
+            Ok(gilrs) => Self {
+                gilrs: Some(gilrs),
+                error: None,
+                active: None,
+                rumble: None,
+            },
+            // gamepads aren't supported on this platform, but the dummy still works without any
+            Err(gilrs::Error::NotImplemented(gilrs)) => Self {
+                gilrs: Some(gilrs),
+                error: Some("gamepads aren't supported on this platform".into()),
+                active: None,
+                rumble: None,
+            },
+            Err(error) => Self {
+                gilrs: None,
+                error: Some(error.to_string()),
+                active: None,
+                rumble: None,
+            },
+        }
+    }
+}
+
+impl Gamepads {
+    /// Handles the events since the last frame and reads the active gamepad,
+    /// stick deflections inside `dead_zone` are ignored so worn sticks don't drift
+    pub fn poll(&mut self, dead_zone: f32) -> GamepadInput {
+        let Some(gilrs) = &mut self.gilrs else {
+            return GamepadInput::NONE;
+        };
+        while let Some(event) = gilrs.next_event() {
+            if let gilrs::EventType::Disconnected = event.event {
+                if self.active == Some(event.id) {
+                    self.active = None;
+                }
+            } else {
+                self.active = Some(event.id);
+            }
+        }
+        let Some(gamepad) = self
+            .active
+            .or_else(|| gilrs.gamepads().next().map(|(id, _)| id))
+            .map(|id| gilrs.gamepad(id))
+        else {
+            return GamepadInput::NONE;
+        };
+
+        let [right, forward] = dead_zoned(
+            [
+                gamepad.value(Axis::LeftStickX),
+                gamepad.value(Axis::LeftStickY),
+            ],
+            dead_zone,
+        );
+        let [yaw, pitch] = dead_zoned(
+            [
+                gamepad.value(Axis::RightStickX),
+                gamepad.value(Axis::RightStickY),
+            ],
+            dead_zone,
+        );
+        let trigger = |button| gamepad.button_data(button).map_or(0.0, |data| data.value());
+        let pressed = |button| gamepad.is_pressed(button) as u8 as f32;
+        GamepadInput {
+            movement: Vector3 {
+                x: forward,
+                y: trigger(Button::RightTrigger2) - trigger(Button::LeftTrigger2),
+                z: right,
+            },
+            yaw,
+            pitch,
+            roll: pressed(Button::RightTrigger) - pressed(Button::LeftTrigger),
+            boost: gamepad.is_pressed(Button::LeftThumb),
+        }
+    }
+
+    /// Rumbles the active gamepad if it can, replacing any rumble that is still playing
+    pub fn rumble(&mut self, rumble: Rumble) {
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        let Some(id) = self
+            .active
+            .or_else(|| gilrs.gamepads().next().map(|(id, _)| id))
+            .filter(|&id| gilrs.gamepad(id).is_ff_supported())
+        else {
+            return;
+        };
+        if rumble.is_none() {
+            return;
+        }
+
+        let duration = Ticks::from_ms((rumble.duration * 1000.0) as u32);
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: (rumble.strength.clamp(0.0, 1.0) * u16::MAX as f32) as u16,
+                },
+                scheduling: Replay {
+                    play_for: duration,
+                    ..Replay::default()
+                },
+                ..BaseEffect::default()
+            })
+            .gamepads(&[id])
+            .repeat(Repeat::For(duration))
+            .finish(gilrs)
+            .and_then(|effect| effect.play().map(|()| effect));
+        match effect {
+            Ok(effect) => self.rumble = Some(effect),
+            Err(error) => tracing::warn!("failed to rumble the gamepad: {error}"),
+        }
+    }
+
+    /// Which gamepad flies the camera, or why there is none
+    pub fn ui(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Gamepad:");
+            if let Some(error) = &self.error {
+                ui.colored_label(ui.visuals().warn_fg_color, error);
+                return;
+            }
+            let gamepad = self.gilrs.as_ref().and_then(|gilrs| {
+                self.active
+                    .map(|id| gilrs.gamepad(id))
+                    .or_else(|| gilrs.gamepads().next().map(|(_, gamepad)| gamepad))
+            });
+            match gamepad {
+                Some(gamepad) => ui.label(gamepad.name()),
+                None => ui.weak("None connected"),
+            };
+        });
+    }
+}
+
+/// Scales the stick so it starts from 0 at the edge of the dead zone and still reaches 1 when fully deflected
+fn dead_zoned([x, y]: [f32; 2], dead_zone: f32) -> [f32; 2] {
+    let magnitude = x.hypot(y);
+    if magnitude <= dead_zone || magnitude == 0.0 {
+        return [0.0, 0.0];
+    }
+    let scale = ((magnitude - dead_zone) / (1.0 - dead_zone).max(0.0001)).min(1.0) / magnitude;
+    [x * scale, y * scale]
+}
+
+impl TraversalListener for Gamepads {
+    fn traversed(&mut self, traversal: &Traversal) {
+        self.rumble(traversal.rumble);
+    }
+}
@@ -0,0 +1,28 @@
+use eframe::{egui_wgpu::RenderState, wgpu};
+
+/// Logs wgpu errors instead of panicking on them, and what the adapter can't do,
+/// for the validation mode that is turned on with `RenderSettings::gpu_validation`
+pub fn enable_gpu_diagnostics(render_state: &RenderState) {
+    render_state
+        .device
+        .on_uncaptured_error(Box::new(|error| tracing::error!("wgpu: {error}")));
+
+    let info = render_state.adapter.get_info();
+    tracing::info!(
+        "adapter: {} ({:?}, {:?}), driver: {} {}",
+        info.name,
+        info.backend,
+        info.device_type,
+        info.driver,
+        info.driver_info
+    );
+
+    let downlevel = render_state.adapter.get_downlevel_capabilities();
+    tracing::info!("shader model: {:?}", downlevel.shader_model);
+    if downlevel.is_webgpu_compliant() {
+        tracing::info!("the adapter supports all of WebGPU");
+    } else {
+        let missing = wgpu::DownlevelFlags::all() - downlevel.flags;
+        tracing::warn!("the adapter is missing downlevel features: {missing:?}");
+    }
+}
@@ -0,0 +1,384 @@
+use crate::{Plane, Units, rotation_angles, rotation_from_angles, ui_vector3_with_suffix};
+use eframe::egui;
+use math::{Rotor, Transform, Vector3};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, collections::HashSet};
+
+/// Identifies a group no matter where it is in the scene's groups, like [`crate::PlaneId`] does for planes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GroupId(u64);
+
+impl GroupId {
+    /// A random id, unique in practice
+    pub fn new() -> Self {
+        Self(rand::random())
+    }
+}
+
+impl Default for GroupId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A named transform that planes and other groups can be parented under,
+/// moving or turning the group moves and turns everything in it, so a whole room can be placed at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaneGroup {
+    pub id: GroupId,
+    pub name: String,
+    /// The group this one's position and rotation are relative to, if it is in one
+    pub parent: Option<GroupId>,
+    pub position: Vector3,
+    pub xy_rotation: f32,
+    pub yz_rotation: f32,
+    pub xz_rotation: f32,
+}
+
+impl Default for PlaneGroup {
+    fn default() -> Self {
+        Self {
+            id: GroupId::new(),
+            name: "Group".into(),
+            parent: None,
+            position: Vector3::ZERO,
+            xy_rotation: 0.0,
+            yz_rotation: 0.0,
+            xz_rotation: 0.0,
+        }
+    }
+}
+
+impl PlaneGroup {
+    /// Relative to its parent
+    pub fn transform(&self) -> Transform {
+        Transform::translation(self.position).then(Transform::from_rotor(self.rotation()))
+    }
+
+    pub fn rotation(&self) -> Rotor {
+        rotation_from_angles(self.xy_rotation, self.yz_rotation, self.xz_rotation)
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.position = transform.translation_part();
+        (self.xy_rotation, self.yz_rotation, self.xz_rotation) =
+            rotation_angles(transform.rotor_part());
+    }
+}
+
+pub fn find_group(groups: &[PlaneGroup], id: GroupId) -> Option<usize> {
+    groups.iter().position(|group| group.id == id)
+}
+
+/// The transform from inside `group` to the scene, through all of its parents,
+/// missing groups count as no group and a parent that loops back to a group already gone through ends it
+pub fn group_transform(groups: &[PlaneGroup], group: Option<GroupId>) -> Transform {
+    let mut transform = Transform::IDENTITY;
+    let mut visited = HashSet::new();
+    let mut current = group;
+    while let Some(id) = current
+        && visited.insert(id)
+        && let Some(index) = find_group(groups, id)
+    {
+        transform = groups[index].transform().then(transform);
+        current = groups[index].parent;
+    }
+    transform
+}
+
+/// Whether `group` is `ancestor` or somewhere inside it
+pub fn group_is_inside(groups: &[PlaneGroup], group: GroupId, ancestor: GroupId) -> bool {
+    let mut visited = HashSet::new();
+    let mut current = Some(group);
+    while let Some(id) = current
+        && visited.insert(id)
+    {
+        if id == ancestor {
+            return true;
+        }
+        current = find_group(groups, id).and_then(|index| groups[index].parent);
+    }
+    false
+}
+
+/// The planes with their groups' transforms applied, so they are all relative to the scene,
+/// which is what rendering, collisions and validation use
+pub fn flatten_planes<'a>(planes: &'a [Plane], groups: &[PlaneGroup]) -> Cow<'a, [Plane]> {
+    if planes.iter().all(|plane| plane.group.is_none()) {
+        return Cow::Borrowed(planes);
+    }
+    planes
+        .iter()
+        .map(|plane| {
+            let mut plane = plane.clone();
+            if let Some(group) = plane.group.take() {
+                let transform = group_transform(groups, Some(group)).then(plane.transform());
+                plane.position = transform.translation_part();
+                plane.set_rotation(transform.rotor_part());
+            }
+            plane
+        })
+        .collect()
+}
+
+/// Moves `plane` into `group`, or out of any group for `None`, without moving it in the scene
+pub fn set_plane_group(plane: &mut Plane, groups: &[PlaneGroup], group: Option<GroupId>) {
+    let transform = group_transform(groups, group)
+        .reverse()
+        .then(group_transform(groups, plane.group))
+        .then(plane.transform());
+    plane.position = transform.translation_part();
+    plane.set_rotation(transform.rotor_part());
+    plane.group = group;
+}
+
+/// Moves the group at `index` into `parent` without moving it in the scene,
+/// returns false without changing anything if `parent` is inside the group
+pub fn set_group_parent(groups: &mut [PlaneGroup], index: usize, parent: Option<GroupId>) -> bool {
+    if parent.is_some_and(|parent| group_is_inside(groups, parent, groups[index].id)) {
+        return false;
+    }
+    let transform = group_transform(groups, parent)
+        .reverse()
+        .then(group_transform(groups, groups[index].parent))
+        .then(groups[index].transform());
+    groups[index].set_transform(transform);
+    groups[index].parent = parent;
+    true
+}
+
+/// Removes the group with `id`, whatever was in it moves to its parent without moving in the scene
+pub fn ungroup(planes: &mut [Plane], groups: &mut Vec<PlaneGroup>, id: GroupId) {
+    let Some(index) = find_group(groups, id) else {
+        return;
+    };
+    let parent = groups[index].parent;
+    for plane in planes.iter_mut().filter(|plane| plane.group == Some(id)) {
+        set_plane_group(plane, groups, parent);
+    }
+    for child in 0..groups.len() {
+        if child != index && groups[child].parent == Some(id) {
+            set_group_parent(groups, child, parent);
+        }
+    }
+    groups.remove(index);
+}
+
+/// Lets the user pick a group or none, leaving out `exclude` and the groups inside it,
+/// returns the picked group if it changed
+pub fn ui_pick_group(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    current: Option<GroupId>,
+    groups: &[PlaneGroup],
+    exclude: Option<GroupId>,
+) -> Option<Option<GroupId>> {
+    let name = |id: Option<GroupId>| {
+        id.and_then(|id| find_group(groups, id))
+            .map_or("None", |index| groups[index].name.as_str())
+    };
+    let mut picked = current;
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(name(current))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut picked, None, "None");
+            for group in groups {
+                if exclude.is_some_and(|exclude| group_is_inside(groups, group.id, exclude)) {
+                    continue;
+                }
+                ui.selectable_value(&mut picked, Some(group.id), &group.name);
+            }
+        });
+    (picked != current).then_some(picked)
+}
+
+enum GroupAction {
+    SetParent(usize, Option<GroupId>),
+    Ungroup(GroupId),
+    RemovePlane(usize),
+}
+
+/// Shows the groups as a tree with the planes in each, returns whether anything that moves planes changed
+pub fn ui_groups(
+    ui: &mut egui::Ui,
+    planes: &mut [Plane],
+    groups: &mut Vec<PlaneGroup>,
+    units: Units,
+) -> bool {
+    let mut changed = false;
+    let mut actions = vec![];
+    let mut shown = HashSet::new();
+    for index in 0..groups.len() {
+        // groups in a loop of parents, which only hand edited scenes can have, are shown at the top too
+        let is_root = groups[index].parent.is_none_or(|parent| {
+            find_group(groups, parent).is_none()
+                || group_is_inside(groups, parent, groups[index].id)
+        });
+        if is_root {
+            changed |= ui_group(ui, index, planes, groups, units, &mut shown, &mut actions);
+        }
+    }
+    for action in actions {
+        match action {
+            GroupAction::SetParent(index, parent) => {
+                changed |= set_group_parent(groups, index, parent);
+            }
+            GroupAction::Ungroup(id) => {
+                ungroup(planes, groups, id);
+                changed = true;
+            }
+            GroupAction::RemovePlane(index) => {
+                set_plane_group(&mut planes[index], groups, None);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+fn ui_group(
+    ui: &mut egui::Ui,
+    index: usize,
+    planes: &[Plane],
+    groups: &mut [PlaneGroup],
+    units: Units,
+    shown: &mut HashSet<GroupId>,
+    actions: &mut Vec<GroupAction>,
+) -> bool {
+    let id = groups[index].id;
+    if !shown.insert(id) {
+        return false;
+    }
+    let mut changed = false;
+    egui::CollapsingHeader::new(&groups[index].name)
+        .id_salt(id)
+        .default_open(true)
+        .show(ui, |ui| {
+            let group = &mut groups[index];
+            ui.text_edit_singleline(&mut group.name);
+            ui.horizontal(|ui| {
+                ui.label("Position:");
+                changed |=
+                    ui_vector3_with_suffix(ui, &mut group.position, units.suffix()).changed();
+            });
+            for (label, angle) in [
+                ("XY Rotation:", &mut group.xy_rotation),
+                ("YZ Rotation:", &mut group.yz_rotation),
+                ("XZ Rotation:", &mut group.xz_rotation),
+            ] {
+                ui.horizontal(|ui| {
+                    ui.label(label);
+                    changed |= ui.drag_angle(angle).changed();
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label("Parent:");
+                if let Some(parent) = ui_pick_group(
+                    ui,
+                    ("Group Parent", id),
+                    groups[index].parent,
+                    groups,
+                    Some(id),
+                ) {
+                    actions.push(GroupAction::SetParent(index, parent));
+                }
+                if ui
+                    .button("Ungroup")
+                    .on_hover_text("Deletes the group, keeping everything in it where it is")
+                    .clicked()
+                {
+                    actions.push(GroupAction::Ungroup(id));
+                }
+            });
+
+            for child in 0..groups.len() {
+                if groups[child].parent == Some(id) {
+                    changed |= ui_group(ui, child, planes, groups, units, shown, actions);
+                }
+            }
+            for (plane_index, plane) in planes.iter().enumerate() {
+                if plane.group != Some(id) {
+                    continue;
+                }
+                ui.horizontal(|ui| {
+                    ui.label(format!("▭ {}", plane.name));
+                    if ui
+                        .small_button("✖")
+                        .on_hover_text("Takes the plane out of the group, keeping it where it is")
+                        .clicked()
+                    {
+                        actions.push(GroupAction::RemovePlane(plane_index));
+                    }
+                });
+            }
+        });
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_near(a: Vector3, b: Vector3) {
+        assert!((a - b).magnitude() < 1e-4, "{a:?} != {b:?}");
+    }
+
+    fn nested() -> (Vec<Plane>, Vec<PlaneGroup>) {
+        let outer = PlaneGroup {
+            position: Vector3::X * 10.0,
+            xz_rotation: FRAC_PI_2,
+            ..PlaneGroup::default()
+        };
+        let inner = PlaneGroup {
+            parent: Some(outer.id),
+            position: Vector3::Z * 2.0,
+            ..PlaneGroup::default()
+        };
+        let plane = Plane {
+            position: Vector3::X,
+            group: Some(inner.id),
+            ..Plane::default()
+        };
+        (vec![plane], vec![outer, inner])
+    }
+
+    #[test]
+    fn nested_groups_compose() {
+        let (planes, groups) = nested();
+        let flat = flatten_planes(&planes, &groups);
+        let expected = groups[0]
+            .transform()
+            .transform_point(Vector3::Z * 2.0 + Vector3::X);
+        assert_near(flat[0].position, expected);
+        assert!(flat[0].group.is_none());
+        assert_near(
+            flat[0].rotation().rotate(Vector3::X),
+            groups[0].rotation().rotate(Vector3::X),
+        );
+    }
+
+    #[test]
+    fn ungrouping_keeps_planes_in_place() {
+        let (mut planes, mut groups) = nested();
+        let before = flatten_planes(&planes, &groups)[0].position;
+        let outer = groups[0].id;
+        ungroup(&mut planes, &mut groups, outer);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].parent, None);
+        assert_near(flatten_planes(&planes, &groups)[0].position, before);
+    }
+
+    #[test]
+    fn parent_loops() {
+        let (_, mut groups) = nested();
+        let inner = groups[1].id;
+        assert!(!set_group_parent(&mut groups, 0, Some(inner)));
+        assert_eq!(groups[0].parent, None);
+        // a loop from a hand edited file still flattens
+        groups[0].parent = Some(inner);
+        group_transform(&groups, Some(inner));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{RenderSettings, Scene, frame_seed};
+use eframe::wgpu;
+use ray_tracing::{RayTracingPaintCallback, RayTracingRenderer, TargetImage};
+
+/// A renderer on its own device without a window, for rendering from the command line
+pub struct Headless {
+    pub adapter_info: wgpu::AdapterInfo,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub renderer: RayTracingRenderer,
+}
+
+impl Headless {
+    /// Uses the default adapter, preferring a discrete gpu
+    pub fn new() -> Result<Self, String> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .map_err(|error| error.to_string())?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("Headless Device"),
+            required_features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                | (adapter.features() & ray_tracing::OPTIONAL_FEATURES),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .map_err(|error| error.to_string())?;
+        // it never displays anything, so the target format doesn't matter
+        let renderer = RayTracingRenderer::new(&device, &queue, wgpu::TextureFormat::Rgba8Unorm)
+            .map_err(|error| error.to_string())?;
+        Ok(Self {
+            adapter_info: adapter.get_info(),
+            device,
+            queue,
+            renderer,
+        })
+    }
+
+    /// Traces `frame` and waits for the gpu to finish it
+    pub fn render_frame(&mut self, frame: &RayTracingPaintCallback) -> Result<(), String> {
+        let submission_index =
+            self.queue.submit([self
+                .renderer
+                .prepare_frame(&self.device, &self.queue, frame)]);
+        self.device
+            .poll(wgpu::PollType::WaitForSubmissionIndex(submission_index))
+            .map_err(|error| error.to_string())?;
+        Ok(())
+    }
+}
+
+impl RenderSettings {
+    /// A frame accumulating `samples_per_pixel` more samples into an image that already has
+    /// `accumulated_samples` over `accumulated_frames`, what offline renders trace every dispatch
+    pub fn accumulation_frame(
+        &self,
+        scene: &Scene,
+        width: u32,
+        height: u32,
+        accumulated_frames: u32,
+        accumulated_samples: u64,
+        samples_per_pixel: u32,
+    ) -> RayTracingPaintCallback {
+        let frame = self.paint_callback(scene, width, height);
+        RayTracingPaintCallback {
+            image: TargetImage {
+                accumulated_frames,
+                accumulated_samples,
+                ..frame.image
+            },
+            random_seed: frame_seed(self.seed, accumulated_frames),
+            samples_per_pixel,
+            max_samples_per_dispatch: samples_per_pixel,
+            histogram: false,
+            focus_peaking: None,
+            ..frame
+        }
+    }
+}
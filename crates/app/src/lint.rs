@@ -0,0 +1,43 @@
+use crate::{Scene, Severity, load_scene, validate_planes};
+use std::path::{Path, PathBuf};
+
+/// Runs `portals lint <file.scene>...`, printing what validation finds in each scene,
+/// returns the exit code, which is nonzero if any scene failed to load or has errors
+pub fn lint(paths: &[PathBuf]) -> i32 {
+    if paths.is_empty() {
+        eprintln!("usage: portals lint <file.scene>...");
+        return 2;
+    }
+
+    let mut failed = false;
+    for path in paths {
+        match load_scene(path) {
+            Ok(scene) => failed |= lint_scene(path, &scene),
+            Err(error) => {
+                println!("{}: error: {error}", path.display());
+                failed = true;
+            }
+        }
+    }
+    failed as i32
+}
+
+/// Returns true if the scene has errors
+fn lint_scene(path: &Path, scene: &Scene) -> bool {
+    let warnings = validate_planes(&scene.world_planes());
+    for warning in &warnings {
+        let severity = match warning.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        println!(
+            "{}: {severity}: plane {}: {}",
+            path.display(),
+            warning.plane_index,
+            warning.message
+        );
+    }
+    warnings
+        .iter()
+        .any(|warning| warning.severity == Severity::Error)
+}
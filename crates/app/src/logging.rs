@@ -0,0 +1,211 @@
+use eframe::egui;
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    sync::{Arc, Mutex},
+};
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+    span,
+};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, layer::Context, prelude::*, registry::LookupSpan, reload,
+};
+
+const MAX_RECORDS: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub level: Level,
+    pub target: String,
+    /// The names of the spans the event happened in, outermost first
+    pub spans: String,
+    pub message: String,
+}
+
+/// The events shown in the log window, the filter can be changed while the app is running
+#[derive(Clone)]
+pub struct Log {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+    filter: reload::Handle<EnvFilter, Registry>,
+}
+
+impl Log {
+    /// The log and the subscriber that fills it, events that pass `filter` are also printed to stderr
+    /// so they aren't lost if the app crashes, `log` records are collected once the subscriber is installed with
+    /// [`SubscriberInitExt::init`](tracing_subscriber::util::SubscriberInitExt::init)
+    pub fn new(filter: EnvFilter) -> (Self, impl Subscriber + Send + Sync + 'static) {
+        let (filter, handle) = reload::Layer::new(filter);
+        let records = Arc::new(Mutex::new(VecDeque::new()));
+        let subscriber = tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+            .with(RecordLayer {
+                records: records.clone(),
+            });
+        (
+            Self {
+                records,
+                filter: handle,
+            },
+            subscriber,
+        )
+    }
+
+    pub fn set_filter(&self, filter: EnvFilter) {
+        if let Err(error) = self.filter.reload(filter) {
+            tracing::warn!("Could not change the log filter: {error}");
+        }
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.records.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Keeps the last [`MAX_RECORDS`] events that passed the filter for the log window
+struct RecordLayer {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+}
+
+/// A span's name with its fields, stored in the span's extensions
+struct SpanName(String);
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            _ = write!(self.message, "{value:?}");
+        } else if !field.name().starts_with("log.") {
+            _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else if !field.name().starts_with("log.") {
+            _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+}
+
+impl<S> Layer<S> for RecordLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanName(format!(
+                "{}{{{}}}",
+                attrs.metadata().name(),
+                visitor.fields.trim()
+            )));
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Some(span) = ctx.span(id)
+            && let Some(SpanName(name)) = span.extensions_mut().get_mut::<SpanName>()
+            && let Some(prefix) = name.strip_suffix('}')
+        {
+            *name = format!("{prefix}{}}}", visitor.fields);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        // records from the `log` crate, like wgpu's, keep their own target and level
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .filter_map(|span| {
+                        span.extensions()
+                            .get::<SpanName>()
+                            .map(|SpanName(name)| name.clone())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+            .unwrap_or_default();
+        let record = LogRecord {
+            level: *metadata.level(),
+            target: metadata.target().into(),
+            spans,
+            message: visitor.message + &visitor.fields,
+        };
+
+        let mut records = self.records.lock().unwrap();
+        if records.len() >= MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+/// The contents of the log window, `filter` is the text being edited
+pub fn ui_log(ui: &mut egui::Ui, log: &Log, filter: &mut String) {
+    ui.horizontal(|ui| {
+        ui.label("Filter:");
+        let response = ui.text_edit_singleline(filter).on_hover_text(
+            "Same syntax as RUST_LOG, a default level then target=level directives, \
+            e.g. warn,app=info,ray_tracing=trace",
+        );
+        let parsed = EnvFilter::try_new(filter.as_str());
+        if response.changed()
+            && let Ok(parsed) = parsed.as_ref()
+        {
+            log.set_filter(parsed.clone());
+        }
+        if ui.button("Clear").clicked() {
+            log.clear();
+        }
+        if let Err(error) = parsed {
+            ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+        }
+    });
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .auto_shrink(false)
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for record in log.records() {
+                let color = match record.level {
+                    Level::ERROR => ui.visuals().error_fg_color,
+                    Level::WARN => ui.visuals().warn_fg_color,
+                    Level::INFO => ui.visuals().text_color(),
+                    _ => ui.visuals().weak_text_color(),
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(
+                        color,
+                        egui::RichText::new(record.level.as_str()).monospace(),
+                    );
+                    ui.weak(format!("{} {}", record.target, record.spans));
+                    ui.label(record.message);
+                });
+            }
+        });
+}
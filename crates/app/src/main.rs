@@ -1,39 +1,434 @@
 use eframe::{egui, wgpu};
 use egui_file_dialog::FileDialog;
-use math::{Rotor, Transform, Vector3};
+use math::{Rotor, Transform, UpAxis, Vector3};
 use ray_tracing::{
-    Color, GpuCamera, RENDER_TYPE_LIT, RENDER_TYPE_UNLIT, RayTracingPaintCallback,
-    RayTracingRenderer,
+    AccumulationPrecision, BACKGROUND_BLACK, BACKGROUND_SKY, BACKGROUND_TRANSPARENT, Color,
+    FocusPeaking, GpuCamera, LightGroup, LightGroupIntensities, PORTAL_FILL_COLOR,
+    PORTAL_FILL_NONE, PORTAL_FILL_SKY, RENDER_TYPE_LIT, RENDER_TYPE_PORTAL_DISTANCE,
+    RENDER_TYPE_UNLIT, RayTracingPaintCallback, RayTracingRenderer, RayTracingView, ShaderError,
+    TargetImage, ToneMapper, WorkgroupSize,
 };
 use serde::{Deserialize, Serialize};
-use std::{f32::consts::PI, sync::Arc, time::Instant};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    f32::consts::{FRAC_PI_2, PI},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing_subscriber::{EnvFilter, util::SubscriberInitExt as _};
 
+mod accumulation;
+mod analysis;
+mod animation;
+mod auto_tune;
+mod bake;
+mod benchmark;
 mod camera;
+mod camera_controller;
+mod cli;
+mod collision_debug;
+mod content_hash;
+mod corridor;
+mod crop;
+mod cryptomatte;
+mod denoise;
+mod export;
+mod find_replace;
+mod gamepad;
+mod gpu_diagnostics;
+mod group;
+mod headless;
+mod lint;
+mod logging;
+mod markers;
+mod maze;
+mod multi_gpu;
+mod notifications;
+mod palette;
 mod plane;
+mod plane_undo;
+mod probes;
+mod profiler;
+mod quality_preset;
 mod ray;
+mod render_queue;
+mod scene_file;
+mod shader_export;
+mod stats;
+mod stress_test;
+mod tabs;
+mod teleport;
+mod textures;
+mod thumbnails;
+mod traversal;
+#[cfg(test)]
+mod ui_tests;
+mod units;
+mod validation;
 
+pub use accumulation::*;
+pub use analysis::*;
+pub use animation::*;
+pub use auto_tune::*;
+pub use bake::*;
+pub use benchmark::*;
 pub use camera::*;
+pub use camera_controller::*;
+pub use cli::*;
+pub use collision_debug::*;
+pub use content_hash::*;
+pub use corridor::*;
+pub use crop::*;
+pub use cryptomatte::*;
+pub use denoise::*;
+pub use export::*;
+pub use find_replace::*;
+pub use gamepad::*;
+pub use gpu_diagnostics::*;
+pub use group::*;
+pub use headless::*;
+pub use lint::*;
+pub use logging::*;
+pub use markers::*;
+pub use maze::*;
+pub use multi_gpu::*;
+pub use notifications::*;
+pub use palette::*;
 pub use plane::*;
+pub use plane_undo::*;
+pub use probes::*;
+pub use profiler::*;
+pub use quality_preset::*;
 pub use ray::*;
+pub use render_queue::*;
+pub use scene_file::*;
+pub use shader_export::*;
+pub use stats::*;
+pub use stress_test::*;
+pub use tabs::*;
+pub use teleport::*;
+pub use textures::*;
+pub use thumbnails::*;
+pub use traversal::*;
+pub use units::*;
+pub use validation::*;
+
+/// Camera movement and portal traversal run at a fixed rate so they don't depend on the frame rate
+const SIMULATION_TIMESTEP: f32 = 1.0 / 120.0;
+/// Limits how much simulation time a single frame can catch up on after a long stall
+const MAX_SIMULATION_CATCH_UP: f32 = 0.25;
+/// How long the camera counts as moving after it stops, so the resolution doesn't flicker between simulation steps
+const CAMERA_MOVING_HOLD_TIME: f32 = 0.15;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 enum RenderType {
     Unlit,
     Lit,
+    /// How far camera rays go through portals as colors, see [`RENDER_TYPE_PORTAL_DISTANCE`]
+    PortalDistance,
+}
+
+impl RenderType {
+    const ALL: [Self; 3] = [Self::Unlit, Self::Lit, Self::PortalDistance];
+
+    fn name(self) -> &'static str {
+        match self {
+            RenderType::Unlit => "Unlit",
+            RenderType::Lit => "Lit",
+            RenderType::PortalDistance => "Portal Distance",
+        }
+    }
+}
+
+/// What camera rays that escape the scene see, the sky always lights the scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Background {
+    Sky,
+    Black,
+    /// Exported images keep an alpha channel so they can be composited
+    Transparent,
+}
+
+impl Background {
+    const ALL: [Self; 3] = [Self::Sky, Self::Black, Self::Transparent];
+
+    fn name(self) -> &'static str {
+        match self {
+            Background::Sky => "Sky",
+            Background::Black => "Black",
+            Background::Transparent => "Transparent",
+        }
+    }
+}
+
+/// What is seen on portals past the recursion limit, instead of what is through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum PortalFill {
+    /// The plane the portal is on
+    Plane,
+    /// `RenderSettings::portal_fill_color`
+    Color,
+    /// The average color of the sky, which is roughly what infinite corridors fade into
+    Sky,
+}
+
+impl PortalFill {
+    const ALL: [Self; 3] = [Self::Plane, Self::Color, Self::Sky];
+
+    fn name(self) -> &'static str {
+        match self {
+            PortalFill::Plane => "Plane",
+            PortalFill::Color => "Color",
+            PortalFill::Sky => "Average Sky",
+        }
+    }
+}
+
+/// When the accumulated image is thrown away to start over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum ResetPolicy {
+    /// Whenever anything that changes the image does
+    AnyChange,
+    /// Only when the camera moves, other changes ghost until enough new samples are averaged in
+    CameraChange,
+    /// Every change ghosts until enough new samples are averaged in
+    Never,
+    /// Never starts over, older samples fade out over `RenderSettings::blend_time` instead
+    TimedBlend,
+}
+
+impl ResetPolicy {
+    const ALL: [Self; 4] = [
+        Self::AnyChange,
+        Self::CameraChange,
+        Self::Never,
+        Self::TimedBlend,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ResetPolicy::AnyChange => "Any Change",
+            ResetPolicy::CameraChange => "Camera Change",
+            ResetPolicy::Never => "Never",
+            ResetPolicy::TimedBlend => "Timed Blend",
+        }
+    }
+}
+
+/// How finished frames are shown on the window, trading latency against tearing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum PresentMode {
+    /// Whichever of [`PresentMode::Immediate`] and [`PresentMode::Mailbox`] is supported, otherwise vsync
+    AutoNoVsync,
+    /// Vsync, frames wait for the display so they never tear, supported everywhere
+    Fifo,
+    /// Vsync without waiting, only the newest frame is shown so nothing tears, not supported everywhere
+    Mailbox,
+    /// Frames are shown right away, which has the lowest latency but tears, not supported everywhere
+    Immediate,
+}
+
+impl PresentMode {
+    const ALL: [Self; 4] = [
+        Self::AutoNoVsync,
+        Self::Fifo,
+        Self::Mailbox,
+        Self::Immediate,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            PresentMode::AutoNoVsync => "Auto No Vsync",
+            PresentMode::Fifo => "Fifo",
+            PresentMode::Mailbox => "Mailbox",
+            PresentMode::Immediate => "Immediate",
+        }
+    }
+
+    fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+
+    /// Whether a surface with the `supported` present modes can be configured with this one,
+    /// Fifo is supported everywhere and wgpu picks a supported mode itself for AutoNoVsync
+    fn is_supported(self, supported: &[wgpu::PresentMode]) -> bool {
+        matches!(self, PresentMode::AutoNoVsync | PresentMode::Fifo)
+            || supported.contains(&self.to_wgpu())
+    }
+}
+
+/// What the window's surface and the device are created with, eframe only configures them when the window is created,
+/// so `main` reads the saved ones before creating it and changing them closes the window and runs the app again with them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LaunchSettings {
+    present_mode: PresentMode,
+    /// Creates the wgpu instance with validation and debug layers
+    gpu_validation: bool,
+}
+
+impl Default for LaunchSettings {
+    fn default() -> Self {
+        Self {
+            // can't fail to configure whatever the surface supports
+            present_mode: PresentMode::AutoNoVsync,
+            gpu_validation: false,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// How the app was launched and how it asks `main` to launch it again
+struct Launch {
+    settings: LaunchSettings,
+    /// The present modes the window's surface supports, found when its adapter was picked
+    supported_present_modes: Vec<wgpu::PresentMode>,
+    /// Set before closing the window to run the app again with other settings
+    relaunch: Rc<Cell<Option<LaunchSettings>>>,
+}
+
+impl Launch {
+    /// Closes the window and runs the app again with `settings`, the scene and render settings are saved on closing
+    /// and restored from eframe's storage
+    fn relaunch(&self, ctx: &egui::Context, settings: LaunchSettings) {
+        self.relaunch.set(Some(settings));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
-struct RenderSettings {
+pub struct RenderSettings {
     info_window_open: bool,
     camera_window_open: bool,
     render_settings_window_open: bool,
     planes_window_open: bool,
+    render_queue_window_open: bool,
+    log_window_open: bool,
+    markers_window_open: bool,
+    probe_volumes_window_open: bool,
+    find_replace_window_open: bool,
+    maze_window_open: bool,
+    corridor_window_open: bool,
+    palette_window_open: bool,
+    auto_tune_window_open: bool,
+    animation_window_open: bool,
     render_type: RenderType,
     samples_per_pixel: u32,
     antialiasing: bool,
     recursive_portal_count: u32,
+    portal_fill: PortalFill,
+    portal_fill_color: Color,
+    /// How far camera rays go before the portal distance render type reaches the end of its colors
+    portal_distance_range: f32,
     max_bounces: u32,
+    limit_fps: bool,
+    max_fps: f32,
+    /// See [`LaunchSettings`], modes the window's surface doesn't support fall back to [`PresentMode::AutoNoVsync`]
+    present_mode: PresentMode,
+    /// Creates the wgpu instance with validation and debug layers and logs wgpu errors instead of crashing,
+    /// also see [`LaunchSettings`]
+    gpu_validation: bool,
+    background_accumulation: bool,
+    reset_policy: ResetPolicy,
+    /// How long it takes old samples to fade out with `ResetPolicy::TimedBlend`, in seconds
+    blend_time: f32,
+    /// How long tracing a frame may take with background accumulation, measured on the gpu if it supports timestamps
+    frame_time_budget_ms: f32,
+    max_samples_per_dispatch: u32,
+    /// Lengths in loaded scenes are multiplied by this, and divided by it when saving
+    import_export_scale: f32,
+    display_linear: bool,
+    tone_mapper: ToneMapper,
+    /// Shows `split_tone_mapper` right of `split_position` to pick between it and `tone_mapper`
+    compare_tone_mappers: bool,
+    split_tone_mapper: ToneMapper,
+    /// As a fraction of the viewport's width
+    split_position: f32,
+    false_color: bool,
+    zebra_stripes: bool,
+    /// Highlights what is in focus with depth of field, from the depth of the aovs
+    focus_peaking: bool,
+    /// How many pixels across the blur can be for focus peaking to still highlight it
+    focus_peaking_max_blur: f32,
+    focus_peaking_color: Color,
+    /// Widens the field of view the faster the camera flies
+    speed_fov: bool,
+    /// How much wider the field of view is at full boosted speed, in radians
+    speed_fov_widening: f32,
+    /// Shows a screen space effect for a moment after the camera teleports through a portal
+    teleport_effect: bool,
+    /// In seconds
+    teleport_effect_duration: f32,
+    /// Gamepad stick deflections smaller than this are ignored, from 0 to 1
+    gamepad_dead_zone: f32,
+    /// How fast the right stick turns the camera, relative to the arrow keys
+    gamepad_sensitivity: f32,
+    /// How far the camera turns per point the mouse is dragged while looking around, in radians
+    mouse_sensitivity: f32,
+    /// Dragging the mouse up looks down instead
+    invert_mouse_y: bool,
+    /// Traces at a lower resolution while the camera moves, the image is thrown away every frame then anyway
+    dynamic_resolution: bool,
+    /// The size of the traced image relative to the viewport's physical pixels
+    render_scale: f32,
+    /// The render scale used while the camera moves, relative to `render_scale`
+    moving_render_scale: f32,
+    /// Renders with `draft_preset` while planes or the sky are being edited
+    draft_while_editing: bool,
+    draft_preset: QualityPreset,
+    /// How many frames without edits it takes to go back to the full settings
+    draft_idle_frames: u32,
+    show_histogram: bool,
+    pixel_inspector: bool,
+    noclip: bool,
+    near_plane: f32,
+    /// How far rays and the camera are pushed past a portal when traversing it
+    portal_epsilon: f32,
+    /// How many simulation steps the camera ignores the plane it just exited through
+    portal_cooldown_steps: u32,
+    limit_portal_traversals: bool,
+    /// Limits the total portal traversals of a sample across all bounces
+    max_portal_traversals: u32,
+    /// Stops camera rays at portals smaller than `portal_cull_pixels` on screen instead of tracing what is behind them
+    cull_small_portals: bool,
+    portal_cull_pixels: f32,
+    furnace_test: bool,
+    /// Samples the emissive planes directly at diffuse bounces, so small lights converge much faster
+    sample_lights: bool,
+    /// Traces a wavelength per sample so refractive planes can disperse light
+    spectral: bool,
+    /// Half precision halves the memory and bandwidth of the accumulated image on large viewports
+    accumulation_precision: AccumulationPrecision,
+    /// Accumulates the sky, the sun and the emissive planes separately so they can be rebalanced without rendering again
+    light_groups: bool,
+    light_group_intensities: LightGroupIntensities,
+    /// The Info window warns when the accumulated image takes more memory than this, in MiB
+    vram_budget_mb: u32,
+    /// Widens the sun for paths that refract after a diffuse bounce so sun caustics converge, in radians
+    caustic_regularization: f32,
+    background: Background,
+    /// What the viewport shows where the image doesn't cover it, unless the scene has its own
+    viewport_background: Color,
+    /// Composites exported images over the viewport background instead of keeping their alpha
+    flatten_export_background: bool,
+    /// The random seed of the first accumulated frame, every following frame derives its seed from it
+    seed: u32,
+    export_metadata: bool,
+    export_footer: bool,
+    /// Writes the samples per pixel, bounces, denoiser and tone mapper in a strip below exported pngs
+    export_caption: bool,
+    /// Traces the albedo, normal, depth, plane and portal count of every pixel and the coverage of the planes,
+    /// exported to exr with the image and cryptomatte mattes
+    aovs: bool,
+    /// Smooths exported images with the aovs as guides
+    denoise_export: bool,
 }
 
 impl Default for RenderSettings {
@@ -43,18 +438,230 @@ impl Default for RenderSettings {
             camera_window_open: true,
             render_settings_window_open: true,
             planes_window_open: true,
+            render_queue_window_open: false,
+            log_window_open: false,
+            markers_window_open: false,
+            probe_volumes_window_open: false,
+            find_replace_window_open: false,
+            maze_window_open: false,
+            corridor_window_open: false,
+            palette_window_open: false,
+            auto_tune_window_open: false,
+            animation_window_open: false,
             render_type: RenderType::Unlit,
             samples_per_pixel: 1,
             antialiasing: true,
             recursive_portal_count: 10,
+            portal_fill: PortalFill::Plane,
+            portal_fill_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            portal_distance_range: 50.0,
             max_bounces: 3,
+            limit_fps: true,
+            max_fps: 144.0,
+            present_mode: PresentMode::AutoNoVsync,
+            gpu_validation: false,
+            background_accumulation: false,
+            reset_policy: ResetPolicy::AnyChange,
+            blend_time: 0.25,
+            frame_time_budget_ms: 16.0,
+            max_samples_per_dispatch: 4,
+            import_export_scale: 1.0,
+            display_linear: false,
+            tone_mapper: ToneMapper::None,
+            compare_tone_mappers: false,
+            split_tone_mapper: ToneMapper::AgX,
+            split_position: 0.5,
+            false_color: false,
+            zebra_stripes: false,
+            focus_peaking: false,
+            focus_peaking_max_blur: 2.0,
+            focus_peaking_color: Color {
+                r: 1.0,
+                g: 0.0,
+                b: 1.0,
+            },
+            speed_fov: false,
+            speed_fov_widening: 20f32.to_radians(),
+            teleport_effect: false,
+            teleport_effect_duration: 0.4,
+            gamepad_dead_zone: 0.15,
+            gamepad_sensitivity: 1.0,
+            mouse_sensitivity: 0.003,
+            invert_mouse_y: false,
+            dynamic_resolution: false,
+            render_scale: 1.0,
+            moving_render_scale: 0.5,
+            draft_while_editing: false,
+            draft_preset: QualityPreset::DRAFT,
+            draft_idle_frames: 30,
+            show_histogram: false,
+            pixel_inspector: false,
+            noclip: false,
+            near_plane: 0.0,
+            portal_epsilon: 0.001,
+            portal_cooldown_steps: 2,
+            limit_portal_traversals: false,
+            max_portal_traversals: 64,
+            cull_small_portals: false,
+            portal_cull_pixels: 1.0,
+            furnace_test: false,
+            sample_lights: true,
+            spectral: false,
+            accumulation_precision: AccumulationPrecision::Full,
+            light_groups: false,
+            light_group_intensities: LightGroupIntensities::default(),
+            vram_budget_mb: 512,
+            caustic_regularization: 0.0,
+            background: Background::Sky,
+            viewport_background: Color {
+                r: 0.02,
+                g: 0.02,
+                b: 0.02,
+            },
+            flatten_export_background: false,
+            seed: 0,
+            export_metadata: true,
+            export_footer: false,
+            export_caption: false,
+            aovs: false,
+            denoise_export: false,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// The render settings the last session saved to eframe's storage, which eframe only opens once the window exists
+    fn saved() -> Option<Self> {
+        let path = eframe::storage_dir(APP_NAME)?.join("app.ron");
+        let file = std::fs::File::open(path).ok()?;
+        let storage: HashMap<String, String> =
+            ron::de::from_reader(std::io::BufReader::new(file)).ok()?;
+        serde_json::from_str(storage.get("RenderSettings")?).ok()
+    }
+
+    fn launch_settings(&self) -> LaunchSettings {
+        LaunchSettings {
+            present_mode: self.present_mode,
+            gpu_validation: self.gpu_validation,
         }
     }
+
+    /// The first frame of rendering `scene` with these settings, tracing all the samples in a single dispatch
+    fn paint_callback(&self, scene: &Scene, width: u32, height: u32) -> RayTracingPaintCallback {
+        let samples_per_pixel = self.samples_per_pixel.max(1);
+        RayTracingPaintCallback {
+            image: TargetImage {
+                target: 0,
+                width,
+                height,
+                accumulated_frames: 0,
+                accumulated_samples: 0,
+                planes_version: 0,
+            },
+            camera: GpuCamera {
+                transform: scene.camera.transform(),
+                up_sky_color: scene.up_sky_color * scene.up_sky_intensity,
+                down_sky_color: scene.down_sky_color * scene.down_sky_intensity,
+                sun_color: scene.sun_color * scene.sun_intensity,
+                sun_direction: scene.up_rotation.rotate(scene.sun_direction.normalised()),
+                sun_size: scene.sun_size,
+                recursive_portal_count: self.recursive_portal_count,
+                max_bounces: self.max_bounces,
+                near_plane: self.near_plane,
+                portal_epsilon: self.portal_epsilon,
+                max_portal_traversals: if self.limit_portal_traversals {
+                    self.max_portal_traversals
+                } else {
+                    u32::MAX
+                },
+                caustic_regularization: self.caustic_regularization,
+                fov: scene.camera.fov,
+                aperture: scene.camera.aperture,
+                focus_distance: scene.camera.focus_distance,
+                portal_cull_pixels: if self.cull_small_portals {
+                    self.portal_cull_pixels
+                } else {
+                    0.0
+                },
+                portal_fill: match self.portal_fill {
+                    PortalFill::Plane => PORTAL_FILL_NONE,
+                    PortalFill::Color => PORTAL_FILL_COLOR,
+                    PortalFill::Sky => PORTAL_FILL_SKY,
+                },
+                portal_fill_color: self.portal_fill_color,
+                portal_distance_range: self.portal_distance_range,
+                up: scene.up_rotation.rotate(scene.up_axis.up()),
+            },
+            blend_factor: 0.0,
+            random_seed: frame_seed(self.seed, 0),
+            render_type: match self.render_type {
+                RenderType::Unlit => RENDER_TYPE_UNLIT,
+                RenderType::Lit => RENDER_TYPE_LIT,
+                RenderType::PortalDistance => RENDER_TYPE_PORTAL_DISTANCE,
+            },
+            background: match self.background {
+                Background::Sky => BACKGROUND_SKY,
+                Background::Black => BACKGROUND_BLACK,
+                Background::Transparent => BACKGROUND_TRANSPARENT,
+            },
+            samples_per_pixel,
+            max_samples_per_dispatch: samples_per_pixel,
+            antialiasing: self.antialiasing,
+            display_linear: self.display_linear,
+            tone_mapper: self.tone_mapper,
+            split_tone_mapper: self
+                .compare_tone_mappers
+                .then_some((self.split_tone_mapper, self.split_position)),
+            false_color: self.false_color,
+            zebra_stripes: self.zebra_stripes,
+            focus_peaking: (self.focus_peaking && scene.camera.aperture > 0.0).then_some(
+                FocusPeaking {
+                    max_blur: self.focus_peaking_max_blur,
+                    color: self.focus_peaking_color,
+                },
+            ),
+            teleport_effect: 0.0,
+            histogram: self.show_histogram,
+            inspect_pixel: None,
+            crop: None,
+            furnace_test: self.furnace_test,
+            sample_lights: self.sample_lights,
+            spectral: self.spectral,
+            accumulation_precision: self.accumulation_precision,
+            light_groups: self.light_groups.then_some(self.light_group_intensities),
+            aovs: self.aovs,
+            bake: None,
+            probes: None,
+            planes: planes_to_gpu(&scene.world_planes()),
+            dirty_planes: None,
+        }
+    }
+}
+
+/// The random seed for an accumulated frame, derived from the seed in the render settings
+fn frame_seed(seed: u32, accumulated_frames: u32) -> u32 {
+    seed ^ accumulated_frames.wrapping_mul(0x85EBCA6B)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
-struct Scene {
+pub struct Scene {
+    /// Always [`SCENE_VERSION`] once loaded, older files are migrated by [`parse_scene`]
+    version: u32,
+    units: Units,
+    /// Which way is up for the sky and the camera controls
+    up_axis: UpAxis,
+    /// Portals that turn the camera turn which way is up along with it, so walls can become floors
+    escher_mode: bool,
+    /// How far portals have turned up away from `up_axis` in escher mode, reset whenever the scene is loaded
+    #[serde(skip)]
+    up_rotation: Rotor,
+    /// The acceleration of anything that falls, in units per second squared
+    gravity: Vector3,
     camera: Camera,
     up_sky_color: Color,
     up_sky_intensity: f32,
@@ -64,17 +671,52 @@ struct Scene {
     sun_intensity: f32,
     sun_direction: Vector3,
     sun_size: f32,
+    #[serde(deserialize_with = "deserialize_planes")]
     planes: Vec<Plane>,
+    /// Planes are placed relative to the group they are in, see [`Scene::world_planes`]
+    groups: Vec<PlaneGroup>,
+    bookmarks: Vec<CameraBookmark>,
+    markers: Vec<Marker>,
+    probe_volumes: Vec<ProbeVolume>,
+    animation: Animation,
+    /// Replaces `RenderSettings::viewport_background` for this scene
+    viewport_background: Option<Color>,
+}
+
+impl Scene {
+    /// The hash of the scene as it is saved, see [`ContentHash::of_json`]
+    fn content_hash(&self) -> ContentHash {
+        ContentHash::of_json(self)
+    }
+
+    /// The planes with their groups' transforms applied, only copied if any plane is in a group
+    fn world_planes(&self) -> Cow<'_, [Plane]> {
+        flatten_planes(&self.planes, &self.groups)
+    }
+
+    fn viewport_background(&self, render_settings: &RenderSettings) -> Color {
+        self.viewport_background
+            .unwrap_or(render_settings.viewport_background)
+    }
 }
 
 impl Default for Scene {
     fn default() -> Self {
         Self {
+            version: SCENE_VERSION,
+            units: Units::Meters,
+            up_axis: UpAxis::Y,
+            escher_mode: false,
+            up_rotation: Rotor::IDENTITY,
+            gravity: Vector3::UP * -9.81,
             camera: Camera {
                 position: Vector3::UP * 1.1,
                 rotation: Rotor::IDENTITY,
                 speed: 2.0,
                 rotation_speed: 0.25,
+                fov: FRAC_PI_2,
+                aperture: 0.0,
+                focus_distance: 5.0,
             },
             up_sky_color: Color {
                 r: 0.4,
@@ -101,6 +743,7 @@ impl Default for Scene {
                 z: 0.2,
             },
             planes: vec![Plane {
+                id: PlaneId::new(),
                 name: "Ground".into(),
                 position: Vector3 {
                     x: 0.0,
@@ -127,20 +770,169 @@ impl Default for Scene {
                 },
                 emission_intensity: 0.0,
                 emissive_checker_darkness: 0.5,
+                emissive_sides: EmissiveSides::Both,
+                emission_profile: EmissionProfile::Lambertian,
+                transmission: 0.0,
+                ior: 1.5,
+                dispersion: 0.0,
+                metallic: 0.0,
+                roughness: 1.0,
+                specular: 0.0,
+                light_group: LightGroup::default(),
+                shadow_catcher: false,
+                texture: None,
                 front_portal: PortalConnection::default(),
                 back_portal: PortalConnection::default(),
+                camera_collides: true,
+                group: None,
             }],
+            groups: vec![],
+            bookmarks: vec![],
+            markers: vec![],
+            probe_volumes: vec![],
+            animation: Animation::default(),
+            viewport_background: None,
+        }
+    }
+}
+
+impl Scene {
+    /// The rotation that turns y up into the scene's up, including how far portals have turned it in escher mode
+    fn up_frame(&self) -> Rotor {
+        self.up_rotation.then(self.up_axis.from_y_up())
+    }
+
+    fn scale_lengths(&mut self, factor: f32) {
+        self.gravity *= factor;
+        self.camera.position *= factor;
+        self.camera.speed *= factor;
+        self.camera.aperture *= factor;
+        self.camera.focus_distance *= factor;
+        for plane in &mut self.planes {
+            plane.position *= factor;
+            plane.width *= factor;
+            plane.height *= factor;
+        }
+        for group in &mut self.groups {
+            group.position *= factor;
+        }
+        for bookmark in &mut self.bookmarks {
+            bookmark.position *= factor;
         }
+        for marker in &mut self.markers {
+            marker.position *= factor;
+        }
+        for volume in &mut self.probe_volumes {
+            volume.min *= factor;
+            volume.max *= factor;
+            volume.spacing *= factor;
+        }
+        self.animation.scale_lengths(factor);
+    }
+
+    /// A copy of the scene with its lengths as they are written to a file, divided by the import/export scale,
+    /// every exporter writes this instead of the scene itself so the scale applies to every format,
+    /// only scene files are written so far, an exporter for a format in fixed units like glTF's meters
+    /// would also convert from the scene's units with [`Units::meters_per_unit`]
+    fn exported(&self, import_export_scale: f32) -> Scene {
+        let mut scene = self.clone();
+        scene.scale_lengths(import_export_scale.recip());
+        scene
+    }
+
+    /// A scene read from a file with its lengths multiplied by the import/export scale, the reverse of [`Scene::exported`]
+    fn imported(mut self, import_export_scale: f32) -> Scene {
+        self.scale_lengths(import_export_scale);
+        self
     }
 }
 
 struct App {
     last_time: Option<Instant>,
+    last_sleep_time: Duration,
+    /// Time that has not been simulated yet, always less than `SIMULATION_TIMESTEP` after an update
+    simulation_time: f32,
+    /// The plane the camera last exited through, and how many more steps it should be ignored for
+    portal_cooldown: Option<(PlaneId, u32)>,
+    camera_controller: CameraController,
+    /// How fast the camera has been moving recently, in units per second
+    camera_speed: f32,
+    /// How much wider the field of view is because of `camera_speed`, in radians
+    fov_widening: f32,
+    /// How many more seconds the teleport effect is shown for
+    teleport_effect_time: f32,
+    /// How many more seconds the camera counts as moving for, see `RenderSettings::dynamic_resolution`
+    camera_moving_time: f32,
+    /// How many more frames `RenderSettings::draft_preset` is rendered with
+    draft_frames_left: u32,
     scene: Scene,
+    /// The file name of the last loaded or saved scene
+    scene_name: Option<String>,
+    /// The viewport of the active tab's scene, it accumulates into its own render target
+    view: RayTracingView,
+    /// Where the viewport was last frame, to show its resolution before it is laid out again
+    viewport_rect: egui::Rect,
+    /// Every open scene, the one at `active_tab` is only a stand-in for the state stored in `App`
+    tabs: Vec<SceneTab>,
+    active_tab: usize,
+    next_render_target: u64,
     render_settings: RenderSettings,
+    /// What the window's surface and the wgpu instance were created with
+    launch: Launch,
     file_dialog: FileDialog,
     file_interaction: FileInteraction,
-    accumulated_frames: u32,
+    stats_file_dialog: FileDialog,
+    image_file_dialog: FileDialog,
+    accumulation_file_dialog: FileDialog,
+    shader_file_dialog: FileDialog,
+    texture_file_dialog: FileDialog,
+    /// The plane the texture being picked is for
+    texture_plane: Option<PlaneId>,
+    plane_textures: PlaneTextures,
+    accumulation_file_interaction: FileInteraction,
+    stats: StatsRecorder,
+    profiler: Profiler,
+    find_replace: FindReplace,
+    maze_generator: MazeGenerator,
+    corridor_generator: CorridorGenerator,
+    palette_tool: PaletteTool,
+    gamepads: Gamepads,
+    /// Read once a frame, every simulation step of the frame flies the camera with it
+    gamepad_input: GamepadInput,
+    /// Portals the camera went through in this frame's simulation steps, handled once they are done
+    traversals: Vec<Traversal>,
+    stress_test: StressTest,
+    /// How tall every plane in the planes window was when it was last shown, planes out of view only reserve that space
+    plane_row_heights: Vec<f32>,
+    /// Planes checked in the planes window, what Ctrl+C copies
+    selected_planes: HashSet<PlaneId>,
+    render_queue: RenderQueue,
+    auto_tuner: AutoTuner,
+    timeline: Timeline,
+    collision_debug: CollisionDebug,
+    crop: CropRegion,
+    light_bake: LightBake,
+    irradiance_probes: IrradianceProbes,
+    multi_gpu: MultiGpu,
+    /// Replaces the workgroup size picked for the adapter, for comparing them
+    workgroup_size_override: Option<WorkgroupSize>,
+    scene_warnings: Vec<SceneWarning>,
+    adaptive_samples_per_pixel: u32,
+    /// The planes that changed this frame, `None` when they changed in a way that isn't tracked per plane,
+    /// like planes being added or removed or the whole scene being replaced
+    dirty_planes: Option<Vec<usize>>,
+    /// The [`Scene::content_hash`] shown in the info window, `None` once the scene may have changed since
+    scene_hash: Option<ContentHash>,
+    /// Nothing can be rendered if the shaders failed to compile
+    shader_error: Option<ShaderError>,
+    /// Why the last scene couldn't be loaded, shown in a dialog until it is dismissed
+    scene_load_error: Option<String>,
+    log: Log,
+    log_filter: String,
+    notifications: Notifications,
+    thumbnails: Thumbnails,
+    /// What the next paste is for, a paste button asks egui for the clipboard and it comes as a paste event
+    paste_target: PasteTarget,
 }
 
 enum FileInteraction {
@@ -149,52 +941,414 @@ enum FileInteraction {
     Load,
 }
 
+#[derive(Default)]
+enum PasteTarget {
+    #[default]
+    Planes,
+    CameraPose,
+}
+
 impl App {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    /// `log_filter` is the text `log`'s filter was parsed from,
+    /// `scene_path` is opened instead of the last session's scene
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        log: Log,
+        log_filter: String,
+        scene_path: Option<&std::path::Path>,
+        launch: Launch,
+    ) -> Self {
         let render_state = cc.wgpu_render_state.as_ref().unwrap();
-        let ray_tracer = RayTracingRenderer::new(
-            &render_state.device,
-            &render_state.queue,
-            render_state.target_format,
-        );
-        render_state
-            .renderer
-            .write()
-            .callback_resources
-            .insert(ray_tracer);
+        let mut render_settings: RenderSettings = cc
+            .storage
+            .and_then(|storage| storage.get_string("RenderSettings"))
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        if launch.settings.gpu_validation {
+            enable_gpu_diagnostics(render_state);
+        }
+        // a mode saved on another display makes `main` open the window again without it
+        if !render_settings
+            .present_mode
+            .is_supported(&launch.supported_present_modes)
+        {
+            tracing::warn!(
+                "The {} present mode isn't supported, falling back to {}",
+                render_settings.present_mode.name(),
+                PresentMode::AutoNoVsync.name(),
+            );
+            render_settings.present_mode = PresentMode::AutoNoVsync;
+        }
 
-        Self {
+        // a broken shader is shown in the window instead of panicking with a dump of it
+        let shader_error = match RayTracingView::install(render_state) {
+            Ok(()) => None,
+            Err(error) => {
+                tracing::error!("{error}");
+                Some(error)
+            }
+        };
+
+        let (scene, scene_load_error) = match cc
+            .storage
+            .filter(|_| scene_path.is_none())
+            .and_then(|storage| storage.get_string("Scene"))
+            .map(|s| parse_scene(&s))
+        {
+            Some(Ok(scene)) => (scene, None),
+            Some(Err(error)) => {
+                tracing::error!("failed to restore the last scene: {error}");
+                (
+                    Scene::default(),
+                    Some(format!(
+                        "The scene from last time couldn't be restored: {error}"
+                    )),
+                )
+            }
+            None => (Scene::default(), None),
+        };
+
+        let mut app = Self {
             last_time: None,
-            scene: cc
-                .storage
-                .and_then(|storage| storage.get_string("Scene"))
-                .and_then(|s| serde_json::from_str(&s).ok())
-                .unwrap_or_default(),
-            render_settings: cc
-                .storage
-                .and_then(|storage| storage.get_string("RenderSettings"))
-                .and_then(|s| serde_json::from_str(&s).ok())
-                .unwrap_or_default(),
+            last_sleep_time: Duration::ZERO,
+            simulation_time: 0.0,
+            portal_cooldown: None,
+            camera_controller: CameraController::default(),
+            camera_speed: 0.0,
+            fov_widening: 0.0,
+            teleport_effect_time: 0.0,
+            camera_moving_time: 0.0,
+            draft_frames_left: 0,
+            scene_warnings: validate_planes(&scene.world_planes()),
+            scene,
+            scene_name: None,
+            view: RayTracingView::new(0),
+            viewport_rect: egui::Rect::NOTHING,
+            tabs: vec![SceneTab::new(RayTracingView::new(0), Scene::default())],
+            active_tab: 0,
+            next_render_target: 1,
+            render_settings,
+            launch,
             file_dialog: FileDialog::new()
                 .add_file_filter_extensions("Scene", vec!["scene"])
                 .default_file_filter("Scene")
                 .add_save_extension("Scene", "scene")
                 .default_save_extension("Scene"),
             file_interaction: FileInteraction::None,
-            accumulated_frames: 0,
+            stats_file_dialog: FileDialog::new()
+                .add_save_extension("CSV", "csv")
+                .add_save_extension("JSON", "json")
+                .default_save_extension("CSV"),
+            image_file_dialog: FileDialog::new()
+                .add_save_extension("PNG", "png")
+                .add_save_extension("EXR", "exr")
+                .default_save_extension("PNG"),
+            accumulation_file_dialog: FileDialog::new()
+                .add_file_filter_extensions("Accumulation", vec!["accumulation"])
+                .default_file_filter("Accumulation")
+                .add_save_extension("Accumulation", "accumulation")
+                .default_save_extension("Accumulation"),
+            accumulation_file_interaction: FileInteraction::None,
+            shader_file_dialog: FileDialog::new()
+                .add_save_extension("WGSL", "wgsl")
+                .add_save_extension("Shadertoy", "glsl")
+                .default_save_extension("WGSL"),
+            texture_file_dialog: FileDialog::new()
+                .add_file_filter_extensions("Image", TEXTURE_EXTENSIONS.to_vec())
+                .default_file_filter("Image"),
+            texture_plane: None,
+            plane_textures: PlaneTextures::default(),
+            notifications: Notifications::default(),
+            thumbnails: Thumbnails::default(),
+            paste_target: PasteTarget::default(),
+            stats: StatsRecorder::default(),
+            profiler: Profiler::default(),
+            find_replace: FindReplace::default(),
+            maze_generator: MazeGenerator::default(),
+            corridor_generator: CorridorGenerator::default(),
+            palette_tool: PaletteTool::default(),
+            gamepads: Gamepads::default(),
+            gamepad_input: GamepadInput::NONE,
+            traversals: vec![],
+            stress_test: StressTest::default(),
+            plane_row_heights: vec![],
+            selected_planes: HashSet::new(),
+            render_queue: RenderQueue::default(),
+            auto_tuner: AutoTuner::default(),
+            timeline: Timeline::default(),
+            collision_debug: CollisionDebug::default(),
+            crop: CropRegion::default(),
+            light_bake: LightBake::default(),
+            irradiance_probes: IrradianceProbes::default(),
+            multi_gpu: MultiGpu::default(),
+            workgroup_size_override: None,
+            adaptive_samples_per_pixel: 1,
+            dirty_planes: None,
+            scene_hash: None,
+            shader_error,
+            scene_load_error,
+            log,
+            log_filter,
+        };
+        if let Some(path) = scene_path {
+            app.open_scene(path);
+        }
+        app
+    }
+
+    /// Replaces the scene with the one saved at `path`, or shows why it couldn't be loaded,
+    /// returns whether it was loaded
+    fn open_scene(&mut self, path: &std::path::Path) -> bool {
+        match load_scene(path) {
+            Ok(scene) => {
+                self.notifications
+                    .success(format!("Loaded scene from {}", path.display()));
+                self.scene = scene.imported(self.render_settings.import_export_scale);
+                self.dirty_planes = None;
+                self.scene_name = scene_name(path);
+                self.scene_warnings = validate_planes(&self.scene.world_planes());
+                true
+            }
+            Err(error) => {
+                tracing::error!("failed to load scene from {}: {error}", path.display());
+                self.scene_load_error = Some(format!(
+                    "Failed to load scene from {}: {error}",
+                    path.display()
+                ));
+                false
+            }
+        }
+    }
+
+    /// Copies the selected planes to the clipboard as json, in the order they are in the scene
+    fn copy_selected_planes(&mut self, ctx: &egui::Context) {
+        // grouped planes are copied where they are in the scene, the groups might not be where they are pasted
+        let planes = self
+            .scene
+            .world_planes()
+            .iter()
+            .filter(|plane| self.selected_planes.contains(&plane.id))
+            .cloned()
+            .collect::<Vec<_>>();
+        if planes.is_empty() {
+            return;
+        }
+        ctx.copy_text(serde_json::to_string(&planes).unwrap());
+        self.notifications
+            .success(format!("Copied {} planes", planes.len()));
+    }
+
+    /// Adds copies of the planes copied into `text` to the scene and selects them instead,
+    /// returns whether there were any
+    fn paste_planes(&mut self, text: &str) -> bool {
+        match serde_json::from_str::<Vec<Plane>>(text) {
+            Ok(planes) if !planes.is_empty() => {
+                self.selected_planes = insert_copies(&mut self.scene.planes, planes)
+                    .into_iter()
+                    .collect();
+                self.dirty_planes = None;
+                self.scene_warnings = validate_planes(&self.scene.world_planes());
+                true
+            }
+            _ => {
+                self.notifications
+                    .error("There are no copied planes on the clipboard");
+                false
+            }
+        }
+    }
+
+    fn step_camera(&mut self, i: &egui::InputState, ts: f32) -> bool {
+        let old_position = self.scene.camera.position;
+        let from_y_up = self.scene.up_frame();
+        // borrowed from the fields instead of through `Scene::world_planes` so the camera can still change
+        let planes = flatten_planes(&self.scene.planes, &self.scene.groups);
+        let mut changed =
+            self.camera_controller
+                .update(&mut self.scene.camera, &planes, from_y_up, i, ts);
+        if self.camera_controller.is_free_fly() {
+            changed |= self.scene.camera.fly(
+                &self.gamepad_input,
+                self.render_settings.gamepad_sensitivity,
+                ts,
+            );
+        }
+        let new_position = self.scene.camera.position;
+
+        let speed = (new_position - old_position).magnitude() / ts;
+        self.camera_speed += (speed - self.camera_speed) * (1.0 - (-10.0 * ts).exp());
+
+        // only a freely flying camera collides, the other controllers place it wherever they need it
+        if self.render_settings.noclip || !self.camera_controller.is_free_fly() {
+            return changed;
+        }
+
+        let ignored_id = match &mut self.portal_cooldown {
+            Some((id, steps)) if *steps > 0 => {
+                *steps -= 1;
+                Some(*id)
+            }
+            _ => None,
+        };
+
+        let movement_distance = (new_position - old_position).magnitude();
+        let ray = Ray {
+            origin: old_position,
+            direction: (new_position - old_position).normalised(),
+        };
+
+        let closest_hit = planes
+            .iter()
+            .enumerate()
+            .filter(|&(_, plane)| plane.camera_collides && Some(plane.id) != ignored_id)
+            .map(|(i, plane)| (i, plane.intersect(ray)))
+            .fold(None::<(usize, Hit)>, |closest_hit, (index, hit)| {
+                if let Some((closest_index, closest_hit)) = closest_hit {
+                    if let Some(hit) = hit
+                        && hit.distance < closest_hit.distance
+                    {
+                        Some((index, hit))
+                    } else {
+                        Some((closest_index, closest_hit))
+                    }
+                } else {
+                    hit.map(|hit| (index, hit))
+                }
+            });
+
+        let mut teleported_index = None;
+        if let Some((index, hit)) = closest_hit
+            && hit.distance < movement_distance
+        {
+            let plane = &planes[index];
+            let portal = if hit.front {
+                &plane.front_portal
+            } else {
+                &plane.back_portal
+            };
+            let other_index = portal.other.and_then(|id| find_plane(&planes, id));
+            let scale = portal.scale;
+            let rumble = portal.rumble;
+            let sound = portal.sound.clone();
+            if other_index.is_some() {
+                teleported_index = Some(index);
+            }
+
+            if let Some(other_index) = other_index {
+                let other_plane = &planes[other_index];
+                let pose = teleport_through(
+                    plane,
+                    other_plane,
+                    hit,
+                    Pose {
+                        position: self.scene.camera.position,
+                        rotation: self.scene.camera.rotation,
+                        direction: ray.direction,
+                    },
+                    self.render_settings.portal_epsilon,
+                );
+                if self.scene.escher_mode {
+                    // however the portal turned the camera, rolling included, is how it turns up
+                    let turn = pose.rotation.then(self.scene.camera.rotation.reverse());
+                    self.scene.up_rotation = turn.then(self.scene.up_rotation).normalised();
+                }
+                self.scene.camera.position = pose.position;
+                self.scene.camera.rotation = pose.rotation;
+                // the camera comes out as much bigger as the portal scales things, so it moves that much faster
+                self.scene.camera.speed *= scale;
+                self.portal_cooldown =
+                    Some((other_plane.id, self.render_settings.portal_cooldown_steps));
+                self.traversals.push(Traversal {
+                    plane: plane.id,
+                    front: hit.front,
+                    other: other_plane.id,
+                    rumble,
+                    sound,
+                });
+                if self.render_settings.teleport_effect {
+                    self.teleport_effect_time = self.render_settings.teleport_effect_duration;
+                }
+
+                changed = true;
+            }
         }
+
+        if self.collision_debug.enabled && movement_distance > 0.0 {
+            let hits = planes
+                .iter()
+                .enumerate()
+                .filter(|(_, plane)| plane.camera_collides)
+                .filter_map(|(index, plane)| Some((index, plane.intersect(ray)?)))
+                .filter(|(_, hit)| hit.distance < movement_distance)
+                .map(|(index, hit)| (hit.position, teleported_index == Some(index)))
+                .collect();
+            self.collision_debug.record(CameraMovement {
+                from: old_position,
+                to: new_position,
+                hits,
+            });
+        }
+
+        changed
     }
 }
 
 impl eframe::App for App {
-    fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &eframe::egui::Context, frame: &mut eframe::Frame) {
+        let _frame_span =
+            tracing::trace_span!("frame", accumulated_frames = self.view.accumulated_frames())
+                .entered();
+
+        if let Some(error) = &self.shader_error {
+            egui::CentralPanel::default().show(ctx, |ui| ui_shader_error(ui, error));
+            return;
+        }
+
+        if let Some(error) = &self.scene_load_error {
+            let modal = egui::Modal::new(egui::Id::new("Scene Load Error")).show(ctx, |ui| {
+                ui.heading("Failed to Load Scene");
+                ui.colored_label(ui.visuals().error_fg_color, error);
+                ui.button("Ok").clicked()
+            });
+            if modal.inner || modal.should_close() {
+                self.scene_load_error = None;
+            }
+        }
+
+        let sleep_time = if self.render_settings.limit_fps
+            && let Some(last_time) = self.last_time
+        {
+            let frame_duration =
+                Duration::from_secs_f32(self.render_settings.max_fps.max(1.0).recip());
+            let sleep_time = frame_duration.saturating_sub(last_time.elapsed());
+            std::thread::sleep(sleep_time);
+            sleep_time
+        } else {
+            Duration::ZERO
+        };
+
         let time = Instant::now();
+        let prepare_timings = frame.wgpu_render_state().map(|render_state| {
+            render_state
+                .renderer
+                .write()
+                .callback_resources
+                .get_mut::<RayTracingRenderer>()
+                .unwrap()
+                .take_prepare_timings()
+        });
+        self.stress_test.record_prepare_timings(prepare_timings);
+        self.profiler.begin_frame(time, prepare_timings);
         let dt = time - self.last_time.unwrap_or(time);
         self.last_time = Some(time);
+        // the time the last frame spent doing work, used to scale how many samples get traced
+        let busy_time = dt.saturating_sub(std::mem::replace(&mut self.last_sleep_time, sleep_time));
 
         let ts = dt.as_secs_f32();
 
         let mut rendering_changed = false;
+        // compared against at the end of the frame, for `ResetPolicy::CameraChange`
+        let old_camera = self.scene.camera.clone();
 
         {
             let mut reset_everything = false;
@@ -209,15 +1363,57 @@ impl eframe::App for App {
                         self.file_interaction = FileInteraction::Save;
                         self.file_dialog.save_file();
                     }
+                    if ui
+                        .button("Export Image")
+                        .on_hover_text(
+                            "Saves the accumulated image as a tone mapped PNG or a full HDR EXR (F12)",
+                        )
+                        .clicked()
+                    {
+                        self.image_file_dialog.save_file();
+                    }
+                    if ui
+                        .button("Export Shader")
+                        .on_hover_text(
+                            "A standalone WGSL or Shadertoy shader that traces this scene unlit",
+                        )
+                        .clicked()
+                    {
+                        self.shader_file_dialog.save_file();
+                    }
                     self.render_settings.info_window_open |= ui.button("Info").clicked();
                     self.render_settings.render_settings_window_open |=
                         ui.button("Render Settings").clicked();
                     self.render_settings.camera_window_open |= ui.button("Camera").clicked();
                     self.render_settings.planes_window_open |= ui.button("Planes").clicked();
+                    self.render_settings.render_queue_window_open |=
+                        ui.button("Render Queue").clicked();
+                    self.render_settings.log_window_open |= ui.button("Log").clicked();
+                    self.render_settings.markers_window_open |= ui.button("Markers").clicked();
+                    self.render_settings.probe_volumes_window_open |=
+                        ui.button("Probe Volumes").clicked();
+                    self.render_settings.find_replace_window_open |=
+                        ui.button("Find & Replace").clicked();
+                    self.render_settings.maze_window_open |= ui.button("Generate Maze").clicked();
+                    self.render_settings.corridor_window_open |=
+                        ui.button("Generate Corridor").clicked();
+                    self.render_settings.palette_window_open |= ui.button("Palette").clicked();
+                    self.render_settings.auto_tune_window_open |=
+                        ui.button("Auto Tune").clicked();
+                    self.render_settings.animation_window_open |=
+                        ui.button("Animation").clicked();
+                    ui.separator();
+                    ui.toggle_value(&mut self.render_settings.noclip, "Noclip (N)");
                 });
             });
+            egui::TopBottomPanel::top("Tabs").show(ctx, |ui| {
+                self.ui_tabs(ui, frame.wgpu_render_state());
+            });
             if reset_everything {
                 self.scene = Scene::default();
+                self.dirty_planes = None;
+                self.scene_name = None;
+                self.scene_warnings = validate_planes(&self.scene.world_planes());
                 rendering_changed = true;
             }
         }
@@ -228,6 +1424,74 @@ impl eframe::App for App {
             .show(ctx, |ui| {
                 ui.label(format!("FPS: {:.3}", 1.0 / dt.as_secs_f64()));
                 ui.label(format!("Frame Time: {:.3}ms", dt.as_secs_f64() * 1000.0));
+                ui.separator();
+                if RayTracingView::can_trace(self.viewport_rect) {
+                    let (width, height) = self.view.image_size(self.viewport_rect);
+                    ui.label(format!("Render Resolution: {width}x{height}"));
+                    ui.label(format!(
+                        "Render Scale: {:.0}% of {:.2} Pixels Per Point",
+                        self.view.render_scale() * 100.0,
+                        ctx.pixels_per_point(),
+                    ));
+                    let bytes = self.render_settings.accumulation_precision.accumulation_bytes(
+                        width,
+                        height,
+                        self.render_settings.light_groups,
+                    );
+                    let mib = bytes as f64 / (1024.0 * 1024.0);
+                    ui.label(format!("Accumulation Memory: {mib:.1} MiB"));
+                    if mib > self.render_settings.vram_budget_mb as f64 {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            format!(
+                                "Over the VRAM budget of {} MiB, lower the render scale or use 16 bit accumulation",
+                                self.render_settings.vram_budget_mb
+                            ),
+                        );
+                    }
+                } else {
+                    ui.label("Render Resolution: None");
+                }
+                let scene_hash = *self
+                    .scene_hash
+                    .get_or_insert_with(|| self.scene.content_hash());
+                ui.horizontal(|ui| {
+                    ui.label(format!("Scene Hash: {scene_hash}"))
+                        .on_hover_text(
+                            "Exported images store the hash of their scene, \
+                            renders with the same hash came from identical scenes",
+                        );
+                    if ui.small_button("Copy").clicked() {
+                        ui.ctx().copy_text(scene_hash.to_string());
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Record Stats:");
+                    ui.checkbox(&mut self.stats.recording, "");
+                });
+                ui.label(format!("Recorded Frames: {}", self.stats.frames().len()));
+                ui.horizontal(|ui| {
+                    if ui.button("Clear Stats").clicked() {
+                        self.stats.clear();
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.stats.frames().is_empty(),
+                            egui::Button::new("Export Stats"),
+                        )
+                        .clicked()
+                    {
+                        self.stats_file_dialog.save_file();
+                    }
+                });
+                ui.separator();
+                self.profiler.ui(ui);
+                ui.separator();
+                if self.stress_test.ui(ui) {
+                    self.dirty_planes = None;
+                    rendering_changed = true;
+                }
             });
 
         egui::Window::new("Render Settings")
@@ -236,29 +1500,76 @@ impl eframe::App for App {
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Render Type:");
-                    let name = |render_type: &RenderType| match render_type {
-                        RenderType::Unlit => "Unlit",
-                        RenderType::Lit => "Lit",
-                    };
                     egui::ComboBox::new("Render Type", "")
-                        .selected_text(name(&self.render_settings.render_type))
+                        .selected_text(self.render_settings.render_type.name())
                         .show_ui(ui, |ui| {
-                            rendering_changed |= ui
-                                .selectable_value(
-                                    &mut self.render_settings.render_type,
-                                    RenderType::Unlit,
-                                    name(&RenderType::Unlit),
-                                )
-                                .changed();
-                            rendering_changed |= ui
-                                .selectable_value(
-                                    &mut self.render_settings.render_type,
-                                    RenderType::Lit,
-                                    name(&RenderType::Lit),
-                                )
-                                .changed();
+                            for render_type in RenderType::ALL {
+                                rendering_changed |= ui
+                                    .selectable_value(
+                                        &mut self.render_settings.render_type,
+                                        render_type,
+                                        render_type.name(),
+                                    )
+                                    .changed();
+                            }
+                        });
+                });
+                if self.render_settings.render_type == RenderType::PortalDistance {
+                    ui.horizontal(|ui| {
+                        ui.label("Distance Range:");
+                        rendering_changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.render_settings.portal_distance_range)
+                                    .range(0.01..=f32::INFINITY)
+                                    .speed(0.1)
+                                    .suffix(self.scene.units.suffix()),
+                            )
+                            .on_hover_text(
+                                "How far rays go through portals before the colors reach red, \
+                                the dark bands are every tenth of it",
+                            )
+                            .changed();
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Background:");
+                    egui::ComboBox::new("Background", "")
+                        .selected_text(self.render_settings.background.name())
+                        .show_ui(ui, |ui| {
+                            for background in Background::ALL {
+                                rendering_changed |= ui
+                                    .selectable_value(
+                                        &mut self.render_settings.background,
+                                        background,
+                                        background.name(),
+                                    )
+                                    .changed();
+                            }
                         });
                 });
+                ui.horizontal(|ui| {
+                    ui.label("Viewport Background:");
+                    ui.color_edit_button_rgb(self.render_settings.viewport_background.as_mut())
+                        .on_hover_text("Shown wherever the image doesn't cover the viewport");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Scene Viewport Background:");
+                    let mut override_background = self.scene.viewport_background.is_some();
+                    if ui.checkbox(&mut override_background, "").changed() {
+                        self.scene.viewport_background = override_background
+                            .then_some(self.render_settings.viewport_background);
+                    }
+                    if let Some(background) = &mut self.scene.viewport_background {
+                        ui.color_edit_button_rgb(background.as_mut());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Flatten Export Background:");
+                    ui.checkbox(&mut self.render_settings.flatten_export_background, "")
+                        .on_hover_text(
+                            "Exported images are composited over the viewport background instead of keeping their alpha",
+                        );
+                });
                 ui.horizontal(|ui| {
                     ui.label("Samples Per Pixel:");
                     rendering_changed |= ui
@@ -284,264 +1595,1599 @@ impl eframe::App for App {
                         .changed();
                 });
                 ui.horizontal(|ui| {
-                    ui.label("Max Light Bounces:");
-                    rendering_changed |= ui
-                        .add(egui::DragValue::new(&mut self.render_settings.max_bounces))
-                        .changed();
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Accumulated Frames:");
-                    ui.add_enabled(false, egui::DragValue::new(&mut self.accumulated_frames));
-                    if ui.button("Clear").clicked() {
-                        self.accumulated_frames = 0;
+                    ui.label("Past Max Recursion:");
+                    egui::ComboBox::new("Portal Fill", "")
+                        .selected_text(self.render_settings.portal_fill.name())
+                        .show_ui(ui, |ui| {
+                            for portal_fill in PortalFill::ALL {
+                                rendering_changed |= ui
+                                    .selectable_value(
+                                        &mut self.render_settings.portal_fill,
+                                        portal_fill,
+                                        portal_fill.name(),
+                                    )
+                                    .changed();
+                            }
+                        })
+                        .response
+                        .on_hover_text("What portals show once rays stop going through them");
+                    if self.render_settings.portal_fill == PortalFill::Color {
+                        rendering_changed |= ui
+                            .color_edit_button_rgb(self.render_settings.portal_fill_color.as_mut())
+                            .changed();
                     }
                 });
-            });
-
-        egui::Window::new("Camera")
-            .open(&mut self.render_settings.camera_window_open)
-            .scroll(true)
-            .show(ctx, |ui| {
-                rendering_changed |= self.scene.camera.ui(ui);
                 ui.horizontal(|ui| {
-                    ui.label("Up Sky Color:");
+                    ui.label("Limit Portal Traversals Per Ray:");
                     rendering_changed |= ui
-                        .color_edit_button_rgb(self.scene.up_sky_color.as_mut())
+                        .checkbox(&mut self.render_settings.limit_portal_traversals, "")
+                        .changed();
+                    rendering_changed |= ui
+                        .add_enabled(
+                            self.render_settings.limit_portal_traversals,
+                            egui::DragValue::new(&mut self.render_settings.max_portal_traversals),
+                        )
                         .changed();
                 });
                 ui.horizontal(|ui| {
-                    ui.label("Up Sky Intensity:");
+                    ui.label("Cull Small Portals:");
                     rendering_changed |= ui
-                        .add(egui::DragValue::new(&mut self.scene.up_sky_intensity).speed(0.1))
+                        .checkbox(&mut self.render_settings.cull_small_portals, "")
+                        .on_hover_text(
+                            "Portals smaller than this many pixels on screen show their plane \
+                            instead of what is behind them, deep chains of small portals are skipped",
+                        )
+                        .changed();
+                    rendering_changed |= ui
+                        .add_enabled(
+                            self.render_settings.cull_small_portals,
+                            egui::DragValue::new(&mut self.render_settings.portal_cull_pixels)
+                                .range(0.0..=64.0)
+                                .speed(0.05)
+                                .suffix("px"),
+                        )
                         .changed();
                 });
                 ui.horizontal(|ui| {
-                    ui.label("Down Sky Color:");
+                    ui.label("Near Plane:");
                     rendering_changed |= ui
-                        .color_edit_button_rgb(self.scene.down_sky_color.as_mut())
+                        .add(
+                            egui::DragValue::new(&mut self.render_settings.near_plane)
+                                .range(0.0..=f32::INFINITY)
+                                .speed(0.01),
+                        )
                         .changed();
                 });
                 ui.horizontal(|ui| {
-                    ui.label("Down Sky Intensity:");
+                    ui.label("Portal Epsilon:");
                     rendering_changed |= ui
-                        .add(egui::DragValue::new(&mut self.scene.down_sky_intensity).speed(0.1))
+                        .add(
+                            egui::DragValue::new(&mut self.render_settings.portal_epsilon)
+                                .range(0.0..=1.0)
+                                .speed(0.0001),
+                        )
                         .changed();
                 });
                 ui.horizontal(|ui| {
-                    ui.label("Sun Color:");
+                    ui.label("Portal Cooldown Steps:");
+                    ui.add(egui::DragValue::new(
+                        &mut self.render_settings.portal_cooldown_steps,
+                    ));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Max Light Bounces:");
                     rendering_changed |= ui
-                        .color_edit_button_rgb(self.scene.sun_color.as_mut())
+                        .add(egui::DragValue::new(&mut self.render_settings.max_bounces))
                         .changed();
                 });
                 ui.horizontal(|ui| {
-                    ui.label("Sun Intensity:");
+                    ui.label("White Furnace Test:");
                     rendering_changed |= ui
-                        .add(egui::DragValue::new(&mut self.scene.sun_intensity).speed(0.1))
+                        .checkbox(&mut self.render_settings.furnace_test, "")
+                        .on_hover_text(
+                            "Uniform white sky and white diffuse surfaces, \
+                            Lit mode should converge to exactly white wherever paths can escape",
+                        )
                         .changed();
                 });
                 ui.horizontal(|ui| {
-                    ui.label("Sun Angular Radius:");
-                    rendering_changed |= ui.drag_angle(&mut self.scene.sun_size).changed();
-                    self.scene.sun_size = self.scene.sun_size.clamp(0.0, PI);
+                    ui.label("Sample Lights:");
+                    rendering_changed |= ui
+                        .checkbox(&mut self.render_settings.sample_lights, "")
+                        .on_hover_text(
+                            "Samples the emissive planes directly at every diffuse bounce instead of \
+                            only finding them by chance, converges to the same image faster. Only affects Lit mode",
+                        )
+                        .changed();
                 });
                 ui.horizontal(|ui| {
-                    ui.label("Sun Direction:");
-                    rendering_changed |= ui_vector3(ui, &mut self.scene.sun_direction).changed();
+                    ui.label("Spectral Rendering:");
+                    rendering_changed |= ui
+                        .checkbox(&mut self.render_settings.spectral, "")
+                        .on_hover_text(
+                            "Hero wavelength sampling, refractive planes with dispersion split light into colors. \
+                            Only affects Lit mode",
+                        )
+                        .changed();
                 });
-            });
-
-        egui::Window::new("Planes")
-            .open(&mut self.render_settings.planes_window_open)
-            .scroll(true)
-            .show(ctx, |ui| {
-                if ui.button("New Plane").clicked() {
-                    self.scene.planes.push(Plane::default());
-                    rendering_changed = true;
-                }
-
-                let mut to_delete = vec![];
-                for index in 0..self.scene.planes.len() {
-                    egui::CollapsingHeader::new(&self.scene.planes[index].name)
-                        .id_salt(index)
-                        .show(ui, |ui| {
-                            let plane = &mut self.scene.planes[index];
-                            ui.text_edit_singleline(&mut plane.name);
-                            ui.horizontal(|ui| {
-                                ui.label("Position:");
-                                rendering_changed |= ui_vector3(ui, &mut plane.position).changed();
-                            });
-                            ui.horizontal(|ui| {
-                                ui.label("XY Rotation:");
-                                rendering_changed |=
-                                    ui.drag_angle(&mut plane.xy_rotation).changed();
-                            });
-                            ui.horizontal(|ui| {
-                                ui.label("YZ Rotation:");
-                                rendering_changed |=
-                                    ui.drag_angle(&mut plane.yz_rotation).changed();
-                            });
-                            ui.horizontal(|ui| {
-                                ui.label("XZ Rotation:");
-                                rendering_changed |=
-                                    ui.drag_angle(&mut plane.xz_rotation).changed();
-                            });
-                            ui.horizontal(|ui| {
-                                ui.label("Size:");
-                                rendering_changed |= ui
-                                    .add(
-                                        egui::DragValue::new(&mut plane.width)
-                                            .speed(0.1)
-                                            .prefix("x:"),
-                                    )
-                                    .changed();
+                ui.horizontal(|ui| {
+                    ui.label("Accumulation Precision:");
+                    let name = |precision: &AccumulationPrecision| match precision {
+                        AccumulationPrecision::Full => "32 Bit",
+                        AccumulationPrecision::Half => "16 Bit",
+                    };
+                    egui::ComboBox::new("Accumulation Precision", "")
+                        .selected_text(name(&self.render_settings.accumulation_precision))
+                        .show_ui(ui, |ui| {
+                            for precision in [AccumulationPrecision::Full, AccumulationPrecision::Half]
+                            {
                                 rendering_changed |= ui
-                                    .add(
-                                        egui::DragValue::new(&mut plane.height)
-                                            .speed(0.1)
-                                            .prefix("z:"),
+                                    .selectable_value(
+                                        &mut self.render_settings.accumulation_precision,
+                                        precision,
+                                        name(&precision),
                                     )
                                     .changed();
-                            });
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "16 bit halves the memory and bandwidth of the accumulated image, \
+                            the rounding error is kept in a second texture so it still converges, \
+                            but nothing brighter than 65504 can be stored",
+                        );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Light Groups:");
+                    rendering_changed |= ui
+                        .checkbox(&mut self.render_settings.light_groups, "")
+                        .on_hover_text(
+                            "Accumulates the sky, the sun and every group of emissive planes in its own image, \
+                            so their intensities can be changed without starting the image over. \
+                            Always accumulates at 32 bit",
+                        )
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("VRAM Budget:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.render_settings.vram_budget_mb)
+                            .speed(16.0)
+                            .range(1..=u32::MAX)
+                            .suffix(" MiB"),
+                    )
+                    .on_hover_text(
+                        "The Info window warns when the accumulated image takes more memory than this",
+                    );
+                });
+                if self.render_settings.light_groups {
+                    ui.indent("Light Group Intensities", |ui| {
+                        for group in LightGroup::ALL {
                             ui.horizontal(|ui| {
-                                ui.label("Checker Count:");
-                                rendering_changed |= ui
-                                    .add(
-                                        egui::DragValue::new(&mut plane.checker_count_x)
-                                            .prefix("x:"),
+                                ui.label(format!("{}:", group.name()));
+                                ui.add(
+                                    egui::DragValue::new(
+                                        &mut self.render_settings.light_group_intensities[group],
                                     )
-                                    .changed();
-                                plane.checker_count_x = plane.checker_count_x.max(1);
-                                rendering_changed |= ui
-                                    .add(
-                                        egui::DragValue::new(&mut plane.checker_count_z)
-                                            .prefix("z:"),
-                                    )
-                                    .changed();
-                                plane.checker_count_z = plane.checker_count_z.max(1);
+                                    .speed(0.01)
+                                    .range(0.0..=f32::INFINITY),
+                                );
                             });
-                            ui.horizontal(|ui| {
-                                ui.label("Color:");
-                                rendering_changed |=
-                                    ui.color_edit_button_rgb(plane.color.as_mut()).changed();
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Caustic Regularization:");
+                    rendering_changed |= ui
+                        .drag_angle(&mut self.render_settings.caustic_regularization)
+                        .on_hover_text(
+                            "Makes the sun appear wider to light that refracted after a diffuse bounce, \
+                            caustics from the sun show up much faster but are blurred by this angle. \
+                            0 is unbiased",
+                        )
+                        .changed();
+                    self.render_settings.caustic_regularization = self
+                        .render_settings
+                        .caustic_regularization
+                        .clamp(0.0, PI * 0.5);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Display Linear Colors (Debug):");
+                    ui.checkbox(&mut self.render_settings.display_linear, "");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Tone Mapper:");
+                    egui::ComboBox::new("Tone Mapper", "")
+                        .selected_text(self.render_settings.tone_mapper.name())
+                        .show_ui(ui, |ui| {
+                            for tone_mapper in ToneMapper::ALL {
+                                ui.selectable_value(
+                                    &mut self.render_settings.tone_mapper,
+                                    tone_mapper,
+                                    tone_mapper.name(),
+                                );
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Compare Tone Mapper:");
+                    ui.checkbox(&mut self.render_settings.compare_tone_mappers, "")
+                        .on_hover_text(
+                            "Shows another tone mapper on the right of the viewport, \
+                            drag the line between them to move it",
+                        );
+                    ui.add_enabled_ui(self.render_settings.compare_tone_mappers, |ui| {
+                        egui::ComboBox::new("Compare Tone Mapper", "")
+                            .selected_text(self.render_settings.split_tone_mapper.name())
+                            .show_ui(ui, |ui| {
+                                for tone_mapper in ToneMapper::ALL {
+                                    ui.selectable_value(
+                                        &mut self.render_settings.split_tone_mapper,
+                                        tone_mapper,
+                                        tone_mapper.name(),
+                                    );
+                                }
                             });
-                            ui.horizontal(|ui| {
-                                ui.label("Checker Darkness:");
-                                rendering_changed |= ui
-                                    .add(egui::Slider::new(&mut plane.checker_darkness, 0.0..=1.0))
-                                    .changed();
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("False Color:");
+                    ui.checkbox(&mut self.render_settings.false_color, "")
+                        .on_hover_text(
+                            "Bands of exposure relative to middle grey, \
+                            purple is crushed, grey is middle grey and red is clipped",
+                        );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Zebra Stripes:");
+                    ui.checkbox(&mut self.render_settings.zebra_stripes, "")
+                        .on_hover_text("Stripes over pixels that are clipped on display");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Focus Peaking:");
+                    ui.checkbox(&mut self.render_settings.focus_peaking, "")
+                        .on_hover_text(
+                            "Highlights what is in focus with depth of field, without waiting for the blur to converge",
+                        );
+                    ui.add_enabled_ui(self.render_settings.focus_peaking, |ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut self.render_settings.focus_peaking_max_blur)
+                                .speed(0.05)
+                                .range(0.0..=f32::INFINITY)
+                                .prefix("Max Blur: ")
+                                .suffix("px"),
+                        )
+                        .on_hover_text("How many pixels across the blur can be to still count as in focus");
+                        ui.color_edit_button_rgb(self.render_settings.focus_peaking_color.as_mut());
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Luminance Histogram:");
+                    ui.checkbox(&mut self.render_settings.show_histogram, "");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Pixel Inspector:");
+                    ui.checkbox(&mut self.render_settings.pixel_inspector, "")
+                        .on_hover_text("Shows the accumulated value of the pixel under the cursor");
+                });
+                rendering_changed |= self.crop.ui(ui);
+                rendering_changed |= self.light_bake.ui(ui);
+                rendering_changed |= self.irradiance_probes.ui(ui);
+                if let Some(render_state) = frame.wgpu_render_state() {
+                    rendering_changed |= self.multi_gpu.ui(ui, render_state);
+                    ui.horizontal(|ui| {
+                        ui.label("Workgroup Size:");
+                        let automatic = WorkgroupSize::for_limits(&render_state.device.limits());
+                        let name = |size: Option<WorkgroupSize>| match size {
+                            Some(size) => size.name().to_owned(),
+                            None => format!("Automatic ({})", automatic.name()),
+                        };
+                        let mut workgroup_size = self.workgroup_size_override;
+                        egui::ComboBox::new("Workgroup Size", "")
+                            .selected_text(name(workgroup_size))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut workgroup_size, None, name(None));
+                                for size in WorkgroupSize::ALL {
+                                    ui.selectable_value(&mut workgroup_size, Some(size), name(Some(size)));
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "The shape of the ray tracing workgroups, \
+                                automatic picks one from the adapter's subgroup size and limits",
+                            );
+                        if workgroup_size != self.workgroup_size_override {
+                            self.workgroup_size_override = workgroup_size;
+                            if let Some(ray_tracer) = render_state
+                                .renderer
+                                .write()
+                                .callback_resources
+                                .get_mut::<RayTracingRenderer>()
+                                && let Err(error) = ray_tracer.set_workgroup_size(
+                                    &render_state.device,
+                                    workgroup_size.unwrap_or(automatic),
+                                )
+                            {
+                                self.notifications
+                                    .error(format!("Failed to change the workgroup size: {error}"));
+                            }
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Limit FPS:");
+                    ui.checkbox(&mut self.render_settings.limit_fps, "");
+                    ui.add_enabled(
+                        self.render_settings.limit_fps,
+                        egui::DragValue::new(&mut self.render_settings.max_fps)
+                            .range(1.0..=1000.0)
+                            .suffix(" FPS"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Present Mode:");
+                    egui::ComboBox::new("Present Mode", "")
+                        .selected_text(self.render_settings.present_mode.name())
+                        .show_ui(ui, |ui| {
+                            for present_mode in PresentMode::ALL {
+                                ui.add_enabled_ui(
+                                    present_mode
+                                        .is_supported(&self.launch.supported_present_modes),
+                                    |ui| {
+                                        ui.selectable_value(
+                                            &mut self.render_settings.present_mode,
+                                            present_mode,
+                                            present_mode.name(),
+                                        )
+                                    },
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Fifo is vsync, Immediate has the lowest latency but tears, \
+                            Mailbox and Immediate aren't supported by every display",
+                        );
+                    if self.render_settings.present_mode != self.launch.settings.present_mode
+                        && ui
+                            .button("Reopen Window To Apply")
+                            .on_hover_text("The window's surface is only configured when it is created")
+                            .clicked()
+                    {
+                        self.launch
+                            .relaunch(ctx, LaunchSettings {
+                                present_mode: self.render_settings.present_mode,
+                                gpu_validation: self.render_settings.gpu_validation,
                             });
-                            ui.horizontal(|ui| {
-                                ui.label("Emssive Color:");
-                                rendering_changed |= ui
-                                    .color_edit_button_rgb(plane.emissive_color.as_mut())
-                                    .changed();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("GPU Validation:");
+                    ui.checkbox(&mut self.render_settings.gpu_validation, "")
+                        .on_hover_text(
+                            "Turns on wgpu's validation and debug layers and logs errors instead of crashing, \
+                            slow but the log makes rendering glitches much easier to report",
+                        );
+                    if self.render_settings.gpu_validation != self.launch.settings.gpu_validation
+                        && ui
+                            .button("Reopen Window To Apply")
+                            .on_hover_text("The wgpu instance is only created with the window")
+                            .clicked()
+                    {
+                        self.launch
+                            .relaunch(ctx, LaunchSettings {
+                                present_mode: self.render_settings.present_mode,
+                                gpu_validation: self.render_settings.gpu_validation,
                             });
-                            ui.horizontal(|ui| {
-                                ui.label("Emission Intensity:");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Background Accumulation:");
+                    ui.checkbox(&mut self.render_settings.background_accumulation, "");
+                });
+                ui.add_enabled_ui(self.render_settings.background_accumulation, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Frame Time Budget:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.render_settings.frame_time_budget_ms)
+                                .range(1.0..=1000.0)
+                                .speed(0.1)
+                                .suffix("ms"),
+                        )
+                        .on_hover_text(
+                            "How long tracing a frame may take on the gpu, \
+                            or the whole frame on the cpu if the gpu can't measure it",
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Max Samples Per Dispatch:");
+                        ui.add(
+                            egui::DragValue::new(
+                                &mut self.render_settings.max_samples_per_dispatch,
+                            )
+                            .range(1..=u32::MAX),
+                        );
+                    });
+                    ui.label(format!(
+                        "Samples This Frame: {}",
+                        self.adaptive_samples_per_pixel
+                    ));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Reset Accumulation On:");
+                    egui::ComboBox::new("Reset Accumulation On", "")
+                        .selected_text(self.render_settings.reset_policy.name())
+                        .show_ui(ui, |ui| {
+                            for policy in ResetPolicy::ALL {
                                 rendering_changed |= ui
-                                    .add(
-                                        egui::DragValue::new(&mut plane.emission_intensity)
-                                            .speed(0.1),
+                                    .selectable_value(
+                                        &mut self.render_settings.reset_policy,
+                                        policy,
+                                        policy.name(),
                                     )
                                     .changed();
-                            });
-                            ui.horizontal(|ui| {
-                                ui.label("Emissive Checker Darkness:");
-                                rendering_changed |= ui
-                                    .add(egui::Slider::new(
-                                        &mut plane.emissive_checker_darkness,
-                                        0.0..=1.0,
-                                    ))
-                                    .changed();
-                            });
-                            fn ui_portal_connection(
-                                ui: &mut egui::Ui,
-                                planes: &mut [Plane],
-                                index: usize,
-                                portal: impl Fn(&mut Plane) -> &mut PortalConnection,
-                            ) -> bool {
-                                let mut changed = false;
+                            }
+                        });
+                });
+                ui.add_enabled_ui(
+                    self.render_settings.reset_policy == ResetPolicy::TimedBlend,
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Blend Time:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.render_settings.blend_time)
+                                    .range(0.001..=60.0)
+                                    .speed(0.01)
+                                    .suffix("s"),
+                            );
+                        });
+                    },
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Render Scale:");
+                    let mut percent = self.render_settings.render_scale * 100.0;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut percent)
+                                .range(10.0..=100.0)
+                                .suffix("%"),
+                        )
+                        .on_hover_text(format!(
+                            "Of the viewport's physical resolution, {}% traces a pixel per point of the ui",
+                            (100.0 / ctx.pixels_per_point()).round()
+                        ))
+                        .changed()
+                    {
+                        self.render_settings.render_scale = percent / 100.0;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Dynamic Resolution:");
+                    ui.checkbox(&mut self.render_settings.dynamic_resolution, "")
+                        .on_hover_text("Lowers the resolution while the camera is moving");
+                });
+                ui.add_enabled_ui(self.render_settings.dynamic_resolution, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Moving Render Scale:");
+                        let mut percent = self.render_settings.moving_render_scale * 100.0;
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut percent)
+                                    .range(10.0..=100.0)
+                                    .suffix("%"),
+                            )
+                            .changed()
+                        {
+                            self.render_settings.moving_render_scale = percent / 100.0;
+                        }
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Draft While Editing:");
+                    ui.checkbox(&mut self.render_settings.draft_while_editing, "")
+                        .on_hover_text(
+                            "Renders with the draft preset while editing planes or the sky",
+                        );
+                });
+                ui.add_enabled_ui(self.render_settings.draft_while_editing, |ui| {
+                    ui.collapsing("Draft Preset", |ui| {
+                        // only restarts the image if the draft is what is being shown
+                        rendering_changed |= self.render_settings.draft_preset.ui(ui)
+                            && self.draft_frames_left > 0;
+                        ui.horizontal(|ui| {
+                            ui.label("Idle Frames:");
+                            ui.add(egui::DragValue::new(
+                                &mut self.render_settings.draft_idle_frames,
+                            ))
+                            .on_hover_text(
+                                "How many frames without edits before the full settings are back",
+                            );
+                        });
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Accumulated Frames:");
+                    ui.add_enabled(
+                        false,
+                        egui::DragValue::new(&mut self.view.accumulated_frames()),
+                    );
+                    if ui.button("Clear").clicked() {
+                        self.view.reset();
+                    }
+                });
+                ui.label(format!(
+                    "Accumulated Samples: {}",
+                    self.view.accumulated_samples()
+                ));
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Save Accumulation")
+                        .on_hover_text("Saves the accumulated image with the scene and render settings")
+                        .clicked()
+                    {
+                        self.accumulation_file_interaction = FileInteraction::Save;
+                        self.accumulation_file_dialog.save_file();
+                    }
+                    if ui.button("Resume Accumulation").clicked() {
+                        self.accumulation_file_interaction = FileInteraction::Load;
+                        self.accumulation_file_dialog.pick_file();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Seed:");
+                    rendering_changed |= ui
+                        .add(egui::DragValue::new(&mut self.render_settings.seed))
+                        .changed();
+                    if ui.button("Randomize").clicked() {
+                        self.render_settings.seed = rand::random();
+                        rendering_changed = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Embed Export Metadata:");
+                    ui.checkbox(&mut self.render_settings.export_metadata, "")
+                        .on_hover_text(
+                            "Stores the scene, render settings, seed and sample count in exported images",
+                        );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Draw Export Footer:");
+                    ui.add_enabled(
+                        self.render_settings.export_metadata,
+                        egui::Checkbox::without_text(&mut self.render_settings.export_footer),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Draw Settings Caption:");
+                    ui.checkbox(&mut self.render_settings.export_caption, "")
+                        .on_hover_text(
+                            "Writes the samples per pixel, bounces, denoiser and tone mapper below exported png images, \
+                            so images comparing settings say what they compare",
+                        );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Import/Export Scale:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.render_settings.import_export_scale)
+                            .range(0.0001..=10000.0)
+                            .speed(0.01),
+                    )
+                    .on_hover_text(
+                        "Lengths in opened files are multiplied by this and divided by it when saving, \
+                        scene files are the only format so far",
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("AOVs:");
+                    ui.checkbox(&mut self.render_settings.aovs, "")
+                        .on_hover_text(
+                            "Traces the albedo, normal, depth, plane and portal count of every pixel, \
+                            exported as extra layers of exr images along with cryptomatte mattes of the planes",
+                        );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Denoise Export:");
+                    ui.add_enabled(
+                        self.render_settings.aovs,
+                        egui::Checkbox::without_text(&mut self.render_settings.denoise_export),
+                    )
+                    .on_hover_text(
+                        "Smooths exported images with the AOVs as guides, \
+                        exr images keep the noisy image and get the denoised one as another layer",
+                    );
+                });
+            });
+
+        // the queue snapshots the render settings, so the open flag can't be borrowed from them
+        let mut render_queue_window_open = self.render_settings.render_queue_window_open;
+        egui::Window::new("Render Queue")
+            .open(&mut render_queue_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                self.render_queue.ui(
+                    ui,
+                    &self.scene,
+                    self.scene_name.as_deref().unwrap_or("Untitled"),
+                    &self.render_settings,
+                );
+            });
+        self.render_settings.render_queue_window_open = render_queue_window_open;
+
+        // applying the recommendation changes the render settings, so the open flag can't be borrowed from them
+        let mut auto_tune_window_open = self.render_settings.auto_tune_window_open;
+        egui::Window::new("Auto Tune")
+            .open(&mut auto_tune_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                rendering_changed |= self.auto_tuner.ui(
+                    ui,
+                    &self.scene,
+                    &mut self.render_settings,
+                    self.view.image_size(self.viewport_rect),
+                );
+            });
+        self.render_settings.auto_tune_window_open = auto_tune_window_open;
+
+        egui::Window::new("Animation")
+            .open(&mut self.render_settings.animation_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                if self.timeline.ui(
+                    ui,
+                    &mut self.scene.animation,
+                    &self.scene.camera,
+                    &self.scene.planes,
+                ) {
+                    let (camera_moved, planes_changed) = self.scene.animation.apply(
+                        self.timeline.time,
+                        &mut self.scene.camera,
+                        &mut self.scene.planes,
+                    );
+                    if planes_changed {
+                        self.dirty_planes = None;
+                    }
+                    rendering_changed |= camera_moved || planes_changed;
+                }
+            });
+
+        egui::Window::new("Log")
+            .open(&mut self.render_settings.log_window_open)
+            .default_size([600.0, 300.0])
+            .show(ctx, |ui| {
+                ui_log(ui, &self.log, &mut self.log_filter);
+            });
+
+        egui::Window::new("Markers")
+            .open(&mut self.render_settings.markers_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                rendering_changed |= ui_markers(
+                    ui,
+                    &mut self.scene.markers,
+                    &mut self.scene.camera,
+                    self.scene.units,
+                );
+            });
+
+        egui::Window::new("Probe Volumes")
+            .open(&mut self.render_settings.probe_volumes_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                rendering_changed |= ui_probe_volumes(
+                    ui,
+                    &mut self.scene.probe_volumes,
+                    &flatten_planes(&self.scene.planes, &self.scene.groups),
+                    &self.scene.camera,
+                    self.scene.units,
+                );
+            });
+
+        egui::Window::new("Find & Replace")
+            .open(&mut self.render_settings.find_replace_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                if self.find_replace.ui(ui, &mut self.scene.planes) {
+                    self.dirty_planes = None;
+                    self.scene_warnings = validate_planes(&self.scene.world_planes());
+                    rendering_changed = true;
+                }
+            });
+
+        egui::Window::new("Palette")
+            .open(&mut self.render_settings.palette_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                if self
+                    .palette_tool
+                    .ui(ui, &mut self.scene.planes, &self.scene.groups)
+                {
+                    self.dirty_planes = None;
+                    rendering_changed = true;
+                }
+            });
+
+        egui::Window::new("Generate Maze")
+            .open(&mut self.render_settings.maze_window_open)
+            .show(ctx, |ui| {
+                if self.maze_generator.ui(ui, self.scene.units) {
+                    let maze = self.maze_generator.generate();
+                    self.scene.planes = maze.planes;
+                    self.scene.camera.position = maze.start;
+                    self.camera_controller = CameraController::FreeFly;
+                    self.dirty_planes = None;
+                    self.scene_warnings = validate_planes(&self.scene.world_planes());
+                    rendering_changed = true;
+                }
+            });
+
+        egui::Window::new("Generate Corridor")
+            .open(&mut self.render_settings.corridor_window_open)
+            .show(ctx, |ui| {
+                if self
+                    .corridor_generator
+                    .ui(ui, self.scene.units, &self.scene.planes)
+                {
+                    self.scene.planes.extend(self.corridor_generator.generate());
+                    self.dirty_planes = None;
+                    self.scene_warnings = validate_planes(&self.scene.world_planes());
+                    rendering_changed = true;
+                }
+            });
+
+        let mut sky_changed = false;
+        egui::Window::new("Camera")
+            .open(&mut self.render_settings.camera_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Units:");
+                    let old_units = self.scene.units;
+                    egui::ComboBox::new("Units", "")
+                        .selected_text(old_units.name())
+                        .show_ui(ui, |ui| {
+                            for units in Units::ALL {
+                                ui.selectable_value(&mut self.scene.units, units, units.name());
+                            }
+                        });
+                    if self.scene.units != old_units {
+                        self.scene
+                            .scale_lengths(old_units.conversion_factor(self.scene.units));
+                        self.dirty_planes = None;
+                        rendering_changed = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Up Axis:");
+                    let old_up_axis = self.scene.up_axis;
+                    egui::ComboBox::new("Up Axis", "")
+                        .selected_text(old_up_axis.name())
+                        .show_ui(ui, |ui| {
+                            for up_axis in UpAxis::ALL {
+                                ui.selectable_value(
+                                    &mut self.scene.up_axis,
+                                    up_axis,
+                                    up_axis.name(),
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Which way the sky and the orbiting camera treat as up, for scenes made with z up",
+                        );
+                    if self.scene.up_axis != old_up_axis {
+                        // gravity keeps pointing the same way relative to the scene's up
+                        self.scene.gravity = old_up_axis.convert(self.scene.up_axis, self.scene.gravity);
+                        self.scene.up_rotation = Rotor::IDENTITY;
+                        rendering_changed = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui
+                        .checkbox(&mut self.scene.escher_mode, "Escher Mode")
+                        .on_hover_text(
+                            "Portals that turn the camera turn which way is up along with it, \
+                            for the sky, the sun and orbiting",
+                        )
+                        .changed()
+                        && !self.scene.escher_mode
+                    {
+                        self.scene.up_rotation = Rotor::IDENTITY;
+                        rendering_changed = true;
+                    }
+                    // the identity rotation and its negation are the same rotation
+                    let turned = self.scene.up_rotation.s.abs() < 0.999_999;
+                    if ui
+                        .add_enabled(turned, egui::Button::new("Reset Up"))
+                        .on_hover_text("Turns up back to the up axis")
+                        .clicked()
+                    {
+                        self.scene.up_rotation = Rotor::IDENTITY;
+                        rendering_changed = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Gravity:");
+                    ui_vector3_with_suffix(
+                        ui,
+                        &mut self.scene.gravity,
+                        &format!("{}/s²", self.scene.units.suffix()),
+                    );
+                });
+                self.camera_controller.ui(
+                    ui,
+                    &self.scene.camera,
+                    &self.scene.world_planes(),
+                    self.scene.up_frame(),
+                    self.scene.units,
+                );
+                rendering_changed |= self.scene.camera.ui(ui, self.scene.units);
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Copy Pose")
+                        .on_hover_text("Copies the camera's position and rotation as text")
+                        .clicked()
+                    {
+                        ctx.copy_text(self.scene.camera.pose());
+                    }
+                    if ui.button("Paste Pose").clicked() {
+                        self.paste_target = PasteTarget::CameraPose;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::RequestPaste);
+                    }
+                });
+                ui.collapsing("Bookmarks", |ui| {
+                    let mut to_delete = None;
+                    for (index, bookmark) in self.scene.bookmarks.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut bookmark.name);
+                            if ui.button("Go To").clicked() {
+                                self.scene.camera.go_to(bookmark);
+                                rendering_changed = true;
+                            }
+                            if ui.button("Delete").clicked() {
+                                to_delete = Some(index);
+                            }
+                        });
+                    }
+                    if let Some(index) = to_delete {
+                        self.scene.bookmarks.remove(index);
+                    }
+                    if ui.button("Bookmark Camera").clicked() {
+                        let name = format!("Bookmark {}", self.scene.bookmarks.len() + 1);
+                        self.scene.bookmarks.push(self.scene.camera.bookmark(name));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Mouse Sensitivity:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.render_settings.mouse_sensitivity)
+                            .speed(0.0001)
+                            .range(0.0..=0.1),
+                    )
+                    .on_hover_text(
+                        "How far dragging the viewport with the right mouse button turns the camera",
+                    );
+                    ui.checkbox(&mut self.render_settings.invert_mouse_y, "Invert Y");
+                });
+                ui.collapsing("Gamepad", |ui| {
+                    self.gamepads.ui(ui);
+                    ui.label(
+                        "The left stick and triggers fly, the right stick turns, the bumpers roll \
+                        and pressing the left stick boosts",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Dead Zone:");
+                        ui.add(egui::Slider::new(
+                            &mut self.render_settings.gamepad_dead_zone,
+                            0.0..=0.9,
+                        ));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Look Sensitivity:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.render_settings.gamepad_sensitivity)
+                                .speed(0.01)
+                                .range(0.0..=10.0),
+                        );
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Noclip (N):");
+                    ui.checkbox(&mut self.render_settings.noclip, "");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Speed Field Of View:");
+                    ui.checkbox(&mut self.render_settings.speed_fov, "")
+                        .on_hover_text("Widens the field of view the faster the camera flies");
+                    ui.add_enabled(
+                        self.render_settings.speed_fov,
+                        egui::DragValue::from_get_set(|value| {
+                            if let Some(value) = value {
+                                self.render_settings.speed_fov_widening =
+                                    (value as f32).to_radians();
+                            }
+                            self.render_settings.speed_fov_widening.to_degrees() as f64
+                        })
+                        .range(0.0..=90.0)
+                        .prefix("+")
+                        .suffix("°"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Teleport Effect:");
+                    ui.checkbox(&mut self.render_settings.teleport_effect, "")
+                        .on_hover_text("Briefly distorts the screen after flying through a portal");
+                    ui.add_enabled(
+                        self.render_settings.teleport_effect,
+                        egui::DragValue::new(&mut self.render_settings.teleport_effect_duration)
+                            .range(0.0..=5.0)
+                            .speed(0.01)
+                            .suffix("s"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Collision Debug:");
+                    if ui.checkbox(&mut self.collision_debug.enabled, "").changed() {
+                        self.collision_debug.clear();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Up Sky Color:");
+                    sky_changed |= ui
+                        .color_edit_button_rgb(self.scene.up_sky_color.as_mut())
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Up Sky Intensity:");
+                    sky_changed |= ui
+                        .add(egui::DragValue::new(&mut self.scene.up_sky_intensity).speed(0.1))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Down Sky Color:");
+                    sky_changed |= ui
+                        .color_edit_button_rgb(self.scene.down_sky_color.as_mut())
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Down Sky Intensity:");
+                    sky_changed |= ui
+                        .add(egui::DragValue::new(&mut self.scene.down_sky_intensity).speed(0.1))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Sun Color:");
+                    sky_changed |= ui
+                        .color_edit_button_rgb(self.scene.sun_color.as_mut())
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Sun Intensity:");
+                    sky_changed |= ui
+                        .add(egui::DragValue::new(&mut self.scene.sun_intensity).speed(0.1))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Sun Angular Radius:");
+                    sky_changed |= ui.drag_angle(&mut self.scene.sun_size).changed();
+                    self.scene.sun_size = self.scene.sun_size.clamp(0.0, PI);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Sun Direction:");
+                    sky_changed |= ui_sun_direction(ui, &mut self.scene.sun_direction, self.scene.up_axis).changed();
+                });
+                egui::CollapsingHeader::new("Raw Sun Direction").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Sun Direction:");
+                        sky_changed |= ui_vector3(ui, &mut self.scene.sun_direction).changed();
+                    });
+                });
+            });
+
+        let mut copy_clicked = false;
+        let mut paste_clicked = false;
+        let planes_changed = egui::Window::new("Planes")
+            .open(&mut self.render_settings.planes_window_open)
+            .show(ctx, |ui| {
+                let mut changed = false;
+
+                if !self.scene_warnings.is_empty() {
+                    egui::CollapsingHeader::new(
+                        egui::RichText::new(format!("Warnings ({})", self.scene_warnings.len()))
+                            .color(ui.visuals().warn_fg_color),
+                    )
+                    .show(ui, |ui| {
+                        for warning in &self.scene_warnings {
+                            let color = match warning.severity {
+                                Severity::Warning => ui.visuals().warn_fg_color,
+                                Severity::Error => ui.visuals().error_fg_color,
+                            };
+                            ui.colored_label(color, &warning.message);
+                        }
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("New Plane").clicked() {
+                        self.scene.planes.push(Plane::default());
+                        self.dirty_planes = None;
+                        changed = true;
+                    }
+                    copy_clicked = ui
+                        .add_enabled(
+                            !self.selected_planes.is_empty(),
+                            egui::Button::new("Copy Selected"),
+                        )
+                        .on_hover_text("Ctrl+C")
+                        .clicked();
+                    paste_clicked = ui.button("Paste").on_hover_text("Ctrl+V").clicked();
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("New Group").clicked() {
+                        self.scene.groups.push(PlaneGroup::default());
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.selected_planes.is_empty(),
+                            egui::Button::new("Group Selected"),
+                        )
+                        .on_hover_text("Puts the selected planes in a new group at their middle")
+                        .clicked()
+                    {
+                        let positions = self
+                            .scene
+                            .world_planes()
+                            .iter()
+                            .filter(|plane| self.selected_planes.contains(&plane.id))
+                            .map(|plane| plane.position)
+                            .collect::<Vec<_>>();
+                        let group = PlaneGroup {
+                            position: positions.iter().fold(Vector3::ZERO, |sum, &p| sum + p)
+                                / positions.len().max(1) as f32,
+                            ..PlaneGroup::default()
+                        };
+                        let id = group.id;
+                        self.scene.groups.push(group);
+                        for plane in &mut self.scene.planes {
+                            if self.selected_planes.contains(&plane.id) {
+                                set_plane_group(plane, &self.scene.groups, Some(id));
+                            }
+                        }
+                        self.dirty_planes = None;
+                        changed = true;
+                    }
+                });
+                if !self.scene.groups.is_empty() {
+                    egui::CollapsingHeader::new("Groups")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            if ui_groups(
+                                ui,
+                                &mut self.scene.planes,
+                                &mut self.scene.groups,
+                                self.scene.units,
+                            ) {
+                                self.dirty_planes = None;
+                                changed = true;
+                            }
+                        });
+                }
+
+                let mut to_delete = vec![];
+                let mut to_duplicate = vec![];
+                let mut moved = None;
+                let header_height = ui.spacing().interact_size.y + ui.spacing().item_spacing.y;
+                self.plane_row_heights
+                    .resize(self.scene.planes.len(), header_height);
+                egui::ScrollArea::vertical().show_viewport(ui, |ui, viewport| {
+                    let mut top = 0.0;
+                    for index in 0..self.scene.planes.len() {
+                        let height = self.plane_row_heights[index];
+                        // planes out of view don't build any widgets, they only keep the space they took up when last shown
+                        if top + height < viewport.min.y || top > viewport.max.y {
+                            ui.add_space(height);
+                            top += height;
+                            continue;
+                        }
+                        let row_top = ui.cursor().top();
+                        // tracks whether this plane changed, to only upload it
+                        let others_changed = std::mem::take(&mut changed);
+                        let (_, header, _) =
+                            egui::collapsing_header::CollapsingState::load_with_default_open(
+                                ui.ctx(),
+                                ui.make_persistent_id(index),
+                                false,
+                            )
+                            .show_header(ui, |ui| {
+                                ui.dnd_drag_source(ui.id().with(("Drag Plane", index)), index, |ui| {
+                                    ui.label("☰");
+                                })
+                                .response
+                                .on_hover_text("Drag to reorder");
+                                let id = self.scene.planes[index].id;
+                                let mut selected = self.selected_planes.contains(&id);
+                                if ui
+                                    .checkbox(&mut selected, "")
+                                    .on_hover_text("Selects the plane for copying")
+                                    .changed()
+                                {
+                                    if selected {
+                                        self.selected_planes.insert(id);
+                                    } else {
+                                        self.selected_planes.remove(&id);
+                                    }
+                                }
+                                ui.label(&self.scene.planes[index].name);
+                                if let Some(group) = self.scene.planes[index]
+                                    .group
+                                    .and_then(|id| find_group(&self.scene.groups, id))
+                                {
+                                    ui.weak(format!("in {}", self.scene.groups[group].name));
+                                }
+                            })
+                            .body(|ui| {
+                                let units = self.scene.units;
+                                let plane = &mut self.scene.planes[index];
+                                ui.text_edit_singleline(&mut plane.name);
+                                ui.horizontal(|ui| {
+                                    ui.label("Group:");
+                                    if let Some(group) = ui_pick_group(
+                                        ui,
+                                        ("Plane Group", plane.id),
+                                        plane.group,
+                                        &self.scene.groups,
+                                        None,
+                                    ) {
+                                        set_plane_group(plane, &self.scene.groups, group);
+                                        changed = true;
+                                    }
+                                })
+                                .response
+                                .on_hover_text("The position and rotation are relative to the group");
+                                ui.horizontal(|ui| {
+                                    ui.label("Position:");
+                                    changed |=
+                                        ui_vector3_with_suffix(ui, &mut plane.position, units.suffix())
+                                            .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("XY Rotation:");
+                                    changed |= ui.drag_angle(&mut plane.xy_rotation).changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("YZ Rotation:");
+                                    changed |= ui.drag_angle(&mut plane.yz_rotation).changed();
+                                });
                                 ui.horizontal(|ui| {
-                                    ui.label("Connected Plane:");
-                                    egui::ComboBox::new(("Front Connected Portal", index), "")
-                                        .selected_text(
-                                            portal(&mut planes[index])
-                                                .other_index
-                                                .map(|other_index| {
-                                                    planes[other_index].name.as_str()
-                                                })
-                                                .unwrap_or("None"),
+                                    ui.label("XZ Rotation:");
+                                    changed |= ui.drag_angle(&mut plane.xz_rotation).changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Size:");
+                                    changed |= ui
+                                        .add(
+                                            egui::DragValue::new(&mut plane.width)
+                                                .speed(0.1)
+                                                .prefix("x:")
+                                                .suffix(units.suffix()),
+                                        )
+                                        .changed();
+                                    changed |= ui
+                                        .add(
+                                            egui::DragValue::new(&mut plane.height)
+                                                .speed(0.1)
+                                                .prefix("z:")
+                                                .suffix(units.suffix()),
+                                        )
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Checker Count:");
+                                    changed |= ui
+                                        .add(
+                                            egui::DragValue::new(&mut plane.checker_count_x)
+                                                .prefix("x:"),
+                                        )
+                                        .changed();
+                                    plane.checker_count_x = plane.checker_count_x.max(1);
+                                    changed |= ui
+                                        .add(
+                                            egui::DragValue::new(&mut plane.checker_count_z)
+                                                .prefix("z:"),
+                                        )
+                                        .changed();
+                                    plane.checker_count_z = plane.checker_count_z.max(1);
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Color:");
+                                    changed |= ui.color_edit_button_rgb(plane.color.as_mut()).changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Checker Darkness:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(&mut plane.checker_darkness, 0.0..=1.0))
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Texture:");
+                                    match &plane.texture {
+                                        Some(path) => {
+                                            let name = path.file_name().unwrap_or(path.as_os_str());
+                                            ui.label(name.to_string_lossy())
+                                                .on_hover_text(path.display().to_string());
+                                        }
+                                        None => {
+                                            ui.weak("None");
+                                        }
+                                    }
+                                    if ui.button("Pick").clicked() {
+                                        self.texture_plane = Some(plane.id);
+                                        self.texture_file_dialog.pick_file();
+                                    }
+                                    if plane.texture.is_some() && ui.button("Clear").clicked() {
+                                        plane.texture = None;
+                                        changed = true;
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Emssive Color:");
+                                    changed |= ui
+                                        .color_edit_button_rgb(plane.emissive_color.as_mut())
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Emission Intensity:");
+                                    changed |= ui
+                                        .add(
+                                            egui::DragValue::new(&mut plane.emission_intensity)
+                                                .speed(0.1),
+                                        )
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Emissive Checker Darkness:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(
+                                            &mut plane.emissive_checker_darkness,
+                                            0.0..=1.0,
+                                        ))
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Emissive Sides:");
+                                    changed |= plane.emissive_sides.ui(ui);
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Emission Profile:");
+                                    ui.vertical(|ui| {
+                                        changed |= plane.emission_profile.ui(ui);
+                                    });
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Metallic:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(&mut plane.metallic, 0.0..=1.0))
+                                        .on_hover_text(
+                                            "Metals reflect in their color and don't scatter light diffusely, \
+                                            only affects Lit mode",
+                                        )
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Roughness:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(&mut plane.roughness, 0.0..=1.0))
+                                        .on_hover_text("0 reflects like a mirror, only affects Lit mode")
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Specular:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(&mut plane.specular, 0.0..=1.0))
+                                        .on_hover_text(
+                                            "How much a non-metal reflects like glossy plastic, 0 is completely matte, \
+                                            only affects Lit mode",
                                         )
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Transmission:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(&mut plane.transmission, 0.0..=1.0))
+                                        .on_hover_text(
+                                            "How much light refracts through the plane instead of scattering off it, \
+                                            the back side is treated as the inside of the material",
+                                        )
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("IOR:");
+                                    changed |= ui
+                                        .add(
+                                            egui::DragValue::new(&mut plane.ior)
+                                                .speed(0.01)
+                                                .range(1.0..=4.0),
+                                        )
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Dispersion:");
+                                    changed |= ui
+                                        .add(
+                                            egui::DragValue::new(&mut plane.dispersion)
+                                                .speed(0.001)
+                                                .range(0.0..=0.2)
+                                                .suffix("µm²"),
+                                        )
+                                        .on_hover_text(
+                                            "Cauchy B coefficient, only visible with spectral rendering enabled",
+                                        )
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Light Group:");
+                                    egui::ComboBox::new("Light Group", "")
+                                        .selected_text(plane.light_group.name())
                                         .show_ui(ui, |ui| {
-                                            changed |= ui
-                                                .selectable_value(
-                                                    &mut portal(&mut planes[index]).other_index,
-                                                    None,
-                                                    "None",
-                                                )
-                                                .changed();
-                                            for other_index in 0..planes.len() {
-                                                let name = planes[other_index].name.clone();
+                                            for group in LightGroup::ALL {
                                                 changed |= ui
                                                     .selectable_value(
-                                                        &mut portal(&mut planes[index]).other_index,
-                                                        Some(other_index),
-                                                        name,
+                                                        &mut plane.light_group,
+                                                        group,
+                                                        group.name(),
                                                     )
                                                     .changed();
                                             }
                                         });
                                 });
-                                // ui.horizontal(|ui| {
-                                //     ui.label("Flip:");
-                                //     ui.checkbox(&mut portal(&mut planes[index]).flip, "");
-                                // });
-                                changed
-                            }
-                            ui.collapsing("Front Portal", |ui| {
-                                rendering_changed |= ui_portal_connection(
-                                    ui,
-                                    &mut self.scene.planes,
-                                    index,
-                                    |plane| &mut plane.front_portal,
-                                );
-                            });
-                            ui.collapsing("Back Portal", |ui| {
-                                rendering_changed |= ui_portal_connection(
-                                    ui,
-                                    &mut self.scene.planes,
-                                    index,
-                                    |plane| &mut plane.back_portal,
-                                );
+                                ui.horizontal(|ui| {
+                                    ui.label("Shadow Catcher:");
+                                    changed |= ui
+                                        .checkbox(&mut plane.shadow_catcher, "")
+                                        .on_hover_text(
+                                            "The camera sees only the shadows and light cast onto the plane over the background, \
+                                            use a transparent background to composite them onto a photograph. Only affects Lit mode",
+                                        )
+                                        .changed();
+                                    // the shadows are only in the alpha channel, which a flattened or opaque background covers up
+                                    if plane.shadow_catcher
+                                        && (self.render_settings.background != Background::Transparent
+                                            || self.render_settings.flatten_export_background)
+                                    {
+                                        ui.colored_label(
+                                            ui.visuals().warn_fg_color,
+                                            "Needs A Transparent, Unflattened Background",
+                                        );
+                                    }
+                                });
+                                fn ui_portal_connection(
+                                    ui: &mut egui::Ui,
+                                    planes: &mut [Plane],
+                                    index: usize,
+                                    portal: impl Fn(&mut Plane) -> &mut PortalConnection,
+                                ) -> bool {
+                                    let mut changed = false;
+                                    ui.horizontal(|ui| {
+                                        ui.label("Connected Plane:");
+                                        egui::ComboBox::new(("Front Connected Portal", index), "")
+                                            .selected_text(
+                                                portal(&mut planes[index])
+                                                    .other
+                                                    .and_then(|id| find_plane(planes, id))
+                                                    .map_or("None", |other_index| {
+                                                        planes[other_index].name.as_str()
+                                                    }),
+                                            )
+                                            .show_ui(ui, |ui| {
+                                                changed |= ui
+                                                    .selectable_value(
+                                                        &mut portal(&mut planes[index]).other,
+                                                        None,
+                                                        "None",
+                                                    )
+                                                    .changed();
+                                                for other_index in 0..planes.len() {
+                                                    let name = planes[other_index].name.clone();
+                                                    let id = planes[other_index].id;
+                                                    changed |= ui
+                                                        .selectable_value(
+                                                            &mut portal(&mut planes[index]).other,
+                                                            Some(id),
+                                                            name,
+                                                        )
+                                                        .changed();
+                                                }
+                                            });
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Scale:");
+                                        changed |= ui
+                                            .add(
+                                                egui::DragValue::new(
+                                                    &mut portal(&mut planes[index]).scale,
+                                                )
+                                                .speed(0.01)
+                                                .range(0.001..=1000.0),
+                                            )
+                                            .on_hover_text(
+                                                "How many times bigger anything going through comes out of the connected plane",
+                                            )
+                                            .changed();
+                                        let other_index = portal(&mut planes[index])
+                                            .other
+                                            .and_then(|id| find_plane(planes, id));
+                                        if let Some(other_index) = other_index
+                                            && ui
+                                                .button("Match Sizes")
+                                                .on_hover_text(
+                                                    "Scales by how much bigger the connected plane is",
+                                                )
+                                                .clicked()
+                                        {
+                                            let area = |plane: &Plane| plane.width * plane.height;
+                                            let ratio =
+                                                area(&planes[other_index]) / area(&planes[index]);
+                                            if ratio.is_finite() && ratio > 0.0 {
+                                                portal(&mut planes[index]).scale = ratio.sqrt();
+                                                changed = true;
+                                            }
+                                        }
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Flip:");
+                                        changed |= ui
+                                            .checkbox(&mut portal(&mut planes[index]).flip, "")
+                                            .on_hover_text(
+                                                "Mirrors whatever goes through, so it comes out of the other side of the connected plane",
+                                            )
+                                            .changed();
+                                    });
+                                    changed
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.label("Camera Collides:");
+                                    ui.checkbox(&mut self.scene.planes[index].camera_collides, "");
+                                });
+                                // the rumble and sound aren't drawn, so editing them doesn't mark the plane dirty
+                                ui.collapsing("Front Portal", |ui| {
+                                    changed |= ui_portal_connection(
+                                        ui,
+                                        &mut self.scene.planes,
+                                        index,
+                                        |plane| &mut plane.front_portal,
+                                    );
+                                    self.scene.planes[index].front_portal.rumble.ui(ui);
+                                    self.scene.planes[index].front_portal.sound.ui(ui);
+                                });
+                                ui.collapsing("Back Portal", |ui| {
+                                    changed |= ui_portal_connection(
+                                        ui,
+                                        &mut self.scene.planes,
+                                        index,
+                                        |plane| &mut plane.back_portal,
+                                    );
+                                    self.scene.planes[index].back_portal.rumble.ui(ui);
+                                    self.scene.planes[index].back_portal.sound.ui(ui);
+                                });
+                                ui.horizontal(|ui| {
+                                    if ui
+                                        .button("Duplicate")
+                                        .on_hover_text(
+                                            "Adds a copy of the plane, connected to the same planes",
+                                        )
+                                        .clicked()
+                                    {
+                                        to_duplicate.push(self.scene.planes[index].id);
+                                        changed = true;
+                                    }
+                                    if ui.button("Delete").clicked() {
+                                        to_delete.push(self.scene.planes[index].id);
+                                        changed = true;
+                                    }
+                                });
                             });
-                            if ui.button("Delete").clicked() {
-                                to_delete.push(index);
-                                rendering_changed = true;
-                            }
-                        });
-                }
-                for index_to_delete in to_delete.into_iter().rev() {
-                    for (index, plane) in self.scene.planes.iter_mut().enumerate() {
-                        if let Some(front_portal_index) = &mut plane.front_portal.other_index {
-                            if *front_portal_index == index_to_delete {
-                                plane.front_portal.other_index = None;
-                            } else if index > index_to_delete {
-                                *front_portal_index -= 1;
-                            }
+                        if changed && let Some(dirty_planes) = &mut self.dirty_planes {
+                            dirty_planes.push(index);
                         }
-                        if let Some(back_portal_index) = &mut plane.back_portal.other_index {
-                            if *back_portal_index == index_to_delete {
-                                plane.front_portal.other_index = None;
-                            } else if index > index_to_delete {
-                                *back_portal_index -= 1;
-                            }
+                        if let Some(from) = header.response.dnd_hover_payload::<usize>() {
+                            // dropping moves the plane to this row, so it ends up on the side it came from
+                            let y = if *from < index {
+                                header.response.rect.bottom()
+                            } else {
+                                header.response.rect.top()
+                            };
+                            ui.painter().hline(
+                                header.response.rect.x_range(),
+                                y,
+                                ui.visuals().selection.stroke,
+                            );
                         }
+                        if let Some(from) = header.response.dnd_release_payload::<usize>() {
+                            moved = Some((*from, index));
+                        }
+                        let height = ui.cursor().top() - row_top;
+                        self.plane_row_heights[index] = height;
+                        top += height;
+                        changed |= others_changed;
                     }
-                    self.scene.planes.remove(index_to_delete);
+                });
+                if let Some((from, to)) = moved
+                    && from != to
+                {
+                    let plane = self.scene.planes.remove(from);
+                    self.scene.planes.insert(to, plane);
+                    let height = self.plane_row_heights.remove(from);
+                    self.plane_row_heights.insert(to, height);
+                    self.dirty_planes = None;
+                    changed = true;
                 }
-            });
+                if !to_duplicate.is_empty() {
+                    let copies = to_duplicate
+                        .into_iter()
+                        .filter_map(|id| find_plane(&self.scene.planes, id))
+                        .map(|index| {
+                            let plane = &self.scene.planes[index];
+                            Plane {
+                                name: format!("{} Copy", plane.name),
+                                ..plane.clone()
+                            }
+                        })
+                        .collect();
+                    insert_copies(&mut self.scene.planes, copies);
+                    self.dirty_planes = None;
+                }
+                if !to_delete.is_empty() {
+                    self.dirty_planes = None;
+                }
+                for id in to_delete {
+                    delete_plane(&mut self.scene.planes, id);
+                }
+
+                changed
+            })
+            .and_then(|response| response.inner)
+            .unwrap_or(false);
+        if copy_clicked {
+            self.copy_selected_planes(ctx);
+        }
+        if paste_clicked {
+            self.paste_target = PasteTarget::Planes;
+            ctx.send_viewport_cmd(egui::ViewportCommand::RequestPaste);
+        }
+        if planes_changed {
+            self.scene_warnings = validate_planes(&self.scene.world_planes());
+            rendering_changed = true;
+        }
+
+        rendering_changed |= sky_changed;
+        // the baked lighting and probes don't depend on the camera, so only changes before the camera moves restart them
+        if rendering_changed || self.dirty_planes.is_none() {
+            self.light_bake.restart();
+            self.irradiance_probes.restart();
+        }
+        if self.render_settings.draft_while_editing && (planes_changed || sky_changed) {
+            self.draft_frames_left = self.render_settings.draft_idle_frames.max(1);
+        } else if self.draft_frames_left > 0 {
+            self.draft_frames_left -= 1;
+            // going back to the full settings starts the image over
+            if self.draft_frames_left == 0 {
+                rendering_changed = true;
+            }
+        }
 
-        self.file_dialog.update(ctx);
+        if matches!(self.file_interaction, FileInteraction::Load) {
+            let thumbnails = &mut self.thumbnails;
+            self.file_dialog
+                .update_with_right_panel_ui(ctx, &mut |ui, dialog| {
+                    let path = dialog
+                        .selected_entries()
+                        .next()
+                        .filter(|entry| entry.is_file())
+                        .map(|entry| entry.as_path());
+                    thumbnails.preview_ui(ui, path);
+                });
+        } else {
+            self.file_dialog.update(ctx);
+        }
         if let Some(mut path) = self.file_dialog.take_picked() {
             match std::mem::replace(&mut self.file_interaction, FileInteraction::None) {
                 FileInteraction::None => {}
@@ -549,119 +3195,638 @@ impl eframe::App for App {
                     if path.extension().is_none() {
                         path.set_extension("scene");
                     }
-                    let state = serde_json::to_string(&self.scene).unwrap();
-                    _ = std::fs::write(path, state);
-                }
-                FileInteraction::Load => {
-                    if let Ok(s) = std::fs::read_to_string(path)
-                        && let Ok(state) = serde_json::from_str(&s)
-                    {
-                        self.scene = state;
-                        rendering_changed = true;
+                    let scene = self
+                        .scene
+                        .exported(self.render_settings.import_export_scale);
+                    match save_scene(&path, &scene) {
+                        Ok(()) => {
+                            self.notifications
+                                .success(format!("Saved scene to {}", path.display()));
+                            self.scene_name = scene_name(&path);
+                            if let Some(render_state) = frame.wgpu_render_state()
+                                && let Err(error) = self.thumbnails.save(
+                                    render_state,
+                                    &self.scene,
+                                    &self.render_settings,
+                                    &path,
+                                )
+                            {
+                                self.notifications
+                                    .error(format!("Failed to save scene thumbnail: {error}"));
+                            }
+                        }
+                        Err(error) => {
+                            self.notifications.error(format!(
+                                "Failed to save scene to {}: {error}",
+                                path.display()
+                            ));
+                        }
                     }
                 }
+                FileInteraction::Load => rendering_changed |= self.open_scene(&path),
             }
         }
 
-        if !ctx.wants_keyboard_input() {
-            ctx.input(|i| {
-                let old_position = self.scene.camera.position;
-                rendering_changed |= self.scene.camera.update(i, ts);
-                let new_position = self.scene.camera.position;
+        self.stats_file_dialog.update(ctx);
+        if let Some(mut path) = self.stats_file_dialog.take_picked() {
+            if path.extension().is_none() {
+                path.set_extension("csv");
+            }
+            let stats = if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                self.stats.to_json()
+            } else {
+                self.stats.to_csv()
+            };
+            match std::fs::write(&path, stats) {
+                Ok(()) => self
+                    .notifications
+                    .success(format!("Exported stats to {}", path.display())),
+                Err(error) => self.notifications.error(format!(
+                    "Failed to export stats to {}: {error}",
+                    path.display()
+                )),
+            }
+        }
 
-                let ray = Ray {
-                    origin: old_position,
-                    direction: (new_position - old_position).normalised(),
-                };
+        self.image_file_dialog.update(ctx);
+        if let Some(mut path) = self.image_file_dialog.take_picked()
+            && let Some(render_state) = frame.wgpu_render_state()
+        {
+            if path.extension().is_none() {
+                path.set_extension("png");
+            }
+            let mut renderer = render_state.renderer.write();
+            let ray_tracer: &mut RayTracingRenderer =
+                renderer.callback_resources.get_mut().unwrap();
+            ray_tracer.select_target(&render_state.device, self.view.target());
+            let (width, height) = ray_tracer.texture_size();
+            let mut pixels = ray_tracer.read_texture(&render_state.device, &render_state.queue);
+            if self.render_settings.flatten_export_background {
+                pixels = flatten_background(
+                    &pixels,
+                    self.scene.viewport_background(&self.render_settings),
+                );
+            }
+            let aovs = ray_tracer
+                .read_aovs(&render_state.device, &render_state.queue)
+                .filter(|aovs| (aovs.width, aovs.height) == (width, height));
+            let denoised = aovs
+                .as_ref()
+                .filter(|_| self.render_settings.denoise_export)
+                .map(|aovs| denoise(&pixels, aovs));
+            let metadata = self
+                .render_settings
+                .export_metadata
+                .then(|| ExportMetadata {
+                    scene_name: self.scene_name.clone().unwrap_or_else(|| "Untitled".into()),
+                    samples_per_pixel: self.view.accumulated_samples(),
+                    seed: self.render_settings.seed,
+                    render_settings_json: serde_json::to_string(&self.render_settings).unwrap(),
+                    scene_json: serde_json::to_string(&self.scene).unwrap(),
+                });
+            let encoded = if path.extension().is_some_and(|extension| extension == "exr") {
+                encode_exr(
+                    width,
+                    height,
+                    &pixels,
+                    aovs.as_ref(),
+                    &self
+                        .scene
+                        .planes
+                        .iter()
+                        .map(|plane| plane.name.as_str())
+                        .collect::<Vec<_>>(),
+                    denoised.as_deref(),
+                    metadata.as_ref(),
+                )
+                .map_err(|error| error.to_string())
+            } else {
+                encode_png(
+                    width,
+                    height,
+                    denoised.as_deref().unwrap_or(&pixels),
+                    self.render_settings.tone_mapper,
+                    metadata.as_ref(),
+                    self.render_settings.export_footer,
+                    self.render_settings
+                        .export_caption
+                        .then_some(SettingsCaption {
+                            samples_per_pixel: self.view.accumulated_samples(),
+                            max_bounces: self.render_settings.max_bounces,
+                            denoised: denoised.is_some(),
+                            tone_mapper: self.render_settings.tone_mapper,
+                        }),
+                )
+                .map_err(|error| error.to_string())
+            };
+            let result = encoded
+                .and_then(|image| std::fs::write(&path, image).map_err(|error| error.to_string()));
+            match result {
+                Ok(()) => self
+                    .notifications
+                    .success(format!("Exported image to {}", path.display())),
+                Err(error) => self.notifications.error(format!(
+                    "Failed to export image to {}: {error}",
+                    path.display()
+                )),
+            }
+        }
 
-                let closest_hit = self
-                    .scene
-                    .planes
-                    .iter()
-                    .enumerate()
-                    .map(|(i, plane)| (i, plane.intersect(ray)))
-                    .fold(None::<(usize, Hit)>, |closest_hit, (index, hit)| {
-                        if let Some((closest_index, closest_hit)) = closest_hit {
-                            if let Some(hit) = hit
-                                && hit.distance < closest_hit.distance
-                            {
-                                Some((index, hit))
+        self.texture_file_dialog.update(ctx);
+        if let Some(path) = self.texture_file_dialog.take_picked()
+            && let Some(index) = self
+                .texture_plane
+                .take()
+                .and_then(|id| find_plane(&self.scene.planes, id))
+        {
+            self.scene.planes[index].texture = Some(path);
+            self.dirty_planes = None;
+            rendering_changed = true;
+        }
+
+        self.shader_file_dialog.update(ctx);
+        if let Some(mut path) = self.shader_file_dialog.take_picked() {
+            if path.extension().is_none() {
+                path.set_extension("wgsl");
+            }
+            let language = if path
+                .extension()
+                .is_some_and(|extension| extension == "glsl")
+            {
+                ShaderLanguage::Shadertoy
+            } else {
+                ShaderLanguage::Wgsl
+            };
+            match export_shader(
+                &self.scene,
+                self.scene_name.as_deref().unwrap_or("Untitled"),
+                &self.render_settings,
+                language,
+            )
+            .and_then(|shader| std::fs::write(&path, shader).map_err(|error| error.to_string()))
+            {
+                Ok(()) => {
+                    let textured = self
+                        .scene
+                        .planes
+                        .iter()
+                        .filter(|plane| plane.texture.is_some())
+                        .count();
+                    if textured > 0 {
+                        self.notifications.warning(format!(
+                            "Exported shader to {}, textures aren't exported so {textured} textured planes show their checker pattern instead",
+                            path.display()
+                        ));
+                    } else {
+                        self.notifications
+                            .success(format!("Exported shader to {}", path.display()));
+                    }
+                }
+                Err(error) => self.notifications.error(format!(
+                    "Failed to export shader to {}: {error}",
+                    path.display()
+                )),
+            }
+        }
+
+        self.accumulation_file_dialog.update(ctx);
+        if let Some(mut path) = self.accumulation_file_dialog.take_picked()
+            && let Some(render_state) = frame.wgpu_render_state()
+        {
+            match std::mem::replace(
+                &mut self.accumulation_file_interaction,
+                FileInteraction::None,
+            ) {
+                FileInteraction::None => {}
+                FileInteraction::Save => {
+                    if path.extension().is_none() {
+                        path.set_extension("accumulation");
+                    }
+                    let mut renderer = render_state.renderer.write();
+                    let ray_tracer: &mut RayTracingRenderer =
+                        renderer.callback_resources.get_mut().unwrap();
+                    ray_tracer.select_target(&render_state.device, self.view.target());
+                    let (width, height) = ray_tracer.texture_size();
+                    let saved = SavedAccumulation {
+                        scene: self.scene.clone(),
+                        render_settings: self.render_settings.clone(),
+                        width,
+                        height,
+                        accumulated_frames: self.view.accumulated_frames(),
+                        accumulated_samples: self.view.accumulated_samples(),
+                        pixels: ray_tracer.read_texture(&render_state.device, &render_state.queue),
+                    };
+                    match std::fs::write(&path, saved.encode()) {
+                        Ok(()) => self
+                            .notifications
+                            .success(format!("Saved accumulation to {}", path.display())),
+                        Err(error) => self.notifications.error(format!(
+                            "Failed to save accumulation to {}: {error}",
+                            path.display()
+                        )),
+                    }
+                }
+                FileInteraction::Load => {
+                    let mut renderer = render_state.renderer.write();
+                    let ray_tracer: &mut RayTracingRenderer =
+                        renderer.callback_resources.get_mut().unwrap();
+                    ray_tracer.select_target(&render_state.device, self.view.target());
+                    let result = std::fs::read(&path)
+                        .map_err(|error| error.to_string())
+                        .and_then(|bytes| SavedAccumulation::decode(&bytes))
+                        .and_then(|saved| {
+                            // the texture is recreated whenever the viewport changes size, which would throw it away
+                            let (width, height) = ray_tracer.texture_size();
+                            if (saved.width, saved.height) == (width, height) {
+                                Ok(saved)
                             } else {
-                                Some((closest_index, closest_hit))
+                                Err(format!(
+                                    "Saved at {}x{} but the viewport is {width}x{height}",
+                                    saved.width, saved.height
+                                ))
                             }
-                        } else {
-                            hit.map(|hit| (index, hit))
+                        });
+                    match result {
+                        Ok(saved) => {
+                            ray_tracer.write_texture(
+                                &render_state.device,
+                                &render_state.queue,
+                                saved.width,
+                                saved.height,
+                                &saved.pixels,
+                            );
+                            self.scene = saved.scene;
+                            self.dirty_planes = None;
+                            self.scene_name = scene_name(&path);
+                            self.scene_warnings = validate_planes(&self.scene.world_planes());
+                            self.render_settings = saved.render_settings;
+                            self.crop = CropRegion::default();
+                            self.view
+                                .resume(saved.accumulated_frames, saved.accumulated_samples);
+                            self.notifications
+                                .success(format!("Resumed accumulation from {}", path.display()));
                         }
-                    });
+                        Err(error) => self.notifications.error(format!(
+                            "Failed to resume accumulation from {}: {error}",
+                            path.display()
+                        )),
+                    }
+                }
+            }
+        }
 
-                if let Some((index, hit)) = closest_hit
-                    && hit.distance < (new_position - old_position).magnitude()
-                {
-                    let plane = &self.scene.planes[index];
-                    if let Some(other_index) = plane.front_portal.other_index
-                        && hit.front
-                    {
-                        let other_plane = &self.scene.planes[other_index];
-                        let transform = other_plane.transform().then(plane.transform().reverse());
-                        self.scene.camera.position =
-                            transform.transform_point(self.scene.camera.position);
-                        self.scene.camera.rotation =
-                            transform.rotor_part().then(self.scene.camera.rotation);
-                        rendering_changed = true;
-                    } else if let Some(other_index) = plane.back_portal.other_index
-                        && !hit.front
-                    {
-                        let other_plane = &self.scene.planes[other_index];
-                        let transform = other_plane.transform().then(plane.transform().reverse());
-                        self.scene.camera.position =
-                            transform.transform_point(self.scene.camera.position);
-                        self.scene.camera.rotation =
-                            transform.rotor_part().then(self.scene.camera.rotation);
+        if let Some(render_state) = frame.wgpu_render_state() {
+            self.render_queue.update(render_state);
+            if let Err(error) = self.auto_tuner.update(render_state) {
+                self.notifications
+                    .error(format!("Failed to auto tune: {error}"));
+            }
+        }
+
+        if self.timeline.advance(ts, self.scene.animation.duration) {
+            let (camera_moved, planes_changed) = self.scene.animation.apply(
+                self.timeline.time,
+                &mut self.scene.camera,
+                &mut self.scene.planes,
+            );
+            if camera_moved {
+                self.camera_moving_time = CAMERA_MOVING_HOLD_TIME;
+            }
+            if planes_changed {
+                self.dirty_planes = None;
+            }
+            rendering_changed |= camera_moved || planes_changed;
+        }
+
+        self.simulation_time = (self.simulation_time + ts).min(MAX_SIMULATION_CATCH_UP);
+        let simulation_steps = (self.simulation_time / SIMULATION_TIMESTEP) as u32;
+        self.simulation_time -= simulation_steps as f32 * SIMULATION_TIMESTEP;
+
+        self.gamepad_input = self.gamepads.poll(self.render_settings.gamepad_dead_zone);
+        // text fields copy and paste their own text, anywhere else it is the selected planes
+        if !ctx.wants_keyboard_input() {
+            let (copy, paste) = ctx.input(|i| {
+                i.events
+                    .iter()
+                    .fold((false, None), |(copy, paste), event| match event {
+                        egui::Event::Copy => (true, paste),
+                        egui::Event::Paste(text) => (copy, Some(text.clone())),
+                        _ => (copy, paste),
+                    })
+            });
+            if copy {
+                self.copy_selected_planes(ctx);
+            }
+            if let Some(text) = paste {
+                match std::mem::take(&mut self.paste_target) {
+                    PasteTarget::Planes => rendering_changed |= self.paste_planes(&text),
+                    PasteTarget::CameraPose => match self.scene.camera.set_pose(&text) {
+                        Ok(()) => rendering_changed = true,
+                        Err(error) => self
+                            .notifications
+                            .error(format!("Failed to paste camera pose: {error}")),
+                    },
+                }
+            }
+        }
+        if !ctx.wants_keyboard_input() {
+            ctx.input(|i| {
+                if i.key_pressed(egui::Key::N) {
+                    self.render_settings.noclip = !self.render_settings.noclip;
+                }
+                if i.key_pressed(egui::Key::F12) {
+                    self.image_file_dialog.save_file();
+                }
+                if i.key_pressed(egui::Key::M) {
+                    let marker = Marker::at_camera(&self.scene.camera, self.scene.markers.len());
+                    self.scene.markers.push(marker);
+                }
+
+                for _ in 0..simulation_steps {
+                    if self.step_camera(i, SIMULATION_TIMESTEP) {
+                        self.camera_moving_time = CAMERA_MOVING_HOLD_TIME;
                         rendering_changed = true;
                     }
                 }
             });
         }
+        dispatch_traversals(&mut self.traversals, &mut [&mut self.gamepads]);
+
+        self.teleport_effect_time = (self.teleport_effect_time - ts).max(0.0);
+        let fov_widening = if self.render_settings.speed_fov {
+            // shift doubles the camera's speed, that is full speed
+            let speed = self.camera_speed / (self.scene.camera.speed * 2.0);
+            self.render_settings.speed_fov_widening * speed.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let old_fov_widening = self.fov_widening;
+        // the field of view keeps changing while the camera slows down after it stopped moving
+        if fov_widening != self.fov_widening {
+            self.fov_widening = fov_widening;
+            rendering_changed = true;
+        }
+
+        self.camera_moving_time = (self.camera_moving_time - ts).max(0.0);
+        // a cropped region is refined while standing still, so it is always traced at full resolution
+        let render_scale = if self.render_settings.dynamic_resolution
+            && self.camera_moving_time > 0.0
+            && !self.crop.is_active()
+        {
+            self.render_settings.moving_render_scale
+        } else {
+            1.0
+        };
+        self.view
+            .set_render_scale(self.render_settings.render_scale * render_scale);
+        self.view.set_pixels_per_point(ctx.pixels_per_point());
+
+        egui::TopBottomPanel::bottom("Status").show(ctx, |ui| {
+            self.notifications.status_bar(ui);
+        });
+        self.notifications.show_toasts(ctx);
 
+        self.profiler.record("UI", time);
+
+        let viewport_start = Instant::now();
         egui::CentralPanel::default()
-            .frame(egui::Frame::NONE.fill(egui::Color32::from_rgb(255, 0, 255)))
+            .frame(egui::Frame::NONE.fill({
+                let background = self.scene.viewport_background(&self.render_settings);
+                egui::Rgba::from_rgb(background.r, background.g, background.b).into()
+            }))
             .show(ctx, |ui| {
-                let (rect, _response) =
+                let (rect, response) =
                     ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+                self.viewport_rect = rect;
+                if !RayTracingView::can_trace(rect) {
+                    self.view.show_placeholder(ui.painter(), rect);
+                    return;
+                }
+                rendering_changed |= self.crop.interact(&response, rect);
+                if response.dragged_by(egui::PointerButton::Secondary)
+                    && self.camera_controller.look(
+                        &mut self.scene.camera,
+                        response.drag_delta(),
+                        MouseLook {
+                            sensitivity: self.render_settings.mouse_sensitivity,
+                            invert_y: self.render_settings.invert_mouse_y,
+                        },
+                    )
+                {
+                    self.camera_moving_time = CAMERA_MOVING_HOLD_TIME;
+                    rendering_changed = true;
+                }
+                if response.hovered() {
+                    let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+                    rendering_changed |= self.camera_controller.zoom(scroll);
+                }
+
+                let camera_changed = !self.scene.camera.same_view(&old_camera)
+                    || self.fov_widening != old_fov_widening;
+                let reset = match self.render_settings.reset_policy {
+                    ResetPolicy::AnyChange => rendering_changed,
+                    ResetPolicy::CameraChange => camera_changed,
+                    ResetPolicy::Never | ResetPolicy::TimedBlend => false,
+                };
+                let bake_lighting = self.render_settings.render_type == RenderType::Unlit;
+                // while the lighting is still changing the image can't keep what it accumulated
+                if reset
+                    || (bake_lighting && self.light_bake.baking())
+                    || (!bake_lighting && self.irradiance_probes.updating())
+                {
+                    self.view.reset();
+                }
+
+                let samples_per_pixel = if self.draft_frames_left > 0 {
+                    self.render_settings.draft_preset.samples_per_pixel
+                } else {
+                    self.render_settings.samples_per_pixel
+                }
+                .max(1);
+                let max_samples_per_dispatch = if self.render_settings.background_accumulation {
+                    // while interacting only trace a single sample so the ui stays responsive,
+                    // then ramp back up while the frame time is within budget
+                    let budget = self.render_settings.frame_time_budget_ms as f64;
+                    // what the gpu measured tracing takes, otherwise how long the frame kept the cpu busy
+                    let busy_ms = self
+                        .profiler
+                        .gpu_trace_time()
+                        .unwrap_or(busy_time)
+                        .as_secs_f64()
+                        * 1000.0;
+                    self.adaptive_samples_per_pixel = if rendering_changed {
+                        1
+                    } else if busy_ms > budget {
+                        self.adaptive_samples_per_pixel / 2
+                    } else if busy_ms < budget * 0.8 {
+                        self.adaptive_samples_per_pixel + 1
+                    } else {
+                        self.adaptive_samples_per_pixel
+                    }
+                    .clamp(1, samples_per_pixel);
+                    self.render_settings.max_samples_per_dispatch
+                } else {
+                    self.adaptive_samples_per_pixel = samples_per_pixel;
+                    samples_per_pixel
+                };
+                let samples_per_pixel = self.adaptive_samples_per_pixel;
 
-                if rendering_changed {
-                    self.accumulated_frames = 0;
+                let (width, height) = self.view.image_size(rect);
+                let inspect_pixel = response
+                    .hover_pos()
+                    .filter(|_| self.render_settings.pixel_inspector)
+                    .and_then(|position| self.view.texel_at(rect, position));
+                let scene_conversion_start = Instant::now();
+                let mut scene_frame =
+                    self.render_settings
+                        .paint_callback(&self.scene, width, height);
+                self.stress_test
+                    .apply(&mut scene_frame, scene_conversion_start);
+                self.profiler
+                    .record("Scene Conversion", scene_conversion_start);
+                // edits that don't change the rendering, like renaming a bookmark, still come from some input
+                if rendering_changed
+                    || camera_changed
+                    || self
+                        .dirty_planes
+                        .as_ref()
+                        .is_none_or(|dirty| !dirty.is_empty())
+                    || ui.input(|i| !i.events.is_empty())
+                {
+                    self.scene_hash = None;
+                }
+                let dirty_planes = self.dirty_planes.replace(vec![]);
+                let mut callback = RayTracingPaintCallback {
+                    image: self.view.image(rect),
+                    random_seed: frame_seed(
+                        self.render_settings.seed,
+                        self.view.accumulated_frames(),
+                    ),
+                    samples_per_pixel,
+                    max_samples_per_dispatch,
+                    inspect_pixel,
+                    crop: self.crop.crop_rect(width, height, self.view.pixel_scale()),
+                    blend_factor: match self.render_settings.reset_policy {
+                        ResetPolicy::TimedBlend => {
+                            1.0 - (-ts / self.render_settings.blend_time.max(0.001)).exp()
+                        }
+                        _ => 0.0,
+                    },
+                    dirty_planes,
+                    ..scene_frame
+                };
+                callback.camera.fov += self.fov_widening;
+                if self.draft_frames_left > 0 {
+                    self.render_settings.draft_preset.apply(&mut callback);
                 }
-                ui.painter()
-                    .add(eframe::egui_wgpu::Callback::new_paint_callback(
+                if self.render_settings.teleport_effect_duration > 0.0 {
+                    callback.teleport_effect =
+                        self.teleport_effect_time / self.render_settings.teleport_effect_duration;
+                }
+                self.light_bake.apply(&mut callback, bake_lighting);
+                if let Some(render_state) = frame.wgpu_render_state()
+                    && let Some(ray_tracer) = render_state
+                        .renderer
+                        .write()
+                        .callback_resources
+                        .get_mut::<RayTracingRenderer>()
+                {
+                    let changes = self.plane_textures.apply(
+                        ctx,
+                        &render_state.device,
+                        &render_state.queue,
+                        ray_tracer,
+                        &self.scene.planes,
+                        &mut callback,
+                    );
+                    if changes.changed {
+                        self.view.reset();
+                        callback.image = self.view.image(rect);
+                    }
+                    for error in changes.errors {
+                        self.notifications.error(error);
+                    }
+                }
+                self.irradiance_probes.apply(
+                    &mut callback,
+                    &self.scene.probe_volumes,
+                    !bake_lighting,
+                );
+                if let Some(render_state) = frame.wgpu_render_state() {
+                    self.multi_gpu.split_frame(render_state, &mut callback);
+                }
+                self.view.paint(ui.painter(), rect, callback);
+                let fov = self.scene.camera.fov + self.fov_widening;
+                self.collision_debug
+                    .draw(ui.painter(), rect, &self.scene.camera, fov);
+                self.irradiance_probes
+                    .draw(ui.painter(), rect, &self.scene.camera, fov);
+                draw_markers(
+                    ui.painter(),
+                    rect,
+                    &self.scene.camera,
+                    fov,
+                    &self.scene.markers,
+                );
+                self.crop.draw(ui.painter(), rect);
+                if self.render_settings.compare_tone_mappers && !self.render_settings.false_color {
+                    ui_tone_mapper_split(
+                        ui,
                         rect,
-                        RayTracingPaintCallback {
-                            width: rect.width() as u32,
-                            height: rect.height() as u32,
-                            camera: GpuCamera {
-                                transform: self.scene.camera.transform(),
-                                up_sky_color: self.scene.up_sky_color * self.scene.up_sky_intensity,
-                                down_sky_color: self.scene.down_sky_color
-                                    * self.scene.down_sky_intensity,
-                                sun_color: self.scene.sun_color * self.scene.sun_intensity,
-                                sun_direction: self.scene.sun_direction.normalised(),
-                                sun_size: self.scene.sun_size,
-                                recursive_portal_count: self.render_settings.recursive_portal_count,
-                                max_bounces: self.render_settings.max_bounces,
-                            },
-                            accumulated_frames: self.accumulated_frames,
-                            random_seed: rand::random(),
-                            render_type: match self.render_settings.render_type {
-                                RenderType::Unlit => RENDER_TYPE_UNLIT,
-                                RenderType::Lit => RENDER_TYPE_LIT,
-                            },
-                            samples_per_pixel: self.render_settings.samples_per_pixel,
-                            antialiasing: self.render_settings.antialiasing,
-                            planes: self.scene.planes.iter().map(Plane::to_gpu).collect(),
-                        },
-                    ));
-                self.accumulated_frames += 1;
+                        &mut self.render_settings.split_position,
+                        self.render_settings.tone_mapper,
+                        self.render_settings.split_tone_mapper,
+                    );
+                }
+                if self.render_settings.show_histogram
+                    && let Some(render_state) = frame.wgpu_render_state()
+                {
+                    let renderer = render_state.renderer.read();
+                    let ray_tracer: &RayTracingRenderer =
+                        renderer.callback_resources.get().unwrap();
+                    draw_histogram(ui.painter(), rect, ray_tracer.histogram());
+                }
+                if inspect_pixel.is_some()
+                    && let Some(render_state) = frame.wgpu_render_state()
+                    && let Some(pixel) = render_state
+                        .renderer
+                        .read()
+                        .callback_resources
+                        .get::<RayTracingRenderer>()
+                        .unwrap()
+                        .inspected_pixel()
+                {
+                    let tone_mapper = if self.render_settings.compare_tone_mappers
+                        && pixel.x as f32 >= width as f32 * self.render_settings.split_position
+                    {
+                        self.render_settings.split_tone_mapper
+                    } else {
+                        self.render_settings.tone_mapper
+                    };
+                    response.on_hover_ui_at_pointer(|ui| {
+                        ui_inspected_pixel(
+                            ui,
+                            pixel,
+                            height,
+                            tone_mapper,
+                            self.view.accumulated_samples(),
+                        );
+                    });
+                }
+                self.stats.record(
+                    time,
+                    dt.as_secs_f64(),
+                    width,
+                    height,
+                    samples_per_pixel,
+                    self.view.accumulated_samples(),
+                );
             });
+        self.profiler.record("Viewport", viewport_start);
+        self.profiler.record("Update", time);
 
         ctx.request_repaint();
     }
@@ -698,37 +3863,289 @@ pub fn ui_transform(
         | ui.add(egui::DragValue::new(e0123).prefix("e0123:").speed(0.1))
 }
 
-pub fn ui_vector3(ui: &mut egui::Ui, Vector3 { x, y, z }: &mut Vector3) -> egui::Response {
-    ui.add(egui::DragValue::new(x).prefix("x:").speed(0.1))
-        | ui.add(egui::DragValue::new(y).prefix("y:").speed(0.1))
-        | ui.add(egui::DragValue::new(z).prefix("z:").speed(0.1))
+/// Picks one of `planes` by name
+pub fn ui_plane_id(
+    ui: &mut egui::Ui,
+    label: &str,
+    id: &mut Option<PlaneId>,
+    planes: &[Plane],
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label(label);
+        egui::ComboBox::new(label, "")
+            .selected_text(
+                id.and_then(|id| find_plane(planes, id))
+                    .map_or("None", |index| planes[index].name.as_str()),
+            )
+            .show_ui(ui, |ui| {
+                for plane in planes {
+                    changed |= ui
+                        .selectable_value(id, Some(plane.id), &plane.name)
+                        .changed();
+                }
+            });
+    });
+    changed
+}
+
+pub fn ui_vector3(ui: &mut egui::Ui, vector: &mut Vector3) -> egui::Response {
+    ui_vector3_with_suffix(ui, vector, "")
+}
+
+pub fn ui_vector3_with_suffix(
+    ui: &mut egui::Ui,
+    Vector3 { x, y, z }: &mut Vector3,
+    suffix: &str,
+) -> egui::Response {
+    ui.add(
+        egui::DragValue::new(x)
+            .prefix("x:")
+            .suffix(suffix)
+            .speed(0.1),
+    ) | ui.add(
+        egui::DragValue::new(y)
+            .prefix("y:")
+            .suffix(suffix)
+            .speed(0.1),
+    ) | ui.add(
+        egui::DragValue::new(z)
+            .prefix("z:")
+            .suffix(suffix)
+            .speed(0.1),
+    )
+}
+
+/// A compass to click or drag the direction on, seen from above with the horizon at its edge and straight up in its middle,
+/// next to its azimuth and elevation, [`Vector3::FORWARD`] turned into `up_axis` up is at the top of the compass
+pub fn ui_sun_direction(
+    ui: &mut egui::Ui,
+    direction: &mut Vector3,
+    up_axis: UpAxis,
+) -> egui::Response {
+    let old_angles = direction.azimuth_elevation(up_axis);
+    let (mut azimuth, mut elevation) = old_angles;
+
+    let size = ui.spacing().interact_size.y * 3.0;
+    let (rect, mut response) =
+        ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::click_and_drag());
+    let center = rect.center();
+    let radius = size * 0.5 - 2.0;
+    if let Some(pointer) = response.interact_pointer_pos() {
+        let offset = (pointer - center) / radius;
+        azimuth = offset.x.atan2(-offset.y);
+        elevation = (1.0 - offset.length().min(1.0)) * FRAC_PI_2;
+    }
+
+    let visuals = ui.style().interact(&response);
+    let painter = ui.painter_at(rect.expand(1.0));
+    painter.circle(
+        center,
+        radius,
+        ui.visuals().extreme_bg_color,
+        visuals.fg_stroke,
+    );
+    painter.line_segment(
+        [center, center - egui::vec2(0.0, radius)],
+        egui::Stroke::new(1.0, ui.visuals().weak_text_color()),
+    );
+    // below the horizon the sun is drawn hollow at the edge
+    let distance = (1.0 - elevation.max(0.0) / FRAC_PI_2) * radius;
+    let sun = center + egui::vec2(azimuth.sin(), -azimuth.cos()) * distance;
+    if elevation >= 0.0 {
+        painter.circle_filled(sun, 4.0, visuals.fg_stroke.color);
+    } else {
+        painter.circle_stroke(sun, 4.0, visuals.fg_stroke);
+    }
+
+    ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            ui.label("Azimuth:");
+            ui.drag_angle(&mut azimuth);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Elevation:");
+            ui.drag_angle(&mut elevation);
+            elevation = elevation.clamp(-FRAC_PI_2, FRAC_PI_2);
+        });
+    });
+    // holding the pointer still on the compass keeps setting the same angles, which isn't a change
+    if (azimuth, elevation) != old_angles {
+        *direction = Vector3::from_azimuth_elevation(azimuth, elevation, up_axis);
+        response.mark_changed();
+    }
+    response.on_hover_text("Click or drag to place the sun, the middle is straight up")
+}
+
+fn ui_shader_error(ui: &mut egui::Ui, error: &ShaderError) {
+    ui.heading("Shader Error");
+    ui.label("The shaders failed to compile, nothing can be rendered until they are fixed");
+    ui.separator();
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for message in &error.messages {
+            let location = match (message.line, message.column) {
+                (Some(line), Some(column)) => format!("{}:{line}:{column}", error.file),
+                (Some(line), None) => format!("{}:{line}", error.file),
+                _ => error.file.clone(),
+            };
+            ui.strong(location);
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                egui::RichText::new(&message.message).monospace(),
+            );
+            ui.add_space(8.0);
+        }
+    });
+}
+
+fn scene_name(path: &std::path::Path) -> Option<String> {
+    Some(path.file_stem()?.to_string_lossy().into_owned())
+}
+
+const APP_NAME: &str = "Portals";
+
+/// How the device is created with `launch`, the present modes the window's surface supports
+/// are stored in `supported_present_modes` once its adapter is picked, creating the window fails
+/// if they don't include `launch`'s present mode instead of failing to configure its surface
+fn wgpu_configuration(
+    launch: LaunchSettings,
+    supported_present_modes: Arc<Mutex<Vec<wgpu::PresentMode>>>,
+) -> eframe::egui_wgpu::WgpuConfiguration {
+    let mut wgpu_setup = eframe::egui_wgpu::WgpuSetupCreateNew {
+        device_descriptor: Arc::new(|adapter| wgpu::DeviceDescriptor {
+            label: Some("Device"),
+            required_features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                | (adapter.features() & ray_tracing::OPTIONAL_FEATURES),
+            required_limits: adapter.limits(),
+            memory_hints: wgpu::MemoryHints::default(),
+            trace: wgpu::Trace::Off,
+        }),
+        native_adapter_selector: Some(Arc::new(move |adapters, surface| {
+            // the fastest adapter that can draw to the window
+            let adapter = adapters
+                .iter()
+                .filter(|adapter| {
+                    surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+                })
+                .min_by_key(|adapter| match adapter.get_info().device_type {
+                    wgpu::DeviceType::DiscreteGpu => 0,
+                    wgpu::DeviceType::IntegratedGpu => 1,
+                    wgpu::DeviceType::VirtualGpu => 2,
+                    wgpu::DeviceType::Other => 3,
+                    wgpu::DeviceType::Cpu => 4,
+                })
+                .ok_or_else(|| "No adapter can draw to the window".to_string())?;
+            if let Some(surface) = surface {
+                let present_modes = surface.get_capabilities(adapter).present_modes;
+                let supported = launch.present_mode.is_supported(&present_modes);
+                *supported_present_modes.lock().unwrap() = present_modes;
+                if !supported {
+                    return Err(format!(
+                        "The {} present mode isn't supported",
+                        launch.present_mode.name()
+                    ));
+                }
+            }
+            Ok(adapter.clone())
+        })),
+        ..Default::default()
+    };
+    if launch.gpu_validation {
+        wgpu_setup.instance_descriptor.flags |= wgpu::InstanceFlags::debugging();
+    }
+    eframe::egui_wgpu::WgpuConfiguration {
+        present_mode: launch.present_mode.to_wgpu(),
+        wgpu_setup: eframe::egui_wgpu::WgpuSetup::CreateNew(wgpu_setup),
+        ..Default::default()
+    }
 }
 
 fn main() -> eframe::Result<()> {
-    eframe::run_native(
-        "Portals",
-        eframe::NativeOptions {
-            vsync: false,
-            renderer: eframe::Renderer::Wgpu,
-            wgpu_options: eframe::egui_wgpu::WgpuConfiguration {
-                present_mode: wgpu::PresentMode::AutoNoVsync,
-                wgpu_setup: eframe::egui_wgpu::WgpuSetup::CreateNew(
-                    eframe::egui_wgpu::WgpuSetupCreateNew {
-                        device_descriptor: Arc::new(|adapter| wgpu::DeviceDescriptor {
-                            label: Some("Device"),
-                            required_features:
-                                wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
-                            required_limits: adapter.limits(),
-                            memory_hints: wgpu::MemoryHints::default(),
-                            trace: wgpu::Trace::Off,
-                        }),
-                        ..Default::default()
-                    },
-                ),
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let scene_path = match parse_command(&args) {
+        Ok(Command::Open { scene_path }) => scene_path,
+        Ok(Command::Lint(args)) => std::process::exit(lint(&args)),
+        Ok(Command::Benchmark(args)) => std::process::exit(benchmark(&args)),
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+
+    let log_filter = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|filter| EnvFilter::try_new(filter).is_ok())
+        .unwrap_or_else(|| "info".into());
+    let (log, subscriber) = Log::new(EnvFilter::new(&log_filter));
+    // also sends what wgpu and its validation layers log through the `log` crate to the subscriber
+    subscriber.init();
+
+    let mut launch = RenderSettings::saved()
+        .map(|render_settings| render_settings.launch_settings())
+        .unwrap_or_default();
+    let mut scene_path = scene_path;
+    loop {
+        let supported_present_modes = Arc::new(Mutex::new(vec![]));
+        let relaunch = Rc::new(Cell::new(None));
+        let result = eframe::run_native(
+            APP_NAME,
+            eframe::NativeOptions {
+                viewport: egui::ViewportBuilder::default().with_min_inner_size([320.0, 240.0]),
+                vsync: false,
+                renderer: eframe::Renderer::Wgpu,
+                wgpu_options: wgpu_configuration(launch, supported_present_modes.clone()),
                 ..Default::default()
             },
-            ..Default::default()
-        },
-        Box::new(|cc| Ok(Box::new(App::new(cc)))),
-    )
+            // eframe only creates the app once the renderer is ready, so the scene is loaded with the settings it will be drawn with
+            Box::new({
+                let log = log.clone();
+                let log_filter = log_filter.clone();
+                // reopening the window restores the saved scene instead
+                let scene_path = scene_path.take();
+                let relaunch = relaunch.clone();
+                let supported_present_modes = supported_present_modes.clone();
+                move |cc| {
+                    Ok(Box::new(App::new(
+                        cc,
+                        log,
+                        log_filter,
+                        scene_path.as_deref(),
+                        Launch {
+                            settings: launch,
+                            supported_present_modes: std::mem::take(
+                                &mut *supported_present_modes.lock().unwrap(),
+                            ),
+                            relaunch,
+                        },
+                    )))
+                }
+            }),
+        );
+        // a mode saved on another display is only found to be unsupported once the window exists,
+        // the app falls back from it too when it loads the saved settings
+        if let Err(eframe::Error::Wgpu(
+            eframe::egui_wgpu::WgpuError::CustomNativeAdapterSelectionError(_),
+        )) = result
+            && !launch
+                .present_mode
+                .is_supported(&supported_present_modes.lock().unwrap())
+        {
+            tracing::warn!(
+                "The {} present mode isn't supported, reopening the window with {}",
+                launch.present_mode.name(),
+                PresentMode::AutoNoVsync.name(),
+            );
+            launch.present_mode = PresentMode::AutoNoVsync;
+            continue;
+        }
+        result?;
+        match relaunch.take() {
+            Some(settings) => launch = settings,
+            None => return Ok(()),
+        }
+    }
 }
@@ -0,0 +1,117 @@
+use crate::{Camera, Units, ui_vector3_with_suffix};
+use eframe::egui;
+use math::Vector3;
+use ray_tracing::Color;
+use serde::{Deserialize, Serialize};
+
+/// The radius markers are drawn with, in scene units
+const MARKER_RADIUS: f32 = 0.05;
+
+/// A point of interest in the scene, drawn over the viewport as a glowing dot,
+/// it isn't traced so it isn't hidden by planes or seen through portals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Marker {
+    pub name: String,
+    pub position: Vector3,
+    pub color: Color,
+}
+
+impl Marker {
+    /// Drops a marker where the camera is
+    pub fn at_camera(camera: &Camera, index: usize) -> Self {
+        // spreads the hues of consecutive markers apart so they can be told apart
+        let hue = (index as f32 * 0.618_034).fract();
+        let color = egui::Rgba::from(egui::ecolor::Hsva::new(hue, 0.8, 1.0, 1.0));
+        Self {
+            name: format!("Marker {}", index + 1),
+            position: camera.position,
+            color: Color {
+                r: color.r(),
+                g: color.g(),
+                b: color.b(),
+            },
+        }
+    }
+}
+
+/// Lists the markers, returns whether the camera was moved to one of them
+pub fn ui_markers(
+    ui: &mut egui::Ui,
+    markers: &mut Vec<Marker>,
+    camera: &mut Camera,
+    units: Units,
+) -> bool {
+    let mut moved_camera = false;
+    let mut to_delete = None;
+    for (index, marker) in markers.iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(marker.color.as_mut());
+                ui.text_edit_singleline(&mut marker.name);
+                if ui.button("Go To").clicked() {
+                    camera.position = marker.position;
+                    moved_camera = true;
+                }
+                if ui.button("Delete").clicked() {
+                    to_delete = Some(index);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Position:");
+                ui_vector3_with_suffix(ui, &mut marker.position, units.suffix());
+            });
+        });
+        ui.separator();
+    }
+    if let Some(index) = to_delete {
+        markers.remove(index);
+    }
+    if ui
+        .button("Drop Marker")
+        .on_hover_text("Drops a marker at the camera, M does the same")
+        .clicked()
+    {
+        markers.push(Marker::at_camera(camera, markers.len()));
+    }
+    moved_camera
+}
+
+/// Draws every marker in front of the camera with its name, `fov` is the vertical field of view of the viewport
+pub fn draw_markers(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    camera: &Camera,
+    fov: f32,
+    markers: &[Marker],
+) {
+    let scale = rect.height() * 0.5 / (fov * 0.5).tan();
+    for marker in markers {
+        let local = camera
+            .rotation
+            .reverse()
+            .rotate(marker.position - camera.position);
+        if local.x < 0.001 {
+            continue;
+        }
+        let center = egui::pos2(
+            rect.center().x + local.z / local.x * scale,
+            rect.center().y - local.y / local.x * scale,
+        );
+        if !rect.expand(32.0).contains(center) {
+            continue;
+        }
+
+        let Color { r, g, b } = marker.color;
+        let color = egui::Color32::from(egui::Rgba::from_rgb(r, g, b));
+        let radius = (MARKER_RADIUS / local.x * scale).clamp(3.0, 32.0);
+        painter.circle_filled(center, radius * 2.0, color.gamma_multiply(0.25));
+        painter.circle_filled(center, radius, color);
+        painter.text(
+            center + egui::vec2(0.0, radius + 2.0),
+            egui::Align2::CENTER_TOP,
+            &marker.name,
+            egui::FontId::proportional(12.0),
+            egui::Color32::WHITE,
+        );
+    }
+}
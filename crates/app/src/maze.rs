@@ -0,0 +1,317 @@
+use crate::{Plane, PlaneId, Units};
+use eframe::egui;
+use math::Vector3;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use ray_tracing::Color;
+use std::f32::consts::FRAC_PI_2;
+
+/// Generates a grid maze of walls with some of the walls connected as portals,
+/// the same settings always generate the same maze
+#[derive(Debug, Clone)]
+pub struct MazeGenerator {
+    pub seed: u64,
+    /// How many cells the maze has along x
+    pub width: u32,
+    /// How many cells the maze has along z
+    pub depth: u32,
+    pub cell_size: f32,
+    pub wall_height: f32,
+    /// How many pairs of walls get connected to each other
+    pub portal_pairs: u32,
+}
+
+impl Default for MazeGenerator {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            width: 8,
+            depth: 8,
+            cell_size: 2.0,
+            wall_height: 2.5,
+            portal_pairs: 4,
+        }
+    }
+}
+
+/// A wall between two cells or on the edge of the maze
+#[derive(Debug, Clone, Copy)]
+struct Wall {
+    /// The cell the wall starts at, walls go along +x or +z from it
+    x: u32,
+    z: u32,
+    along_x: bool,
+}
+
+pub struct Maze {
+    pub planes: Vec<Plane>,
+    /// The center of the first cell, where the camera can start
+    pub start: Vector3,
+}
+
+impl MazeGenerator {
+    pub fn generate(&self) -> Maze {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let width = self.width.max(1);
+        let depth = self.depth.max(1);
+
+        // a randomized depth first search carves the passages, so every cell is reachable
+        let cell = |x: u32, z: u32| (z * width + x) as usize;
+        let mut visited = vec![false; (width * depth) as usize];
+        // whether the wall on the -x and -z side of every cell was carved away
+        let mut open_x = vec![false; (width * depth) as usize];
+        let mut open_z = vec![false; (width * depth) as usize];
+        let mut stack = vec![(0, 0)];
+        visited[0] = true;
+        while let Some(&(x, z)) = stack.last() {
+            let mut neighbours = [
+                (x > 0).then(|| (x - 1, z)),
+                (x + 1 < width).then(|| (x + 1, z)),
+                (z > 0).then(|| (x, z - 1)),
+                (z + 1 < depth).then(|| (x, z + 1)),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|&(x, z)| !visited[cell(x, z)])
+            .collect::<Vec<_>>();
+            neighbours.shuffle(&mut rng);
+            let Some(&(next_x, next_z)) = neighbours.first() else {
+                stack.pop();
+                continue;
+            };
+            if next_x != x {
+                open_x[cell(x.max(next_x), z)] = true;
+            } else {
+                open_z[cell(x, z.max(next_z))] = true;
+            }
+            visited[cell(next_x, next_z)] = true;
+            stack.push((next_x, next_z));
+        }
+
+        let mut walls = vec![];
+        for z in 0..=depth {
+            for x in 0..width {
+                if z == 0 || z == depth || !open_z[cell(x, z)] {
+                    walls.push(Wall {
+                        x,
+                        z,
+                        along_x: true,
+                    });
+                }
+            }
+        }
+        for x in 0..=width {
+            for z in 0..depth {
+                if x == 0 || x == width || !open_x[cell(x, z)] {
+                    walls.push(Wall {
+                        x,
+                        z,
+                        along_x: false,
+                    });
+                }
+            }
+        }
+
+        // the maze is centered on the origin
+        let origin = Vector3 {
+            x: width as f32 * self.cell_size * -0.5,
+            y: 0.0,
+            z: depth as f32 * self.cell_size * -0.5,
+        };
+        let mut planes = vec![Plane {
+            id: PlaneId::random(&mut rng),
+            name: "Maze Floor".into(),
+            position: Vector3::ZERO,
+            width: width as f32 * self.cell_size,
+            height: depth as f32 * self.cell_size,
+            checker_count_x: width,
+            checker_count_z: depth,
+            color: Color {
+                r: 0.6,
+                g: 0.6,
+                b: 0.6,
+            },
+            ..Plane::default()
+        }];
+        let first_wall = planes.len();
+        planes.extend(walls.iter().enumerate().map(|(index, wall)| {
+            let corner = origin
+                + Vector3 {
+                    x: wall.x as f32 * self.cell_size,
+                    y: self.wall_height * 0.5,
+                    z: wall.z as f32 * self.cell_size,
+                };
+            let half_cell = self.cell_size * 0.5;
+            // the plane's own xz is turned upright, along x by a yz rotation and along z by an xy rotation
+            let (position, xy_rotation, yz_rotation, plane_width, plane_height) = if wall.along_x {
+                (
+                    corner + Vector3::X * half_cell,
+                    0.0,
+                    FRAC_PI_2,
+                    self.cell_size,
+                    self.wall_height,
+                )
+            } else {
+                (
+                    corner + Vector3::Z * half_cell,
+                    FRAC_PI_2,
+                    0.0,
+                    self.wall_height,
+                    self.cell_size,
+                )
+            };
+            Plane {
+                id: PlaneId::random(&mut rng),
+                name: format!("Maze Wall {index}"),
+                position,
+                xy_rotation,
+                yz_rotation,
+                width: plane_width,
+                height: plane_height,
+                color: Color {
+                    r: 0.9,
+                    g: 0.9,
+                    b: 0.9,
+                },
+                checker_darkness: 0.1,
+                ..Plane::default()
+            }
+        }));
+
+        let mut wall_indices = (first_wall..planes.len()).collect::<Vec<_>>();
+        wall_indices.shuffle(&mut rng);
+        for (pair, portal_walls) in wall_indices
+            .chunks_exact(2)
+            .take(self.portal_pairs as usize)
+            .enumerate()
+        {
+            let &[a, b] = portal_walls else {
+                unreachable!()
+            };
+            let color =
+                egui::Rgba::from(egui::ecolor::Hsva::new(rng.random::<f32>(), 0.7, 1.0, 1.0));
+            // going through either side of one wall comes out of the other wall, so both ways are consistent
+            for (from, to) in [(a, b), (b, a)] {
+                let id = planes[to].id;
+                let plane = &mut planes[from];
+                plane.name = format!("Maze Portal {pair}");
+                plane.color = Color {
+                    r: color.r(),
+                    g: color.g(),
+                    b: color.b(),
+                };
+                plane.front_portal.other = Some(id);
+                plane.back_portal.other = Some(id);
+            }
+        }
+
+        let start = origin
+            + Vector3 {
+                x: self.cell_size * 0.5,
+                y: (self.wall_height * 0.5).min(1.1),
+                z: self.cell_size * 0.5,
+            };
+        Maze { planes, start }
+    }
+
+    /// Returns whether a maze should be generated
+    pub fn ui(&mut self, ui: &mut egui::Ui, units: Units) -> bool {
+        ui.horizontal(|ui| {
+            ui.label("Seed:");
+            ui.add(egui::DragValue::new(&mut self.seed));
+            if ui.button("Random").clicked() {
+                self.seed = rand::random();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Cells:");
+            ui.add(
+                egui::DragValue::new(&mut self.width)
+                    .prefix("x:")
+                    .range(1..=64),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.depth)
+                    .prefix("z:")
+                    .range(1..=64),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Cell Size:");
+            ui.add(
+                egui::DragValue::new(&mut self.cell_size)
+                    .speed(0.1)
+                    .range(0.1..=f32::INFINITY)
+                    .suffix(units.suffix()),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Wall Height:");
+            ui.add(
+                egui::DragValue::new(&mut self.wall_height)
+                    .speed(0.1)
+                    .range(0.1..=f32::INFINITY)
+                    .suffix(units.suffix()),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Portal Pairs:");
+            ui.add(egui::DragValue::new(&mut self.portal_pairs).range(0..=256));
+        });
+        ui.button("Generate Maze")
+            .on_hover_text("Replaces every plane in the scene")
+            .clicked()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_plane;
+
+    #[test]
+    fn same_seed_same_maze() {
+        let generator = MazeGenerator {
+            seed: 42,
+            ..MazeGenerator::default()
+        };
+        let (first, second) = (generator.generate(), generator.generate());
+        assert_eq!(
+            serde_json::to_string(&first.planes).unwrap(),
+            serde_json::to_string(&second.planes).unwrap()
+        );
+        let portals = |maze: &Maze| {
+            maze.planes
+                .iter()
+                .map(|plane| (plane.id, plane.front_portal.other, plane.back_portal.other))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(portals(&first), portals(&second));
+
+        let other = MazeGenerator {
+            seed: 43,
+            ..generator
+        }
+        .generate();
+        assert_ne!(portals(&first), portals(&other));
+    }
+
+    #[test]
+    fn portals_connect_both_ways() {
+        let maze = MazeGenerator::default().generate();
+        let mut portal_count = 0;
+        for plane in &maze.planes {
+            for portal in [&plane.front_portal, &plane.back_portal] {
+                let Some(other) = portal.other else {
+                    continue;
+                };
+                portal_count += 1;
+                let other = &maze.planes[find_plane(&maze.planes, other)
+                    .unwrap_or_else(|| panic!("{} connects to a missing plane", plane.name))];
+                assert_ne!(other.id, plane.id);
+                assert_eq!(other.front_portal.other, Some(plane.id), "{}", plane.name);
+                assert_eq!(other.back_portal.other, Some(plane.id), "{}", plane.name);
+            }
+        }
+        assert_eq!(portal_count, MazeGenerator::default().portal_pairs * 2 * 2);
+    }
+}
@@ -0,0 +1,243 @@
+use eframe::{egui, egui_wgpu::RenderState, wgpu};
+use ray_tracing::{CropRect, RayTracingPaintCallback, RayTracingRenderer, TextureRectReadback};
+
+/// A copy of the second adapter's rows on its way back to the cpu
+struct RowsReadback {
+    readback: TextureRectReadback,
+    /// Which frame the rows were copied in, so an older copy never replaces a newer one
+    frame: u64,
+}
+
+struct SecondaryDevice {
+    adapter_info: wgpu::AdapterInfo,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    /// Every renderer owns all of its gpu resources, so the second device just gets its own
+    renderer: RayTracingRenderer,
+    /// Two copies can be in flight, so a new one can be recorded while the last one is still being mapped
+    readbacks: [Option<RowsReadback>; 2],
+    frame: u64,
+    /// The frame of the rows last copied into the viewport's image
+    shown_frame: u64,
+}
+
+/// Splits the frame across a second adapter, it traces the top rows of the frame
+/// which are copied into the viewport's accumulated image once they have been read back,
+/// so the two adapters never wait on each other and the viewport shows the second adapter's last finished rows
+pub struct MultiGpu {
+    pub enabled: bool,
+    /// The fraction of rows traced by the second adapter
+    pub split: f32,
+    secondary: Option<SecondaryDevice>,
+    error: Option<String>,
+}
+
+impl Default for MultiGpu {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            split: 0.5,
+            secondary: None,
+            error: None,
+        }
+    }
+}
+
+impl MultiGpu {
+    /// Returns whether the split changed, in which case accumulation has to restart
+    pub fn ui(&mut self, ui: &mut egui::Ui, render_state: &RenderState) -> bool {
+        let primary_info = render_state.adapter.get_info();
+        let adapters = render_state
+            .available_adapters
+            .iter()
+            .filter(|adapter| adapter.get_info() != primary_info)
+            .collect::<Vec<_>>();
+
+        let mut changed = false;
+        ui.add_enabled_ui(!adapters.is_empty(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Second GPU:");
+                changed |= ui
+                    .checkbox(&mut self.enabled, "")
+                    .on_disabled_hover_text("There is only one adapter available")
+                    .changed();
+                let selected_text = self.secondary.as_ref().map_or("None".into(), |secondary| {
+                    secondary.adapter_info.name.clone()
+                });
+                egui::ComboBox::new("Second GPU Adapter", "")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for adapter in &adapters {
+                            let info = adapter.get_info();
+                            let selected = self
+                                .secondary
+                                .as_ref()
+                                .is_some_and(|secondary| secondary.adapter_info == info);
+                            let text = format!("{} ({:?})", info.name, info.backend);
+                            if ui.selectable_label(selected, text).clicked() && !selected {
+                                self.select_adapter(adapter);
+                                changed = true;
+                            }
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("Second GPU Rows:");
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.split, 0.05..=0.95).show_value(true))
+                    .changed();
+            });
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        changed
+    }
+
+    fn select_adapter(&mut self, adapter: &wgpu::Adapter) {
+        let device = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("Second Device"),
+            required_features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                | (adapter.features() & ray_tracing::OPTIONAL_FEATURES),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }));
+        let secondary = device
+            .map_err(|error| error.to_string())
+            .and_then(|(device, queue)| {
+                // it never displays anything, so the target format doesn't matter
+                let renderer =
+                    RayTracingRenderer::new(&device, &queue, wgpu::TextureFormat::Rgba8Unorm)
+                        .map_err(|error| error.to_string())?;
+                Ok((device, queue, renderer))
+            });
+        match secondary {
+            Ok((device, queue, renderer)) => {
+                self.secondary = Some(SecondaryDevice {
+                    adapter_info: adapter.get_info(),
+                    device,
+                    queue,
+                    renderer,
+                    readbacks: [None, None],
+                    frame: 0,
+                    shown_frame: 0,
+                });
+                self.error = None;
+            }
+            Err(error) => {
+                self.secondary = None;
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// Traces the top rows of `frame` on the second adapter and copies the last rows it finished into the viewport's renderer,
+    /// then crops `frame` to the rows that are left for the viewport's adapter
+    pub fn split_frame(&mut self, render_state: &RenderState, frame: &mut RayTracingPaintCallback) {
+        // only the accumulated image is copied back, not the light groups, aovs, baked lighting or probes
+        if !self.enabled
+            || frame.light_groups.is_some()
+            || frame.aovs
+            || frame.bake.is_some()
+            || frame.probes.is_some()
+        {
+            return;
+        }
+        let Some(secondary) = &mut self.secondary else {
+            return;
+        };
+
+        let region = frame.crop.unwrap_or(CropRect {
+            x: 0,
+            y: 0,
+            width: frame.image.width,
+            height: frame.image.height,
+        });
+        let secondary_rows = (region.height as f32 * self.split).round() as u32;
+        if region.width == 0 || secondary_rows == 0 || secondary_rows >= region.height {
+            return;
+        }
+        // the texture's first row is the bottom, so the top rows are the end of the region
+        let bottom = CropRect {
+            height: region.height - secondary_rows,
+            ..region
+        };
+        let top = CropRect {
+            y: region.y + bottom.height,
+            height: secondary_rows,
+            ..region
+        };
+
+        let mut renderer = render_state.renderer.write();
+        let primary: &mut RayTracingRenderer = renderer.callback_resources.get_mut().unwrap();
+        primary.select_target(&render_state.device, frame.image.target);
+        // the viewport's texture gets recreated this frame, so there is nowhere to copy into yet
+        if primary.texture_size() != (frame.image.width, frame.image.height) {
+            return;
+        }
+
+        // rows from before accumulation restarted would show the old image
+        if frame.image.accumulated_frames == 0 {
+            secondary.readbacks = [None, None];
+            secondary.shown_frame = secondary.frame;
+        }
+        for readback in secondary.readbacks.iter_mut().flatten() {
+            if let Some(pixels) = readback.readback.poll(&secondary.device)
+                && readback.frame > secondary.shown_frame
+                && readback.readback.rect() == top
+            {
+                primary.write_texture_rect(&render_state.queue, top, &pixels);
+                secondary.shown_frame = readback.frame;
+            }
+        }
+
+        let secondary_frame = RayTracingPaintCallback {
+            crop: Some(top),
+            histogram: false,
+            inspect_pixel: None,
+            planes: frame.planes.clone(),
+            dirty_planes: frame.dirty_planes.clone(),
+            probes: frame.probes.clone(),
+            ..*frame
+        };
+        let trace =
+            secondary
+                .renderer
+                .prepare_frame(&secondary.device, &secondary.queue, &secondary_frame);
+        secondary.frame += 1;
+        let mut encoder =
+            secondary
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Second GPU Readback Encoder"),
+                });
+        let precision = secondary.renderer.accumulation_precision();
+        // both copies are still in flight if neither is idle, then this frame's rows are only accumulated
+        let slot = secondary.readbacks.iter_mut().find(|readback| {
+            readback
+                .as_ref()
+                .is_none_or(|readback| readback.readback.is_idle())
+        });
+        if let Some(slot) = slot {
+            let readback = match slot.take() {
+                Some(readback)
+                    if readback.readback.rect() == top
+                        && readback.readback.precision() == precision =>
+                {
+                    readback.readback
+                }
+                _ => TextureRectReadback::new(&secondary.device, top, precision),
+            };
+            let readback = slot.insert(RowsReadback {
+                readback,
+                frame: secondary.frame,
+            });
+            secondary
+                .renderer
+                .copy_texture_rect(&mut encoder, &mut readback.readback);
+        }
+        secondary.queue.submit([trace, encoder.finish()]);
+
+        frame.crop = Some(bottom);
+    }
+}
@@ -0,0 +1,127 @@
+use eframe::egui;
+
+const SUCCESS_DURATION: f64 = 4.0;
+const ERROR_DURATION: f64 = 10.0;
+const TOAST_WIDTH: f32 = 320.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Success,
+    /// Succeeded, but not completely
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct Notification {
+    message: String,
+    level: Level,
+    /// `egui::InputState::time` when it was shown
+    time: Option<f64>,
+}
+
+impl Notification {
+    fn label(&self, ui: &mut egui::Ui) -> egui::Response {
+        match self.level {
+            Level::Success => ui.label(&self.message),
+            Level::Warning => ui.colored_label(ui.visuals().warn_fg_color, &self.message),
+            Level::Error => ui.colored_label(ui.visuals().error_fg_color, &self.message),
+        }
+    }
+}
+
+/// Toasts for the results of file operations, the last result also stays in the status bar
+#[derive(Debug, Default)]
+pub struct Notifications {
+    toasts: Vec<Notification>,
+    last: Option<Notification>,
+}
+
+impl Notifications {
+    pub fn success(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        tracing::info!("{message}");
+        self.push(Notification {
+            message,
+            level: Level::Success,
+            time: None,
+        });
+    }
+
+    pub fn warning(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        tracing::warn!("{message}");
+        self.push(Notification {
+            message,
+            level: Level::Warning,
+            time: None,
+        });
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        tracing::error!("{message}");
+        self.push(Notification {
+            message,
+            level: Level::Error,
+            time: None,
+        });
+    }
+
+    fn push(&mut self, notification: Notification) {
+        self.last = Some(notification.clone());
+        self.toasts.push(notification);
+    }
+
+    pub fn status_bar(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| match &self.last {
+            Some(last) => {
+                last.label(ui);
+            }
+            None => {
+                ui.weak("Ready");
+            }
+        });
+    }
+
+    /// Draws the toasts stacked in the bottom right corner, clicking one dismisses it
+    pub fn show_toasts(&mut self, ctx: &egui::Context) {
+        let time = ctx.input(|i| i.time);
+        self.toasts.retain_mut(|toast| {
+            let shown = *toast.time.get_or_insert(time);
+            let duration = match toast.level {
+                Level::Success => SUCCESS_DURATION,
+                Level::Warning | Level::Error => ERROR_DURATION,
+            };
+            time - shown < duration
+        });
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("Toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -32.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(TOAST_WIDTH);
+                for (index, toast) in self.toasts.iter().enumerate().rev() {
+                    let response = egui::Frame::popup(ui.style())
+                        .show(ui, |ui| {
+                            ui.set_width(TOAST_WIDTH);
+                            toast.label(ui);
+                        })
+                        .response
+                        .interact(egui::Sense::click());
+                    if response.clicked() {
+                        dismissed = Some(index);
+                    }
+                }
+            });
+        if let Some(index) = dismissed {
+            self.toasts.remove(index);
+        }
+        // keep repainting so the toasts disappear even without input
+        ctx.request_repaint_after_secs(1.0);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{Plane, PlaneField, PlaneGroup, PlaneUndo, flatten_planes};
+use eframe::egui::{self, ecolor::Hsva};
+use math::Vector3;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use ray_tracing::Color;
+use std::collections::HashMap;
+
+/// How the hues of a generated palette are spread around the color wheel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Harmony {
+    /// Hues next to each other
+    #[default]
+    Analogous,
+    /// Opposite hues
+    Complementary,
+    /// A hue and the two next to its opposite
+    SplitComplementary,
+    /// Three evenly spaced hues
+    Triadic,
+    /// Four evenly spaced hues
+    Tetradic,
+    /// A single hue in different shades
+    Monochromatic,
+}
+
+impl Harmony {
+    pub const ALL: [Self; 6] = [
+        Self::Analogous,
+        Self::Complementary,
+        Self::SplitComplementary,
+        Self::Triadic,
+        Self::Tetradic,
+        Self::Monochromatic,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Harmony::Analogous => "Analogous",
+            Harmony::Complementary => "Complementary",
+            Harmony::SplitComplementary => "Split Complementary",
+            Harmony::Triadic => "Triadic",
+            Harmony::Tetradic => "Tetradic",
+            Harmony::Monochromatic => "Monochromatic",
+        }
+    }
+
+    /// The hues relative to the base hue in turns, palettes with more colors go around them again in other shades
+    fn hue_offsets(self) -> &'static [f32] {
+        match self {
+            Harmony::Analogous => &[0.0, 1.0 / 12.0, -1.0 / 12.0, 2.0 / 12.0, -2.0 / 12.0],
+            Harmony::Complementary => &[0.0, 0.5],
+            Harmony::SplitComplementary => &[0.0, 5.0 / 12.0, 7.0 / 12.0],
+            Harmony::Triadic => &[0.0, 1.0 / 3.0, 2.0 / 3.0],
+            Harmony::Tetradic => &[0.0, 0.25, 0.5, 0.75],
+            Harmony::Monochromatic => &[0.0],
+        }
+    }
+}
+
+/// Which planes share a color of the palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorGrouping {
+    /// Every plane gets a random color
+    #[default]
+    Random,
+    /// The planes go through the palette in order
+    InOrder,
+    /// Planes with the same name apart from the number at its end share a color
+    ByName,
+    /// Planes facing along the same axis share a color
+    ByFacing,
+}
+
+impl ColorGrouping {
+    pub const ALL: [Self; 4] = [Self::Random, Self::InOrder, Self::ByName, Self::ByFacing];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorGrouping::Random => "Random",
+            ColorGrouping::InOrder => "In Order",
+            ColorGrouping::ByName => "By Name",
+            ColorGrouping::ByFacing => "By Facing",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaletteTarget {
+    #[default]
+    Color,
+    EmissiveColor,
+}
+
+impl PaletteTarget {
+    pub const ALL: [Self; 2] = [Self::Color, Self::EmissiveColor];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PaletteTarget::Color => "Color",
+            PaletteTarget::EmissiveColor => "Emissive Color",
+        }
+    }
+}
+
+impl PlaneField for PaletteTarget {
+    type Value = Color;
+
+    fn get(self, plane: &Plane) -> Color {
+        match self {
+            PaletteTarget::Color => plane.color,
+            PaletteTarget::EmissiveColor => plane.emissive_color,
+        }
+    }
+
+    fn set(self, plane: &mut Plane, color: Color) {
+        match self {
+            PaletteTarget::Color => plane.color = color,
+            PaletteTarget::EmissiveColor => plane.emissive_color = color,
+        }
+    }
+}
+
+/// Generates palettes of colors that go together and gives them to the planes,
+/// the same settings always generate the same palette and assign the same colors
+#[derive(Debug, Clone)]
+pub struct PaletteTool {
+    pub seed: u64,
+    pub harmony: Harmony,
+    /// The hue the palette is built around in turns
+    pub base_hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+    pub color_count: usize,
+    pub colors: Vec<Color>,
+    pub grouping: ColorGrouping,
+    pub target: PaletteTarget,
+    /// Only planes with names containing this get colors, empty for every plane
+    pub name_filter: String,
+    undo: PlaneUndo<PaletteTarget>,
+}
+
+impl Default for PaletteTool {
+    fn default() -> Self {
+        let mut palette_tool = Self {
+            seed: 0,
+            harmony: Harmony::default(),
+            base_hue: 0.6,
+            saturation: 0.6,
+            value: 0.8,
+            color_count: 5,
+            colors: vec![],
+            grouping: ColorGrouping::default(),
+            target: PaletteTarget::default(),
+            name_filter: String::new(),
+            undo: PlaneUndo::default(),
+        };
+        palette_tool.generate();
+        palette_tool
+    }
+}
+
+impl PaletteTool {
+    /// Replaces the colors with a palette from the harmony, base hue, saturation and value
+    pub fn generate(&mut self) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let offsets = self.harmony.hue_offsets();
+        self.colors = (0..self.color_count.max(1))
+            .map(|index| {
+                // every time the palette goes around the hues again the shades get darker and less saturated
+                let round = (index / offsets.len()) as f32;
+                let shade = if self.harmony == Harmony::Monochromatic {
+                    round / self.color_count.max(1) as f32
+                } else {
+                    round * 0.2
+                };
+                let hue =
+                    self.base_hue + offsets[index % offsets.len()] + rng.random_range(-0.01..=0.01);
+                let hsva = Hsva::new(
+                    hue.rem_euclid(1.0),
+                    (self.saturation * (1.0 - shade * 0.5)).clamp(0.0, 1.0),
+                    (self.value * (1.0 - shade)).clamp(0.0, 1.0),
+                    1.0,
+                );
+                Color::from(hsva.to_rgb())
+            })
+            .collect();
+    }
+
+    /// The planes that get colors and which color of the palette each of them gets
+    pub fn assignments(&self, planes: &[Plane]) -> Vec<(usize, Color)> {
+        if self.colors.is_empty() {
+            return vec![];
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut groups = HashMap::new();
+        planes
+            .iter()
+            .enumerate()
+            .filter(|(_, plane)| plane.name.contains(&self.name_filter))
+            .enumerate()
+            .map(|(order, (index, plane))| {
+                let color = match self.grouping {
+                    ColorGrouping::Random => rng.random_range(0..self.colors.len()),
+                    ColorGrouping::InOrder => order,
+                    ColorGrouping::ByName => {
+                        let name = plane
+                            .name
+                            .trim_end_matches(|c: char| c.is_ascii_digit() || c.is_whitespace());
+                        let group_count = groups.len();
+                        *groups.entry(name.to_owned()).or_insert(group_count)
+                    }
+                    ColorGrouping::ByFacing => facing(plane),
+                };
+                (index, self.colors[color % self.colors.len()])
+            })
+            .collect()
+    }
+
+    /// Returns whether any planes changed, `groups` are only used to find which way grouped planes face
+    pub fn ui(&mut self, ui: &mut egui::Ui, planes: &mut [Plane], groups: &[PlaneGroup]) -> bool {
+        let mut palette_changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Harmony:");
+            egui::ComboBox::new("Harmony", "")
+                .selected_text(self.harmony.name())
+                .show_ui(ui, |ui| {
+                    for harmony in Harmony::ALL {
+                        palette_changed |= ui
+                            .selectable_value(&mut self.harmony, harmony, harmony.name())
+                            .changed();
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Base Hue:");
+            let mut degrees = self.base_hue * 360.0;
+            palette_changed |= ui
+                .add(egui::Slider::new(&mut degrees, 0.0..=360.0).suffix("°"))
+                .changed();
+            self.base_hue = degrees / 360.0;
+        });
+        ui.horizontal(|ui| {
+            ui.label("Saturation:");
+            palette_changed |= ui
+                .add(egui::Slider::new(&mut self.saturation, 0.0..=1.0))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Value:");
+            palette_changed |= ui
+                .add(egui::Slider::new(&mut self.value, 0.0..=1.0))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Colors:");
+            palette_changed |= ui
+                .add(egui::DragValue::new(&mut self.color_count).range(1..=16))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Seed:");
+            palette_changed |= ui.add(egui::DragValue::new(&mut self.seed)).changed();
+            if ui
+                .button("Randomize")
+                .on_hover_text("Picks a new seed and base hue")
+                .clicked()
+            {
+                self.seed = rand::random();
+                self.base_hue = rand::random();
+                palette_changed = true;
+            }
+        });
+        if palette_changed {
+            self.generate();
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Palette:");
+            for color in &mut self.colors {
+                ui.color_edit_button_rgb(color.as_mut());
+            }
+        });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Assign To:");
+            egui::ComboBox::new("Palette Target", "")
+                .selected_text(self.target.name())
+                .show_ui(ui, |ui| {
+                    for target in PaletteTarget::ALL {
+                        ui.selectable_value(&mut self.target, target, target.name());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Grouping:");
+            egui::ComboBox::new("Color Grouping", "")
+                .selected_text(self.grouping.name())
+                .show_ui(ui, |ui| {
+                    for grouping in ColorGrouping::ALL {
+                        ui.selectable_value(&mut self.grouping, grouping, grouping.name());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Name Filter:");
+            ui.text_edit_singleline(&mut self.name_filter)
+                .on_hover_text("Only planes with names containing this get colors");
+        });
+
+        let mut changed = false;
+        let assignments = self.assignments(&flatten_planes(planes, groups));
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !assignments.is_empty(),
+                    egui::Button::new(format!("Assign Colors ({})", assignments.len())),
+                )
+                .clicked()
+            {
+                self.undo.apply(planes, self.target, assignments);
+                changed = true;
+            }
+            changed |= self.undo.ui(ui, "Undo Assign", planes);
+        });
+        changed
+    }
+}
+
+/// The axis a plane faces along, its front and back count as the same
+fn facing(plane: &Plane) -> usize {
+    let normal = plane.transform().rotor_part().rotate(Vector3::UP);
+    let normal = normal.as_ref().map(f32::abs);
+    (0..3)
+        .max_by(|&a, &b| normal[a].total_cmp(&normal[b]))
+        .unwrap()
+}
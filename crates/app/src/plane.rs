@@ -1,12 +1,44 @@
+use eframe::egui;
 use math::{Rotor, Transform, Vector3};
-use ray_tracing::{Color, GpuPlane, GpuPortalConnection};
-use serde::{Deserialize, Serialize};
+use ray_tracing::{
+    Color, EMISSIVE_BACK, EMISSIVE_FRONT, GpuPlane, GpuPortalConnection, LightGroup,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::PI,
+    path::PathBuf,
+};
 
-use crate::{Hit, Ray};
+use crate::{GroupId, Hit, Ray, Rumble, TraversalSound};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Identifies a plane no matter where it is in the scene's planes, so connections survive reordering and deleting planes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PlaneId(u64);
+
+impl PlaneId {
+    /// A random id, unique in practice
+    pub fn new() -> Self {
+        Self(rand::random())
+    }
+
+    /// A random id from `rng`, for generating the same scene from a seed
+    pub fn random(rng: &mut impl rand::Rng) -> Self {
+        Self(rng.random())
+    }
+}
+
+impl Default for PlaneId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Plane {
+    pub id: PlaneId,
     pub name: String,
     pub position: Vector3,
     pub xy_rotation: f32,
@@ -21,19 +53,202 @@ pub struct Plane {
     pub emissive_color: Color,
     pub emission_intensity: f32,
     pub emissive_checker_darkness: f32,
+    pub emissive_sides: EmissiveSides,
+    pub emission_profile: EmissionProfile,
+    /// The chance a ray refracts through the plane instead of scattering off it
+    pub transmission: f32,
+    pub ior: f32,
+    /// Cauchy B coefficient in µm², only used with spectral rendering
+    pub dispersion: f32,
+    /// From 0 for dielectrics to 1 for metals
+    pub metallic: f32,
+    /// How blurry specular reflections are, from 0 for a mirror to 1
+    pub roughness: f32,
+    /// How strongly a dielectric reflects specularly as a fraction of its Fresnel reflection,
+    /// 0 is matte with no specular lobe
+    pub specular: f32,
+    /// Which light group the plane's emission is accumulated in
+    pub light_group: LightGroup,
+    /// The camera sees only the shadows and light the rest of the scene casts onto the plane, over the background,
+    /// for compositing onto photographs with a transparent background
+    pub shadow_catcher: bool,
+    /// A png or jpeg image that multiplies the color instead of the checker pattern, stretched over the whole plane
+    pub texture: Option<PathBuf>,
     pub front_portal: PortalConnection,
     pub back_portal: PortalConnection,
+    /// Whether the camera can collide with and teleport through this plane
+    pub camera_collides: bool,
+    /// The group the plane's position and rotation are relative to, if it is in one
+    pub group: Option<GroupId>,
+}
+
+/// Which sides of a plane emit its light, the front is the side its y axis points out of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EmissiveSides {
+    #[default]
+    Both,
+    Front,
+    Back,
+}
+
+impl EmissiveSides {
+    pub const ALL: [Self; 3] = [Self::Both, Self::Front, Self::Back];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EmissiveSides::Both => "Both",
+            EmissiveSides::Front => "Front",
+            EmissiveSides::Back => "Back",
+        }
+    }
+
+    /// See [`GpuPlane::emissive_sides`]
+    pub fn gpu_value(self) -> u32 {
+        match self {
+            EmissiveSides::Both => EMISSIVE_FRONT | EMISSIVE_BACK,
+            EmissiveSides::Front => EMISSIVE_FRONT,
+            EmissiveSides::Back => EMISSIVE_BACK,
+        }
+    }
+
+    /// Returns whether the sides changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        for sides in Self::ALL {
+            changed |= ui.radio_value(self, sides, sides.name()).changed();
+        }
+        changed
+    }
+}
+
+/// How the brightness of an emissive plane changes with the angle it is seen from
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum EmissionProfile {
+    /// Equally bright from every angle, like a diffuse panel
+    #[default]
+    Lambertian,
+    /// Brightest straight on and falling off with the cosine of the angle to the plane's normal to the power of `exponent`,
+    /// like a spotlight panel, it emits as much light in total as a lambertian plane does
+    Focused { exponent: f32 },
+}
+
+impl EmissionProfile {
+    /// See [`GpuPlane::emission_exponent`]
+    pub fn exponent(self) -> f32 {
+        match self {
+            EmissionProfile::Lambertian => 0.0,
+            EmissionProfile::Focused { exponent } => exponent.max(0.0),
+        }
+    }
+
+    /// How much brighter the plane looks at `angle` radians from its normal than a lambertian plane would
+    pub fn brightness(self, angle: f32) -> f32 {
+        let exponent = self.exponent();
+        if exponent <= 0.0 {
+            return 1.0;
+        }
+        angle.cos().max(0.0).powf(exponent) * (exponent + 2.0) * 0.5
+    }
+
+    /// Returns whether the profile changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            if ui
+                .radio(*self == EmissionProfile::Lambertian, "Lambertian")
+                .clicked()
+                && *self != EmissionProfile::Lambertian
+            {
+                *self = EmissionProfile::Lambertian;
+                changed = true;
+            }
+            if ui
+                .radio(matches!(self, EmissionProfile::Focused { .. }), "Focused")
+                .clicked()
+                && *self == EmissionProfile::Lambertian
+            {
+                *self = EmissionProfile::Focused { exponent: 8.0 };
+                changed = true;
+            }
+        });
+        if let EmissionProfile::Focused { exponent } = self {
+            ui.horizontal(|ui| {
+                ui.label("Exponent:");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(exponent)
+                            .speed(0.1)
+                            .range(0.0..=1000.0),
+                    )
+                    .on_hover_text("Higher exponents focus the light into a narrower cone")
+                    .changed();
+            });
+        }
+        self.preview(ui);
+        changed
+    }
+
+    /// The brightness at every angle as a polar curve, with the plane at the bottom and its normal pointing up,
+    /// scaled so the brightest direction reaches the top
+    fn preview(self, ui: &mut egui::Ui) {
+        const SEGMENTS: usize = 64;
+
+        let width = ui.spacing().interact_size.y * 6.0;
+        let (rect, _) =
+            ui.allocate_exact_size(egui::vec2(width, width * 0.5), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        let origin = rect.center_bottom();
+        let radius = rect.height() - 2.0;
+        let peak = self.brightness(0.0);
+
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        painter.circle_stroke(
+            origin,
+            radius,
+            egui::Stroke::new(1.0, ui.visuals().weak_text_color()),
+        );
+        let points = (0..=SEGMENTS)
+            .map(|segment| {
+                let angle = (segment as f32 / SEGMENTS as f32 - 0.5) * PI;
+                let distance = self.brightness(angle) / peak * radius;
+                origin + egui::vec2(angle.sin(), -angle.cos()) * distance
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.5, ui.visuals().strong_text_color()),
+        ));
+    }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PortalConnection {
-    pub other_index: Option<usize>,
-    // pub flip: bool,
+    pub other: Option<PlaneId>,
+    /// How many times bigger anything that goes through comes out of the other plane
+    pub scale: f32,
+    /// Mirrors whatever goes through off of the plane first, so it comes out of the other side of the other plane
+    pub flip: bool,
+    pub rumble: Rumble,
+    pub sound: TraversalSound,
+}
+
+impl Default for PortalConnection {
+    fn default() -> Self {
+        Self {
+            other: None,
+            scale: 1.0,
+            flip: false,
+            rumble: Rumble::default(),
+            sound: TraversalSound::default(),
+        }
+    }
 }
 
 impl Default for Plane {
     fn default() -> Self {
         Self {
+            id: PlaneId::new(),
             name: "Default Plane".into(),
             position: Vector3 {
                 x: 0.0,
@@ -60,19 +275,37 @@ impl Default for Plane {
             },
             emission_intensity: 0.0,
             emissive_checker_darkness: 0.5,
+            emissive_sides: EmissiveSides::Both,
+            emission_profile: EmissionProfile::Lambertian,
+            transmission: 0.0,
+            ior: 1.5,
+            dispersion: 0.0,
+            metallic: 0.0,
+            roughness: 1.0,
+            specular: 0.0,
+            light_group: LightGroup::default(),
+            shadow_catcher: false,
+            texture: None,
             front_portal: PortalConnection::default(),
             back_portal: PortalConnection::default(),
+            camera_collides: true,
+            group: None,
         }
     }
 }
 
 impl Plane {
     pub fn transform(&self) -> Transform {
-        Transform::translation(self.position).then(Transform::from_rotor(
-            Rotor::rotation_xy(self.xy_rotation)
-                .then(Rotor::rotation_yz(self.yz_rotation))
-                .then(Rotor::rotation_xz(self.xz_rotation)),
-        ))
+        Transform::translation(self.position).then(Transform::from_rotor(self.rotation()))
+    }
+
+    pub fn rotation(&self) -> Rotor {
+        rotation_from_angles(self.xy_rotation, self.yz_rotation, self.xz_rotation)
+    }
+
+    /// Sets the rotation angles so the plane is turned by `rotation`, see [`Plane::rotation`]
+    pub fn set_rotation(&mut self, rotation: Rotor) {
+        (self.xy_rotation, self.yz_rotation, self.xz_rotation) = rotation_angles(rotation);
     }
 
     pub fn intersect(&self, ray: Ray) -> Option<Hit> {
@@ -88,7 +321,8 @@ impl Plane {
         let distance = (origin.y / direction.y).abs();
         let position = ray.origin + ray.direction * distance;
         let normal = transform
-            .transform_point(Vector3 {
+            .rotor_part()
+            .rotate(Vector3 {
                 x: 0.0,
                 y: -direction.y,
                 z: 0.0,
@@ -113,8 +347,10 @@ impl Plane {
         })
     }
 
-    pub fn to_gpu(&self) -> GpuPlane {
+    /// `indices` is where every plane is in the planes sent to the gpu, see [`plane_indices`]
+    pub fn to_gpu(&self, indices: &HashMap<PlaneId, usize>) -> GpuPlane {
         let Self {
+            id: _,
             name: _,
             position: _,
             xy_rotation: _,
@@ -129,8 +365,21 @@ impl Plane {
             emissive_color,
             emission_intensity,
             emissive_checker_darkness,
+            emissive_sides,
+            emission_profile,
+            transmission,
+            ior,
+            dispersion,
+            metallic,
+            roughness,
+            specular,
+            light_group,
+            shadow_catcher,
+            texture: _,
             ref front_portal,
             ref back_portal,
+            camera_collides: _,
+            group: _,
         } = *self;
         GpuPlane {
             transform: self.transform(),
@@ -142,20 +391,180 @@ impl Plane {
             checker_darkness,
             emissive_color: emissive_color * emission_intensity,
             emissive_checker_darkness,
+            emissive_sides: emissive_sides.gpu_value(),
+            emission_exponent: emission_profile.exponent(),
+            transmission,
+            ior,
+            dispersion,
+            metallic,
+            roughness,
+            specular,
+            light_group: light_group.index(),
+            shadow_catcher: shadow_catcher as u32,
+            baked_offset: u32::MAX,
+            // given its texture by `PlaneTextures`
+            texture: u32::MAX,
             front_portal: GpuPortalConnection {
                 other_index: front_portal
-                    .other_index
-                    .map(|index| index as u32)
-                    .unwrap_or(u32::MAX),
-                // flip: front_portal.flip as u32,
+                    .other
+                    .and_then(|id| indices.get(&id))
+                    .map_or(u32::MAX, |&index| index as u32),
+                scale: front_portal.scale,
+                flip: front_portal.flip as u32,
             },
             back_portal: GpuPortalConnection {
                 other_index: back_portal
-                    .other_index
-                    .map(|index| index as u32)
-                    .unwrap_or(u32::MAX),
-                // flip: back_portal.flip as u32,
+                    .other
+                    .and_then(|id| indices.get(&id))
+                    .map_or(u32::MAX, |&index| index as u32),
+                scale: back_portal.scale,
+                flip: back_portal.flip as u32,
             },
         }
     }
 }
+
+/// The xy, yz and xz rotations in the order planes and groups apply them
+pub fn rotation_from_angles(xy: f32, yz: f32, xz: f32) -> Rotor {
+    Rotor::rotation_xy(xy)
+        .then(Rotor::rotation_yz(yz))
+        .then(Rotor::rotation_xz(xz))
+}
+
+/// The xy, yz and xz angles that turn by `rotation`, see [`rotation_from_angles`]
+pub fn rotation_angles(rotation: Rotor) -> (f32, f32, f32) {
+    let x = rotation.rotate(Vector3::X);
+    let y = rotation.rotate(Vector3::Y);
+    let z = rotation.rotate(Vector3::Z);
+    // asin of y.z loses most of its precision close to a right angle
+    let yz = y.z.atan2(y.x.hypot(y.y));
+    // the xy and xz rotations turn around the same axis when the yz rotation is a right angle,
+    // so all of it is put in the xz rotation
+    if y.z.abs() > 0.9999 {
+        (0.0, yz, (-z.x).atan2(x.x))
+    } else {
+        ((-y.x).atan2(y.y), yz, x.z.atan2(z.z))
+    }
+}
+
+/// The index of every plane by its id
+pub fn plane_indices(planes: &[Plane]) -> HashMap<PlaneId, usize> {
+    planes
+        .iter()
+        .enumerate()
+        .map(|(index, plane)| (plane.id, index))
+        .collect()
+}
+
+/// The planes as they are sent to the gpu, where portals connect by index
+pub fn planes_to_gpu(planes: &[Plane]) -> Vec<GpuPlane> {
+    let indices = plane_indices(planes);
+    planes.iter().map(|plane| plane.to_gpu(&indices)).collect()
+}
+
+pub fn find_plane(planes: &[Plane], id: PlaneId) -> Option<usize> {
+    planes.iter().position(|plane| plane.id == id)
+}
+
+/// Deletes the plane with `id`, disconnecting the portals that were connected to it
+pub fn delete_plane(planes: &mut Vec<Plane>, id: PlaneId) {
+    planes.retain(|plane| plane.id != id);
+    for plane in planes {
+        for portal in [&mut plane.front_portal, &mut plane.back_portal] {
+            if portal.other == Some(id) {
+                portal.other = None;
+            }
+        }
+    }
+}
+
+/// Adds `copies` to the end of `planes` with new ids and returns them, connections between the copies go to the copies,
+/// connections to planes already in `planes` are kept and connections to anything else are removed
+pub fn insert_copies(planes: &mut Vec<Plane>, mut copies: Vec<Plane>) -> Vec<PlaneId> {
+    let existing = planes.iter().map(|plane| plane.id).collect::<HashSet<_>>();
+    let ids = copies.iter().map(|_| PlaneId::new()).collect::<Vec<_>>();
+    let mut new_ids = HashMap::new();
+    for (plane, &id) in copies.iter().zip(&ids) {
+        new_ids.entry(plane.id).or_insert(id);
+    }
+    for (plane, &id) in copies.iter_mut().zip(&ids) {
+        plane.id = id;
+        for portal in [&mut plane.front_portal, &mut plane.back_portal] {
+            portal.other = portal.other.and_then(|other| {
+                new_ids
+                    .get(&other)
+                    .copied()
+                    .or(existing.contains(&other).then_some(other))
+            });
+        }
+    }
+    planes.extend(copies);
+    ids
+}
+
+/// Deserializes a scene's planes, giving planes that share an id new ones
+pub fn deserialize_planes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Plane>, D::Error> {
+    let mut planes = Vec::<Plane>::deserialize(deserializer)?;
+    let mut ids = HashSet::new();
+    for plane in &mut planes {
+        while !ids.insert(plane.id) {
+            plane.id = PlaneId::new();
+        }
+    }
+    Ok(planes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    const ANGLES: [f32; 7] = [-3.0, -FRAC_PI_2, -0.7, 0.0, 0.4, FRAC_PI_2, 2.5];
+
+    /// Whether both turn every axis to the same place
+    fn assert_same_rotation(a: Rotor, b: Rotor, context: &str) {
+        for axis in [Vector3::X, Vector3::Y, Vector3::Z] {
+            let error = (a.rotate(axis) - b.rotate(axis)).magnitude();
+            assert!(error < 1e-4, "{context}: {a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn angles_round_trip() {
+        for xy in ANGLES {
+            for yz in [-1.5, -0.7, 0.0, 0.4, 1.5] {
+                for xz in ANGLES {
+                    let (new_xy, new_yz, new_xz) =
+                        rotation_angles(rotation_from_angles(xy, yz, xz));
+                    for (angle, new_angle) in [(xy, new_xy), (yz, new_yz), (xz, new_xz)] {
+                        assert!(
+                            (angle - new_angle).abs() < 1e-4,
+                            "({xy}, {yz}, {xz}) came back as ({new_xy}, {new_yz}, {new_xz})"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// At a right angle yz rotation the xy and xz rotations turn around the same axis,
+    /// so the angles can't come back the same but the rotation still has to
+    #[test]
+    fn rotation_round_trip() {
+        for xy in ANGLES {
+            for yz in [-FRAC_PI_2, -1.5, -0.7, 0.0, 0.4, 1.5, FRAC_PI_2] {
+                for xz in ANGLES {
+                    let rotation = rotation_from_angles(xy, yz, xz);
+                    let (new_xy, new_yz, new_xz) = rotation_angles(rotation);
+                    assert_same_rotation(
+                        rotation_from_angles(new_xy, new_yz, new_xz),
+                        rotation,
+                        &format!("({xy}, {yz}, {xz})"),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{Plane, PlaneId, find_plane};
+use eframe::egui;
+
+/// A part of a plane that tools set on many planes at once
+pub trait PlaneField: Copy {
+    type Value: Clone + PartialEq;
+
+    fn get(self, plane: &Plane) -> Self::Value;
+    fn set(self, plane: &mut Plane, value: Self::Value);
+}
+
+/// Undoes the last batch edit a tool made to one field of many planes, tools that edit planes in bulk share it
+/// so they undo the same way, it only covers that tool's last edit, it isn't an undo history of the whole app
+#[derive(Debug, Clone)]
+pub struct PlaneUndo<F: PlaneField> {
+    field: Option<F>,
+    /// Each edited plane's value before and after the edit
+    edits: Vec<(PlaneId, F::Value, F::Value)>,
+}
+
+impl<F: PlaneField> Default for PlaneUndo<F> {
+    fn default() -> Self {
+        Self {
+            field: None,
+            edits: vec![],
+        }
+    }
+}
+
+impl<F: PlaneField> PlaneUndo<F> {
+    /// Sets `field` of the plane at each index to its value, replacing the edit that can be undone
+    pub fn apply(
+        &mut self,
+        planes: &mut [Plane],
+        field: F,
+        values: impl IntoIterator<Item = (usize, F::Value)>,
+    ) {
+        self.field = Some(field);
+        self.edits = values
+            .into_iter()
+            .map(|(index, value)| {
+                let plane = &mut planes[index];
+                let before = field.get(plane);
+                field.set(plane, value);
+                (plane.id, before, field.get(plane))
+            })
+            .collect();
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Restores what the last edit changed, planes deleted since then are skipped,
+    /// and so are planes whose field was edited again so that edit isn't lost,
+    /// returns how many planes were restored
+    pub fn undo(&mut self, planes: &mut [Plane]) -> usize {
+        let Some(field) = self.field.take() else {
+            return 0;
+        };
+        let mut restored = 0;
+        for (id, before, after) in self.edits.drain(..) {
+            if let Some(index) = find_plane(planes, id)
+                && field.get(&planes[index]) == after
+            {
+                field.set(&mut planes[index], before);
+                restored += 1;
+            }
+        }
+        restored
+    }
+
+    /// A button undoing the last edit, returns whether any planes changed
+    pub fn ui(&mut self, ui: &mut egui::Ui, label: &str, planes: &mut [Plane]) -> bool {
+        if ui
+            .add_enabled(
+                !self.is_empty(),
+                egui::Button::new(format!("{label} ({})", self.len())),
+            )
+            .on_hover_text("Planes edited again since are left as they are")
+            .clicked()
+        {
+            let count = self.len();
+            let restored = self.undo(planes);
+            if restored < count {
+                tracing::info!(
+                    "{} planes were deleted or edited again since, so they weren't undone",
+                    count - restored
+                );
+            }
+            restored > 0
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy)]
+    struct Width;
+
+    impl PlaneField for Width {
+        type Value = f32;
+
+        fn get(self, plane: &Plane) -> f32 {
+            plane.width
+        }
+
+        fn set(self, plane: &mut Plane, value: f32) {
+            plane.width = value;
+        }
+    }
+
+    #[test]
+    fn undo_keeps_later_edits() {
+        let mut planes = vec![Plane::default(), Plane::default(), Plane::default()];
+        for plane in &mut planes {
+            plane.width = 1.0;
+        }
+        let mut undo = PlaneUndo::default();
+        undo.apply(&mut planes, Width, [(0, 2.0), (1, 2.0), (2, 2.0)]);
+        assert!(planes.iter().all(|plane| plane.width == 2.0));
+
+        planes[1].width = 3.0;
+        planes.remove(2);
+        planes.reverse();
+        assert_eq!(undo.undo(&mut planes), 1);
+        assert_eq!(planes[0].width, 3.0);
+        assert_eq!(planes[1].width, 1.0);
+        assert!(undo.is_empty());
+    }
+}
@@ -0,0 +1,407 @@
+use crate::{Camera, Plane, Units, find_plane, ui_vector3_with_suffix};
+use eframe::egui;
+use math::{Transform, Vector3};
+use ray_tracing::{GpuPlane, ProbeFrame, ProbeGrid, RayTracingPaintCallback};
+use serde::{Deserialize, Serialize};
+
+/// The most probes all the grids together can have, the spacing of a grid grows until it fits its share
+const MAX_PROBES: u32 = 1 << 15;
+
+/// A box of the scene lit by its own grid of probes, usually a room,
+/// rooms that are connected by portals need their own volumes on both sides to be lit the same
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProbeVolume {
+    pub name: String,
+    pub min: Vector3,
+    pub max: Vector3,
+    /// The distance between neighbouring probes, grows if the volume would need too many probes
+    pub spacing: f32,
+}
+
+impl Default for ProbeVolume {
+    fn default() -> Self {
+        Self {
+            name: "Room".into(),
+            min: Vector3::ZERO,
+            max: Vector3::ZERO,
+            spacing: 1.0,
+        }
+    }
+}
+
+impl ProbeVolume {
+    /// A volume centered on the camera
+    pub fn around_camera(camera: &Camera, index: usize) -> Self {
+        let half_size = Vector3 {
+            x: 2.0,
+            y: 1.5,
+            z: 2.0,
+        };
+        Self {
+            name: format!("Room {}", index + 1),
+            min: camera.position - half_size,
+            max: camera.position + half_size,
+            spacing: 1.0,
+        }
+    }
+
+    /// The box around this volume after going through a portal with `transform`,
+    /// rotated volumes can't be represented exactly so it is the box around the rotated one
+    pub fn through_portal(&self, transform: Transform, name: String) -> Self {
+        let corners = (0..8).map(|corner: u32| {
+            let pick = |bit, min, max| if corner & bit == 0 { min } else { max };
+            transform.transform_point(Vector3 {
+                x: pick(1, self.min.x, self.max.x),
+                y: pick(2, self.min.y, self.max.y),
+                z: pick(4, self.min.z, self.max.z),
+            })
+        });
+        let (min, max) = bounds(corners);
+        Self {
+            name,
+            min,
+            max,
+            spacing: self.spacing,
+        }
+    }
+
+    fn grid(&self, max_probes: u32) -> ProbeGrid {
+        let (min, max) = bounds([self.min, self.max].into_iter());
+        grid_between(min, max, self.spacing, max_probes)
+    }
+}
+
+/// Grids of irradiance probes over the scene's [`ProbeVolume`]s, or a single one covering every plane if there are none,
+/// see [`ProbeFrame`], the probes accumulate over a number of frames and are then kept until the scene changes
+pub struct IrradianceProbes {
+    pub enabled: bool,
+    /// The distance between neighbouring probes of the grid covering every plane,
+    /// grows if the scene would need too many probes
+    pub spacing: f32,
+    /// How many rays every probe traces per frame
+    pub samples_per_probe: u32,
+    /// How many frames the probes accumulate before they stop updating
+    pub frames: u32,
+    /// Draws the probes over the viewport
+    pub show_probes: bool,
+    accumulated_frames: u32,
+    /// The grids of the last frame, empty if the probes weren't used
+    grids: Vec<ProbeGrid>,
+}
+
+impl Default for IrradianceProbes {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 1.0,
+            samples_per_probe: 64,
+            frames: 32,
+            show_probes: false,
+            accumulated_frames: 0,
+            grids: vec![],
+        }
+    }
+}
+
+impl IrradianceProbes {
+    /// Returns whether the settings changed, in which case the probes have started over
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Irradiance Probes:");
+            changed |= ui
+                .checkbox(&mut self.enabled, "")
+                .on_hover_text(
+                    "Lights diffuse bounces past the first one with grids of probes instead of tracing on, \
+                    much less noisy indoors but light can leak through thin walls",
+                )
+                .changed();
+        });
+        if self.enabled {
+            ui.indent("Irradiance Probes", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Spacing:");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.spacing)
+                                .speed(0.01)
+                                .range(0.05..=f32::INFINITY),
+                        )
+                        .on_hover_text(
+                            "Of the grid covering every plane, only used when the scene has no probe volumes",
+                        )
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Samples Per Probe:");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.samples_per_probe).range(1..=4096))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Frames:");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.frames).range(1..=u32::MAX))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Show Probes:");
+                    ui.checkbox(&mut self.show_probes, "");
+                });
+                let probe_count = self
+                    .grids
+                    .iter()
+                    .map(ProbeGrid::probe_count)
+                    .sum::<u32>();
+                ui.label(format!(
+                    "{probe_count} probes in {} grids",
+                    self.grids.len()
+                ));
+                ui.add(
+                    egui::ProgressBar::new(self.accumulated_frames as f32 / self.frames as f32)
+                        .text(format!(
+                            "{}/{} frames",
+                            self.accumulated_frames, self.frames
+                        )),
+                );
+            });
+        }
+        if changed {
+            self.restart();
+        }
+        changed
+    }
+
+    /// Starts accumulating the probes over, for when the scene changes
+    pub fn restart(&mut self) {
+        self.accumulated_frames = 0;
+    }
+
+    /// Whether the probes are still accumulating
+    pub fn updating(&self) -> bool {
+        self.enabled && self.accumulated_frames < self.frames
+    }
+
+    /// Puts grids over the `volumes`, or around the planes of `frame` if there are none, and lights it with the probes,
+    /// updating them another frame if `update` is set
+    pub fn apply(
+        &mut self,
+        frame: &mut RayTracingPaintCallback,
+        volumes: &[ProbeVolume],
+        update: bool,
+    ) {
+        if !self.enabled {
+            self.grids.clear();
+            return;
+        }
+
+        let grids = if volumes.is_empty() {
+            vec![fit_grid(&frame.planes, self.spacing)]
+        } else {
+            let max_probes = MAX_PROBES / volumes.len() as u32;
+            volumes
+                .iter()
+                .map(|volume| volume.grid(max_probes))
+                .collect()
+        };
+        let same_grid = |a: &ProbeGrid, b: &ProbeGrid| {
+            <[f32; 3]>::from(a.origin) == <[f32; 3]>::from(b.origin)
+                && a.spacing == b.spacing
+                && (a.count_x, a.count_y, a.count_z) == (b.count_x, b.count_y, b.count_z)
+        };
+        if grids.len() != self.grids.len()
+            || !grids.iter().zip(&self.grids).all(|(a, b)| same_grid(a, b))
+        {
+            self.restart();
+        }
+
+        let update = update && self.updating();
+        frame.probes = Some(ProbeFrame {
+            grids: grids.clone(),
+            accumulated_frames: self.accumulated_frames,
+            samples_per_probe: if update { self.samples_per_probe } else { 0 },
+        });
+        self.grids = grids;
+        if update {
+            self.accumulated_frames += 1;
+        }
+    }
+
+    /// Draws every probe of the last frame's grids in front of the camera,
+    /// `fov` is the vertical field of view of the viewport
+    pub fn draw(&self, painter: &egui::Painter, rect: egui::Rect, camera: &Camera, fov: f32) {
+        if !self.enabled || !self.show_probes {
+            return;
+        }
+
+        let scale = rect.height() * 0.5 / (fov * 0.5).tan();
+        for grid in &self.grids {
+            for position in grid.probe_positions() {
+                let local = camera.rotation.reverse().rotate(position - camera.position);
+                if local.x < 0.001 {
+                    continue;
+                }
+                let center = egui::pos2(
+                    rect.center().x + local.z / local.x * scale,
+                    rect.center().y - local.y / local.x * scale,
+                );
+                if !rect.contains(center) {
+                    continue;
+                }
+                let radius = (0.03 / local.x * scale).clamp(1.5, 6.0);
+                painter.circle_filled(center, radius, egui::Color32::from_rgb(255, 220, 80));
+            }
+        }
+    }
+}
+
+/// Lists the probe volumes, returns whether any of them changed
+pub fn ui_probe_volumes(
+    ui: &mut egui::Ui,
+    volumes: &mut Vec<ProbeVolume>,
+    planes: &[Plane],
+    camera: &Camera,
+    units: Units,
+) -> bool {
+    let mut changed = false;
+    let mut to_delete = None;
+    let mut to_add = None;
+    for (index, volume) in volumes.iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut volume.name);
+                egui::ComboBox::new("Duplicate Through", "")
+                    .selected_text("Duplicate Through")
+                    .show_ui(ui, |ui| {
+                        for plane in planes {
+                            for (side, connection) in
+                                [("Front", &plane.front_portal), ("Back", &plane.back_portal)]
+                            {
+                                let Some(other_plane) = connection
+                                    .other
+                                    .and_then(|id| find_plane(planes, id))
+                                    .map(|index| &planes[index])
+                                else {
+                                    continue;
+                                };
+                                if ui
+                                    .selectable_label(false, format!("{} ({side})", plane.name))
+                                    .clicked()
+                                {
+                                    let transform =
+                                        other_plane.transform().then(plane.transform().reverse());
+                                    to_add = Some(volume.through_portal(
+                                        transform,
+                                        format!("{} through {}", volume.name, plane.name),
+                                    ));
+                                }
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Adds a copy of this volume on the other side of a portal, \
+                        so the lighting matches on both sides",
+                    );
+                if ui.button("Delete").clicked() {
+                    to_delete = Some(index);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Min:");
+                changed |= ui_vector3_with_suffix(ui, &mut volume.min, units.suffix()).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Max:");
+                changed |= ui_vector3_with_suffix(ui, &mut volume.max, units.suffix()).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Spacing:");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut volume.spacing)
+                            .speed(0.01)
+                            .range(0.05..=f32::INFINITY)
+                            .suffix(units.suffix()),
+                    )
+                    .changed();
+            });
+        });
+        ui.separator();
+    }
+    if let Some(index) = to_delete {
+        volumes.remove(index);
+        changed = true;
+    }
+    if let Some(volume) = to_add {
+        volumes.push(volume);
+        changed = true;
+    }
+    if ui
+        .button("Add Room Around Camera")
+        .on_hover_text("Probe volumes replace the grid covering every plane")
+        .clicked()
+    {
+        volumes.push(ProbeVolume::around_camera(camera, volumes.len()));
+        changed = true;
+    }
+    changed
+}
+
+/// A grid from `min` to `max`, with the spacing grown until it fits in `max_probes`
+fn grid_between(min: Vector3, max: Vector3, spacing: f32, max_probes: u32) -> ProbeGrid {
+    let size = max - min;
+    let mut spacing = spacing.max(0.05);
+    let counts = |spacing: f32| {
+        [size.x, size.y, size.z].map(|size| (size / spacing).ceil().clamp(0.0, 1024.0) as u32 + 1)
+    };
+    while counts(spacing).iter().product::<u32>() > max_probes.max(1) {
+        spacing *= 1.25;
+    }
+    let [count_x, count_y, count_z] = counts(spacing);
+    ProbeGrid {
+        origin: min,
+        spacing,
+        count_x,
+        count_y,
+        count_z,
+    }
+}
+
+/// A grid covering every corner of `planes`
+fn fit_grid(planes: &[GpuPlane], spacing: f32) -> ProbeGrid {
+    let corners = planes.iter().flat_map(|plane| {
+        [(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5), (0.5, 0.5)].map(|(x, z)| {
+            plane.transform.transform_point(Vector3 {
+                x: x * plane.width,
+                y: 0.0,
+                z: z * plane.height,
+            })
+        })
+    });
+    let (min, max) = bounds(corners);
+    grid_between(min, max, spacing, MAX_PROBES)
+}
+
+/// The smallest and largest coordinates of `points`, zero if there are none
+fn bounds(points: impl Iterator<Item = Vector3>) -> (Vector3, Vector3) {
+    let mut min = Vector3::ZERO;
+    let mut max = Vector3::ZERO;
+    for (index, point) in points.enumerate() {
+        if index == 0 {
+            (min, max) = (point, point);
+        }
+        min = Vector3 {
+            x: min.x.min(point.x),
+            y: min.y.min(point.y),
+            z: min.z.min(point.z),
+        };
+        max = Vector3 {
+            x: max.x.max(point.x),
+            y: max.y.max(point.y),
+            z: max.z.max(point.z),
+        };
+    }
+    (min, max)
+}
@@ -0,0 +1,299 @@
+use eframe::egui;
+use ray_tracing::PrepareTimings;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How many frames the timeline keeps
+const MAX_FRAMES: usize = 240;
+const TIMELINE_HEIGHT: f32 = 48.0;
+const FLAME_ROW_HEIGHT: f32 = 18.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProfileScope {
+    pub name: &'static str,
+    /// Relative to the start of the frame
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+impl ProfileScope {
+    pub fn end(&self) -> Duration {
+        self.start + self.duration
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfiledFrame {
+    pub scopes: Vec<ProfileScope>,
+}
+
+impl ProfiledFrame {
+    pub fn duration(&self) -> Duration {
+        self.scopes
+            .iter()
+            .map(ProfileScope::end)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The scopes sorted by when they start with how deep they are nested in the scopes around them
+    fn nested_scopes(&self) -> Vec<(usize, ProfileScope)> {
+        let mut scopes = self.scopes.clone();
+        scopes.sort_by_key(|scope| (scope.start, std::cmp::Reverse(scope.duration)));
+        let mut ends = Vec::<Duration>::new();
+        scopes
+            .into_iter()
+            .map(|scope| {
+                while ends.last().is_some_and(|&end| end <= scope.start) {
+                    ends.pop();
+                }
+                let depth = ends.len();
+                ends.push(scope.end());
+                (depth, scope)
+            })
+            .collect()
+    }
+}
+
+/// Records how long the cpu spends in the parts of every frame, to find what gets slow as scenes grow,
+/// and how long the gpu takes to trace them when it can measure that
+#[derive(Default)]
+pub struct Profiler {
+    pub enabled: bool,
+    pub paused: bool,
+    /// See [`Profiler::gpu_trace_time`]
+    gpu_trace_time: Option<Duration>,
+    frames: VecDeque<ProfiledFrame>,
+    current: ProfiledFrame,
+    /// When the current frame started, `None` while disabled
+    frame_start: Option<Instant>,
+    /// The frame shown in the flame graph, an index into `frames`, the latest one if `None`
+    selected: Option<usize>,
+}
+
+impl Profiler {
+    /// Finishes the previous frame and starts recording a new one, `prepare_timings` are the renderer's
+    /// since the previous frame started, the renderer prepares frames after the ui is built so they go at the end
+    pub fn begin_frame(&mut self, time: Instant, prepare_timings: Option<PrepareTimings>) {
+        if let Some(gpu_trace) = prepare_timings.and_then(|timings| timings.gpu_trace) {
+            self.gpu_trace_time = Some(gpu_trace);
+        }
+        if self.frame_start.is_some() {
+            let mut frame = std::mem::take(&mut self.current);
+            if let Some(prepare_timings) = prepare_timings
+                && prepare_timings.frames > 0
+            {
+                let start = frame.duration();
+                frame.scopes.push(ProfileScope {
+                    name: "Prepare Frame",
+                    start,
+                    duration: prepare_timings.total,
+                });
+                frame.scopes.push(ProfileScope {
+                    name: "Encode Scene",
+                    start,
+                    duration: prepare_timings.encoding,
+                });
+            }
+            if !self.paused {
+                if self.frames.len() >= MAX_FRAMES {
+                    self.frames.pop_front();
+                    self.selected = self.selected.and_then(|selected| selected.checked_sub(1));
+                }
+                self.frames.push_back(frame);
+            }
+        }
+        self.current = ProfiledFrame::default();
+        self.frame_start = self.enabled.then_some(time);
+    }
+
+    /// How long the gpu took to trace the latest frame it measured, a few frames behind what is shown,
+    /// kept even while profiling is off, `None` if the gpu can't measure it
+    pub fn gpu_trace_time(&self) -> Option<Duration> {
+        self.gpu_trace_time
+    }
+
+    /// Records a scope from `start` until now in the current frame
+    pub fn record(&mut self, name: &'static str, start: Instant) {
+        if let Some(frame_start) = self.frame_start {
+            self.current.scopes.push(ProfileScope {
+                name,
+                start: start.saturating_duration_since(frame_start),
+                duration: start.elapsed(),
+            });
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Profile CPU:");
+            ui.checkbox(&mut self.enabled, "");
+            ui.toggle_value(&mut self.paused, "Pause");
+            if ui.button("Clear").clicked() {
+                self.frames.clear();
+                self.selected = None;
+            }
+        });
+        match self.gpu_trace_time {
+            Some(gpu_trace_time) => ui.label(format!(
+                "GPU Trace: {:.3}ms",
+                gpu_trace_time.as_secs_f64() * 1000.0
+            )),
+            None => ui.label("GPU Trace: Not Measured"),
+        };
+        if self.frames.is_empty() {
+            return;
+        }
+
+        self.ui_timeline(ui);
+        let Some(frame) = self
+            .selected
+            .and_then(|selected| self.frames.get(selected))
+            .or(self.frames.back())
+        else {
+            return;
+        };
+        ui.label(format!(
+            "Frame: {:.3}ms",
+            frame.duration().as_secs_f64() * 1000.0
+        ));
+        ui_flame_graph(ui, frame);
+
+        // averages over every kept frame, slowest first
+        let mut totals = Vec::<(&'static str, Duration)>::new();
+        for scope in self.frames.iter().flat_map(|frame| &frame.scopes) {
+            match totals.iter_mut().find(|(name, _)| *name == scope.name) {
+                Some((_, total)) => *total += scope.duration,
+                None => totals.push((scope.name, scope.duration)),
+            }
+        }
+        totals.sort_by_key(|&(_, total)| std::cmp::Reverse(total));
+        for (name, total) in totals {
+            ui.label(format!(
+                "{name}: {:.3}ms average",
+                total.as_secs_f64() * 1000.0 / self.frames.len() as f64
+            ));
+        }
+    }
+
+    /// The duration of every kept frame as a bar, clicking a bar shows that frame in the flame graph
+    fn ui_timeline(&mut self, ui: &mut egui::Ui) {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width().max(200.0), TIMELINE_HEIGHT),
+            egui::Sense::click(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(192));
+
+        let max = self
+            .frames
+            .iter()
+            .map(ProfiledFrame::duration)
+            .max()
+            .unwrap_or_default()
+            .as_secs_f32()
+            .max(f32::EPSILON);
+        let bar_width = rect.width() / MAX_FRAMES as f32;
+        let selected = self.selected.unwrap_or(self.frames.len() - 1);
+        for (index, frame) in self.frames.iter().enumerate() {
+            let height = frame.duration().as_secs_f32() / max * rect.height();
+            let left = rect.left() + index as f32 * bar_width;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(left, rect.bottom() - height),
+                    egui::pos2(left + bar_width, rect.bottom()),
+                ),
+                0.0,
+                if index == selected {
+                    egui::Color32::YELLOW
+                } else {
+                    egui::Color32::from_gray(200)
+                },
+            );
+        }
+
+        let hovered_index = response.hover_pos().and_then(|position| {
+            let index = ((position.x - rect.left()) / bar_width) as usize;
+            (index < self.frames.len()).then_some(index)
+        });
+        if let Some(index) = hovered_index {
+            if response.clicked() {
+                // selecting the latest frame follows new frames again
+                self.selected = (index + 1 < self.frames.len()).then_some(index);
+            }
+            response.on_hover_text(format!(
+                "{:.3}ms",
+                self.frames[index].duration().as_secs_f64() * 1000.0
+            ));
+        }
+    }
+}
+
+/// Every scope of `frame` as a bar under the scope it is nested in
+fn ui_flame_graph(ui: &mut egui::Ui, frame: &ProfiledFrame) {
+    let scopes = frame.nested_scopes();
+    let rows = scopes
+        .iter()
+        .map(|&(depth, _)| depth + 1)
+        .max()
+        .unwrap_or(1);
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(
+            ui.available_width().max(200.0),
+            rows as f32 * FLAME_ROW_HEIGHT,
+        ),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    let scale = rect.width() / frame.duration().as_secs_f32().max(f32::EPSILON);
+
+    let mut hovered = None;
+    for (depth, scope) in scopes {
+        let scope_rect = egui::Rect::from_min_size(
+            egui::pos2(
+                rect.left() + scope.start.as_secs_f32() * scale,
+                rect.top() + depth as f32 * FLAME_ROW_HEIGHT,
+            ),
+            egui::vec2(
+                (scope.duration.as_secs_f32() * scale).max(1.0),
+                FLAME_ROW_HEIGHT - 1.0,
+            ),
+        );
+        painter.rect_filled(scope_rect, 2.0, scope_color(scope.name));
+        let text = egui::WidgetText::from(scope.name).into_galley(
+            ui,
+            Some(egui::TextWrapMode::Truncate),
+            scope_rect.width() - 4.0,
+            egui::TextStyle::Small,
+        );
+        painter.with_clip_rect(scope_rect).galley(
+            scope_rect.left_center() + egui::vec2(2.0, -text.size().y * 0.5),
+            text,
+            egui::Color32::BLACK,
+        );
+        if response
+            .hover_pos()
+            .is_some_and(|position| scope_rect.contains(position))
+        {
+            hovered = Some(scope);
+        }
+    }
+    if let Some(scope) = hovered {
+        response.on_hover_text(format!(
+            "{}: {:.3}ms",
+            scope.name,
+            scope.duration.as_secs_f64() * 1000.0
+        ));
+    }
+}
+
+/// The same scope always gets the same color
+fn scope_color(name: &str) -> egui::Color32 {
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    let hue = (hash % 360) as f32 / 360.0;
+    egui::Rgba::from(egui::ecolor::Hsva::new(hue, 0.5, 0.9, 1.0)).into()
+}
@@ -0,0 +1,58 @@
+use eframe::egui;
+use ray_tracing::RayTracingPaintCallback;
+use serde::{Deserialize, Serialize};
+
+/// The render settings that trade image quality for speed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityPreset {
+    pub samples_per_pixel: u32,
+    pub max_bounces: u32,
+    pub recursive_portal_count: u32,
+}
+
+impl QualityPreset {
+    /// Fast enough to stay interactive while editing, portals and bounces are still visible but shallow
+    pub const DRAFT: Self = Self {
+        samples_per_pixel: 1,
+        max_bounces: 1,
+        recursive_portal_count: 3,
+    };
+
+    /// Overrides the settings of `frame` that are part of the preset, except the samples per pixel,
+    /// which have to be known before the frame is made so they can be counted as accumulated
+    pub fn apply(&self, frame: &mut RayTracingPaintCallback) {
+        frame.camera.max_bounces = self.max_bounces;
+        frame.camera.recursive_portal_count = self.recursive_portal_count;
+    }
+
+    /// Returns whether the preset changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Samples Per Pixel:");
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.samples_per_pixel).range(1..=u32::MAX))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Max Light Bounces:");
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.max_bounces))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Max Portal Recursion:");
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.recursive_portal_count))
+                .changed();
+        });
+        changed
+    }
+}
+
+impl Default for QualityPreset {
+    fn default() -> Self {
+        Self::DRAFT
+    }
+}
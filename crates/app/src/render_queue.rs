@@ -0,0 +1,292 @@
+use crate::{
+    ExportMetadata, RenderSettings, Scene, SettingsCaption, encode_png, flatten_background,
+};
+use eframe::{egui, egui_wgpu::RenderState};
+use ray_tracing::RayTracingRenderer;
+use std::path::PathBuf;
+
+struct RenderJob {
+    name: String,
+    /// Snapshots of the scene and settings when the job was queued, the camera is already moved to the bookmark
+    scene: Scene,
+    render_settings: RenderSettings,
+    width: u32,
+    height: u32,
+    samples_per_pixel: u32,
+    output_path: PathBuf,
+    status: JobStatus,
+}
+
+enum JobStatus {
+    Queued,
+    Rendering {
+        accumulated_frames: u32,
+        accumulated_samples: u64,
+    },
+    Done,
+    Failed(String),
+}
+
+pub struct RenderQueue {
+    pub running: bool,
+    jobs: Vec<RenderJob>,
+    /// Separate from the viewport's renderer so jobs don't disturb what is being accumulated there
+    renderer: Option<RayTracingRenderer>,
+    bookmark: Option<usize>,
+    width: u32,
+    height: u32,
+    samples_per_pixel: u32,
+    output_path: String,
+}
+
+impl Default for RenderQueue {
+    fn default() -> Self {
+        Self {
+            running: false,
+            jobs: vec![],
+            renderer: None,
+            bookmark: None,
+            width: 1920,
+            height: 1080,
+            samples_per_pixel: 256,
+            output_path: "render_1.png".into(),
+        }
+    }
+}
+
+impl RenderQueue {
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        scene: &Scene,
+        scene_name: &str,
+        render_settings: &RenderSettings,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Camera:");
+            let camera_name = |bookmark: Option<usize>| match bookmark {
+                Some(index) => scene.bookmarks[index].name.as_str(),
+                None => "Scene Camera",
+            };
+            if self
+                .bookmark
+                .is_some_and(|index| index >= scene.bookmarks.len())
+            {
+                self.bookmark = None;
+            }
+            egui::ComboBox::new("Job Camera", "")
+                .selected_text(camera_name(self.bookmark))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.bookmark, None, camera_name(None));
+                    for index in 0..scene.bookmarks.len() {
+                        ui.selectable_value(
+                            &mut self.bookmark,
+                            Some(index),
+                            camera_name(Some(index)),
+                        );
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Resolution:");
+            ui.add(egui::DragValue::new(&mut self.width).range(1..=16384));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut self.height).range(1..=16384));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Samples Per Pixel:");
+            ui.add(egui::DragValue::new(&mut self.samples_per_pixel).range(1..=u32::MAX));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Output:");
+            ui.text_edit_singleline(&mut self.output_path);
+        });
+        if ui
+            .button("Queue Job")
+            .on_hover_text("Snapshots the current scene and render settings")
+            .clicked()
+        {
+            let mut scene = scene.clone();
+            let mut name = scene_name.to_string();
+            if let Some(index) = self.bookmark {
+                let bookmark = scene.bookmarks[index].clone();
+                scene.camera.go_to(&bookmark);
+                name = format!("{name} ({})", bookmark.name);
+            }
+            self.jobs.push(RenderJob {
+                name,
+                scene,
+                render_settings: render_settings.clone(),
+                width: self.width,
+                height: self.height,
+                samples_per_pixel: self.samples_per_pixel,
+                output_path: self.output_path.clone().into(),
+                status: JobStatus::Queued,
+            });
+            self.output_path = format!("render_{}.png", self.jobs.len() + 1);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            let text = if self.running { "Pause" } else { "Start" };
+            ui.toggle_value(&mut self.running, text);
+            if ui.button("Clear Finished").clicked() {
+                self.jobs.retain(|job| {
+                    matches!(job.status, JobStatus::Queued | JobStatus::Rendering { .. })
+                });
+            }
+        });
+
+        let mut to_remove = None;
+        for (index, job) in self.jobs.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{}: {}x{}, {} spp -> {}",
+                    job.name,
+                    job.width,
+                    job.height,
+                    job.samples_per_pixel,
+                    job.output_path.display()
+                ));
+                if ui.button("Remove").clicked() {
+                    to_remove = Some(index);
+                }
+            });
+            match job.status {
+                JobStatus::Queued => {
+                    ui.label("Queued");
+                }
+                JobStatus::Rendering {
+                    accumulated_samples,
+                    ..
+                } => {
+                    ui.add(
+                        egui::ProgressBar::new(
+                            accumulated_samples as f32 / job.samples_per_pixel as f32,
+                        )
+                        .show_percentage(),
+                    );
+                }
+                JobStatus::Done => {
+                    ui.label("Done");
+                }
+                JobStatus::Failed(ref error) => {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+            }
+        }
+        if let Some(index) = to_remove {
+            self.jobs.remove(index);
+        }
+    }
+
+    /// Traces one dispatch of the current job, finishing it if it has all its samples
+    pub fn update(&mut self, render_state: &RenderState) {
+        if !self.running {
+            return;
+        }
+        let Some(job) = self
+            .jobs
+            .iter_mut()
+            .find(|job| matches!(job.status, JobStatus::Queued | JobStatus::Rendering { .. }))
+        else {
+            self.running = false;
+            return;
+        };
+
+        let renderer = match self.renderer.take().map_or_else(
+            || {
+                RayTracingRenderer::new(
+                    &render_state.device,
+                    &render_state.queue,
+                    render_state.target_format,
+                )
+            },
+            Ok,
+        ) {
+            Ok(renderer) => self.renderer.insert(renderer),
+            Err(error) => {
+                tracing::error!(job = job.name, %error, "render job failed");
+                job.status = JobStatus::Failed(error.to_string());
+                return;
+            }
+        };
+
+        let (accumulated_frames, accumulated_samples) = match job.status {
+            JobStatus::Rendering {
+                accumulated_frames,
+                accumulated_samples,
+            } => (accumulated_frames, accumulated_samples),
+            _ => (0, 0),
+        };
+        let samples_per_pixel = (job.samples_per_pixel as u64 - accumulated_samples)
+            .min(job.render_settings.max_samples_per_dispatch.max(1) as u64)
+            as u32;
+        let frame = job.render_settings.accumulation_frame(
+            &job.scene,
+            job.width,
+            job.height,
+            accumulated_frames,
+            accumulated_samples,
+            samples_per_pixel,
+        );
+        render_state.queue.submit([renderer.prepare_frame(
+            &render_state.device,
+            &render_state.queue,
+            &frame,
+        )]);
+
+        let accumulated_samples = accumulated_samples + samples_per_pixel as u64;
+        job.status = if accumulated_samples < job.samples_per_pixel as u64 {
+            JobStatus::Rendering {
+                accumulated_frames: accumulated_frames + 1,
+                accumulated_samples,
+            }
+        } else {
+            let mut pixels = renderer.read_texture(&render_state.device, &render_state.queue);
+            if job.render_settings.flatten_export_background {
+                pixels = flatten_background(
+                    &pixels,
+                    job.scene.viewport_background(&job.render_settings),
+                );
+            }
+            let metadata = job.render_settings.export_metadata.then(|| ExportMetadata {
+                scene_name: job.name.clone(),
+                samples_per_pixel: accumulated_samples,
+                seed: job.render_settings.seed,
+                render_settings_json: serde_json::to_string(&job.render_settings).unwrap(),
+                scene_json: serde_json::to_string(&job.scene).unwrap(),
+            });
+            let result = encode_png(
+                job.width,
+                job.height,
+                &pixels,
+                job.render_settings.tone_mapper,
+                metadata.as_ref(),
+                job.render_settings.export_footer,
+                job.render_settings
+                    .export_caption
+                    .then_some(SettingsCaption {
+                        samples_per_pixel: accumulated_samples,
+                        max_bounces: job.render_settings.max_bounces,
+                        denoised: false,
+                        tone_mapper: job.render_settings.tone_mapper,
+                    }),
+            )
+            .map_err(|error| error.to_string())
+            .and_then(|png| {
+                std::fs::write(&job.output_path, png).map_err(|error| error.to_string())
+            });
+            match result {
+                Ok(()) => {
+                    tracing::info!(job = job.name, path = %job.output_path.display(), "finished render job");
+                    JobStatus::Done
+                }
+                Err(error) => {
+                    tracing::error!(job = job.name, %error, "render job failed");
+                    JobStatus::Failed(error)
+                }
+            }
+        };
+    }
+}
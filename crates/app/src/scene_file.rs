@@ -0,0 +1,252 @@
+use crate::{Plane, PlaneId, Scene};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashSet,
+    path::{Component, Path, PathBuf},
+};
+
+/// The version scenes are saved as, bumped with a new migration whenever a change to [`Scene`]
+/// would load older files wrong, files from before versions existed are version 0
+pub const SCENE_VERSION: u32 = 1;
+
+/// Upgrades a scene from one version to the next
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// The migration at each index upgrades from that version
+const MIGRATIONS: [Migration; SCENE_VERSION as usize] = [index_connections_to_ids];
+
+/// Loads a scene file, migrating it if it was saved by an older version,
+/// the paths of textures and sounds are relative to the file and are made absolute
+pub fn load_scene(path: &Path) -> Result<Scene, String> {
+    let mut scene = std::fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|s| parse_scene(&s))?;
+    let directory = scene_directory(path)?;
+    for file in scene.planes.iter_mut().flat_map(plane_files) {
+        *file = directory.join(&*file);
+    }
+    Ok(scene)
+}
+
+/// Saves `scene` to a scene file, with the paths of textures and sounds relative to the file so the scene can be moved
+/// along with them
+pub fn save_scene(path: &Path, scene: &Scene) -> Result<(), String> {
+    let directory = scene_directory(path)?;
+    let mut scene = scene.clone();
+    for file in scene.planes.iter_mut().flat_map(plane_files) {
+        if let Some(relative) = relative_path(file, &directory) {
+            *file = relative;
+        }
+    }
+    std::fs::write(path, serde_json::to_string(&scene).unwrap()).map_err(|error| error.to_string())
+}
+
+/// The paths of the files a plane uses
+fn plane_files(plane: &mut Plane) -> impl Iterator<Item = &mut PathBuf> {
+    plane
+        .texture
+        .as_mut()
+        .into_iter()
+        .chain(plane.front_portal.sound.path.as_mut())
+        .chain(plane.back_portal.sound.path.as_mut())
+}
+
+/// The absolute path of the directory the scene file at `path` is in
+fn scene_directory(path: &Path) -> Result<PathBuf, String> {
+    let path = std::path::absolute(path).map_err(|error| error.to_string())?;
+    Ok(path.parent().map(Path::to_path_buf).unwrap_or(path))
+}
+
+/// `path` relative to the absolute `directory`, going up out of it where they differ,
+/// `None` if `path` isn't absolute or they have nothing in common, like being on different drives
+fn relative_path(path: &Path, directory: &Path) -> Option<PathBuf> {
+    let path = path.components().collect::<Vec<_>>();
+    let directory = directory.components().collect::<Vec<_>>();
+    if !matches!(
+        path.first(),
+        Some(Component::Prefix(_) | Component::RootDir)
+    ) {
+        return None;
+    }
+    let shared = path
+        .iter()
+        .zip(&directory)
+        .take_while(|(a, b)| a == b)
+        .count();
+    // the root alone is shared by everything
+    if shared <= 1 {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for _ in shared..directory.len() {
+        relative.push("..");
+    }
+    relative.extend(&path[shared..]);
+    Some(relative)
+}
+
+/// Parses a saved scene, migrating it if it was saved by an older version
+pub fn parse_scene(s: &str) -> Result<Scene, String> {
+    let mut value = serde_json::from_str::<Value>(s).map_err(|error| error.to_string())?;
+    let scene = value
+        .as_object_mut()
+        .ok_or("the scene isn't a JSON object")?;
+    let version = match scene.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("the scene's version isn't a number: {version}"))?,
+    };
+    if version > SCENE_VERSION as u64 {
+        return Err(format!(
+            "the scene was saved as version {version}, this build of Portals only loads up to version {SCENE_VERSION}"
+        ));
+    }
+    for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migrate(scene).map_err(|error| {
+            format!(
+                "failed to upgrade the scene from version {from} to {}: {error}",
+                from + 1
+            )
+        })?;
+    }
+    scene.insert("version".into(), SCENE_VERSION.into());
+    serde_json::from_value(value).map_err(|error| error.to_string())
+}
+
+/// Planes before version 1 may not have ids and may connect portals by index as `other_index`,
+/// every plane gets a unique id and the indices become those ids, indices past the end of the planes become an id
+/// no plane has so validation still reports them as dangling
+fn index_connections_to_ids(scene: &mut Map<String, Value>) -> Result<(), String> {
+    let Some(planes) = scene.get_mut("planes") else {
+        return Ok(());
+    };
+    let planes = planes.as_array_mut().ok_or("planes isn't an array")?;
+
+    let mut ids = vec![];
+    let mut seen = HashSet::new();
+    for plane in &mut *planes {
+        let plane = plane.as_object_mut().ok_or("a plane isn't a JSON object")?;
+        let id = plane
+            .get("id")
+            .and_then(|id| serde_json::from_value::<PlaneId>(id.clone()).ok())
+            .filter(|&id| seen.insert(id))
+            .unwrap_or_else(|| {
+                let mut id = PlaneId::new();
+                while !seen.insert(id) {
+                    id = PlaneId::new();
+                }
+                id
+            });
+        plane.insert("id".into(), serde_json::to_value(id).unwrap());
+        ids.push(id);
+    }
+
+    for plane in planes {
+        for portal in ["front_portal", "back_portal"] {
+            let Some(portal) = plane.get_mut(portal).and_then(Value::as_object_mut) else {
+                continue;
+            };
+            let index = portal.remove("other_index");
+            if portal.get("other").is_none_or(Value::is_null) {
+                let other = index
+                    .as_ref()
+                    .and_then(Value::as_u64)
+                    .filter(|&index| index != u32::MAX as u64)
+                    .map(|index| {
+                        ids.get(index as usize).copied().unwrap_or_else(|| {
+                            let mut id = PlaneId::new();
+                            while seen.contains(&id) {
+                                id = PlaneId::new();
+                            }
+                            id
+                        })
+                    });
+                portal.insert("other".into(), serde_json::to_value(other).unwrap());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_plane;
+
+    #[test]
+    fn unversioned_index_connections() {
+        let scene = parse_scene(
+            r#"{
+                "planes": [
+                    { "name": "A", "front_portal": { "other_index": 1 } },
+                    { "name": "B", "back_portal": { "other_index": 0 } },
+                    { "name": "C", "front_portal": { "other_index": 4294967295 }, "back_portal": { "other_index": 3 } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let planes = &scene.planes;
+        assert_eq!(scene.version, SCENE_VERSION);
+        assert_eq!(planes[0].front_portal.other, Some(planes[1].id));
+        assert_eq!(planes[1].back_portal.other, Some(planes[0].id));
+        assert_eq!(planes[2].front_portal.other, None);
+        let dangling = planes[2].back_portal.other.unwrap();
+        assert_eq!(find_plane(planes, dangling), None);
+        assert_eq!(find_plane(planes, planes[2].id), Some(2));
+    }
+
+    #[test]
+    fn unversioned_duplicate_ids() {
+        let scene = parse_scene(
+            r#"{
+                "planes": [
+                    { "id": 7, "front_portal": { "other": 7 } },
+                    { "id": 7, "front_portal": { "other_index": 0 } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let planes = &scene.planes;
+        assert_ne!(planes[0].id, planes[1].id);
+        assert_eq!(planes[0].front_portal.other, Some(planes[0].id));
+        assert_eq!(planes[1].front_portal.other, Some(planes[0].id));
+    }
+
+    #[test]
+    fn round_trip() {
+        let scene = Scene::default();
+        let loaded = parse_scene(&serde_json::to_string(&scene).unwrap()).unwrap();
+        assert_eq!(loaded.content_hash(), scene.content_hash());
+    }
+
+    #[test]
+    fn relative_texture_paths() {
+        let directory = Path::new("/scenes/portals");
+        assert_eq!(
+            relative_path(Path::new("/scenes/portals/textures/brick.png"), directory),
+            Some(PathBuf::from("textures/brick.png"))
+        );
+        assert_eq!(
+            relative_path(Path::new("/scenes/textures/brick.png"), directory),
+            Some(PathBuf::from("../textures/brick.png"))
+        );
+        assert_eq!(
+            relative_path(Path::new("/textures/brick.png"), directory),
+            None
+        );
+        assert_eq!(relative_path(Path::new("brick.png"), directory), None);
+    }
+
+    #[test]
+    fn newer_version() {
+        let error = parse_scene(&format!(r#"{{ "version": {} }}"#, SCENE_VERSION + 1)).unwrap_err();
+        assert!(error.contains("version"), "{error}");
+    }
+
+    #[test]
+    fn not_an_object() {
+        assert!(parse_scene("[]").is_err());
+        assert!(parse_scene("not json").is_err());
+    }
+}
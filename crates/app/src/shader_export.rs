@@ -0,0 +1,249 @@
+use crate::{RenderSettings, Scene};
+use math::{Transform, Vector3};
+use ray_tracing::{
+    Color, EMISSIVE_BACK, EMISSIVE_FRONT, GpuPlane, GpuPortalConnection, LightGroup,
+};
+use std::cell::Cell;
+
+const WGSL_TEMPLATE: &str = include_str!("shader_export/standalone.wgsl");
+const SHADERTOY_TEMPLATE: &str = include_str!("shader_export/shadertoy.glsl");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderLanguage {
+    /// A vertex and fragment shader that only needs a resolution uniform
+    Wgsl,
+    /// GLSL with Shadertoy's `mainImage` entry point
+    Shadertoy,
+}
+
+/// Generates a standalone shader with the scene's planes and camera baked into it as constants,
+/// it traces the scene the same way as the unlit render type so it can be shared without the app,
+/// fails if any of the baked in values is NaN or infinite, as neither language has a literal for them
+pub fn export_shader(
+    scene: &Scene,
+    scene_name: &str,
+    render_settings: &RenderSettings,
+    language: ShaderLanguage,
+) -> Result<String, String> {
+    let callback = render_settings.paint_callback(scene, 1, 1);
+    let camera = callback.camera;
+    let code = Code {
+        language,
+        non_finite: Cell::new(false),
+    };
+
+    let planes = callback
+        .planes
+        .iter()
+        .map(|plane| code.plane(plane, &callback.planes))
+        .collect::<Vec<_>>();
+    // arrays can't be empty, so an empty scene still gets a plane that is never intersected
+    let plane_array_length = planes.len().max(1);
+    let planes = if planes.is_empty() {
+        code.plane(&empty_plane(), &[])
+    } else {
+        planes.join(",\n")
+    };
+
+    let template = match language {
+        ShaderLanguage::Wgsl => WGSL_TEMPLATE,
+        ShaderLanguage::Shadertoy => SHADERTOY_TEMPLATE,
+    };
+    let shader = template
+        // the name goes in a comment, so it can't be allowed to end the line
+        .replace("{{SCENE_NAME}}", &scene_name.replace(['\n', '\r'], " "))
+        .replace("{{PLANE_COUNT}}", &callback.planes.len().to_string())
+        .replace("{{PLANE_ARRAY_LENGTH}}", &plane_array_length.to_string())
+        .replace("{{PLANES}}", &planes)
+        .replace(
+            "{{CAMERA_ORIGIN}}",
+            &code.vector(camera.transform.transform_point(Vector3::ZERO)),
+        )
+        .replace("{{CAMERA_ROTATION}}", &code.rotation(camera.transform))
+        .replace("{{UP_SKY_COLOR}}", &code.color(camera.up_sky_color))
+        .replace("{{DOWN_SKY_COLOR}}", &code.color(camera.down_sky_color))
+        .replace("{{SKY_UP}}", &code.vector(camera.up))
+        .replace("{{SUN_COLOR}}", &code.color(camera.sun_color))
+        .replace("{{SUN_DIRECTION}}", &code.vector(camera.sun_direction))
+        .replace("{{SUN_SIZE}}", &code.float(camera.sun_size))
+        .replace(
+            "{{RECURSIVE_PORTAL_COUNT}}",
+            &camera
+                .recursive_portal_count
+                .min(i32::MAX as u32)
+                .to_string(),
+        )
+        .replace("{{NEAR_PLANE}}", &code.float(camera.near_plane))
+        .replace("{{PORTAL_EPSILON}}", &code.float(camera.portal_epsilon));
+    if code.non_finite.get() {
+        return Err(
+            "the scene or camera has NaN or infinite values, the scene's warnings list the planes that do".into(),
+        );
+    }
+    Ok(shader)
+}
+
+fn empty_plane() -> GpuPlane {
+    let black = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+    };
+    GpuPlane {
+        transform: Transform::IDENTITY,
+        width: 0.0,
+        height: 0.0,
+        checker_count_x: 1,
+        checker_count_z: 1,
+        color: black,
+        checker_darkness: 0.0,
+        emissive_color: black,
+        emissive_checker_darkness: 0.0,
+        emissive_sides: EMISSIVE_FRONT | EMISSIVE_BACK,
+        emission_exponent: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        dispersion: 0.0,
+        metallic: 0.0,
+        roughness: 1.0,
+        specular: 0.0,
+        light_group: LightGroup::default().index(),
+        shadow_catcher: 0,
+        baked_offset: u32::MAX,
+        texture: u32::MAX,
+        front_portal: GpuPortalConnection {
+            other_index: u32::MAX,
+            scale: 1.0,
+            flip: 0,
+        },
+        back_portal: GpuPortalConnection {
+            other_index: u32::MAX,
+            scale: 1.0,
+            flip: 0,
+        },
+    }
+}
+
+struct Code {
+    language: ShaderLanguage,
+    /// Set once a NaN or infinite value has been emitted
+    non_finite: Cell<bool>,
+}
+
+impl Code {
+    /// `Debug` always includes a decimal point or exponent, so it is a float literal in both languages
+    fn float(&self, value: f32) -> String {
+        if !value.is_finite() {
+            self.non_finite.set(true);
+        }
+        format!("{value:?}")
+    }
+
+    fn vector(&self, vector: Vector3) -> String {
+        let Vector3 { x, y, z } = vector;
+        match self.language {
+            ShaderLanguage::Wgsl => {
+                format!(
+                    "vec3<f32>({}, {}, {})",
+                    self.float(x),
+                    self.float(y),
+                    self.float(z)
+                )
+            }
+            ShaderLanguage::Shadertoy => format!(
+                "vec3({}, {}, {})",
+                self.float(x),
+                self.float(y),
+                self.float(z)
+            ),
+        }
+    }
+
+    fn color(&self, color: Color) -> String {
+        let Color { r, g, b } = color;
+        self.vector(Vector3 { x: r, y: g, z: b })
+    }
+
+    /// The rotation part of `transform` as a matrix, the columns are where each axis is rotated to
+    fn rotation(&self, transform: Transform) -> String {
+        let rotor = transform.rotor_part();
+        self.matrix([Vector3::X, Vector3::Y, Vector3::Z].map(|axis| rotor.rotate(axis)))
+    }
+
+    fn matrix(&self, columns: [Vector3; 3]) -> String {
+        let [x, y, z] = columns.map(|column| self.vector(column));
+        match self.language {
+            ShaderLanguage::Wgsl => format!("mat3x3<f32>({x}, {y}, {z})"),
+            ShaderLanguage::Shadertoy => format!("mat3({x}, {y}, {z})"),
+        }
+    }
+
+    /// The rotation and scale matrix and the translation for taking world space through a portal
+    /// and the normal of the side of the other plane it comes out of, or `-1` if there is none,
+    /// `front` is whether it is the plane's front portal
+    fn portal(
+        &self,
+        plane: &GpuPlane,
+        portal: GpuPortalConnection,
+        front: bool,
+        planes: &[GpuPlane],
+    ) -> String {
+        let Some(other_plane) = planes.get(portal.other_index as usize) else {
+            return format!(
+                "-1, {}, {}, {}",
+                self.rotation(Transform::IDENTITY),
+                self.vector(Vector3::ZERO),
+                self.vector(Vector3::ZERO)
+            );
+        };
+        // into the plane's local space, mirrored and scaled there, and out of the other plane
+        let to_local = plane.transform.reverse();
+        let through = |point: Vector3| {
+            let mut local = to_local.transform_point(point);
+            if portal.flip != 0 {
+                local.y = -local.y;
+            }
+            other_plane.transform.transform_point(local * portal.scale)
+        };
+        let translation = through(Vector3::ZERO);
+        // going through the front comes out of the other plane's back, unless it is mirrored
+        let mut exit_normal = other_plane.transform.rotor_part().rotate(Vector3::Y);
+        if front != (portal.flip != 0) {
+            exit_normal = -exit_normal;
+        }
+        format!(
+            "{}, {}, {}, {}",
+            portal.other_index,
+            self.matrix(
+                [Vector3::X, Vector3::Y, Vector3::Z].map(|axis| through(axis) - translation)
+            ),
+            self.vector(translation),
+            self.vector(exit_normal)
+        )
+    }
+
+    fn plane(&self, plane: &GpuPlane, planes: &[GpuPlane]) -> String {
+        let to_local = plane.transform.reverse();
+        let fields = [
+            self.rotation(to_local),
+            self.vector(to_local.transform_point(Vector3::ZERO)),
+            self.vector(plane.transform.rotor_part().rotate(Vector3::Y)),
+            self.vec2(plane.width, plane.height),
+            self.vec2(plane.checker_count_x as f32, plane.checker_count_z as f32),
+            self.color(plane.color),
+            self.float(plane.checker_darkness),
+            self.color(plane.emissive_color),
+            self.float(plane.emissive_checker_darkness),
+            self.portal(plane, plane.front_portal, true, planes),
+            self.portal(plane, plane.back_portal, false, planes),
+        ];
+        format!("    Plane({})", fields.join(", "))
+    }
+
+    fn vec2(&self, x: f32, y: f32) -> String {
+        match self.language {
+            ShaderLanguage::Wgsl => format!("vec2<f32>({}, {})", self.float(x), self.float(y)),
+            ShaderLanguage::Shadertoy => format!("vec2({}, {})", self.float(x), self.float(y)),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// {{SCENE_NAME}}, exported from Portals
+// a standalone version of the unlit portal tracing, paste it into a new shader on https://www.shadertoy.com
+
+struct Plane
+{
+    // world space to the plane's local space, where the plane is the XZ plane
+    mat3 to_local_rotation;
+    vec3 to_local_translation;
+    vec3 normal;
+    vec2 size;
+    vec2 checker_count;
+    vec3 color;
+    float checker_darkness;
+    vec3 emissive_color;
+    float emissive_checker_darkness;
+    // -1 is no portal, the transforms take world space through the portal, the rotations include its scale and mirroring,
+    // the exit normals point to the side of the other plane the portal comes out of
+    int front_portal;
+    mat3 front_rotation;
+    vec3 front_translation;
+    vec3 front_exit_normal;
+    int back_portal;
+    mat3 back_rotation;
+    vec3 back_translation;
+    vec3 back_exit_normal;
+};
+
+const int PLANE_COUNT = {{PLANE_COUNT}};
+const Plane planes[{{PLANE_ARRAY_LENGTH}}] = Plane[{{PLANE_ARRAY_LENGTH}}](
+{{PLANES}}
+);
+
+const vec3 CAMERA_ORIGIN = {{CAMERA_ORIGIN}};
+const mat3 CAMERA_ROTATION = {{CAMERA_ROTATION}};
+const vec3 UP_SKY_COLOR = {{UP_SKY_COLOR}};
+const vec3 DOWN_SKY_COLOR = {{DOWN_SKY_COLOR}};
+const vec3 SKY_UP = {{SKY_UP}};
+const vec3 SUN_COLOR = {{SUN_COLOR}};
+const vec3 SUN_DIRECTION = {{SUN_DIRECTION}};
+const float SUN_SIZE = {{SUN_SIZE}};
+const int RECURSIVE_PORTAL_COUNT = {{RECURSIVE_PORTAL_COUNT}};
+const float NEAR_PLANE = {{NEAR_PLANE}};
+const float PORTAL_EPSILON = {{PORTAL_EPSILON}};
+
+struct Hit
+{
+    int plane;
+    float distance;
+    bool front;
+    vec3 color;
+};
+
+bool intersect_plane(Plane plane, vec3 origin, vec3 direction, out Hit hit)
+{
+    vec3 local_origin = plane.to_local_rotation * origin + plane.to_local_translation;
+    vec3 local_direction = plane.to_local_rotation * direction;
+    if (sign(local_origin.y) == sign(local_direction.y) || abs(local_direction.y) < 0.001)
+        return false;
+
+    hit.distance = abs(local_origin.y / local_direction.y);
+    hit.front = local_direction.y < 0.0;
+
+    vec2 local_position = local_origin.xz + local_direction.xz * hit.distance;
+    if (any(greaterThan(abs(local_position), plane.size * 0.5)))
+        return false;
+
+    vec3 color = plane.color;
+    vec3 emissive_color = plane.emissive_color;
+    ivec2 cell = ivec2((local_position / plane.size + 0.5) * plane.checker_count);
+    if ((cell.x + cell.y) % 2 == 1)
+    {
+        color *= plane.checker_darkness;
+        emissive_color *= plane.emissive_checker_darkness;
+    }
+    hit.color = color + emissive_color;
+    return true;
+}
+
+// hits behind `clip_plane` are ignored, xyz is the normal of the side that is kept and w the offset along it
+Hit intersect_scene(vec3 origin, vec3 direction, float min_distance, int ignored_plane, vec4 clip_plane)
+{
+    Hit closest_hit;
+    closest_hit.plane = -1;
+    for (int i = 0; i < PLANE_COUNT; i++)
+    {
+        Hit hit;
+        if (i != ignored_plane && intersect_plane(planes[i], origin, direction, hit) && hit.distance >= min_distance && dot(clip_plane.xyz, origin + direction * hit.distance) + clip_plane.w >= 0.0 && (closest_hit.plane < 0 || hit.distance < closest_hit.distance))
+        {
+            hit.plane = i;
+            closest_hit = hit;
+        }
+    }
+    return closest_hit;
+}
+
+vec3 skybox(vec3 direction)
+{
+    if (acos(dot(SUN_DIRECTION, direction)) < SUN_SIZE)
+        return SUN_COLOR;
+    return mix(DOWN_SKY_COLOR, UP_SKY_COLOR, dot(direction, SKY_UP) * 0.5 + 0.5);
+}
+
+vec3 ray_color(vec3 origin, vec3 direction)
+{
+    Hit hit = intersect_scene(origin, direction, NEAR_PLANE, -1, vec4(0.0));
+    for (int i = 0; i < RECURSIVE_PORTAL_COUNT; i++)
+    {
+        if (hit.plane < 0)
+            break;
+        Plane plane = planes[hit.plane];
+        int other_plane = hit.front ? plane.front_portal : plane.back_portal;
+        if (other_plane < 0)
+            break;
+        mat3 rotation = hit.front ? plane.front_rotation : plane.back_rotation;
+        vec3 translation = hit.front ? plane.front_translation : plane.back_translation;
+
+        vec3 normal = hit.front ? plane.normal : -plane.normal;
+        vec3 exit_point = rotation * (origin + direction * hit.distance) + translation;
+        vec3 position = origin + direction * hit.distance - normal * PORTAL_EPSILON;
+        origin = rotation * position + translation;
+        direction = normalize(rotation * direction);
+        // only what is on the side the ray comes out of the other plane can be seen through the portal
+        vec3 exit_normal = hit.front ? plane.front_exit_normal : plane.back_exit_normal;
+        hit = intersect_scene(origin, direction, 0.0, other_plane, vec4(exit_normal, -dot(exit_normal, exit_point)));
+    }
+    if (hit.plane < 0)
+        return skybox(direction);
+    return hit.color;
+}
+
+vec3 linear_to_srgb(vec3 color)
+{
+    color = clamp(color, 0.0, 1.0);
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(0.0031308, color));
+}
+
+void mainImage(out vec4 fragColor, in vec2 fragCoord)
+{
+    vec2 uv = fragCoord / iResolution.xy * 2.0 - 1.0;
+    float aspect = iResolution.x / iResolution.y;
+    vec3 direction = normalize(CAMERA_ROTATION * vec3(1.0, uv.y, uv.x * aspect));
+    fragColor = vec4(linear_to_srgb(ray_color(CAMERA_ORIGIN, direction)), 1.0);
+}
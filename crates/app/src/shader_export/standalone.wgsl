@@ -0,0 +1,160 @@
+// {{SCENE_NAME}}, exported from Portals
+// a standalone version of the unlit portal tracing, draw a single triangle with `vertex` and `fragment`,
+// `resolution` is the size of the target in pixels, the colors are linear so the target should be sRGB
+
+struct Plane {
+    // world space to the plane's local space, where the plane is the XZ plane
+    to_local_rotation: mat3x3<f32>,
+    to_local_translation: vec3<f32>,
+    normal: vec3<f32>,
+    size: vec2<f32>,
+    checker_count: vec2<f32>,
+    color: vec3<f32>,
+    checker_darkness: f32,
+    emissive_color: vec3<f32>,
+    emissive_checker_darkness: f32,
+    // -1 is no portal, the transforms take world space through the portal, the rotations include its scale and mirroring,
+    // the exit normals point to the side of the other plane the portal comes out of
+    front_portal: i32,
+    front_rotation: mat3x3<f32>,
+    front_translation: vec3<f32>,
+    front_exit_normal: vec3<f32>,
+    back_portal: i32,
+    back_rotation: mat3x3<f32>,
+    back_translation: vec3<f32>,
+    back_exit_normal: vec3<f32>,
+}
+
+const PLANE_COUNT: i32 = {{PLANE_COUNT}};
+var<private> planes: array<Plane, {{PLANE_ARRAY_LENGTH}}> = array<Plane, {{PLANE_ARRAY_LENGTH}}>(
+{{PLANES}}
+);
+
+const CAMERA_ORIGIN = {{CAMERA_ORIGIN}};
+const CAMERA_ROTATION = {{CAMERA_ROTATION}};
+const UP_SKY_COLOR = {{UP_SKY_COLOR}};
+const DOWN_SKY_COLOR = {{DOWN_SKY_COLOR}};
+const SKY_UP = {{SKY_UP}};
+const SUN_COLOR = {{SUN_COLOR}};
+const SUN_DIRECTION = {{SUN_DIRECTION}};
+const SUN_SIZE: f32 = {{SUN_SIZE}};
+const RECURSIVE_PORTAL_COUNT: i32 = {{RECURSIVE_PORTAL_COUNT}};
+const NEAR_PLANE: f32 = {{NEAR_PLANE}};
+const PORTAL_EPSILON: f32 = {{PORTAL_EPSILON}};
+
+@group(0) @binding(0)
+var<uniform> resolution: vec2<f32>;
+
+struct Hit {
+    plane: i32,
+    distance: f32,
+    front: bool,
+    color: vec3<f32>,
+}
+
+fn intersect_plane(plane: Plane, origin: vec3<f32>, direction: vec3<f32>) -> Hit {
+    var hit: Hit;
+    hit.plane = -1;
+
+    let local_origin = plane.to_local_rotation * origin + plane.to_local_translation;
+    let local_direction = plane.to_local_rotation * direction;
+    if sign(local_origin.y) == sign(local_direction.y) || abs(local_direction.y) < 0.001 {
+        return hit;
+    }
+
+    hit.distance = abs(local_origin.y / local_direction.y);
+    hit.front = local_direction.y < 0.0;
+
+    let local_position = local_origin.xz + local_direction.xz * hit.distance;
+    if any(abs(local_position) > plane.size * 0.5) {
+        return hit;
+    }
+
+    var color = plane.color;
+    var emissive_color = plane.emissive_color;
+    let cell = vec2<i32>((local_position / plane.size + 0.5) * plane.checker_count);
+    if (cell.x + cell.y) % 2 == 1 {
+        color *= plane.checker_darkness;
+        emissive_color *= plane.emissive_checker_darkness;
+    }
+    hit.color = color + emissive_color;
+    hit.plane = 0;
+    return hit;
+}
+
+// hits behind `clip_plane` are ignored, xyz is the normal of the side that is kept and w the offset along it
+fn intersect_scene(origin: vec3<f32>, direction: vec3<f32>, min_distance: f32, ignored_plane: i32, clip_plane: vec4<f32>) -> Hit {
+    var closest_hit: Hit;
+    closest_hit.plane = -1;
+    for (var i = 0; i < PLANE_COUNT; i++) {
+        if i == ignored_plane {
+            continue;
+        }
+        var hit = intersect_plane(planes[i], origin, direction);
+        let position = origin + direction * hit.distance;
+        if hit.plane >= 0 && hit.distance >= min_distance && dot(clip_plane.xyz, position) + clip_plane.w >= 0.0 && (closest_hit.plane < 0 || hit.distance < closest_hit.distance) {
+            hit.plane = i;
+            closest_hit = hit;
+        }
+    }
+    return closest_hit;
+}
+
+fn skybox(direction: vec3<f32>) -> vec3<f32> {
+    if acos(dot(SUN_DIRECTION, direction)) < SUN_SIZE {
+        return SUN_COLOR;
+    }
+    return mix(DOWN_SKY_COLOR, UP_SKY_COLOR, dot(direction, SKY_UP) * 0.5 + 0.5);
+}
+
+fn ray_color(ray_origin: vec3<f32>, ray_direction: vec3<f32>) -> vec3<f32> {
+    var origin = ray_origin;
+    var direction = ray_direction;
+    var hit = intersect_scene(origin, direction, NEAR_PLANE, -1, vec4<f32>(0.0));
+    for (var i = 0; i < RECURSIVE_PORTAL_COUNT; i++) {
+        if hit.plane < 0 {
+            break;
+        }
+        let plane = planes[hit.plane];
+        let other_plane = select(plane.back_portal, plane.front_portal, hit.front);
+        if other_plane < 0 {
+            break;
+        }
+        var rotation = plane.back_rotation;
+        var translation = plane.back_translation;
+        var exit_normal = plane.back_exit_normal;
+        var normal = -plane.normal;
+        if hit.front {
+            rotation = plane.front_rotation;
+            translation = plane.front_translation;
+            exit_normal = plane.front_exit_normal;
+            normal = plane.normal;
+        }
+
+        let exit_point = rotation * (origin + direction * hit.distance) + translation;
+        let position = origin + direction * hit.distance - normal * PORTAL_EPSILON;
+        origin = rotation * position + translation;
+        direction = normalize(rotation * direction);
+        // only what is on the side the ray comes out of the other plane can be seen through the portal
+        hit = intersect_scene(origin, direction, 0.0, other_plane, vec4<f32>(exit_normal, -dot(exit_normal, exit_point)));
+    }
+    if hit.plane < 0 {
+        return skybox(direction);
+    }
+    return hit.color;
+}
+
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    // the fragment position starts at the top of the target
+    let uv = vec2<f32>(position.x, resolution.y - position.y) / resolution * 2.0 - 1.0;
+    let aspect = resolution.x / resolution.y;
+    let direction = normalize(CAMERA_ROTATION * vec3<f32>(1.0, uv.y, uv.x * aspect));
+    return vec4<f32>(ray_color(CAMERA_ORIGIN, direction), 1.0);
+}
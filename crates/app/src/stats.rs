@@ -0,0 +1,91 @@
+use serde::Serialize;
+use std::{fmt::Write, time::Instant};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FrameStats {
+    pub frame: u64,
+    pub time: f64,
+    pub frame_time_ms: f64,
+    pub width: u32,
+    pub height: u32,
+    pub samples_per_pixel: u32,
+    pub accumulated_samples: u64,
+    /// Camera samples traced per second, the rays of their bounces and portal traversals aren't counted
+    pub primary_samples_per_second: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct StatsRecorder {
+    pub recording: bool,
+    start_time: Option<Instant>,
+    frames: Vec<FrameStats>,
+}
+
+impl StatsRecorder {
+    pub fn frames(&self) -> &[FrameStats] {
+        &self.frames
+    }
+
+    pub fn clear(&mut self) {
+        self.start_time = None;
+        self.frames.clear();
+    }
+
+    pub fn record(
+        &mut self,
+        time: Instant,
+        frame_time_secs: f64,
+        width: u32,
+        height: u32,
+        samples_per_pixel: u32,
+        accumulated_samples: u64,
+    ) {
+        if !self.recording {
+            return;
+        }
+
+        let start_time = *self.start_time.get_or_insert(time);
+        let primary_samples_per_second = if frame_time_secs > 0.0 {
+            (width as f64 * height as f64 * samples_per_pixel as f64) / frame_time_secs
+        } else {
+            0.0
+        };
+        self.frames.push(FrameStats {
+            frame: self.frames.len() as u64,
+            time: (time - start_time).as_secs_f64(),
+            frame_time_ms: frame_time_secs * 1000.0,
+            width,
+            height,
+            samples_per_pixel,
+            accumulated_samples,
+            primary_samples_per_second,
+        });
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "frame,time,frame_time_ms,width,height,samples_per_pixel,accumulated_samples,primary_samples_per_second\n",
+        );
+        for &FrameStats {
+            frame,
+            time,
+            frame_time_ms,
+            width,
+            height,
+            samples_per_pixel,
+            accumulated_samples,
+            primary_samples_per_second,
+        } in &self.frames
+        {
+            _ = writeln!(
+                csv,
+                "{frame},{time},{frame_time_ms},{width},{height},{samples_per_pixel},{accumulated_samples},{primary_samples_per_second}"
+            );
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.frames).unwrap()
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{Plane, planes_to_gpu};
+use eframe::egui;
+use math::Vector3;
+use ray_tracing::{Color, PrepareTimings, RayTracingPaintCallback};
+use std::time::{Duration, Instant};
+
+/// How far apart the synthetic planes are
+const SYNTHETIC_SPACING: f32 = 2.0;
+
+/// A cubic grid of `count` small planes floating above the origin
+pub fn synthetic_planes(count: u32) -> Vec<Plane> {
+    let side = (count as f32).cbrt().ceil().max(1.0) as u32;
+    let offset = (side - 1) as f32 * SYNTHETIC_SPACING * -0.5;
+    (0..count)
+        .map(|index| {
+            let (x, y, z) = (index % side, index / side / side, index / side % side);
+            Plane {
+                name: format!("Synthetic Plane {index}"),
+                position: Vector3 {
+                    x: offset + x as f32 * SYNTHETIC_SPACING,
+                    y: 2.0 + y as f32 * SYNTHETIC_SPACING,
+                    z: offset + z as f32 * SYNTHETIC_SPACING,
+                },
+                color: Color {
+                    r: x as f32 / side as f32,
+                    g: y as f32 / side as f32,
+                    b: z as f32 / side as f32,
+                },
+                ..Plane::default()
+            }
+        })
+        .collect()
+}
+
+/// Adds synthetic planes to every frame without adding them to the scene,
+/// to see how converting, uploading and tracing scale with the number of planes
+pub struct StressTest {
+    pub enabled: bool,
+    pub plane_count: u32,
+    planes: Vec<Plane>,
+    /// How long converting the scene with the synthetic planes took last frame
+    conversion_time: Duration,
+    /// What the renderer spent on the last frame, see [`PrepareTimings`]
+    prepare_timings: PrepareTimings,
+}
+
+impl Default for StressTest {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            plane_count: 1000,
+            planes: vec![],
+            conversion_time: Duration::ZERO,
+            prepare_timings: PrepareTimings::default(),
+        }
+    }
+}
+
+impl StressTest {
+    /// Returns whether the synthetic planes changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Stress Test Planes:");
+            changed |= ui.checkbox(&mut self.enabled, "").changed();
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.plane_count)
+                        .speed(10.0)
+                        .range(1..=1_000_000),
+                )
+                .changed();
+        });
+        if self.enabled {
+            let per_frame = |duration: Duration| {
+                duration.as_secs_f64() * 1000.0 / self.prepare_timings.frames.max(1) as f64
+            };
+            ui.label(format!(
+                "Scene Conversion: {:.3}ms",
+                self.conversion_time.as_secs_f64() * 1000.0
+            ));
+            ui.label(format!(
+                "Prepare Frame: {:.3}ms",
+                per_frame(self.prepare_timings.total)
+            ));
+            ui.label(format!(
+                "Encode Scene: {:.3}ms",
+                per_frame(self.prepare_timings.encoding)
+            ));
+        }
+        changed
+    }
+
+    /// Keeps the renderer's timings of the last frame to show them
+    pub fn record_prepare_timings(&mut self, prepare_timings: Option<PrepareTimings>) {
+        if let Some(prepare_timings) = prepare_timings {
+            self.prepare_timings = prepare_timings;
+        }
+    }
+
+    /// Appends the synthetic planes to `frame`, converting them like the scene's planes are every frame,
+    /// `conversion_start` is when converting the scene started
+    pub fn apply(&mut self, frame: &mut RayTracingPaintCallback, conversion_start: Instant) {
+        if !self.enabled {
+            return;
+        }
+        if self.planes.len() != self.plane_count as usize {
+            self.planes = synthetic_planes(self.plane_count);
+        }
+        frame.planes.extend(planes_to_gpu(&self.planes));
+        self.conversion_time = conversion_start.elapsed();
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{App, CameraController, PlaneId, Scene, SceneWarning, validate_planes};
+use eframe::{egui, egui_wgpu::RenderState};
+use ray_tracing::{RayTracingRenderer, RayTracingView};
+
+/// A scene open in a tab, the active tab's state lives in `App` itself and is swapped in when switching tabs
+pub struct SceneTab {
+    /// Accumulates into its own render target, so switching back continues where it left off
+    pub view: RayTracingView,
+    pub scene: Scene,
+    pub scene_name: Option<String>,
+    pub scene_warnings: Vec<SceneWarning>,
+    pub portal_cooldown: Option<(PlaneId, u32)>,
+    /// Orbits and follows planes of this scene by their ids, so every scene has its own
+    pub camera_controller: CameraController,
+    pub adaptive_samples_per_pixel: u32,
+}
+
+impl SceneTab {
+    pub fn new(view: RayTracingView, scene: Scene) -> Self {
+        Self {
+            view,
+            scene_warnings: validate_planes(&scene.world_planes()),
+            scene,
+            scene_name: None,
+            portal_cooldown: None,
+            camera_controller: CameraController::default(),
+            adaptive_samples_per_pixel: 1,
+        }
+    }
+}
+
+impl App {
+    /// Swaps the active scene's state with what is stored for the tab at `index`
+    fn swap_tab(&mut self, index: usize) {
+        let tab = &mut self.tabs[index];
+        std::mem::swap(&mut self.view, &mut tab.view);
+        std::mem::swap(&mut self.scene, &mut tab.scene);
+        std::mem::swap(&mut self.scene_name, &mut tab.scene_name);
+        std::mem::swap(&mut self.scene_warnings, &mut tab.scene_warnings);
+        std::mem::swap(&mut self.portal_cooldown, &mut tab.portal_cooldown);
+        std::mem::swap(&mut self.camera_controller, &mut tab.camera_controller);
+        std::mem::swap(
+            &mut self.adaptive_samples_per_pixel,
+            &mut tab.adaptive_samples_per_pixel,
+        );
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab {
+            return;
+        }
+        // stores the active scene back in its tab, then takes out the other one
+        self.swap_tab(self.active_tab);
+        self.swap_tab(index);
+        self.active_tab = index;
+        self.dirty_planes = None;
+    }
+
+    fn new_tab(&mut self) {
+        let view = RayTracingView::new(self.next_render_target);
+        self.next_render_target += 1;
+        self.tabs.push(SceneTab::new(view, Scene::default()));
+        self.switch_tab(self.tabs.len() - 1);
+    }
+
+    fn close_tab(&mut self, index: usize, render_state: Option<&RenderState>) {
+        if self.tabs.len() <= 1 {
+            return;
+        }
+        if index == self.active_tab {
+            self.switch_tab(if index == 0 { 1 } else { index - 1 });
+        }
+        let tab = self.tabs.remove(index);
+        if index < self.active_tab {
+            self.active_tab -= 1;
+        }
+
+        if let Some(render_state) = render_state
+            && let Some(ray_tracer) = render_state
+                .renderer
+                .write()
+                .callback_resources
+                .get_mut::<RayTracingRenderer>()
+        {
+            ray_tracer.remove_target(&render_state.device, tab.view.target());
+        }
+    }
+
+    /// The tab bar, Ctrl+Tab switches to the next tab
+    pub fn ui_tabs(&mut self, ui: &mut egui::Ui, render_state: Option<&RenderState>) {
+        if ui
+            .ctx()
+            .input_mut(|i| i.consume_key(egui::Modifiers::CTRL, egui::Key::Tab))
+        {
+            self.switch_tab((self.active_tab + 1) % self.tabs.len());
+        }
+
+        let mut switch_to = None;
+        let mut close = None;
+        let mut new_tab = false;
+        ui.horizontal(|ui| {
+            for index in 0..self.tabs.len() {
+                // the active tab's stored state is stale, its name is in `App`
+                let scene_name = if index == self.active_tab {
+                    &self.scene_name
+                } else {
+                    &self.tabs[index].scene_name
+                };
+                let title = scene_name.as_deref().unwrap_or("Untitled");
+                if ui
+                    .selectable_label(index == self.active_tab, title)
+                    .clicked()
+                {
+                    switch_to = Some(index);
+                }
+                if self.tabs.len() > 1 && ui.small_button("x").on_hover_text("Close").clicked() {
+                    close = Some(index);
+                }
+                ui.separator();
+            }
+            new_tab = ui.button("+").on_hover_text("New Tab").clicked();
+        });
+
+        if let Some(index) = close {
+            self.close_tab(index, render_state);
+        } else if new_tab {
+            self.new_tab();
+        } else if let Some(index) = switch_to {
+            self.switch_tab(index);
+        }
+    }
+}
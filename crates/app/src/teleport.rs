@@ -0,0 +1,464 @@
+use crate::{Hit, Plane};
+use math::{Rotor, Vector3};
+
+/// Where the camera is, which way it faces and which way it is moving
+#[derive(Debug, Clone, Copy)]
+pub struct Pose {
+    pub position: Vector3,
+    pub rotation: Rotor,
+    /// Normalised
+    pub direction: Vector3,
+}
+
+/// Moves a pose that went through `plane` at `hit` out of `other`, the plane its portal on that side is connected to,
+/// `epsilon` is how far it is pushed away from `other` so it can't immediately go back in
+pub fn teleport_through(plane: &Plane, other: &Plane, hit: Hit, pose: Pose, epsilon: f32) -> Pose {
+    let portal = if hit.front {
+        &plane.front_portal
+    } else {
+        &plane.back_portal
+    };
+    let Pose {
+        mut position,
+        mut rotation,
+        mut direction,
+    } = pose;
+
+    if portal.flip {
+        // the camera can't be mirrored, so it turns to look where the mirrored direction goes instead
+        let mirrored = direction.reflect(hit.normal);
+        let turn = if direction.dot(mirrored) < -0.999 {
+            // straight into the plane it turns by way of whichever of its own axes is most across the direction,
+            // its right when moving forward, so it turns around its up
+            let side = [Vector3::RIGHT, Vector3::UP, Vector3::FORWARD]
+                .map(|axis| rotation.rotate(axis))
+                .into_iter()
+                .min_by(|a, b| a.dot(direction).abs().total_cmp(&b.dot(direction).abs()))
+                .unwrap();
+            let side = (side - direction * side.dot(direction)).normalised();
+            Rotor::between(side, mirrored).then(Rotor::between(direction, side))
+        } else {
+            Rotor::between(direction, mirrored)
+        };
+        rotation = turn.then(rotation);
+        position -= hit.normal * (2.0 * (position - hit.position).dot(hit.normal));
+        direction = mirrored;
+    }
+
+    // anything that goes through comes out as much bigger as the portal scales things
+    let transform = other.transform().then(plane.transform().reverse());
+    let local_position = plane.transform().reverse().transform_point(position);
+    let mut position = other
+        .transform()
+        .transform_point(local_position * portal.scale);
+    let rotation = transform.rotor_part().then(rotation);
+
+    let direction = transform.rotor_part().rotate(direction);
+    let normal = other.transform().rotor_part().rotate(Vector3::UP);
+    position += normal * direction.dot(normal).signum() * epsilon;
+
+    Pose {
+        position,
+        rotation,
+        direction,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ray;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    const EPSILON: f32 = 0.001;
+    const TOLERANCE: f32 = 1e-3;
+
+    fn assert_close(a: Vector3, b: Vector3, what: &str) {
+        assert!(
+            (a - b).magnitude() < TOLERANCE,
+            "{what}: {a:?} isn't close to {b:?}"
+        );
+    }
+
+    fn plane(position: Vector3, [xy, yz, xz]: [f32; 3]) -> Plane {
+        Plane {
+            position,
+            xy_rotation: xy,
+            yz_rotation: yz,
+            xz_rotation: xz,
+            width: 100.0,
+            height: 100.0,
+            ..Plane::default()
+        }
+    }
+
+    /// Moves a pose from `from` to `to`, through `plane` into `other` if it crosses it
+    fn travel(plane: &Plane, other: &Plane, from: Vector3, to: Vector3, rotation: Rotor) -> Pose {
+        let direction = (to - from).normalised();
+        let hit = plane
+            .intersect(Ray {
+                origin: from,
+                direction,
+            })
+            .expect("the movement goes through the plane");
+        teleport_through(
+            plane,
+            other,
+            hit,
+            Pose {
+                position: to,
+                rotation,
+                direction,
+            },
+            EPSILON,
+        )
+    }
+
+    /// What is true of any pose going through any portal, the pose is the same relative to the other plane
+    /// as it was to the plane it went into, scaled, and mirrored across the plane if the portal flips
+    fn assert_traversal(plane: &Plane, other: &Plane, from: Vector3, to: Vector3, rotation: Rotor) {
+        let direction = (to - from).normalised();
+        let front = plane.transform().reverse().rotor_part().rotate(direction).y < 0.0;
+        let portal = if front {
+            &plane.front_portal
+        } else {
+            &plane.back_portal
+        };
+        let out = travel(plane, other, from, to, rotation);
+
+        let into_plane = |point| plane.transform().reverse().transform_point(point);
+        let into_other = |point| other.transform().reverse().transform_point(point);
+        let rotate_into_plane = |vector| plane.rotation().reverse().rotate(vector);
+        let rotate_into_other = |vector| other.rotation().reverse().rotate(vector);
+        let mirror = |vector: Vector3| {
+            if portal.flip {
+                Vector3 {
+                    y: -vector.y,
+                    ..vector
+                }
+            } else {
+                vector
+            }
+        };
+
+        let expected_direction = mirror(rotate_into_plane(direction));
+        assert_close(
+            rotate_into_other(out.direction),
+            expected_direction,
+            "direction",
+        );
+
+        let mut expected_position = mirror(into_plane(to)) * portal.scale;
+        expected_position.y += expected_direction.y.signum() * EPSILON;
+        assert_close(into_other(out.position), expected_position, "position");
+        assert!(
+            into_other(out.position).y * expected_direction.y > 0.0,
+            "the pose comes out on the side of the other plane it is moving away from"
+        );
+
+        // the camera turns with what it is moving along, even when it can't be mirrored
+        assert_close(
+            out.rotation.reverse().rotate(out.direction),
+            rotation.reverse().rotate(direction),
+            "direction relative to the camera",
+        );
+        if !portal.flip {
+            for axis in [Vector3::FORWARD, Vector3::UP, Vector3::RIGHT] {
+                assert_close(
+                    rotate_into_other(out.rotation.rotate(axis)),
+                    rotate_into_plane(rotation.rotate(axis)),
+                    "camera axis",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn front_face() {
+        let plane = plane(Vector3::ZERO, [0.0; 3]);
+        let other = plane_at_x(10.0);
+        let out = travel(
+            &plane,
+            &other,
+            Vector3 {
+                x: 0.5,
+                y: 0.1,
+                z: 0.2,
+            },
+            Vector3 {
+                x: 0.5,
+                y: -0.1,
+                z: 0.2,
+            },
+            Rotor::IDENTITY,
+        );
+        assert_close(
+            out.position,
+            Vector3 {
+                x: 10.5,
+                y: -0.1 - EPSILON,
+                z: 0.2,
+            },
+            "position",
+        );
+        assert_close(out.direction, -Vector3::UP, "direction");
+    }
+
+    #[test]
+    fn back_face() {
+        let mut plane = plane(Vector3::ZERO, [0.0; 3]);
+        plane.front_portal.scale = 3.0;
+        let other = plane_at_x(10.0);
+        let out = travel(
+            &plane,
+            &other,
+            Vector3 {
+                x: 0.5,
+                y: -0.1,
+                z: 0.2,
+            },
+            Vector3 {
+                x: 0.5,
+                y: 0.1,
+                z: 0.2,
+            },
+            Rotor::IDENTITY,
+        );
+        // the front portal's scale doesn't apply to the back
+        assert_close(
+            out.position,
+            Vector3 {
+                x: 10.5,
+                y: 0.1 + EPSILON,
+                z: 0.2,
+            },
+            "position",
+        );
+        assert_close(out.direction, Vector3::UP, "direction");
+    }
+
+    #[test]
+    fn rotated_other_plane() {
+        let plane = plane(Vector3::ZERO, [0.0; 3]);
+        // turned upside down, going down into the plane goes up out of the other
+        let other = self::plane(
+            Vector3 {
+                x: 0.0,
+                y: 5.0,
+                z: 0.0,
+            },
+            [PI, 0.0, 0.0],
+        );
+        let out = travel(
+            &plane,
+            &other,
+            Vector3 {
+                x: 0.0,
+                y: 0.1,
+                z: 0.0,
+            },
+            Vector3 {
+                x: 0.0,
+                y: -0.1,
+                z: 0.0,
+            },
+            Rotor::IDENTITY,
+        );
+        assert_close(out.direction, Vector3::UP, "direction");
+        assert_close(
+            out.position,
+            Vector3 {
+                x: 0.0,
+                y: 5.1 + EPSILON,
+                z: 0.0,
+            },
+            "position",
+        );
+        assert_close(
+            out.rotation.rotate(Vector3::FORWARD),
+            -Vector3::FORWARD,
+            "forward",
+        );
+    }
+
+    #[test]
+    fn flipped_portal() {
+        let mut plane = plane(
+            Vector3 {
+                x: 3.0,
+                y: 2.0,
+                z: 1.0,
+            },
+            [0.0; 3],
+        );
+        plane.front_portal.flip = true;
+        let other = plane_at_x(10.0);
+        // looking and moving straight down into the plane, it comes back up out of the other one
+        let looking_down = Rotor::rotation_xy(-FRAC_PI_2);
+        assert_close(
+            looking_down.rotate(Vector3::FORWARD),
+            -Vector3::UP,
+            "looking down",
+        );
+        let out = travel(
+            &plane,
+            &other,
+            Vector3 {
+                x: 3.0,
+                y: 2.1,
+                z: 1.0,
+            },
+            Vector3 {
+                x: 3.0,
+                y: 1.9,
+                z: 1.0,
+            },
+            looking_down,
+        );
+        assert_close(out.direction, Vector3::UP, "direction");
+        assert_close(
+            out.rotation.rotate(Vector3::FORWARD),
+            Vector3::UP,
+            "forward",
+        );
+        assert_close(
+            out.position,
+            Vector3 {
+                x: 10.0,
+                y: 0.1 + EPSILON,
+                z: 0.0,
+            },
+            "position",
+        );
+    }
+
+    /// Going through one portal and then another is the same as going through one
+    /// that does both of their transforms
+    #[test]
+    fn concatenated_traversals() {
+        let a = plane(Vector3::ZERO, [0.0, 0.0, 0.3]);
+        let b = plane(
+            Vector3 {
+                x: 10.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            [0.0, 0.0, 1.1],
+        );
+        let c = plane(
+            Vector3 {
+                x: 10.0,
+                y: -5.0,
+                z: 0.0,
+            },
+            [0.0, 0.0, 1.1],
+        );
+        let d = plane(
+            Vector3 {
+                x: -4.0,
+                y: 7.0,
+                z: 2.0,
+            },
+            [0.0, 0.0, 2.0],
+        );
+        // c is b moved straight down, so what comes out of b ends up going into c
+        let rotation = Rotor::rotation_xz(0.4).then(Rotor::rotation_xy(-1.0));
+        let from = Vector3 {
+            x: 0.3,
+            y: 0.2,
+            z: -0.4,
+        };
+        let to = Vector3 {
+            x: 0.3,
+            y: -0.2,
+            z: -0.4,
+        };
+        let through_b = travel(&a, &b, from, to, rotation);
+        let further = through_b.position + through_b.direction * 5.0;
+        let through_d = travel(&c, &d, through_b.position, further, through_b.rotation);
+
+        // a to b then c to d moves things by the same as a straight to d, moved up by as much as c is below b
+        let d_above = plane(d.position + (b.position - c.position), [0.0, 0.0, 2.0]);
+        let direct = travel(
+            &a,
+            &d_above,
+            from,
+            to + (to - from).normalised() * 5.0,
+            rotation,
+        );
+        // the first traversal pushed the pose away from b once more
+        assert_close(
+            through_d.position,
+            direct.position + direct.direction * EPSILON,
+            "position",
+        );
+        assert_close(through_d.direction, direct.direction, "direction");
+        for axis in [Vector3::FORWARD, Vector3::UP, Vector3::RIGHT] {
+            assert_close(
+                through_d.rotation.rotate(axis),
+                direct.rotation.rotate(axis),
+                "camera axis",
+            );
+        }
+    }
+
+    /// Every combination of faces, flipping and scale, through planes turned every which way
+    #[test]
+    fn random_traversals() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut random_vector = |scale: f32| Vector3 {
+            x: rng.random_range(-scale..scale),
+            y: rng.random_range(-scale..scale),
+            z: rng.random_range(-scale..scale),
+        };
+        for _ in 0..200 {
+            let angles = |vector: Vector3| [vector.x * PI, vector.y * FRAC_PI_2, vector.z * PI];
+            let mut plane = plane(random_vector(10.0), angles(random_vector(1.0)));
+            let other = self::plane(random_vector(10.0), angles(random_vector(1.0)));
+            let camera_angles = random_vector(PI);
+            let rotation = Rotor::rotation_xy(camera_angles.x)
+                .then(Rotor::rotation_yz(camera_angles.y))
+                .then(Rotor::rotation_xz(camera_angles.z));
+            let across = random_vector(1.0);
+            for front in [true, false] {
+                for flip in [false, true] {
+                    for scale in [1.0, 0.5, 2.0] {
+                        let portal = if front {
+                            &mut plane.front_portal
+                        } else {
+                            &mut plane.back_portal
+                        };
+                        portal.flip = flip;
+                        portal.scale = scale;
+
+                        // a short movement through the plane from the side it hits
+                        let up = plane.rotation().rotate(Vector3::UP);
+                        let side = if front { 1.0 } else { -1.0 };
+                        let center = plane.position
+                            + plane.rotation().rotate(Vector3 {
+                                x: across.x,
+                                y: 0.0,
+                                z: across.z,
+                            });
+                        let mut offset = up * (0.1 * side) + random_vector(0.05);
+                        if offset.dot(up) * side < 0.02 {
+                            offset += up * (0.05 * side);
+                        }
+                        assert_traversal(
+                            &plane,
+                            &other,
+                            center + offset,
+                            center - offset,
+                            rotation,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn plane_at_x(x: f32) -> Plane {
+        plane(Vector3 { x, y: 0.0, z: 0.0 }, [0.0; 3])
+    }
+}
@@ -0,0 +1,264 @@
+use eframe::{egui, wgpu};
+use ray_tracing::{RayTracingPaintCallback, RayTracingRenderer, TextureId, TextureImage};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, Sender, channel},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::Plane;
+
+/// The extensions of the images planes can show, see [`load_texture`]
+pub const TEXTURE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// How often the files of the textures are checked for changes
+const MODIFIED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+struct PlaneTexture {
+    /// When the file was last modified as of the last time it was read, it is read again once this changes,
+    /// `None` if the file couldn't be found
+    modified: Option<SystemTime>,
+    /// `None` until the file has been decoded for the first time, a changed file keeps its old texture until then,
+    /// images that failed to load keep their error so they aren't loaded again every frame
+    texture: Option<Result<TextureId, String>>,
+}
+
+/// An image decoded on a worker thread
+struct Decoded {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    image: Result<TextureImage, String>,
+}
+
+/// What [`PlaneTextures::apply`] did
+pub struct TextureChanges {
+    /// Whether a texture was added, replaced or removed, so what was accumulated with the old textures is wrong
+    pub changed: bool,
+    /// The errors of the images that failed to load
+    pub errors: Vec<String>,
+}
+
+/// The images the planes show instead of their checker pattern, decoded on worker threads so loading them never
+/// stalls a frame and kept on the gpu while any plane uses them, changed files are loaded again
+pub struct PlaneTextures {
+    loaded: HashMap<PathBuf, PlaneTexture>,
+    /// The texture of every plane in the last frame, see [`ray_tracing::GpuPlane::texture`]
+    indices: Vec<u32>,
+    decoded_sender: Sender<Decoded>,
+    decoded: Receiver<Decoded>,
+    last_modified_check: Option<Instant>,
+}
+
+impl Default for PlaneTextures {
+    fn default() -> Self {
+        let (decoded_sender, decoded) = channel();
+        Self {
+            loaded: HashMap::new(),
+            indices: vec![],
+            decoded_sender,
+            decoded,
+            last_modified_check: None,
+        }
+    }
+}
+
+impl PlaneTextures {
+    /// Starts decoding the textures of `planes` that aren't loaded yet or whose files changed, uploads the ones that
+    /// finished decoding and gives the planes of `frame` their textures, textures no plane uses anymore are removed
+    pub fn apply(
+        &mut self,
+        ctx: &egui::Context,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        ray_tracer: &mut RayTracingRenderer,
+        planes: &[Plane],
+        frame: &mut RayTracingPaintCallback,
+    ) -> TextureChanges {
+        let mut changes = TextureChanges {
+            changed: false,
+            errors: vec![],
+        };
+        for Decoded {
+            path,
+            modified,
+            image,
+        } in self.decoded.try_iter()
+        {
+            // anything else was decoded from a file that has changed again since, or isn't used anymore
+            let Some(loaded) = self
+                .loaded
+                .get_mut(&path)
+                .filter(|loaded| loaded.modified == modified)
+            else {
+                continue;
+            };
+            let texture = image.and_then(|image| {
+                ray_tracer
+                    .add_texture(device, queue, image)
+                    .map_err(|error| error.to_string())
+            });
+            if let Err(error) = &texture {
+                changes.errors.push(format!(
+                    "Failed to load texture {}: {error}",
+                    path.display()
+                ));
+            }
+            if let Some(Ok(old)) = loaded.texture.replace(texture) {
+                ray_tracer.remove_texture(device, queue, old);
+            }
+            changes.changed = true;
+        }
+
+        let check_modified = self
+            .last_modified_check
+            .is_none_or(|time| time.elapsed() >= MODIFIED_CHECK_INTERVAL);
+        if check_modified {
+            self.last_modified_check = Some(Instant::now());
+        }
+        for path in planes.iter().filter_map(|plane| plane.texture.as_deref()) {
+            let loaded = self.loaded.get_mut(path);
+            if loaded.is_some() && !check_modified {
+                continue;
+            }
+            let modified = modified_time(path);
+            if let Some(loaded) = loaded {
+                if loaded.modified == modified {
+                    continue;
+                }
+                loaded.modified = modified;
+            } else {
+                self.loaded.insert(
+                    path.to_owned(),
+                    PlaneTexture {
+                        modified,
+                        texture: None,
+                    },
+                );
+            }
+
+            let path = path.to_owned();
+            let sender = self.decoded_sender.clone();
+            let ctx = ctx.clone();
+            std::thread::spawn(move || {
+                let image = load_texture(&path);
+                _ = sender.send(Decoded {
+                    path,
+                    modified,
+                    image,
+                });
+                ctx.request_repaint();
+            });
+        }
+
+        self.loaded.retain(|path, loaded| {
+            let used = planes
+                .iter()
+                .any(|plane| plane.texture.as_deref() == Some(path));
+            if !used && let Some(Ok(id)) = loaded.texture {
+                ray_tracer.remove_texture(device, queue, id);
+                changes.changed = true;
+            }
+            used
+        });
+
+        let mut indices = Vec::with_capacity(frame.planes.len());
+        for (gpu_plane, plane) in frame.planes.iter_mut().zip(planes) {
+            gpu_plane.texture = plane
+                .texture
+                .as_deref()
+                .and_then(|path| self.loaded.get(path)?.texture.as_ref()?.as_ref().ok())
+                .map_or(u32::MAX, |id| id.index());
+            indices.push(gpu_plane.texture);
+        }
+        if changes.changed || indices != self.indices {
+            self.indices = indices;
+            frame.dirty_planes = None;
+        }
+        changes
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Reads a png or jpeg image, anything that isn't 8 bit rgba is converted to it
+pub fn load_texture(path: &Path) -> Result<TextureImage, String> {
+    let bytes = std::fs::read(path).map_err(|error| error.to_string())?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("png") => decode_png(&bytes).map_err(|error| error.to_string()),
+        Some("jpg" | "jpeg") => decode_jpeg(&bytes).map_err(|error| error.to_string()),
+        _ => Err(format!(
+            "only {} images are supported",
+            TEXTURE_EXTENSIONS.join(", ")
+        )),
+    }
+}
+
+fn decode_png(bytes: &[u8]) -> Result<TextureImage, png::DecodingError> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut data = vec![0; reader.output_buffer_size().unwrap_or(0)];
+    let info = reader.next_frame(&mut data)?;
+    let data = &data[..info.buffer_size()];
+    let pixels = match info.color_type {
+        png::ColorType::Grayscale => data.iter().map(|&l| [l, l, l, 255]).collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .map(|texel| [texel[0], texel[0], texel[0], texel[1]])
+            .collect(),
+        png::ColorType::Rgb => data
+            .chunks_exact(3)
+            .map(|texel| [texel[0], texel[1], texel[2], 255])
+            .collect(),
+        // expanded to one of the others
+        png::ColorType::Rgba | png::ColorType::Indexed => data
+            .chunks_exact(4)
+            .map(|texel| [texel[0], texel[1], texel[2], texel[3]])
+            .collect(),
+    };
+    Ok(TextureImage {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
+
+fn decode_jpeg(bytes: &[u8]) -> Result<TextureImage, jpeg_decoder::Error> {
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let data = decoder.decode()?;
+    let info = decoder.info().unwrap();
+    let pixels = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => data.iter().map(|&l| [l, l, l, 255]).collect(),
+        // big endian, the high byte is the 8 bit value
+        jpeg_decoder::PixelFormat::L16 => data
+            .chunks_exact(2)
+            .map(|texel| [texel[0], texel[0], texel[0], 255])
+            .collect(),
+        jpeg_decoder::PixelFormat::RGB24 => data
+            .chunks_exact(3)
+            .map(|texel| [texel[0], texel[1], texel[2], 255])
+            .collect(),
+        // already inverted by the decoder, so 255 is no ink
+        jpeg_decoder::PixelFormat::CMYK32 => data
+            .chunks_exact(4)
+            .map(|texel| {
+                let channel = |value: u8| (value as u16 * texel[3] as u16 / 255) as u8;
+                [channel(texel[0]), channel(texel[1]), channel(texel[2]), 255]
+            })
+            .collect(),
+    };
+    Ok(TextureImage {
+        width: info.width as u32,
+        height: info.height as u32,
+        pixels,
+    })
+}
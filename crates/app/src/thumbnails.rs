@@ -0,0 +1,125 @@
+use crate::{RenderSettings, Scene, encode_png};
+use eframe::{egui, egui_wgpu::RenderState};
+use ray_tracing::{RayTracingPaintCallback, RayTracingRenderer};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+const THUMBNAIL_WIDTH: u32 = 192;
+const THUMBNAIL_HEIGHT: u32 = 108;
+const THUMBNAIL_SAMPLES_PER_PIXEL: u32 = 16;
+
+/// Thumbnails are saved next to the scene, `name.scene` gets `name.thumbnail.png`
+pub fn thumbnail_path(scene_path: &Path) -> PathBuf {
+    scene_path.with_extension("thumbnail.png")
+}
+
+#[derive(Default)]
+pub struct Thumbnails {
+    /// Separate from the viewport's renderer so rendering a thumbnail doesn't disturb what is being accumulated there
+    renderer: Option<RayTracingRenderer>,
+    /// `None` if the scene has no thumbnail, so it isn't looked for every frame
+    previews: HashMap<PathBuf, Option<egui::TextureHandle>>,
+}
+
+impl Thumbnails {
+    /// Renders `scene` at a low sample count and saves it next to `scene_path`
+    pub fn save(
+        &mut self,
+        render_state: &RenderState,
+        scene: &Scene,
+        render_settings: &RenderSettings,
+        scene_path: &Path,
+    ) -> Result<(), String> {
+        let renderer = self.renderer.take().map_or_else(
+            || {
+                RayTracingRenderer::new(
+                    &render_state.device,
+                    &render_state.queue,
+                    render_state.target_format,
+                )
+            },
+            Ok,
+        );
+        let renderer = self
+            .renderer
+            .insert(renderer.map_err(|error| error.to_string())?);
+
+        let frame = RayTracingPaintCallback {
+            samples_per_pixel: THUMBNAIL_SAMPLES_PER_PIXEL,
+            max_samples_per_dispatch: THUMBNAIL_SAMPLES_PER_PIXEL,
+            histogram: false,
+            ..render_settings.paint_callback(scene, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)
+        };
+        render_state.queue.submit([renderer.prepare_frame(
+            &render_state.device,
+            &render_state.queue,
+            &frame,
+        )]);
+        let pixels = renderer.read_texture(&render_state.device, &render_state.queue);
+
+        let png = encode_png(
+            THUMBNAIL_WIDTH,
+            THUMBNAIL_HEIGHT,
+            &pixels,
+            render_settings.tone_mapper,
+            None,
+            false,
+            None,
+        )
+        .map_err(|error| error.to_string())?;
+        let path = thumbnail_path(scene_path);
+        std::fs::write(&path, png).map_err(|error| error.to_string())?;
+        self.previews.remove(scene_path);
+        Ok(())
+    }
+
+    /// Shows the thumbnail of the scene at `scene_path`, for the side panel of the load dialog
+    pub fn preview_ui(&mut self, ui: &mut egui::Ui, scene_path: Option<&Path>) {
+        ui.set_width(THUMBNAIL_WIDTH as f32);
+        let Some(scene_path) = scene_path.filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "scene")
+        }) else {
+            return;
+        };
+
+        let preview = self
+            .previews
+            .entry(scene_path.to_path_buf())
+            .or_insert_with(|| {
+                let image = load_thumbnail(&thumbnail_path(scene_path))?;
+                Some(ui.ctx().load_texture(
+                    scene_path.to_string_lossy(),
+                    image,
+                    egui::TextureOptions::LINEAR,
+                ))
+            });
+        match preview {
+            Some(texture) => {
+                ui.image((texture.id(), texture.size_vec2()));
+            }
+            None => {
+                ui.weak("No thumbnail");
+            }
+        }
+    }
+}
+
+fn load_thumbnail(path: &Path) -> Option<egui::ColorImage> {
+    let bytes = std::fs::read(path).ok()?;
+    let mut reader = png::Decoder::new(std::io::Cursor::new(bytes))
+        .read_info()
+        .ok()?;
+    let mut data = vec![0; reader.output_buffer_size()?];
+    let info = reader.next_frame(&mut data).ok()?;
+    // only thumbnails written by `Thumbnails::save` are expected
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return None;
+    }
+    Some(egui::ColorImage::from_rgba_unmultiplied(
+        [info.width as usize, info.height as usize],
+        &data[..info.buffer_size()],
+    ))
+}
@@ -0,0 +1,169 @@
+use crate::PlaneId;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// How the gamepad rumbles when the camera goes through a portal, set per connection
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rumble {
+    /// From 0 to 1, 0 doesn't rumble at all
+    pub strength: f32,
+    /// In seconds
+    pub duration: f32,
+}
+
+impl Default for Rumble {
+    fn default() -> Self {
+        Self {
+            strength: 0.0,
+            duration: 0.2,
+        }
+    }
+}
+
+impl Rumble {
+    pub fn is_none(&self) -> bool {
+        self.strength <= 0.0 || self.duration <= 0.0
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Rumble:");
+            ui.add(
+                egui::DragValue::new(&mut self.strength)
+                    .speed(0.01)
+                    .range(0.0..=1.0),
+            )
+            .on_hover_text(
+                "How strongly the gamepad rumbles when the camera goes through, 0 doesn't rumble",
+            );
+            ui.add_enabled(
+                self.strength > 0.0,
+                egui::DragValue::new(&mut self.duration)
+                    .speed(0.01)
+                    .range(0.0..=10.0)
+                    .suffix(" s"),
+            );
+        });
+    }
+}
+
+/// What plays when the camera goes through a portal, set per connection,
+/// the app doesn't play sounds yet so it is only handed to [`TraversalListener`]s
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraversalSound {
+    /// The sound file, relative to the scene file in saved scenes like textures
+    pub path: Option<PathBuf>,
+    /// From 0 to 1
+    pub volume: f32,
+}
+
+impl Default for TraversalSound {
+    fn default() -> Self {
+        Self {
+            path: None,
+            volume: 1.0,
+        }
+    }
+}
+
+impl TraversalSound {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Sound:");
+            let mut path = self
+                .path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+            if ui
+                .add(egui::TextEdit::singleline(&mut path).hint_text("Sound File"))
+                .on_hover_text("Played when the camera goes through")
+                .changed()
+            {
+                self.path = (!path.is_empty()).then(|| path.into());
+            }
+            ui.add_enabled(
+                self.path.is_some(),
+                egui::DragValue::new(&mut self.volume)
+                    .speed(0.01)
+                    .range(0.0..=1.0)
+                    .prefix("Volume: "),
+            );
+        });
+    }
+}
+
+/// The camera going through a portal, queued while the camera is stepped and handled once a frame's steps are done,
+/// anything that reacts to traversals gets them from there instead of from inside the collision code
+#[derive(Debug, Clone)]
+pub struct Traversal {
+    /// The plane the camera went into
+    pub plane: PlaneId,
+    /// Whether it went through the front of `plane`
+    pub front: bool,
+    /// The plane the camera came out of
+    pub other: PlaneId,
+    /// Of the connection that was gone through
+    pub rumble: Rumble,
+    /// Of the connection that was gone through
+    pub sound: TraversalSound,
+}
+
+/// Reacts to the camera going through portals, like the gamepad rumbling, a sound player would be another
+pub trait TraversalListener {
+    fn traversed(&mut self, traversal: &Traversal);
+}
+
+/// Hands every queued traversal to each of the listeners in the order they happened, emptying the queue
+pub fn dispatch_traversals(
+    traversals: &mut Vec<Traversal>,
+    listeners: &mut [&mut dyn TraversalListener],
+) {
+    for traversal in traversals.drain(..) {
+        tracing::debug!(
+            "the camera went through the {} of {:?} and came out of {:?}",
+            if traversal.front { "front" } else { "back" },
+            traversal.plane,
+            traversal.other,
+        );
+        for listener in listeners.iter_mut() {
+            listener.traversed(&traversal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder(Vec<(PlaneId, PlaneId)>);
+
+    impl TraversalListener for Recorder {
+        fn traversed(&mut self, traversal: &Traversal) {
+            self.0.push((traversal.plane, traversal.other));
+        }
+    }
+
+    #[test]
+    fn every_listener_gets_every_traversal_in_order() {
+        let [a, b, c] = [PlaneId::new(), PlaneId::new(), PlaneId::new()];
+        let traversal = |plane, other| Traversal {
+            plane,
+            front: true,
+            other,
+            rumble: Rumble::default(),
+            sound: TraversalSound::default(),
+        };
+        let mut traversals = vec![traversal(a, b), traversal(b, c)];
+        let (mut first, mut second) = (Recorder::default(), Recorder::default());
+        dispatch_traversals(&mut traversals, &mut [&mut first, &mut second]);
+
+        assert!(traversals.is_empty());
+        assert_eq!(first.0, [(a, b), (b, c)]);
+        assert_eq!(second.0, [(a, b), (b, c)]);
+    }
+}
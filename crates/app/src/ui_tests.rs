@@ -0,0 +1,477 @@
+//! Drives the whole app headlessly through egui, clicking widgets by their labels like a user would,
+//! then checks that the scene is still consistent
+
+use crate::{
+    App, Launch, LaunchSettings, Log, Severity, find_plane, planes_to_gpu, wgpu_configuration,
+};
+use eframe::{egui, egui::accesskit, egui_wgpu};
+use std::collections::HashSet;
+use tracing_subscriber::EnvFilter;
+
+/// Set to skip the tests instead of failing them on machines without a gpu adapter
+const SKIP_GPU_TESTS_VAR: &str = "PORTALS_SKIP_GPU_TESTS";
+
+/// The size of the window the app is run in, in points, tall enough that a whole plane fits in the planes window
+const SCREEN_SIZE: egui::Vec2 = egui::vec2(1600.0, 3000.0);
+
+/// A widget from the accesskit tree of the last frame
+#[derive(Debug)]
+struct Widget {
+    role: accesskit::Role,
+    /// The text of buttons and labels, or the value of drag values
+    label: String,
+    rect: egui::Rect,
+}
+
+impl Widget {
+    fn interactive(&self) -> bool {
+        !matches!(
+            self.role,
+            accesskit::Role::Label | accesskit::Role::TextRun | accesskit::Role::Window
+        )
+    }
+
+    /// Whether the widget comes after `anchor` when reading left to right, top to bottom
+    fn after(&self, anchor: &Widget) -> bool {
+        let dy = self.rect.center().y - anchor.rect.center().y;
+        dy > 2.0 || (dy.abs() <= 2.0 && self.rect.left() > anchor.rect.left())
+    }
+}
+
+struct Harness {
+    ctx: egui::Context,
+    frame: eframe::Frame,
+    app: App,
+    /// Sent with the next frame
+    events: Vec<egui::Event>,
+    widgets: Vec<Widget>,
+    /// The text the app last copied, for pasting back in like the system clipboard would
+    clipboard: String,
+}
+
+impl Harness {
+    /// Panics if there is no adapter available, returns `None` instead when [`SKIP_GPU_TESTS_VAR`] is set
+    fn new() -> Option<Self> {
+        let configuration = wgpu_configuration(LaunchSettings::default(), Default::default());
+        let egui_wgpu::WgpuSetup::CreateNew(setup) = &configuration.wgpu_setup else {
+            unreachable!()
+        };
+        let instance = eframe::wgpu::Instance::new(&setup.instance_descriptor);
+        let render_state = pollster::block_on(egui_wgpu::RenderState::create(
+            &configuration,
+            &instance,
+            None,
+            None,
+            1,
+            false,
+        ));
+        let render_state = match render_state {
+            Ok(render_state) => render_state,
+            Err(error) if std::env::var_os(SKIP_GPU_TESTS_VAR).is_some() => {
+                eprintln!("skipping, no adapter available: {error}");
+                return None;
+            }
+            Err(error) => panic!(
+                "no adapter available to run the app on: {error}, set {SKIP_GPU_TESTS_VAR} to skip the ui tests"
+            ),
+        };
+
+        let ctx = egui::Context::default();
+        ctx.enable_accesskit();
+        let creation_context = eframe_hidden::creation_context(ctx.clone(), render_state);
+        let mut app = App::new(
+            &creation_context,
+            Log::new(EnvFilter::new("info")).0,
+            "info".into(),
+            None,
+            Launch {
+                settings: LaunchSettings::default(),
+                supported_present_modes: vec![],
+                relaunch: Default::default(),
+            },
+        );
+        // nothing is traced without a render state in the frame, so the ui can be driven even if the shaders failed
+        app.shader_error = None;
+        // only the planes window is open, so no other window covers what is clicked
+        app.render_settings.info_window_open = false;
+        app.render_settings.camera_window_open = false;
+        app.render_settings.render_settings_window_open = false;
+        app.render_settings.planes_window_open = true;
+
+        let mut harness = Self {
+            ctx,
+            frame: eframe_hidden::frame(),
+            app,
+            events: vec![],
+            widgets: vec![],
+            clipboard: String::new(),
+        };
+        harness.run();
+        // planes out of view in the planes window aren't built, so it is stretched down to the bottom of the screen
+        let window = harness
+            .find(|widget| widget.role == accesskit::Role::Window && widget.label == "Planes");
+        let bottom = egui::pos2(window.rect.center().x, window.rect.bottom());
+        harness.drag(bottom, egui::pos2(bottom.x, SCREEN_SIZE.y - 40.0));
+        Some(harness)
+    }
+
+    /// Runs a frame with the queued events
+    fn step(&mut self) {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, SCREEN_SIZE)),
+            events: std::mem::take(&mut self.events),
+            ..Default::default()
+        };
+        let output = self.ctx.run(input, |ctx| {
+            eframe::App::update(&mut self.app, ctx, &mut self.frame)
+        });
+        for command in output.platform_output.commands {
+            if let egui::OutputCommand::CopyText(text) = command {
+                self.clipboard = text;
+            }
+        }
+        if let Some(update) = output.platform_output.accesskit_update {
+            let scale = output.pixels_per_point;
+            self.widgets = update
+                .nodes
+                .iter()
+                .filter_map(|(_, node)| {
+                    let bounds = node.bounds()?;
+                    Some(Widget {
+                        role: node.role(),
+                        label: node.label().or(node.value()).unwrap_or_default().to_owned(),
+                        rect: egui::Rect::from_min_max(
+                            egui::pos2(bounds.x0 as f32, bounds.y0 as f32) / scale,
+                            egui::pos2(bounds.x1 as f32, bounds.y1 as f32) / scale,
+                        ),
+                    })
+                })
+                .collect();
+        }
+    }
+
+    /// Runs frames until the layout settles, windows and collapsing headers take a few to size themselves
+    fn run(&mut self) {
+        for _ in 0..4 {
+            self.step();
+        }
+    }
+
+    fn click_at(&mut self, position: egui::Pos2) {
+        self.events.push(egui::Event::PointerMoved(position));
+        self.step();
+        for pressed in [true, false] {
+            self.events.push(egui::Event::PointerButton {
+                pos: position,
+                button: egui::PointerButton::Primary,
+                pressed,
+                modifiers: egui::Modifiers::NONE,
+            });
+            self.step();
+        }
+        self.run();
+    }
+
+    fn drag(&mut self, from: egui::Pos2, to: egui::Pos2) {
+        self.events.push(egui::Event::PointerMoved(from));
+        self.step();
+        self.events.push(egui::Event::PointerButton {
+            pos: from,
+            button: egui::PointerButton::Primary,
+            pressed: true,
+            modifiers: egui::Modifiers::NONE,
+        });
+        self.step();
+        self.events.push(egui::Event::PointerMoved(to));
+        self.step();
+        self.events.push(egui::Event::PointerButton {
+            pos: to,
+            button: egui::PointerButton::Primary,
+            pressed: false,
+            modifiers: egui::Modifiers::NONE,
+        });
+        self.run();
+    }
+
+    /// The only widget that matches, panics listing the widgets if there isn't exactly one
+    fn find(&self, matches: impl Fn(&Widget) -> bool) -> &Widget {
+        let mut found = self.widgets.iter().filter(|widget| matches(widget));
+        match (found.next(), found.next()) {
+            (Some(widget), None) => widget,
+            (None, _) => panic!("no widget matches, the widgets are {:#?}", self.widgets),
+            (Some(a), Some(b)) => panic!("more than one widget matches: {a:?}, {b:?}"),
+        }
+    }
+
+    /// The label with `text`, such as the name in a plane's header
+    fn label(&self, text: &str) -> &Widget {
+        self.find(|widget| widget.role == accesskit::Role::Label && widget.label == text)
+    }
+
+    /// Clicks the only button, checkbox or other interactive widget labeled `label`
+    fn click(&mut self, label: &str) {
+        let position = self
+            .find(|widget| widget.interactive() && widget.label == label)
+            .rect
+            .center();
+        self.click_at(position);
+    }
+
+    /// Clicks the first interactive widget that matches after the label `anchor`, in reading order
+    fn click_after(&mut self, anchor: &str, matches: impl Fn(&Widget) -> bool) {
+        let anchor = self.label(anchor);
+        let position = self
+            .widgets
+            .iter()
+            .filter(|widget| widget.interactive() && widget.after(anchor) && matches(widget))
+            .min_by(|a, b| {
+                (a.rect.center().y.round(), a.rect.left())
+                    .partial_cmp(&(b.rect.center().y.round(), b.rect.left()))
+                    .unwrap()
+            })
+            .unwrap_or_else(|| panic!("nothing matches after {anchor:?}"))
+            .rect
+            .center();
+        self.click_at(position);
+    }
+
+    /// Opens or closes the plane called `name` in the planes window, with the arrow left of its name
+    fn toggle_plane(&mut self, name: &str) {
+        let label = self.label(name);
+        let position = self
+            .widgets
+            .iter()
+            .filter(|widget| {
+                widget.interactive()
+                    && (widget.rect.center().y - label.rect.center().y).abs() <= 2.0
+                    && widget.rect.right() <= label.rect.left()
+            })
+            .min_by(|a, b| a.rect.left().total_cmp(&b.rect.left()))
+            .unwrap_or_else(|| panic!("{name} has no arrow to open it"))
+            .rect
+            .center();
+        self.click_at(position);
+    }
+
+    /// Checks the checkbox in the header of the plane called `name`
+    fn select_plane(&mut self, name: &str) {
+        let label = self.label(name);
+        let position = self
+            .find(|widget| {
+                widget.role == accesskit::Role::CheckBox
+                    && (widget.rect.center().y - label.rect.center().y).abs() <= 2.0
+                    && widget.rect.right() <= label.rect.left()
+            })
+            .rect
+            .center();
+        self.click_at(position);
+    }
+
+    /// Sends an event like a keyboard shortcut would
+    fn send(&mut self, event: egui::Event) {
+        self.events.push(event);
+        self.run();
+    }
+
+    /// Connects the front portal of the plane called `from` to the plane called `to`,
+    /// the plane must be the only one open
+    fn connect_front_portal(&mut self, from: &str, to: &str) {
+        self.click_after(from, |widget| widget.label == "Front Portal");
+        self.click_after("Connected Plane:", |widget| {
+            widget.role == accesskit::Role::ComboBox
+        });
+        self.click(to);
+    }
+
+    /// Connects the front portals of the closed planes called `a` and `b` to each other, closing them again after
+    fn connect_pair(&mut self, a: &str, b: &str) {
+        for (from, to) in [(a, b), (b, a)] {
+            self.toggle_plane(from);
+            self.connect_front_portal(from, to);
+            self.toggle_plane(from);
+        }
+    }
+
+    fn plane_index(&self, name: &str) -> usize {
+        self.app
+            .scene
+            .planes
+            .iter()
+            .position(|plane| plane.name == name)
+            .unwrap_or_else(|| panic!("there is no plane called {name}"))
+    }
+
+    /// Clicks new plane and names the plane, the name isn't typed so the planes can be told apart right away
+    fn new_plane(&mut self, name: &str) {
+        self.click("New Plane");
+        self.app.scene.planes.last_mut().unwrap().name = name.into();
+        self.run();
+    }
+
+    /// Checks what has to hold after any edit, whatever was clicked
+    fn assert_scene_consistent(&self) {
+        let planes = &self.app.scene.planes;
+        let mut ids = HashSet::new();
+        for plane in planes {
+            assert!(ids.insert(plane.id), "{} shares its id", plane.name);
+            for portal in [&plane.front_portal, &plane.back_portal] {
+                if let Some(other) = portal.other {
+                    assert!(
+                        find_plane(planes, other).is_some(),
+                        "{} is connected to a plane that doesn't exist",
+                        plane.name
+                    );
+                }
+            }
+        }
+        for (plane, gpu_plane) in planes.iter().zip(planes_to_gpu(planes)) {
+            for (portal, gpu_portal) in [
+                (&plane.front_portal, gpu_plane.front_portal),
+                (&plane.back_portal, gpu_plane.back_portal),
+            ] {
+                let expected = portal
+                    .other
+                    .and_then(|other| find_plane(planes, other))
+                    .map_or(u32::MAX, |index| index as u32);
+                assert_eq!(
+                    gpu_portal.other_index, expected,
+                    "{} is uploaded connected to the wrong plane",
+                    plane.name
+                );
+            }
+        }
+        assert_eq!(self.app.plane_row_heights.len(), planes.len());
+        assert!(
+            !self
+                .app
+                .scene_warnings
+                .iter()
+                .any(|warning| warning.severity == Severity::Error),
+            "{:?}",
+            self.app.scene_warnings
+        );
+    }
+}
+
+#[test]
+fn create_connect_and_delete_planes() {
+    let Some(mut harness) = Harness::new() else {
+        return;
+    };
+    let initial_count = harness.app.scene.planes.len();
+    harness.new_plane("Portal A");
+    harness.new_plane("Portal B");
+    assert_eq!(harness.app.scene.planes.len(), initial_count + 2);
+    harness.assert_scene_consistent();
+
+    harness.connect_pair("Portal A", "Portal B");
+    let a = harness.plane_index("Portal A");
+    let b = harness.plane_index("Portal B");
+    let planes = &harness.app.scene.planes;
+    assert_eq!(planes[a].front_portal.other, Some(planes[b].id));
+    assert_eq!(planes[b].front_portal.other, Some(planes[a].id));
+    harness.assert_scene_consistent();
+
+    harness.toggle_plane("Portal A");
+    harness.click_after("Portal A", |widget| widget.label == "Delete");
+    assert_eq!(harness.app.scene.planes.len(), initial_count + 1);
+    let b = harness.plane_index("Portal B");
+    assert_eq!(harness.app.scene.planes[b].front_portal.other, None);
+    harness.assert_scene_consistent();
+}
+
+/// Deleting a plane before connected planes moves them down, their uploaded connections have to follow
+#[test]
+fn delete_plane_before_connected_planes() {
+    let Some(mut harness) = Harness::new() else {
+        return;
+    };
+    harness.new_plane("First");
+    harness.new_plane("Portal A");
+    harness.new_plane("Portal B");
+    harness.connect_pair("Portal A", "Portal B");
+    harness.assert_scene_consistent();
+
+    harness.toggle_plane("First");
+    harness.click_after("First", |widget| widget.label == "Delete");
+    let a = harness.plane_index("Portal A");
+    let b = harness.plane_index("Portal B");
+    let planes = &harness.app.scene.planes;
+    assert_eq!(planes[a].front_portal.other, Some(planes[b].id));
+    assert_eq!(planes[b].front_portal.other, Some(planes[a].id));
+    harness.assert_scene_consistent();
+}
+
+/// A duplicate goes to the same plane as the original, the original stays connected the way it was
+#[test]
+fn duplicate_connected_plane() {
+    let Some(mut harness) = Harness::new() else {
+        return;
+    };
+    harness.new_plane("Portal A");
+    harness.new_plane("Portal B");
+    harness.connect_pair("Portal A", "Portal B");
+
+    harness.toggle_plane("Portal A");
+    harness.click_after("Portal A", |widget| widget.label == "Duplicate");
+    let a = harness.plane_index("Portal A");
+    let b = harness.plane_index("Portal B");
+    let copy = harness.plane_index("Portal A Copy");
+    let planes = &harness.app.scene.planes;
+    assert_ne!(planes[copy].id, planes[a].id);
+    assert_eq!(planes[copy].front_portal.other, Some(planes[b].id));
+    assert_eq!(planes[b].front_portal.other, Some(planes[a].id));
+    harness.assert_scene_consistent();
+}
+
+/// Pasted planes that were connected to each other are connected to each other's copies
+#[test]
+fn copy_and_paste_connected_planes() {
+    let Some(mut harness) = Harness::new() else {
+        return;
+    };
+    harness.new_plane("Portal A");
+    harness.new_plane("Portal B");
+    harness.connect_pair("Portal A", "Portal B");
+    let count = harness.app.scene.planes.len();
+
+    harness.select_plane("Portal A");
+    harness.select_plane("Portal B");
+    harness.send(egui::Event::Copy);
+    let copied = std::mem::take(&mut harness.clipboard);
+    assert!(!copied.is_empty(), "nothing was copied");
+    harness.send(egui::Event::Paste(copied));
+
+    let planes = &harness.app.scene.planes;
+    assert_eq!(planes.len(), count + 2);
+    let (a, b) = (&planes[count], &planes[count + 1]);
+    assert_eq!(a.name, "Portal A");
+    assert_eq!(b.name, "Portal B");
+    assert_eq!(a.front_portal.other, Some(b.id));
+    assert_eq!(b.front_portal.other, Some(a.id));
+    assert_eq!(
+        harness.app.selected_planes,
+        HashSet::from([a.id, b.id]),
+        "the pasted planes are selected instead"
+    );
+    harness.assert_scene_consistent();
+}
+
+/// The hidden constructors eframe exposes for its own kittest integration, kept in one place since they aren't
+/// part of eframe's stable api and can change in any release
+mod eframe_hidden {
+    use eframe::{egui, egui_wgpu};
+
+    pub fn creation_context(
+        ctx: egui::Context,
+        render_state: egui_wgpu::RenderState,
+    ) -> eframe::CreationContext<'static> {
+        let mut creation_context = eframe::CreationContext::_new_kittest(ctx);
+        creation_context.wgpu_render_state = Some(render_state);
+        creation_context
+    }
+
+    pub fn frame() -> eframe::Frame {
+        eframe::Frame::_new_kittest()
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Units {
+    #[default]
+    Meters,
+    Feet,
+}
+
+impl Units {
+    pub const ALL: [Self; 2] = [Self::Meters, Self::Feet];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Units::Meters => "Meters",
+            Units::Feet => "Feet",
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Units::Meters => "m",
+            Units::Feet => "ft",
+        }
+    }
+
+    pub fn meters_per_unit(self) -> f32 {
+        match self {
+            Units::Meters => 1.0,
+            Units::Feet => 0.3048,
+        }
+    }
+
+    /// The factor that lengths in `self` need to be multiplied by to be in `other`
+    pub fn conversion_factor(self, other: Self) -> f32 {
+        self.meters_per_unit() / other.meters_per_unit()
+    }
+}
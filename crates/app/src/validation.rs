@@ -0,0 +1,290 @@
+use crate::{Plane, PlaneId, Ray, plane_indices};
+use math::Vector3;
+use std::collections::HashMap;
+
+/// How many traversals a probe ray can make through a portal before it is considered to be looping
+const LOOP_PROBE_TRAVERSALS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Likely a mistake, but the scene still renders
+    Warning,
+    /// The scene can't render correctly
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneWarning {
+    pub plane_index: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+pub fn validate_planes(planes: &[Plane]) -> Vec<SceneWarning> {
+    let mut warnings = vec![];
+    let indices = plane_indices(planes);
+    for (plane_index, plane) in planes.iter().enumerate() {
+        let non_finite = non_finite_fields(plane);
+        if !non_finite.is_empty() {
+            warnings.push(SceneWarning {
+                plane_index,
+                severity: Severity::Error,
+                message: format!(
+                    "'{}' has non-finite values in {}",
+                    plane.name,
+                    non_finite.join(", ")
+                ),
+            });
+        }
+
+        for (side, portal, front) in [
+            ("Front", &plane.front_portal, true),
+            ("Back", &plane.back_portal, false),
+        ] {
+            let Some(other_id) = portal.other else {
+                continue;
+            };
+
+            let Some(&other_index) = indices.get(&other_id) else {
+                warnings.push(SceneWarning {
+                    plane_index,
+                    severity: Severity::Error,
+                    message: format!(
+                        "{side} portal of '{}' is connected to a plane which doesn't exist",
+                        plane.name
+                    ),
+                });
+                continue;
+            };
+
+            if other_index == plane_index {
+                warnings.push(SceneWarning {
+                    plane_index,
+                    severity: Severity::Error,
+                    message: format!("{side} portal of '{}' is connected to itself", plane.name),
+                });
+            } else {
+                let other_plane = &planes[other_index];
+                if other_plane.front_portal.other != Some(plane.id)
+                    && other_plane.back_portal.other != Some(plane.id)
+                {
+                    warnings.push(SceneWarning {
+                        plane_index,
+                        severity: Severity::Warning,
+                        message: format!(
+                            "{side} portal of '{}' leads to '{}' which doesn't lead back to it",
+                            plane.name, other_plane.name
+                        ),
+                    });
+                }
+            }
+
+            if probe_portal_loop(planes, &indices, plane_index, front) {
+                warnings.push(SceneWarning {
+                    plane_index,
+                    severity: Severity::Warning,
+                    message: format!(
+                        "Rays entering the {} portal of '{}' are still traversing portals after {LOOP_PROBE_TRAVERSALS} traversals",
+                        side.to_lowercase(),
+                        plane.name
+                    ),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+/// The names of the fields of `plane` that are NaN or infinite
+fn non_finite_fields(plane: &Plane) -> Vec<&'static str> {
+    let fields = [
+        ("position", plane.position.x),
+        ("position", plane.position.y),
+        ("position", plane.position.z),
+        ("xy rotation", plane.xy_rotation),
+        ("yz rotation", plane.yz_rotation),
+        ("xz rotation", plane.xz_rotation),
+        ("width", plane.width),
+        ("height", plane.height),
+        ("color", plane.color.r),
+        ("color", plane.color.g),
+        ("color", plane.color.b),
+        ("checker darkness", plane.checker_darkness),
+        ("emissive color", plane.emissive_color.r),
+        ("emissive color", plane.emissive_color.g),
+        ("emissive color", plane.emissive_color.b),
+        ("emission intensity", plane.emission_intensity),
+        ("emissive checker darkness", plane.emissive_checker_darkness),
+        ("transmission", plane.transmission),
+        ("ior", plane.ior),
+        ("dispersion", plane.dispersion),
+        ("metallic", plane.metallic),
+        ("roughness", plane.roughness),
+        ("specular", plane.specular),
+        ("front portal scale", plane.front_portal.scale),
+        ("back portal scale", plane.back_portal.scale),
+    ];
+    let mut names = fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_finite())
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    names.dedup();
+    names
+}
+
+/// Shoots a ray into the center of a portal and follows it through the scene,
+/// returning true if it never escapes the portals
+fn probe_portal_loop(
+    planes: &[Plane],
+    indices: &HashMap<PlaneId, usize>,
+    plane_index: usize,
+    front: bool,
+) -> bool {
+    let plane = &planes[plane_index];
+    let transform = plane.transform();
+    let normal = transform.rotor_part().rotate(Vector3::UP) * if front { 1.0 } else { -1.0 };
+    let center = transform.transform_point(Vector3::ZERO);
+    let mut ray = Ray {
+        origin: center + normal * 0.01,
+        direction: -normal,
+    };
+
+    let mut ignored_index = None;
+    for _ in 0..LOOP_PROBE_TRAVERSALS {
+        let closest_hit = planes
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| Some(index) != ignored_index)
+            .filter_map(|(index, plane)| Some((index, plane.intersect(ray)?)))
+            .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance));
+        let Some((index, hit)) = closest_hit else {
+            return false;
+        };
+
+        let plane = &planes[index];
+        let portal = if hit.front {
+            &plane.front_portal
+        } else {
+            &plane.back_portal
+        };
+        let Some(&other_index) = portal.other.and_then(|id| indices.get(&id)) else {
+            return false;
+        };
+        let other_plane = &planes[other_index];
+
+        let transform = other_plane.transform().then(plane.transform().reverse());
+        ray = Ray {
+            origin: transform.transform_point(hit.position),
+            direction: transform.rotor_part().rotate(ray.direction),
+        };
+        ignored_index = Some(other_index);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortalConnection;
+
+    fn connected(name: &str, position: Vector3, other: Option<PlaneId>) -> Plane {
+        Plane {
+            name: name.into(),
+            position,
+            front_portal: PortalConnection {
+                other,
+                ..PortalConnection::default()
+            },
+            ..Plane::default()
+        }
+    }
+
+    /// Validates `planes` and returns the severity and message of every warning
+    fn warnings(planes: &[Plane]) -> Vec<(Severity, String)> {
+        validate_planes(planes)
+            .into_iter()
+            .map(|warning| (warning.severity, warning.message))
+            .collect()
+    }
+
+    #[test]
+    fn valid_pair() {
+        let mut a = connected("A", Vector3::ZERO, None);
+        let b = connected("B", Vector3::X * 5.0, Some(a.id));
+        a.front_portal.other = Some(b.id);
+        assert!(warnings(&[a, b]).is_empty());
+    }
+
+    #[test]
+    fn non_finite() {
+        let plane = Plane {
+            width: f32::INFINITY,
+            ior: f32::NAN,
+            ..connected("A", Vector3::ZERO, None)
+        };
+        assert_eq!(
+            warnings(&[plane]),
+            [(
+                Severity::Error,
+                "'A' has non-finite values in width, ior".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn dangling() {
+        let plane = connected("A", Vector3::ZERO, Some(PlaneId::new()));
+        assert_eq!(
+            warnings(&[plane]),
+            [(
+                Severity::Error,
+                "Front portal of 'A' is connected to a plane which doesn't exist".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn connected_to_itself() {
+        let mut plane = connected("A", Vector3::ZERO, None);
+        plane.front_portal.other = Some(plane.id);
+        let warnings = warnings(&[plane]);
+        assert_eq!(
+            warnings[0],
+            (
+                Severity::Error,
+                "Front portal of 'A' is connected to itself".into()
+            )
+        );
+    }
+
+    #[test]
+    fn one_way() {
+        let a = connected("A", Vector3::ZERO, None);
+        let b = connected("B", Vector3::X * 5.0, Some(a.id));
+        assert_eq!(
+            warnings(&[a, b]),
+            [(
+                Severity::Warning,
+                "Front portal of 'B' leads to 'A' which doesn't lead back to it".into()
+            )]
+        );
+    }
+
+    /// Rays going down into A come out of B going down, right above A again
+    #[test]
+    fn portal_loop() {
+        let mut a = connected("A", -Vector3::UP, None);
+        let b = connected("B", Vector3::ZERO, Some(a.id));
+        a.front_portal.other = Some(b.id);
+        assert_eq!(
+            warnings(&[a, b]),
+            [(
+                Severity::Warning,
+                format!(
+                    "Rays entering the front portal of 'A' are still traversing portals after {LOOP_PROBE_TRAVERSALS} traversals"
+                )
+            )]
+        );
+    }
+}
@@ -1,7 +1,11 @@
+mod parse;
 mod rotor;
 mod transform;
+mod up_axis;
 mod vector3;
 
+pub use parse::*;
 pub use rotor::*;
 pub use transform::*;
+pub use up_axis::*;
 pub use vector3::*;
@@ -0,0 +1,144 @@
+use std::{error::Error, fmt};
+
+/// Why text couldn't be parsed as a [`crate::Vector3`], [`crate::Rotor`] or [`crate::Transform`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    expected: &'static str,
+}
+
+impl ParseError {
+    pub(crate) const fn expected(expected: &'static str) -> Self {
+        Self { expected }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}", self.expected)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Writes `value` with the formatter's precision if it has one,
+/// otherwise with the fewest digits that still parse back to exactly the same value
+pub(crate) fn write_float(f: &mut fmt::Formatter<'_>, value: f32) -> fmt::Result {
+    match f.precision() {
+        Some(precision) => write!(f, "{value:.precision$}"),
+        None => write!(f, "{value:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Rotor, Transform, Vector3};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    const CASES: usize = 10_000;
+
+    fn any_float(rng: &mut StdRng) -> f32 {
+        loop {
+            let value = f32::from_bits(rng.random());
+            if !value.is_nan() {
+                return value;
+            }
+        }
+    }
+
+    fn vector(rng: &mut StdRng, range: f32) -> Vector3 {
+        Vector3 {
+            x: rng.random_range(-range..=range),
+            y: rng.random_range(-range..=range),
+            z: rng.random_range(-range..=range),
+        }
+    }
+
+    fn rotor(rng: &mut StdRng) -> Rotor {
+        let axis = vector(rng, 1.0);
+        let angle = rng.random_range(-std::f32::consts::TAU..=std::f32::consts::TAU);
+        Rotor::from_axis_angle(axis, angle)
+    }
+
+    /// Whether both turn the same way to within `tolerance`, a rotor and its negation are the same rotation
+    fn same_rotation(a: Rotor, b: Rotor, tolerance: f32) -> bool {
+        let difference = |b: Rotor| {
+            [a.s - b.s, a.e12 - b.e12, a.e13 - b.e13, a.e23 - b.e23]
+                .map(f32::abs)
+                .into_iter()
+                .fold(0.0, f32::max)
+        };
+        difference(b).min(difference(Rotor {
+            s: -b.s,
+            e12: -b.e12,
+            e13: -b.e13,
+            e23: -b.e23,
+        })) <= tolerance
+    }
+
+    #[test]
+    fn vector_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..CASES {
+            let vector = Vector3 {
+                x: any_float(&mut rng),
+                y: any_float(&mut rng),
+                z: any_float(&mut rng),
+            };
+            let text = vector.to_string();
+            let parsed = text.parse::<Vector3>().unwrap();
+            // bitwise so the sign of zero counts too
+            assert_eq!(
+                [parsed.x, parsed.y, parsed.z].map(f32::to_bits),
+                [vector.x, vector.y, vector.z].map(f32::to_bits),
+                "{text}"
+            );
+        }
+    }
+
+    /// Going through an angle and axis rounds, so a rotor comes back as the same rotation to within a few ulps
+    #[test]
+    fn rotor_round_trip() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..CASES {
+            let rotor = rotor(&mut rng);
+            let text = rotor.to_string();
+            let parsed = text.parse::<Rotor>().unwrap();
+            assert!(
+                same_rotation(parsed, rotor, 4.0 * f32::EPSILON),
+                "{text}: {parsed:?} != {rotor:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn transform_round_trip() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..CASES {
+            let translation = vector(&mut rng, 1000.0);
+            let transform =
+                Transform::translation(translation).then(Transform::from_rotor(rotor(&mut rng)));
+            let text = transform.to_string();
+            let parsed = text.parse::<Transform>().unwrap();
+            assert!(
+                same_rotation(
+                    parsed.rotor_part(),
+                    transform.rotor_part(),
+                    4.0 * f32::EPSILON
+                ),
+                "{text}: {parsed:?} != {transform:?}"
+            );
+            let error = (parsed.translation_part() - transform.translation_part()).magnitude();
+            assert!(
+                error <= 4.0 * f32::EPSILON * translation.magnitude().max(1.0),
+                "{text}: {parsed:?} != {transform:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn precision() {
+        let rotor = Rotor::from_axis_angle(Vector3::UP, std::f32::consts::FRAC_PI_2);
+        assert_eq!(format!("{rotor:.1}"), "90.0° around (0.0, 1.0, 0.0)");
+        assert_eq!(Vector3::X.to_string(), "(1.0, 0.0, 0.0)");
+    }
+}
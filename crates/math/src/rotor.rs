@@ -1,10 +1,12 @@
 use bytemuck::{Pod, Zeroable};
 use encase::ShaderType;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+use std::{fmt, str::FromStr};
 
-use crate::Vector3;
+use crate::{ParseError, Vector3, parse::write_float};
 
-#[derive(Debug, Clone, Copy, Zeroable, Pod, ShaderType, Serialize, Deserialize)]
+/// Human readable formats like json store it as text, see its [`fmt::Display`] impl, and read either form
+#[derive(Debug, Clone, Copy, Zeroable, Pod, ShaderType)]
 #[repr(C)]
 pub struct Rotor {
     pub s: f32,
@@ -54,6 +56,57 @@ impl Rotor {
         }
     }
 
+    /// Turns counterclockwise by `angle` radians when looking down `axis`, which doesn't have to be normalised
+    #[inline]
+    #[must_use]
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Self {
+        let axis = axis.normalised();
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        Self {
+            s: cos,
+            e12: axis.z * sin,
+            e13: -axis.y * sin,
+            e23: axis.x * sin,
+        }
+    }
+
+    /// The axis and angle of [`Rotor::from_axis_angle`], the angle is from 0 to pi,
+    /// a rotor that doesn't turn at all turns by 0 around [`Vector3::UP`]
+    #[inline]
+    #[must_use]
+    pub fn axis_angle(self) -> (Vector3, f32) {
+        // the rotor and its negation are the same rotation, the one with a positive s turns the shorter way
+        let sign = if self.s < 0.0 { -1.0 } else { 1.0 };
+        let axis = Vector3 {
+            x: self.e23,
+            y: -self.e13,
+            z: self.e12,
+        } * sign;
+        let sin = axis.magnitude();
+        if sin <= f32::EPSILON {
+            return (Vector3::UP, 0.0);
+        }
+        (axis / sin, 2.0 * sin.atan2(self.s * sign))
+    }
+
+    /// The rotation that takes `from` to `to` along the shortest way, both have to be normalised,
+    /// it is undefined if they point in opposite directions
+    #[inline]
+    #[must_use]
+    pub fn between(from: Vector3, to: Vector3) -> Self {
+        let s = 1.0 + from.dot(to);
+        let e12 = from.x * to.y - from.y * to.x;
+        let e13 = from.x * to.z - from.z * to.x;
+        let e23 = from.y * to.z - from.z * to.y;
+        let inverse_magnitude = (s * s + e12 * e12 + e13 * e13 + e23 * e23).sqrt().recip();
+        Self {
+            s: s * inverse_magnitude,
+            e12: e12 * inverse_magnitude,
+            e13: e13 * inverse_magnitude,
+            e23: e23 * inverse_magnitude,
+        }
+    }
+
     #[inline]
     #[must_use]
     pub const fn reverse(self) -> Self {
@@ -91,6 +144,42 @@ impl Rotor {
         }
     }
 
+    /// Turns from `self` towards `to` at a constant speed along the shorter way around, `t` is from 0 to 1
+    #[inline]
+    #[must_use]
+    pub fn slerp(self, to: Self, t: f32) -> Self {
+        let dot = self.s * to.s + self.e12 * to.e12 + self.e13 * to.e13 + self.e23 * to.e23;
+        // the rotor and its negation are the same rotation, the one closer to `self` turns the shorter way
+        let (to, dot) = if dot < 0.0 {
+            (
+                Self {
+                    s: -to.s,
+                    e12: -to.e12,
+                    e13: -to.e13,
+                    e23: -to.e23,
+                },
+                -dot,
+            )
+        } else {
+            (to, dot)
+        };
+        // almost the same rotation, dividing by the sine of the angle between them would blow up
+        let (from_weight, to_weight) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = dot.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Self {
+            s: self.s * from_weight + to.s * to_weight,
+            e12: self.e12 * from_weight + to.e12 * to_weight,
+            e13: self.e13 * from_weight + to.e13 * to_weight,
+            e23: self.e23 * from_weight + to.e23 * to_weight,
+        }
+        .normalised()
+    }
+
     #[inline]
     #[must_use]
     pub const fn then(self, then: Self) -> Self {
@@ -184,3 +273,179 @@ impl Rotor {
         }
     }
 }
+
+/// Written as an angle in degrees and the axis it turns around, like `90° around (0, 1, 0)`,
+/// with the formatter's precision if it has one
+impl fmt::Display for Rotor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (axis, angle) = self.axis_angle();
+        write_float(f, angle.to_degrees())?;
+        f.write_str("° around ")?;
+        fmt::Display::fmt(&axis, f)
+    }
+}
+
+impl FromStr for Rotor {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (angle, axis) = s
+            .split_once(" around ")
+            .ok_or(ParseError::expected("a rotation like 90° around (0, 1, 0)"))?;
+        let angle = angle
+            .trim()
+            .trim_end_matches('°')
+            .parse::<f32>()
+            .map_err(|_| ParseError::expected("an angle in degrees"))?;
+        let axis = axis.parse::<Vector3>()?;
+        if axis.sqr_magnitude() == 0.0 {
+            return Err(ParseError::expected("an axis that isn't zero"));
+        }
+        Ok(Self::from_axis_angle(axis, angle.to_radians()))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Rotor")]
+struct RotorCoefficients {
+    s: f32,
+    e12: f32,
+    e13: f32,
+    e23: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReadableRotor {
+    Text(String),
+    Coefficients(#[serde(with = "RotorCoefficients")] Rotor),
+}
+
+impl Serialize for Rotor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            RotorCoefficients::serialize(self, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Rotor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            match ReadableRotor::deserialize(deserializer)? {
+                ReadableRotor::Text(text) => text.parse().map_err(D::Error::custom),
+                ReadableRotor::Coefficients(rotor) => Ok(rotor),
+            }
+        } else {
+            RotorCoefficients::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    /// The angle it takes to turn from `a` to `b`, from the distance between them as that stays precise
+    /// for small angles, where the acos of their dot product doesn't
+    fn angle_between(a: Rotor, b: Rotor) -> f32 {
+        let distance = |sign: f32| {
+            [
+                a.s - b.s * sign,
+                a.e12 - b.e12 * sign,
+                a.e13 - b.e13 * sign,
+                a.e23 - b.e23 * sign,
+            ]
+            .map(|difference| difference * difference)
+            .into_iter()
+            .sum::<f32>()
+            .sqrt()
+        };
+        4.0 * (distance(1.0).min(distance(-1.0)) * 0.5).min(1.0).asin()
+    }
+
+    fn rotors() -> [Rotor; 6] {
+        [
+            Rotor::IDENTITY,
+            Rotor::rotation_xy(FRAC_PI_2),
+            Rotor::rotation_yz(-FRAC_PI_2),
+            Rotor::rotation_xz(3.0),
+            Rotor::from_axis_angle(
+                Vector3 {
+                    x: 1.0,
+                    y: -2.0,
+                    z: 0.5,
+                },
+                2.0,
+            ),
+            Rotor::rotation_xy(0.7).then(Rotor::rotation_yz(FRAC_PI_2)),
+        ]
+    }
+
+    #[test]
+    fn slerp_ends() {
+        for from in rotors() {
+            for to in rotors() {
+                assert!(
+                    angle_between(from.slerp(to, 0.0), from) < 1e-3,
+                    "{from:?} {to:?}"
+                );
+                assert!(
+                    angle_between(from.slerp(to, 1.0), to) < 1e-3,
+                    "{from:?} {to:?}"
+                );
+            }
+        }
+    }
+
+    /// Turning halfway there and then the rest of the way ends up at the same rotation, at a constant speed
+    #[test]
+    fn slerp_constant_speed() {
+        for from in rotors() {
+            for to in rotors() {
+                let total = angle_between(from, to);
+                assert!(total <= PI + 1e-4);
+                for t in [0.25, 0.5, 0.75] {
+                    let between = from.slerp(to, t);
+                    assert!(
+                        (angle_between(from, between) - total * t).abs() < 1e-3,
+                        "{from:?} {to:?} {t}"
+                    );
+                    assert!(
+                        angle_between(between.slerp(to, 1.0), to) < 1e-3,
+                        "{from:?} {to:?} {t}"
+                    );
+                    assert!(
+                        angle_between(to.slerp(from, 1.0 - t), between) < 1e-3,
+                        "{from:?} {to:?} {t}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// A rotor and its negation are the same rotation, so slerping to either takes the same path
+    #[test]
+    fn slerp_shorter_way() {
+        for from in rotors() {
+            for to in rotors() {
+                // half a turn apart both ways are as short
+                if (angle_between(from, to) - PI).abs() < 1e-3 {
+                    continue;
+                }
+                let negated = Rotor {
+                    s: -to.s,
+                    e12: -to.e12,
+                    e13: -to.e13,
+                    e23: -to.e23,
+                };
+                let a = from.slerp(to, 0.3);
+                let b = from.slerp(negated, 0.3);
+                assert!(angle_between(a, b) < 1e-3, "{from:?} {to:?}");
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use bytemuck::{Pod, Zeroable};
 use encase::ShaderType;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+use std::{fmt, str::FromStr};
 
-use crate::{Rotor, Vector3};
+use crate::{ParseError, Rotor, Vector3};
 
-#[derive(Debug, Clone, Copy, Zeroable, Pod, ShaderType, Serialize, Deserialize)]
+/// Human readable formats like json store it as text, see its [`fmt::Display`] impl, and read either form
+#[derive(Debug, Clone, Copy, Zeroable, Pod, ShaderType)]
 #[repr(C)]
 pub struct Transform {
     pub s: f32,
@@ -74,6 +76,13 @@ impl Transform {
         }
     }
 
+    /// Where the transform moves the origin to
+    #[inline]
+    #[must_use]
+    pub const fn translation_part(self) -> Vector3 {
+        self.transform_point(Vector3::ZERO)
+    }
+
     #[inline]
     #[must_use]
     pub const fn rotor_part(self) -> Rotor {
@@ -232,3 +241,72 @@ impl Transform {
         }
     }
 }
+
+/// Written as where it moves the origin to and how it turns, like `at (1, 2, 3) turned 90° around (0, 1, 0)`,
+/// with the formatter's precision if it has one
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("at ")?;
+        fmt::Display::fmt(&self.translation_part(), f)?;
+        f.write_str(" turned ")?;
+        fmt::Display::fmt(&self.rotor_part(), f)
+    }
+}
+
+impl FromStr for Transform {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (translation, rotation) = s
+            .trim()
+            .strip_prefix("at ")
+            .and_then(|s| s.split_once(" turned "))
+            .ok_or(ParseError::expected(
+                "a transform like at (1, 2, 3) turned 90° around (0, 1, 0)",
+            ))?;
+        Ok(Self::translation(translation.parse()?).then(Self::from_rotor(rotation.parse()?)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Transform")]
+struct TransformCoefficients {
+    s: f32,
+    e12: f32,
+    e13: f32,
+    e23: f32,
+    e01: f32,
+    e02: f32,
+    e03: f32,
+    e0123: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReadableTransform {
+    Text(String),
+    Coefficients(#[serde(with = "TransformCoefficients")] Transform),
+}
+
+impl Serialize for Transform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            TransformCoefficients::serialize(self, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Transform {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            match ReadableTransform::deserialize(deserializer)? {
+                ReadableTransform::Text(text) => text.parse().map_err(D::Error::custom),
+                ReadableTransform::Coefficients(transform) => Ok(transform),
+            }
+        } else {
+            TransformCoefficients::deserialize(deserializer)
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Rotor, Transform, Vector3};
+
+/// Which axis points up in a scene, [`Vector3::UP`] is y so y up needs no conversion
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UpAxis {
+    X,
+    #[default]
+    Y,
+    Z,
+}
+
+impl UpAxis {
+    pub const ALL: [Self; 3] = [Self::X, Self::Y, Self::Z];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            UpAxis::X => "X Up",
+            UpAxis::Y => "Y Up",
+            UpAxis::Z => "Z Up",
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn up(self) -> Vector3 {
+        match self {
+            UpAxis::X => Vector3::X,
+            UpAxis::Y => Vector3::Y,
+            UpAxis::Z => Vector3::Z,
+        }
+    }
+
+    /// The rotation that turns y up into this axis up, along the shortest way
+    #[inline]
+    #[must_use]
+    pub fn from_y_up(self) -> Rotor {
+        Rotor::between(Vector3::UP, self.up())
+    }
+
+    /// The rotation that turns this axis up into y up, the reverse of [`UpAxis::from_y_up`]
+    #[inline]
+    #[must_use]
+    pub fn to_y_up(self) -> Rotor {
+        self.from_y_up().reverse()
+    }
+
+    /// The rotation that turns this axis up into `other` up
+    #[inline]
+    #[must_use]
+    pub fn to(self, other: Self) -> Rotor {
+        other.from_y_up().then(self.to_y_up())
+    }
+
+    /// Converts a point or direction from this axis up to `other` up
+    #[inline]
+    #[must_use]
+    pub fn convert(self, other: Self, vector: Vector3) -> Vector3 {
+        self.to(other).rotate(vector)
+    }
+
+    /// Converts a transform from this axis up to `other` up, rotating both where it is and how it is turned
+    #[inline]
+    #[must_use]
+    pub fn convert_transform(self, other: Self, transform: Transform) -> Transform {
+        Transform::from_rotor(self.to(other)).then(transform)
+    }
+}
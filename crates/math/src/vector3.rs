@@ -1,6 +1,12 @@
 use bytemuck::{Pod, Zeroable};
 use serde::{Deserialize, Serialize};
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use crate::{ParseError, UpAxis, parse::write_float};
 
 #[derive(Debug, Clone, Copy, Zeroable, Pod, Serialize, Deserialize)]
 #[repr(C)]
@@ -79,6 +85,32 @@ impl Vector3 {
     pub fn reflect(self, n: Self) -> Self {
         self - n * (2.0 * self.dot(n))
     }
+
+    /// The unit direction `azimuth` radians around from [`Vector3::FORWARD`] towards [`Vector3::RIGHT`]
+    /// and `elevation` radians up from the horizon towards `up_axis`, both turned by [`UpAxis::from_y_up`]
+    #[inline]
+    #[must_use]
+    pub fn from_azimuth_elevation(azimuth: f32, elevation: f32, up_axis: UpAxis) -> Self {
+        let (azimuth_sin, azimuth_cos) = azimuth.sin_cos();
+        let (elevation_sin, elevation_cos) = elevation.sin_cos();
+        up_axis.from_y_up().rotate(Self {
+            x: elevation_cos * azimuth_cos,
+            y: elevation_sin,
+            z: elevation_cos * azimuth_sin,
+        })
+    }
+
+    /// The azimuth and elevation of this direction in radians, the reverse of [`Vector3::from_azimuth_elevation`],
+    /// the azimuth is from -pi to pi and 0 for straight up or down
+    #[inline]
+    #[must_use]
+    pub fn azimuth_elevation(self, up_axis: UpAxis) -> (f32, f32) {
+        let direction = up_axis.to_y_up().rotate(self.normalised());
+        (
+            direction.z.atan2(direction.x),
+            direction.y.clamp(-1.0, 1.0).asin(),
+        )
+    }
 }
 
 impl AsRef<[f32; 3]> for Vector3 {
@@ -111,6 +143,19 @@ impl From<Vector3> for [f32; 3] {
 
 encase::impl_vector!(3, Vector3, f32; using AsRef AsMut From);
 
+impl Neg for Vector3 {
+    type Output = Vector3;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
 impl Add<Vector3> for Vector3 {
     type Output = Vector3;
 
@@ -270,3 +315,41 @@ impl DivAssign<f32> for Vector3 {
         *self = *self / rhs;
     }
 }
+
+/// Written as `(x, y, z)`, with the formatter's precision if it has one
+impl fmt::Display for Vector3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(")?;
+        write_float(f, self.x)?;
+        f.write_str(", ")?;
+        write_float(f, self.y)?;
+        f.write_str(", ")?;
+        write_float(f, self.z)?;
+        f.write_str(")")
+    }
+}
+
+impl FromStr for Vector3 {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = ParseError::expected("a vector like (1, 2, 3)");
+        let components = s
+            .trim()
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .ok_or(error)?
+            .split(',')
+            .map(|component| {
+                component
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| ParseError::expected("a number"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let &[x, y, z] = components.as_slice() else {
+            return Err(error);
+        };
+        Ok(Self { x, y, z })
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{
+    frame_graph::{FrameGraph, FrameResources, GraphPass, GraphTexture, TextureAccess},
+    gpu_scene::GpuScene,
+    light_groups::LIGHT_GROUP_COUNT,
+    shader_error::{ShaderError, create_pipeline, create_shader_module},
+    textures::TextureBuffers,
+    workgroup::WorkgroupSize,
+};
+use eframe::wgpu;
+use half::f16;
+use serde::{Deserialize, Serialize};
+
+/// What the accumulated image is stored as
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccumulationPrecision {
+    /// 32 bit floats
+    #[default]
+    Full,
+    /// 16 bit floats, half the memory and bandwidth of [`AccumulationPrecision::Full`],
+    /// the rounding error of every frame is carried in a second texture so the image still converges,
+    /// but nothing brighter than 65504 can be stored
+    Half,
+}
+
+impl AccumulationPrecision {
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            AccumulationPrecision::Full => wgpu::TextureFormat::Rgba32Float,
+            AccumulationPrecision::Half => wgpu::TextureFormat::Rgba16Float,
+        }
+    }
+
+    pub(crate) fn bytes_per_pixel(self) -> u32 {
+        self.texture_format().block_copy_size(None).unwrap()
+    }
+
+    /// How much memory the textures accumulating a `width` by `height` image take,
+    /// with the compensation texture at half precision and the light groups, which are always full precision
+    pub fn accumulation_bytes(self, width: u32, height: u32, light_groups: bool) -> u64 {
+        let bytes_per_pixel = if light_groups {
+            let full = AccumulationPrecision::Full.bytes_per_pixel();
+            full * (1 + LIGHT_GROUP_COUNT as u32)
+        } else {
+            match self {
+                AccumulationPrecision::Full => self.bytes_per_pixel(),
+                AccumulationPrecision::Half => self.bytes_per_pixel() * 2,
+            }
+        };
+        width as u64 * height as u64 * bytes_per_pixel as u64
+    }
+
+    /// Converts texels of the accumulated image to colors
+    pub(crate) fn decode(self, bytes: &[u8]) -> Vec<[f32; 4]> {
+        match self {
+            AccumulationPrecision::Full => bytes
+                .chunks_exact(16)
+                .map(bytemuck::pod_read_unaligned)
+                .collect(),
+            AccumulationPrecision::Half => bytes
+                .chunks_exact(8)
+                .map(|texel| {
+                    std::array::from_fn(|channel| {
+                        f16::from_ne_bytes([texel[channel * 2], texel[channel * 2 + 1]]).to_f32()
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Converts colors to texels of the accumulated image
+    pub(crate) fn encode(self, pixels: &[[f32; 4]]) -> Vec<u8> {
+        match self {
+            AccumulationPrecision::Full => bytemuck::cast_slice(pixels).to_vec(),
+            AccumulationPrecision::Half => pixels
+                .iter()
+                .flatten()
+                .flat_map(|&value| f16::from_f32(value).to_ne_bytes())
+                .collect(),
+        }
+    }
+}
+
+/// The frame graph and ray tracing pipeline for accumulating at one precision
+pub(crate) struct Accumulation {
+    pub frame_graph: FrameGraph,
+    /// The accumulated image
+    pub ray_tracing_texture: GraphTexture,
+    /// The rounding error of every pixel in the accumulated image, only at half precision
+    pub compensation_texture: Option<GraphTexture>,
+    /// Every light group accumulated on its own, the accumulated image is them mixed
+    pub light_group_textures: Option<[GraphTexture; LIGHT_GROUP_COUNT]>,
+    pub ray_tracing_pass: GraphPass,
+    pub histogram_pass: GraphPass,
+    pub full_screen_quad_pass: GraphPass,
+    pub ray_tracing_shader: wgpu::ShaderModule,
+    ray_tracing_pipeline_layout: wgpu::PipelineLayout,
+    pub workgroup_size: WorkgroupSize,
+    pub ray_tracing_pipeline: wgpu::ComputePipeline,
+    /// Mixes the light groups into the accumulated image without tracing, only with light groups
+    pub mix_light_groups_pipeline: Option<wgpu::ComputePipeline>,
+    /// Traces lighting into the checker cells of the planes instead of the accumulated image
+    pub bake_lighting_pipeline: wgpu::ComputePipeline,
+    /// Traces from the probes of the probe grid instead of the camera
+    pub update_probes_pipeline: wgpu::ComputePipeline,
+}
+
+impl Accumulation {
+    /// Light groups are always accumulated at full precision
+    pub fn new(
+        device: &wgpu::Device,
+        precision: AccumulationPrecision,
+        light_groups: bool,
+        scene_info_bind_group_layout: &wgpu::BindGroupLayout,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        workgroup_size: WorkgroupSize,
+    ) -> Result<Self, ShaderError> {
+        let precision = if light_groups {
+            AccumulationPrecision::Full
+        } else {
+            precision
+        };
+        let ray_tracing_shader = match precision {
+            AccumulationPrecision::Full if light_groups => create_shader_module(
+                device,
+                "ray_tracing_light_groups.wgsl",
+                wgpu::include_wgsl!(concat!(
+                    env!("OUT_DIR"),
+                    "/shaders/ray_tracing_light_groups.wgsl"
+                )),
+            )?,
+            AccumulationPrecision::Full => create_shader_module(
+                device,
+                "ray_tracing.wgsl",
+                wgpu::include_wgsl!(concat!(env!("OUT_DIR"), "/shaders/ray_tracing.wgsl")),
+            )?,
+            AccumulationPrecision::Half => create_shader_module(
+                device,
+                "ray_tracing_half.wgsl",
+                wgpu::include_wgsl!(concat!(env!("OUT_DIR"), "/shaders/ray_tracing_half.wgsl")),
+            )?,
+        };
+
+        let mut frame_graph = FrameGraph::default();
+        let ray_tracing_texture = frame_graph.add_texture(
+            "Ray Tracing Texture",
+            precision.texture_format(),
+            wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+        );
+        let compensation_texture = match precision {
+            AccumulationPrecision::Full => None,
+            AccumulationPrecision::Half => Some(frame_graph.add_texture(
+                "Compensation Texture",
+                precision.texture_format(),
+                wgpu::TextureUsages::COPY_DST,
+            )),
+        };
+        const LIGHT_GROUP_LABELS: [&str; LIGHT_GROUP_COUNT] = [
+            "Light Group Texture 0",
+            "Light Group Texture 1",
+            "Light Group Texture 2",
+            "Light Group Texture 3",
+        ];
+        let light_group_textures = light_groups.then(|| {
+            LIGHT_GROUP_LABELS.map(|label| {
+                frame_graph.add_texture(
+                    label,
+                    precision.texture_format(),
+                    wgpu::TextureUsages::COPY_DST,
+                )
+            })
+        });
+        let ray_tracing_pass = frame_graph.add_pass(
+            device,
+            "Ray Tracing Pass",
+            wgpu::ShaderStages::COMPUTE,
+            &std::iter::once(ray_tracing_texture)
+                .chain(compensation_texture)
+                .chain(light_group_textures.into_iter().flatten())
+                .map(|texture| (texture, TextureAccess::Storage))
+                .collect::<Vec<_>>(),
+        );
+        // these layouts are the same at every precision, so the pipelines using them can be shared
+        let histogram_pass = frame_graph.add_pass(
+            device,
+            "Histogram Pass",
+            wgpu::ShaderStages::COMPUTE,
+            &[(ray_tracing_texture, TextureAccess::Sample)],
+        );
+        let full_screen_quad_pass = frame_graph.add_pass(
+            device,
+            "Full Screen Quad Pass",
+            wgpu::ShaderStages::FRAGMENT,
+            &[(ray_tracing_texture, TextureAccess::Sample)],
+        );
+
+        let ray_tracing_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Ray Tracing Pipeline Layout"),
+                bind_group_layouts: &[
+                    frame_graph.bind_group_layout(ray_tracing_pass),
+                    scene_info_bind_group_layout,
+                    objects_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        let ray_tracing_pipeline = Self::create_ray_tracing_pipeline(
+            device,
+            &ray_tracing_shader,
+            &ray_tracing_pipeline_layout,
+            workgroup_size,
+            "ray_trace",
+        )?;
+        let mix_light_groups_pipeline = light_groups
+            .then(|| {
+                Self::create_ray_tracing_pipeline(
+                    device,
+                    &ray_tracing_shader,
+                    &ray_tracing_pipeline_layout,
+                    workgroup_size,
+                    "mix_light_groups",
+                )
+            })
+            .transpose()?;
+        let bake_lighting_pipeline = Self::create_ray_tracing_pipeline(
+            device,
+            &ray_tracing_shader,
+            &ray_tracing_pipeline_layout,
+            workgroup_size,
+            "bake_lighting",
+        )?;
+        let update_probes_pipeline = Self::create_ray_tracing_pipeline(
+            device,
+            &ray_tracing_shader,
+            &ray_tracing_pipeline_layout,
+            workgroup_size,
+            "update_probes",
+        )?;
+
+        Ok(Self {
+            frame_graph,
+            ray_tracing_texture,
+            compensation_texture,
+            light_group_textures,
+            ray_tracing_pass,
+            histogram_pass,
+            full_screen_quad_pass,
+            ray_tracing_shader,
+            ray_tracing_pipeline_layout,
+            workgroup_size,
+            ray_tracing_pipeline,
+            mix_light_groups_pipeline,
+            bake_lighting_pipeline,
+            update_probes_pipeline,
+        })
+    }
+
+    /// Recreates the ray tracing pipeline with a different workgroup size, keeping the old one if that fails
+    pub fn set_workgroup_size(
+        &mut self,
+        device: &wgpu::Device,
+        workgroup_size: WorkgroupSize,
+    ) -> Result<(), ShaderError> {
+        if workgroup_size == self.workgroup_size {
+            return Ok(());
+        }
+        let ray_tracing_pipeline = Self::create_ray_tracing_pipeline(
+            device,
+            &self.ray_tracing_shader,
+            &self.ray_tracing_pipeline_layout,
+            workgroup_size,
+            "ray_trace",
+        )?;
+        if self.mix_light_groups_pipeline.is_some() {
+            self.mix_light_groups_pipeline = Some(Self::create_ray_tracing_pipeline(
+                device,
+                &self.ray_tracing_shader,
+                &self.ray_tracing_pipeline_layout,
+                workgroup_size,
+                "mix_light_groups",
+            )?);
+        }
+        self.bake_lighting_pipeline = Self::create_ray_tracing_pipeline(
+            device,
+            &self.ray_tracing_shader,
+            &self.ray_tracing_pipeline_layout,
+            workgroup_size,
+            "bake_lighting",
+        )?;
+        self.update_probes_pipeline = Self::create_ray_tracing_pipeline(
+            device,
+            &self.ray_tracing_shader,
+            &self.ray_tracing_pipeline_layout,
+            workgroup_size,
+            "update_probes",
+        )?;
+        self.ray_tracing_pipeline = ray_tracing_pipeline;
+        self.workgroup_size = workgroup_size;
+        Ok(())
+    }
+
+    pub fn create_ray_tracing_pipeline(
+        device: &wgpu::Device,
+        ray_tracing_shader: &wgpu::ShaderModule,
+        ray_tracing_pipeline_layout: &wgpu::PipelineLayout,
+        workgroup_size: WorkgroupSize,
+        entry_point: &str,
+    ) -> Result<wgpu::ComputePipeline, ShaderError> {
+        create_pipeline(device, "Ray Tracing Pipeline", || {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Ray Tracing Pipeline"),
+                layout: Some(ray_tracing_pipeline_layout),
+                module: ray_tracing_shader,
+                entry_point: Some(entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &workgroup_size.pipeline_constants(),
+                    ..Default::default()
+                },
+                cache: None,
+            })
+        })
+    }
+}
+
+/// Every way of accumulating, render targets switch between them
+pub(crate) struct Accumulations {
+    pub full_precision: Accumulation,
+    pub half_precision: Accumulation,
+    pub light_groups: Accumulation,
+}
+
+impl Accumulations {
+    pub fn new(
+        device: &wgpu::Device,
+        scene_info_bind_group_layout: &wgpu::BindGroupLayout,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        workgroup_size: WorkgroupSize,
+    ) -> Result<Self, ShaderError> {
+        let accumulation = |precision, light_groups| {
+            Accumulation::new(
+                device,
+                precision,
+                light_groups,
+                scene_info_bind_group_layout,
+                objects_bind_group_layout,
+                workgroup_size,
+            )
+        };
+        Ok(Self {
+            full_precision: accumulation(AccumulationPrecision::Full, false)?,
+            half_precision: accumulation(AccumulationPrecision::Half, false)?,
+            light_groups: accumulation(AccumulationPrecision::Full, true)?,
+        })
+    }
+
+    pub fn get(&self, precision: AccumulationPrecision, light_groups: bool) -> &Accumulation {
+        match precision {
+            _ if light_groups => &self.light_groups,
+            AccumulationPrecision::Full => &self.full_precision,
+            AccumulationPrecision::Half => &self.half_precision,
+        }
+    }
+
+    pub fn for_target(&self, target: &RenderTarget) -> &Accumulation {
+        self.get(target.precision, target.light_groups)
+    }
+
+    pub fn workgroup_size(&self) -> WorkgroupSize {
+        self.full_precision.workgroup_size
+    }
+
+    pub fn set_workgroup_size(
+        &mut self,
+        device: &wgpu::Device,
+        workgroup_size: WorkgroupSize,
+    ) -> Result<(), ShaderError> {
+        self.full_precision
+            .set_workgroup_size(device, workgroup_size)?;
+        self.half_precision
+            .set_workgroup_size(device, workgroup_size)?;
+        self.light_groups
+            .set_workgroup_size(device, workgroup_size)?;
+        Ok(())
+    }
+}
+
+/// The accumulated image and uploaded planes of one render target
+pub(crate) struct RenderTarget {
+    /// Always [`AccumulationPrecision::Full`] with light groups
+    pub precision: AccumulationPrecision,
+    pub light_groups: bool,
+    pub resources: FrameResources,
+    /// Only while [`crate::RayTracingPaintCallback::aovs`] is set, the same size as the accumulated image
+    pub aovs: Option<FrameResources>,
+    /// The planes uploaded for this target, so switching between targets doesn't upload everything again
+    pub scene: GpuScene,
+}
+
+impl RenderTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        accumulation: &Accumulation,
+        precision: AccumulationPrecision,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        textures: &TextureBuffers,
+    ) -> Self {
+        Self {
+            precision,
+            light_groups: false,
+            aovs: None,
+            resources: accumulation.frame_graph.create_resources(device, 1, 1),
+            scene: GpuScene::new(device, objects_bind_group_layout, textures),
+        }
+    }
+}
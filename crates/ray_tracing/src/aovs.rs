@@ -0,0 +1,239 @@
+use crate::{
+    accumulation::Accumulation,
+    frame_graph::{FrameGraph, FrameResources, GraphPass, GraphTexture, TextureAccess},
+    shader_error::ShaderError,
+    workgroup::WorkgroupSize,
+};
+use eframe::wgpu;
+
+/// What [`Aovs::object_id`] is where nothing was hit
+pub const NO_OBJECT: u32 = u32::MAX;
+
+/// How many planes the coverage of every pixel is kept for, see [`Aovs::mattes`]
+pub const MATTE_RANKS: usize = 4;
+
+/// How much of a pixel a plane covers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatteSample {
+    /// The index of the plane, [`NO_OBJECT`] if the slot is empty
+    pub object_id: u32,
+    /// From 0 to 1
+    pub coverage: f32,
+}
+
+/// Auxiliary outputs, what the ray through the middle of every pixel first hits,
+/// traced alongside the accumulated image with [`crate::RayTracingPaintCallback::aovs`],
+/// every buffer is bottom row first like the accumulated image
+#[derive(Debug, Default, Clone)]
+pub struct Aovs {
+    pub width: u32,
+    pub height: u32,
+    /// The color of the plane that was hit, or the background where nothing was hit
+    pub albedo: Vec<[f32; 3]>,
+    /// The world space normal of the plane that was hit facing the ray, zero where nothing was hit
+    pub normal: Vec<[f32; 3]>,
+    /// How far the ray went including before going through portals, infinite where nothing was hit
+    pub depth: Vec<f32>,
+    /// The index of the plane that was hit, [`NO_OBJECT`] where nothing was hit
+    pub object_id: Vec<u32>,
+    /// How many portals the ray went through
+    pub portal_count: Vec<u32>,
+    /// The planes covering the most of every pixel with how much they cover, most coverage first,
+    /// accumulated from a ray through a random point in the pixel every frame instead of the middle,
+    /// when more planes cover a pixel than there are ranks the ones with the least coverage are left out
+    pub mattes: Vec<[MatteSample; MATTE_RANKS]>,
+}
+
+/// The textures and pipeline for tracing the [`Aovs`] of a render target
+pub(crate) struct AovPass {
+    pub frame_graph: FrameGraph,
+    /// In the order of the buffers of [`Aovs`], the mattes take two textures
+    pub textures: [GraphTexture; 7],
+    pub pass: GraphPass,
+    /// Samples the depth to draw over the displayed image, see [`crate::FocusPeaking`]
+    pub display_pass: GraphPass,
+    /// The pipeline doesn't use the accumulated image, but the first bind group still has to be set
+    pub empty_bind_group: wgpu::BindGroup,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    pub workgroup_size: WorkgroupSize,
+    pub pipeline: wgpu::ComputePipeline,
+}
+
+impl AovPass {
+    /// Uses the entry point of the full precision ray tracing shader,
+    /// the outputs are bound as the fourth bind group so they don't collide with the accumulated image
+    pub fn new(
+        device: &wgpu::Device,
+        accumulation: &Accumulation,
+        scene_info_bind_group_layout: &wgpu::BindGroupLayout,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Result<Self, ShaderError> {
+        let mut frame_graph = FrameGraph::default();
+        let mut add_texture =
+            |label, format| frame_graph.add_texture(label, format, wgpu::TextureUsages::COPY_SRC);
+        let albedo_texture = add_texture("Albedo Texture", wgpu::TextureFormat::Rgba32Float);
+        let normal_texture = add_texture("Normal Texture", wgpu::TextureFormat::Rgba32Float);
+        let depth_texture = add_texture("Depth Texture", wgpu::TextureFormat::R32Float);
+        let object_id_texture = add_texture("Object ID Texture", wgpu::TextureFormat::R32Uint);
+        let portal_count_texture =
+            add_texture("Portal Count Texture", wgpu::TextureFormat::R32Uint);
+        let matte_texture_0 = add_texture("Matte Texture 0", wgpu::TextureFormat::Rgba32Float);
+        let matte_texture_1 = add_texture("Matte Texture 1", wgpu::TextureFormat::Rgba32Float);
+        let textures = [
+            albedo_texture,
+            normal_texture,
+            depth_texture,
+            object_id_texture,
+            portal_count_texture,
+            matte_texture_0,
+            matte_texture_1,
+        ];
+        let pass = frame_graph.add_pass(
+            device,
+            "AOV Pass",
+            wgpu::ShaderStages::COMPUTE,
+            &textures.map(|texture| (texture, TextureAccess::Storage)),
+        );
+        let display_pass = frame_graph.add_pass(
+            device,
+            "AOV Display Pass",
+            wgpu::ShaderStages::FRAGMENT,
+            &[(depth_texture, TextureAccess::Sample)],
+        );
+
+        let empty_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Empty Bind Group Layout"),
+                entries: &[],
+            });
+        let empty_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Empty Bind Group"),
+            layout: &empty_bind_group_layout,
+            entries: &[],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("AOV Pipeline Layout"),
+            bind_group_layouts: &[
+                &empty_bind_group_layout,
+                scene_info_bind_group_layout,
+                objects_bind_group_layout,
+                frame_graph.bind_group_layout(pass),
+            ],
+            push_constant_ranges: &[],
+        });
+        let shader = accumulation.ray_tracing_shader.clone();
+        let workgroup_size = accumulation.workgroup_size;
+        let pipeline = Accumulation::create_ray_tracing_pipeline(
+            device,
+            &shader,
+            &pipeline_layout,
+            workgroup_size,
+            "trace_aovs",
+        )?;
+
+        Ok(Self {
+            frame_graph,
+            textures,
+            pass,
+            display_pass,
+            empty_bind_group,
+            shader,
+            pipeline_layout,
+            workgroup_size,
+            pipeline,
+        })
+    }
+
+    /// Recreates the pipeline with a different workgroup size, keeping the old one if that fails
+    pub fn set_workgroup_size(
+        &mut self,
+        device: &wgpu::Device,
+        workgroup_size: WorkgroupSize,
+    ) -> Result<(), ShaderError> {
+        if workgroup_size == self.workgroup_size {
+            return Ok(());
+        }
+        self.pipeline = Accumulation::create_ray_tracing_pipeline(
+            device,
+            &self.shader,
+            &self.pipeline_layout,
+            workgroup_size,
+            "trace_aovs",
+        )?;
+        self.workgroup_size = workgroup_size;
+        Ok(())
+    }
+
+    /// Converts the texels read back from every texture in [`AovPass::textures`]
+    pub fn decode(
+        width: u32,
+        height: u32,
+        [
+            albedo,
+            normal,
+            depth,
+            object_id,
+            portal_count,
+            matte_0,
+            matte_1,
+        ]: [Vec<u8>; 7],
+    ) -> Aovs {
+        let rgb = |bytes: Vec<u8>| {
+            bytes
+                .chunks_exact(16)
+                .map(|texel| {
+                    let [r, g, b, _]: [f32; 4] = bytemuck::pod_read_unaligned(texel);
+                    [r, g, b]
+                })
+                .collect()
+        };
+        fn scalars<T: bytemuck::AnyBitPattern>(bytes: Vec<u8>) -> Vec<T> {
+            bytes
+                .chunks_exact(4)
+                .map(bytemuck::pod_read_unaligned)
+                .collect()
+        }
+        Aovs {
+            width,
+            height,
+            albedo: rgb(albedo),
+            normal: rgb(normal),
+            depth: scalars(depth),
+            object_id: scalars(object_id),
+            portal_count: scalars(portal_count),
+            mattes: matte_0
+                .chunks_exact(16)
+                .zip(matte_1.chunks_exact(16))
+                .map(|(first, second)| {
+                    let [id_0, coverage_0, id_1, coverage_1]: [f32; 4] =
+                        bytemuck::pod_read_unaligned(first);
+                    let [id_2, coverage_2, id_3, coverage_3]: [f32; 4] =
+                        bytemuck::pod_read_unaligned(second);
+                    let sample = |id: f32, coverage| MatteSample {
+                        // the shader stores the index plus one as a float, so 0 can be an empty slot
+                        object_id: if id > 0.0 { id as u32 - 1 } else { NO_OBJECT },
+                        coverage,
+                    };
+                    let mut samples = [
+                        sample(id_0, coverage_0),
+                        sample(id_1, coverage_1),
+                        sample(id_2, coverage_2),
+                        sample(id_3, coverage_3),
+                    ];
+                    samples.sort_by(|a, b| b.coverage.total_cmp(&a.coverage));
+                    samples
+                })
+                .collect(),
+        }
+    }
+
+    pub fn create_resources(
+        &self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> FrameResources {
+        self.frame_graph.create_resources(device, width, height)
+    }
+}
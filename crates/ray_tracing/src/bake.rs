@@ -0,0 +1,23 @@
+/// How many cells are in a row of the bake's dispatch, the same as in the ray tracing shader
+pub(crate) const BAKE_ROW_WIDTH: u32 = 256;
+
+/// Lighting traced for both sides of every checker cell of the planes with a [`crate::GpuPlane::baked_offset`],
+/// Unlit mode shows those planes lit by what was baked, so static scenes keep their light at interactive framerates,
+/// the baked lighting is kept in the render target between frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BakeFrame {
+    /// How many cells the planes have baked lighting for, both sides of a checker cell count separately
+    pub cell_count: u32,
+    /// How many frames of baking were accumulated before this one, 0 starts the bake over
+    pub accumulated_frames: u32,
+    /// How many samples every cell traces this frame, 0 only shows what was baked,
+    /// should stay the same for the whole bake because every frame is weighted the same
+    pub samples_per_cell: u32,
+}
+
+impl BakeFrame {
+    /// The workgroups of the bake's dispatch, every thread bakes a cell
+    pub(crate) fn dispatch_size(&self, workgroup_size: crate::WorkgroupSize) -> (u32, u32) {
+        workgroup_size.dispatch_size(BAKE_ROW_WIDTH, self.cell_count.div_ceil(BAKE_ROW_WIDTH))
+    }
+}
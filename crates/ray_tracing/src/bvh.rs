@@ -0,0 +1,224 @@
+use crate::GpuPlane;
+use encase::ShaderType;
+use math::Vector3;
+
+/// Nodes with at most this many planes aren't split any further
+const MAX_LEAF_PLANES: usize = 2;
+/// How many places along each axis the surface area heuristic tries splitting the planes at
+const SAH_BINS: usize = 8;
+/// The shader's traversal stack has room for a node from every level, see BVH_STACK_SIZE in ray_tracing.slang
+const MAX_DEPTH: u32 = 31;
+/// Planes are flat, their bounds are grown by this so rays can always enter them
+const BOUNDS_PADDING: f32 = 0.0001;
+
+/// A node of the bounding volume hierarchy the shader finds the planes a ray could hit with
+#[derive(Debug, Clone, Copy, ShaderType)]
+pub(crate) struct GpuBvhNode {
+    pub min: Vector3,
+    /// How many planes the node has if it is a leaf, 0 if it has children
+    pub plane_count: u32,
+    pub max: Vector3,
+    /// The first of the leaf's planes in [`Bvh::plane_indices`], otherwise the first child, the second child follows it
+    pub first: u32,
+}
+
+/// The planes of a scene sorted into nested boxes, so rays only test the planes in the boxes they go through
+pub(crate) struct Bvh {
+    /// The root is the first node, there always is one so the buffer is never empty
+    pub nodes: Vec<GpuBvhNode>,
+    /// The indices of the planes in the order the leaves refer to them
+    pub plane_indices: Vec<u32>,
+}
+
+impl Bvh {
+    /// Splits the planes where the surface area heuristic says tracing through them is cheapest
+    pub fn build(planes: &[GpuPlane]) -> Self {
+        let bounds = planes.iter().map(Bounds::of_plane).collect::<Vec<_>>();
+        let mut plane_indices = (0..planes.len() as u32).collect::<Vec<_>>();
+        let mut nodes = vec![GpuBvhNode::EMPTY];
+
+        // the node, the range of plane indices it has and how deep it is
+        let mut stack = vec![(0, 0, plane_indices.len(), 0)];
+        while let Some((node, first, count, depth)) = stack.pop() {
+            let node_planes = &mut plane_indices[first..first + count];
+            let node_bounds = Bounds::around(node_planes, &bounds);
+            nodes[node].min = node_bounds.min;
+            nodes[node].max = node_bounds.max;
+
+            let split = if count > MAX_LEAF_PLANES && depth < MAX_DEPTH {
+                split(node_planes, &bounds, node_bounds)
+            } else {
+                None
+            };
+            let Some(left_count) = split else {
+                nodes[node].plane_count = count as u32;
+                nodes[node].first = first as u32;
+                continue;
+            };
+
+            let left = nodes.len();
+            nodes.extend([GpuBvhNode::EMPTY; 2]);
+            nodes[node].plane_count = 0;
+            nodes[node].first = left as u32;
+            stack.push((left, first, left_count, depth + 1));
+            stack.push((left + 1, first + left_count, count - left_count, depth + 1));
+        }
+
+        Self {
+            nodes,
+            plane_indices,
+        }
+    }
+}
+
+impl GpuBvhNode {
+    /// A leaf without any planes that no ray goes through
+    const EMPTY: Self = Self {
+        min: Bounds::EMPTY.min,
+        plane_count: 0,
+        max: Bounds::EMPTY.max,
+        first: 0,
+    };
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min: Vector3,
+    max: Vector3,
+}
+
+impl Bounds {
+    /// Inside out, so growing it by anything is just that
+    const EMPTY: Self = Self {
+        min: Vector3 {
+            x: f32::INFINITY,
+            y: f32::INFINITY,
+            z: f32::INFINITY,
+        },
+        max: Vector3 {
+            x: f32::NEG_INFINITY,
+            y: f32::NEG_INFINITY,
+            z: f32::NEG_INFINITY,
+        },
+    };
+
+    fn of_plane(plane: &GpuPlane) -> Self {
+        let (half_width, half_height) = (plane.width * 0.5, plane.height * 0.5);
+        let bounds = [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)]
+            .into_iter()
+            .fold(Self::EMPTY, |bounds, (x, z)| {
+                bounds.grow(plane.transform.transform_point(Vector3 {
+                    x: x * half_width,
+                    y: 0.0,
+                    z: z * half_height,
+                }))
+            });
+        Self {
+            min: bounds.min - BOUNDS_PADDING,
+            max: bounds.max + BOUNDS_PADDING,
+        }
+    }
+
+    fn around(plane_indices: &[u32], bounds: &[Self]) -> Self {
+        plane_indices.iter().fold(Self::EMPTY, |around, &index| {
+            around.union(bounds[index as usize])
+        })
+    }
+
+    fn grow(self, point: Vector3) -> Self {
+        self.union(Self {
+            min: point,
+            max: point,
+        })
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self {
+            min: Vector3 {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+                z: self.min.z.min(other.min.z),
+            },
+            max: Vector3 {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+                z: self.max.z.max(other.max.z),
+            },
+        }
+    }
+
+    fn center(self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// 0 for empty bounds
+    fn surface_area(self) -> f32 {
+        let size = self.max - self.min;
+        if size.x < 0.0 || size.y < 0.0 || size.z < 0.0 {
+            return 0.0;
+        }
+        (size.x * size.y + size.y * size.z + size.z * size.x) * 2.0
+    }
+}
+
+/// Partitions the planes by the cheapest of the splits between bins of their centers along every axis,
+/// returns how many planes end up in the first part, `None` if keeping them together is cheaper
+fn split(plane_indices: &mut [u32], bounds: &[Bounds], node_bounds: Bounds) -> Option<usize> {
+    let centers = plane_indices.iter().fold(Bounds::EMPTY, |centers, &index| {
+        centers.grow(bounds[index as usize].center())
+    });
+    let bin = |index: u32, axis: usize| {
+        let min = centers.min.as_ref()[axis];
+        let extent = centers.max.as_ref()[axis] - min;
+        let offset = (bounds[index as usize].center().as_ref()[axis] - min) / extent;
+        ((offset * SAH_BINS as f32) as usize).min(SAH_BINS - 1)
+    };
+
+    // the cost, the axis and the first bin of the second part
+    let mut best = None::<(f32, usize, usize)>;
+    for axis in 0..3 {
+        if centers.max.as_ref()[axis] <= centers.min.as_ref()[axis] {
+            continue;
+        }
+        let mut bins = [(Bounds::EMPTY, 0); SAH_BINS];
+        for &index in plane_indices.iter() {
+            let (bin_bounds, count) = &mut bins[bin(index, axis)];
+            *bin_bounds = bin_bounds.union(bounds[index as usize]);
+            *count += 1;
+        }
+        let sum = |bins: &[(Bounds, usize)]| {
+            bins.iter()
+                .fold((Bounds::EMPTY, 0), |(sum, total), &(bin_bounds, count)| {
+                    (sum.union(bin_bounds), total + count)
+                })
+        };
+        for split in 1..SAH_BINS {
+            let (first, first_count) = sum(&bins[..split]);
+            let (second, second_count) = sum(&bins[split..]);
+            if first_count == 0 || second_count == 0 {
+                continue;
+            }
+            let cost = first.surface_area() * first_count as f32
+                + second.surface_area() * second_count as f32;
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, split));
+            }
+        }
+    }
+
+    let (cost, axis, split) = best?;
+    // going into both children costs about as much as testing a single plane
+    let area = node_bounds.surface_area();
+    if area > 0.0 && 1.0 + cost / area >= plane_indices.len() as f32 {
+        return None;
+    }
+
+    let mut first_count = 0;
+    for i in 0..plane_indices.len() {
+        if bin(plane_indices[i], axis) < split {
+            plane_indices.swap(first_count, i);
+            first_count += 1;
+        }
+    }
+    Some(first_count)
+}
@@ -2,7 +2,8 @@ use bytemuck::{Pod, Zeroable};
 use serde::{Deserialize, Serialize};
 use std::ops::Mul;
 
-#[derive(Debug, Clone, Copy, Zeroable, Pod, Serialize, Deserialize)]
+/// A color in linear RGB, conversion to sRGB happens when the image is displayed
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod, Serialize, Deserialize)]
 #[repr(C)]
 pub struct Color {
     pub r: f32,
@@ -47,3 +48,13 @@ impl From<Color> for [f32; 3] {
 }
 
 encase::impl_vector!(3, Color, f32; using AsRef AsMut From);
+
+/// The same conversion the display shader does, for when the image leaves the gpu
+pub fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    Color, GpuCamera, PaintTarget,
+    aovs::AovPass,
+    frame_graph::FrameResources,
+    shader_error::{ShaderError, create_pipeline, create_shader_module},
+};
+use eframe::wgpu;
+use encase::{ShaderSize, ShaderType};
+
+/// Highlights what is in focus with depth of field over the displayed image, from the depth of the [`crate::Aovs`],
+/// so the focus distance can be set without waiting for the blur to converge
+#[derive(Debug, Clone, Copy)]
+pub struct FocusPeaking {
+    /// How many pixels across the blur of a point can be for it to still count as in focus
+    pub max_blur: f32,
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, Copy, ShaderType)]
+struct GpuFocusPeakingInfo {
+    color: Color,
+    /// Non-zero when the surface format does not do the linear to sRGB conversion itself
+    encode_srgb: u32,
+    aperture: f32,
+    focus_distance: f32,
+    /// The tangent of half the vertical field of view
+    half_height: f32,
+    aspect: f32,
+    max_blur: f32,
+}
+
+/// Draws [`FocusPeaking`] over the traced image, sampling the depth texture of the target's aovs
+pub(crate) struct FocusPeakingPass {
+    info_buffer: wgpu::Buffer,
+    info_bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl FocusPeakingPass {
+    pub fn new(
+        device: &wgpu::Device,
+        paint_target: PaintTarget,
+        aov_pass: &AovPass,
+    ) -> Result<Self, ShaderError> {
+        let shader = create_shader_module(
+            device,
+            "focus_peaking.wgsl",
+            wgpu::include_wgsl!(concat!(env!("OUT_DIR"), "/shaders/focus_peaking.wgsl")),
+        )?;
+
+        let info_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Focus Peaking Info Buffer"),
+            size: GpuFocusPeakingInfo::SHADER_SIZE.get(),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let info_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Focus Peaking Info Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(GpuFocusPeakingInfo::SHADER_SIZE),
+                    },
+                    count: None,
+                }],
+            });
+        let info_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Focus Peaking Info Bind Group"),
+            layout: &info_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: info_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Focus Peaking Pipeline Layout"),
+            bind_group_layouts: &[
+                aov_pass
+                    .frame_graph
+                    .bind_group_layout(aov_pass.display_pass),
+                &info_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = create_pipeline(device, "Focus Peaking Pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Focus Peaking Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vertex"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Cw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: paint_target.depth_stencil(),
+                multisample: paint_target.multisample(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fragment"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(paint_target.color_target(Some(
+                        wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+                    )))],
+                }),
+                multiview: None,
+                cache: None,
+            })
+        })?;
+
+        Ok(Self {
+            info_buffer,
+            info_bind_group,
+            pipeline,
+        })
+    }
+
+    pub fn prepare(
+        &self,
+        queue: &wgpu::Queue,
+        focus_peaking: FocusPeaking,
+        camera: &GpuCamera,
+        aspect: f32,
+        encode_srgb: bool,
+    ) {
+        let info = GpuFocusPeakingInfo {
+            color: focus_peaking.color,
+            encode_srgb: encode_srgb as u32,
+            aperture: camera.aperture,
+            focus_distance: camera.focus_distance,
+            half_height: (camera.fov * 0.5).tan(),
+            aspect,
+            max_blur: focus_peaking.max_blur.max(0.0),
+        };
+        let mut info_buffer = queue
+            .write_buffer_with(&self.info_buffer, 0, GpuFocusPeakingInfo::SHADER_SIZE)
+            .unwrap();
+        encase::UniformBuffer::new(&mut *info_buffer)
+            .write(&info)
+            .unwrap();
+    }
+
+    /// `aovs` are the aov resources of the target being painted
+    pub fn paint(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'static>,
+        aov_pass: &AovPass,
+        aovs: &FrameResources,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, aovs.bind_group(aov_pass.display_pass), &[]);
+        render_pass.set_bind_group(1, &self.info_bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+}
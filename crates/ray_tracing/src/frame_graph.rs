@@ -0,0 +1,230 @@
+use eframe::wgpu;
+
+/// A texture in a [`FrameGraph`], every render target gets its own at the size of its frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GraphTexture(usize);
+
+/// A pass in a [`FrameGraph`], its textures are bound as a single bind group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GraphPass(usize);
+
+/// How a pass uses one of the graph's textures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TextureAccess {
+    /// Sampled, a nearest neighbour sampler is bound after all of the pass's textures
+    Sample,
+    /// Read and written as a storage texture
+    Storage,
+}
+
+struct TextureDescriptor {
+    label: &'static str,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+}
+
+struct PassDescriptor {
+    label: &'static str,
+    textures: Vec<(GraphTexture, TextureAccess)>,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+/// The textures of a frame and the passes that read and write them,
+/// the passes declare which textures they use and how so the textures get the right usages
+/// and every pass gets a bind group with them in the order they were declared
+#[derive(Default)]
+pub(crate) struct FrameGraph {
+    textures: Vec<TextureDescriptor>,
+    passes: Vec<PassDescriptor>,
+}
+
+impl FrameGraph {
+    /// `usage` only needs what isn't implied by the passes, like copying to or from the texture
+    pub fn add_texture(
+        &mut self,
+        label: &'static str,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+    ) -> GraphTexture {
+        self.textures.push(TextureDescriptor {
+            label,
+            format,
+            usage,
+        });
+        GraphTexture(self.textures.len() - 1)
+    }
+
+    /// Binding `i` of the pass's bind group is `textures[i]`, followed by a sampler if any of them are sampled
+    pub fn add_pass(
+        &mut self,
+        device: &wgpu::Device,
+        label: &'static str,
+        visibility: wgpu::ShaderStages,
+        textures: &[(GraphTexture, TextureAccess)],
+    ) -> GraphPass {
+        let mut entries = vec![];
+        for (binding, &(texture, access)) in textures.iter().enumerate() {
+            let descriptor = &mut self.textures[texture.0];
+            let ty = match access {
+                TextureAccess::Sample => {
+                    descriptor.usage |= wgpu::TextureUsages::TEXTURE_BINDING;
+                    wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    }
+                }
+                TextureAccess::Storage => {
+                    descriptor.usage |= wgpu::TextureUsages::STORAGE_BINDING;
+                    wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::ReadWrite,
+                        format: descriptor.format,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    }
+                }
+            };
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: binding as u32,
+                visibility,
+                ty,
+                count: None,
+            });
+        }
+        if textures
+            .iter()
+            .any(|&(_, access)| access == TextureAccess::Sample)
+        {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: textures.len() as u32,
+                visibility,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                count: None,
+            });
+        }
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &entries,
+        });
+        self.passes.push(PassDescriptor {
+            label,
+            textures: textures.to_vec(),
+            bind_group_layout,
+        });
+        GraphPass(self.passes.len() - 1)
+    }
+
+    pub fn bind_group_layout(&self, pass: GraphPass) -> &wgpu::BindGroupLayout {
+        &self.passes[pass.0].bind_group_layout
+    }
+
+    /// Creates every texture at `width` by `height` and the bind groups of every pass
+    pub fn create_resources(
+        &self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> FrameResources {
+        let textures = self
+            .textures
+            .iter()
+            .map(|descriptor| {
+                device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(descriptor.label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: descriptor.format,
+                    usage: descriptor.usage,
+                    view_formats: &[],
+                })
+            })
+            .collect::<Vec<_>>();
+        let views = textures
+            .iter()
+            .map(|texture| texture.create_view(&Default::default()))
+            .collect::<Vec<_>>();
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Frame Graph Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let bind_groups = self
+            .passes
+            .iter()
+            .map(|pass| {
+                let mut entries = pass
+                    .textures
+                    .iter()
+                    .enumerate()
+                    .map(|(binding, &(texture, _))| wgpu::BindGroupEntry {
+                        binding: binding as u32,
+                        resource: wgpu::BindingResource::TextureView(&views[texture.0]),
+                    })
+                    .collect::<Vec<_>>();
+                if pass
+                    .textures
+                    .iter()
+                    .any(|&(_, access)| access == TextureAccess::Sample)
+                {
+                    entries.push(wgpu::BindGroupEntry {
+                        binding: pass.textures.len() as u32,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    });
+                }
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some(pass.label),
+                    layout: &pass.bind_group_layout,
+                    entries: &entries,
+                })
+            })
+            .collect();
+
+        FrameResources {
+            width,
+            height,
+            textures,
+            bind_groups,
+        }
+    }
+}
+
+/// The textures and bind groups of a [`FrameGraph`] for one render target
+pub(crate) struct FrameResources {
+    width: u32,
+    height: u32,
+    textures: Vec<wgpu::Texture>,
+    bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl FrameResources {
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Recreates everything if the size changed, which throws away what the textures contained
+    pub fn resize(&mut self, graph: &FrameGraph, device: &wgpu::Device, width: u32, height: u32) {
+        if self.size() != (width, height) {
+            *self = graph.create_resources(device, width, height);
+        }
+    }
+
+    pub fn texture(&self, texture: GraphTexture) -> &wgpu::Texture {
+        &self.textures[texture.0]
+    }
+
+    pub fn bind_group(&self, pass: GraphPass) -> &wgpu::BindGroup {
+        &self.bind_groups[pass.0]
+    }
+}
@@ -0,0 +1,479 @@
+use crate::{
+    GpuPlane, ProbeFrame,
+    bvh::{Bvh, GpuBvhNode},
+    lights::{GpuLight, build_lights, emitted_power},
+    probes::GpuProbeGrid,
+    textures::TextureBuffers,
+};
+use eframe::wgpu;
+use encase::ShaderSize;
+use std::time::{Duration, Instant};
+
+/// The baked lighting of a cell is an rgba float
+pub(crate) const BAKED_CELL_SIZE: wgpu::BufferAddress = 16;
+/// A probe is an rgba float for every face of its ambient cube
+pub(crate) const PROBE_SIZE: wgpu::BufferAddress = 16 * 6;
+
+/// The planes of a scene on the gpu, kept between frames so only the planes that changed get uploaded,
+/// the bvh and lights are only rebuilt when what they are built from changed,
+/// materials are part of the planes so changing one only uploads the plane it is on
+pub(crate) struct GpuScene {
+    /// The encoded planes that are in `planes_buffer`, what the next planes get compared against
+    uploaded: Vec<u8>,
+    /// What the bvh in `bvh_buffers` was built from, one for every plane
+    geometry: Vec<PlaneGeometry>,
+    /// The [`emitted_power`] of every plane when the lights were last built
+    light_powers: Vec<f32>,
+    /// The [`TargetImage::planes_version`](crate::TargetImage::planes_version) of the last update
+    version: Option<u64>,
+    planes_buffer: wgpu::Buffer,
+    /// The lighting baked into the checker cells of the planes, see [`BakeFrame`](crate::BakeFrame)
+    baked_lighting_buffer: wgpu::Buffer,
+    /// The irradiance of the probes, see [`ProbeGrid`](crate::ProbeGrid)
+    probes_buffer: wgpu::Buffer,
+    probe_grids_buffer: wgpu::Buffer,
+    bvh_buffers: BvhBuffers,
+    /// The emissive planes, see [`GpuLight`]
+    lights_buffer: wgpu::Buffer,
+    light_count: u32,
+    /// The textures of the renderer, bound again when they change, see [`GpuScene::update_textures`]
+    textures: TextureBuffers,
+    objects_bind_group: wgpu::BindGroup,
+}
+
+/// The parts of a plane its bounds in the [`Bvh`] come from, as bits so they compare exactly
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct PlaneGeometry([u32; 10]);
+
+impl PlaneGeometry {
+    fn of(plane: &GpuPlane) -> Self {
+        let mut bits = [0; 10];
+        bits[..8].copy_from_slice(bytemuck::cast_slice(bytemuck::bytes_of(&plane.transform)));
+        bits[8] = plane.width.to_bits();
+        bits[9] = plane.height.to_bits();
+        Self(bits)
+    }
+}
+
+/// The [`Bvh`] of the planes, rebuilt whenever the geometry of any of them changes
+struct BvhBuffers {
+    nodes: wgpu::Buffer,
+    plane_indices: wgpu::Buffer,
+}
+
+impl BvhBuffers {
+    fn new(device: &wgpu::Device, node_count: usize, plane_count: usize) -> Self {
+        let buffer = |label, size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        Self {
+            nodes: buffer(
+                "BVH Nodes Buffer",
+                node_count.max(1) as wgpu::BufferAddress * GpuBvhNode::SHADER_SIZE.get(),
+            ),
+            // never empty, so it can always be bound
+            plane_indices: buffer(
+                "BVH Plane Indices Buffer",
+                plane_count.max(1) as wgpu::BufferAddress * size_of::<u32>() as wgpu::BufferAddress,
+            ),
+        }
+    }
+}
+
+impl GpuScene {
+    pub fn new(
+        device: &wgpu::Device,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        textures: &TextureBuffers,
+    ) -> Self {
+        let planes_buffer = Self::planes_buffer(device, GpuPlane::SHADER_SIZE.get());
+        let baked_lighting_buffer = Self::baked_lighting_buffer(device, 1);
+        let probes_buffer = Self::probes_buffer(device, 1);
+        let probe_grids_buffer = Self::probe_grids_buffer(device, 1);
+        let bvh_buffers = BvhBuffers::new(device, 1, 1);
+        let lights_buffer = Self::lights_buffer(device, 1);
+        let objects_bind_group = Self::create_objects_bind_group(
+            device,
+            objects_bind_group_layout,
+            [
+                &planes_buffer,
+                &baked_lighting_buffer,
+                &probes_buffer,
+                &probe_grids_buffer,
+                &bvh_buffers.nodes,
+                &bvh_buffers.plane_indices,
+                &textures.infos,
+                &textures.texels,
+                &lights_buffer,
+            ],
+        );
+        Self {
+            uploaded: vec![],
+            geometry: vec![],
+            light_powers: vec![],
+            version: None,
+            planes_buffer,
+            baked_lighting_buffer,
+            probes_buffer,
+            probe_grids_buffer,
+            bvh_buffers,
+            lights_buffer,
+            light_count: 0,
+            textures: textures.clone(),
+            objects_bind_group,
+        }
+    }
+
+    /// Binds `textures` if they were recreated since they were last bound
+    pub fn update_textures(
+        &mut self,
+        device: &wgpu::Device,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        textures: &TextureBuffers,
+    ) {
+        if textures.generation == self.textures.generation {
+            return;
+        }
+        self.textures = textures.clone();
+        self.bind_objects(device, objects_bind_group_layout);
+    }
+
+    /// Grows the baked lighting buffer to fit `cell_count` cells, what was baked is lost when it grows
+    pub fn reserve_baked_cells(
+        &mut self,
+        device: &wgpu::Device,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        cell_count: u32,
+    ) {
+        let size = cell_count as wgpu::BufferAddress * BAKED_CELL_SIZE;
+        if size <= self.baked_lighting_buffer.size() {
+            return;
+        }
+        tracing::trace!(cell_count, "growing baked lighting buffer");
+        self.baked_lighting_buffer = Self::baked_lighting_buffer(device, cell_count);
+        self.bind_objects(device, objects_bind_group_layout);
+    }
+
+    /// Uploads the grids of `probes` and grows the probes buffer to fit all of their probes,
+    /// what the probes accumulated is lost when it grows, returns how long encoding the grids took
+    pub fn update_probes(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        probes: &ProbeFrame,
+    ) -> Duration {
+        let probe_count = probes.probe_count();
+        let grids = probes.gpu_grids();
+        let grows = probe_count as wgpu::BufferAddress * PROBE_SIZE > self.probes_buffer.size();
+        let grids_grow = grids.len() as wgpu::BufferAddress * GpuProbeGrid::SHADER_SIZE.get()
+            > self.probe_grids_buffer.size();
+        if grows {
+            tracing::trace!(probe_count, "growing probes buffer");
+            self.probes_buffer = Self::probes_buffer(device, probe_count);
+        }
+        if grids_grow {
+            self.probe_grids_buffer = Self::probe_grids_buffer(device, grids.len());
+        }
+        if grows || grids_grow {
+            self.bind_objects(device, objects_bind_group_layout);
+        }
+
+        let encoding_start = Instant::now();
+        if !grids.is_empty() {
+            let mut encoded = encase::StorageBuffer::new(vec![]);
+            encoded.write(&grids).unwrap();
+            queue.write_buffer(&self.probe_grids_buffer, 0, &encoded.into_inner());
+        }
+        encoding_start.elapsed()
+    }
+
+    /// Uploads only the `dirty_planes` if the last update was the previous version,
+    /// otherwise the planes that are different from the last update, everything if the buffer has to grow,
+    /// the bvh is rebuilt if any plane moved or was resized and the lights if any plane emits differently,
+    /// returns how long encoding the planes and building them took
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        planes: &Vec<GpuPlane>,
+        version: u64,
+        dirty_planes: Option<&[usize]>,
+    ) -> Duration {
+        let previous_version = self.version.replace(version);
+        let stride = GpuPlane::SHADER_SIZE.get() as usize;
+        if let Some(dirty_planes) = dirty_planes
+            && previous_version.is_some_and(|previous_version| previous_version + 1 == version)
+            && self.uploaded.len() == planes.len() * stride
+        {
+            let mut encoding = Duration::ZERO;
+            for &index in dirty_planes {
+                let Some(plane) = planes.get(index) else {
+                    continue;
+                };
+                let encoding_start = Instant::now();
+                let mut encoded = encase::StorageBuffer::new(vec![]);
+                encoded.write(plane).unwrap();
+                let encoded = encoded.into_inner();
+                encoding += encoding_start.elapsed();
+
+                let start = index * stride;
+                queue.write_buffer(&self.planes_buffer, start as _, &encoded);
+                self.uploaded[start..start + stride].copy_from_slice(&encoded);
+            }
+            if !dirty_planes.is_empty() {
+                tracing::trace!(dirty_planes = dirty_planes.len(), "uploading dirty planes");
+            }
+
+            let encoding_start = Instant::now();
+            let mut geometry_changed = false;
+            let mut lights_changed = false;
+            for &index in dirty_planes {
+                let Some(plane) = planes.get(index) else {
+                    continue;
+                };
+                let geometry = PlaneGeometry::of(plane);
+                geometry_changed |=
+                    std::mem::replace(&mut self.geometry[index], geometry) != geometry;
+                let power = emitted_power(plane);
+                lights_changed |= std::mem::replace(&mut self.light_powers[index], power) != power;
+            }
+            encoding += encoding_start.elapsed();
+            if geometry_changed {
+                encoding += self.update_bvh(device, queue, objects_bind_group_layout, planes);
+            }
+            if lights_changed {
+                encoding += self.update_lights(device, queue, objects_bind_group_layout);
+            }
+            return encoding;
+        }
+
+        let encoding_start = Instant::now();
+        let mut encoded = encase::StorageBuffer::new(vec![]);
+        encoded.write(planes).unwrap();
+        let encoded = encoded.into_inner();
+        let mut encoding = encoding_start.elapsed();
+
+        if encoded.len() as wgpu::BufferAddress > self.planes_buffer.size() {
+            tracing::trace!(
+                planes = planes.len(),
+                bytes = encoded.len(),
+                "growing planes buffer"
+            );
+            self.planes_buffer = Self::planes_buffer(device, encoded.len() as _);
+            self.bind_objects(device, objects_bind_group_layout);
+            self.uploaded.clear();
+        }
+
+        // consecutive planes that changed are uploaded together
+        let mut dirty_planes = 0;
+        let mut dirty_start = None;
+        for (index, plane) in encoded.chunks(stride).enumerate() {
+            let start = index * stride;
+            let dirty = self.uploaded.get(start..start + plane.len()) != Some(plane);
+            if dirty {
+                dirty_planes += 1;
+                dirty_start.get_or_insert(start);
+            } else if let Some(dirty_start) = dirty_start.take() {
+                queue.write_buffer(
+                    &self.planes_buffer,
+                    dirty_start as _,
+                    &encoded[dirty_start..start],
+                );
+            }
+        }
+        if let Some(dirty_start) = dirty_start {
+            queue.write_buffer(
+                &self.planes_buffer,
+                dirty_start as _,
+                &encoded[dirty_start..],
+            );
+        }
+        if dirty_planes > 0 {
+            tracing::trace!(dirty_planes, "uploading planes");
+        }
+
+        let encoding_start = Instant::now();
+        let geometry = planes.iter().map(PlaneGeometry::of).collect::<Vec<_>>();
+        let light_powers = planes.iter().map(emitted_power).collect::<Vec<_>>();
+        encoding += encoding_start.elapsed();
+        // the first update builds them even without planes, the buffers start out with nothing in them
+        if geometry != self.geometry || previous_version.is_none() {
+            self.geometry = geometry;
+            encoding += self.update_bvh(device, queue, objects_bind_group_layout, planes);
+        }
+        if light_powers != self.light_powers || previous_version.is_none() {
+            self.light_powers = light_powers;
+            encoding += self.update_lights(device, queue, objects_bind_group_layout);
+        }
+
+        self.uploaded = encoded;
+        encoding
+    }
+
+    /// Rebuilds and uploads the bvh of `planes`, growing its buffers if they are too small,
+    /// returns how long building and encoding it took
+    fn update_bvh(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        planes: &[GpuPlane],
+    ) -> Duration {
+        let encoding_start = Instant::now();
+        let bvh = Bvh::build(planes);
+        let mut nodes = encase::StorageBuffer::new(vec![]);
+        nodes.write(&bvh.nodes).unwrap();
+        let nodes = nodes.into_inner();
+        let encoding = encoding_start.elapsed();
+        tracing::trace!(nodes = bvh.nodes.len(), "rebuilt bvh");
+
+        let plane_indices = bytemuck::cast_slice::<u32, u8>(&bvh.plane_indices);
+        if nodes.len() as wgpu::BufferAddress > self.bvh_buffers.nodes.size()
+            || plane_indices.len() as wgpu::BufferAddress > self.bvh_buffers.plane_indices.size()
+        {
+            self.bvh_buffers = BvhBuffers::new(device, bvh.nodes.len(), bvh.plane_indices.len());
+            self.bind_objects(device, objects_bind_group_layout);
+        }
+        queue.write_buffer(&self.bvh_buffers.nodes, 0, &nodes);
+        if !plane_indices.is_empty() {
+            queue.write_buffer(&self.bvh_buffers.plane_indices, 0, plane_indices);
+        }
+        encoding
+    }
+
+    /// Rebuilds and uploads the lights from `light_powers`, growing their buffer if it is too small,
+    /// returns how long building and encoding them took
+    fn update_lights(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Duration {
+        let encoding_start = Instant::now();
+        let lights = build_lights(&self.light_powers);
+        let mut encoded = encase::StorageBuffer::new(vec![]);
+        encoded.write(&lights).unwrap();
+        let encoded = encoded.into_inner();
+        let encoding = encoding_start.elapsed();
+        tracing::trace!(lights = lights.len(), "rebuilt lights");
+
+        if encoded.len() as wgpu::BufferAddress > self.lights_buffer.size() {
+            self.lights_buffer = Self::lights_buffer(device, lights.len());
+            self.bind_objects(device, objects_bind_group_layout);
+        }
+        if !lights.is_empty() {
+            queue.write_buffer(&self.lights_buffer, 0, &encoded);
+        }
+        self.light_count = lights.len() as u32;
+        encoding
+    }
+
+    /// How many emissive planes the shader can sample directly, as of the last update
+    pub fn light_count(&self) -> u32 {
+        self.light_count
+    }
+
+    pub fn objects_bind_group(&self) -> &wgpu::BindGroup {
+        &self.objects_bind_group
+    }
+
+    fn planes_buffer(device: &wgpu::Device, size: wgpu::BufferAddress) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Planes Buffer"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Never empty, so it can always be bound
+    fn baked_lighting_buffer(device: &wgpu::Device, cell_count: u32) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Baked Lighting Buffer"),
+            size: cell_count.max(1) as wgpu::BufferAddress * BAKED_CELL_SIZE,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Never empty, so it can always be bound
+    fn probes_buffer(device: &wgpu::Device, probe_count: u32) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Probes Buffer"),
+            size: probe_count.max(1) as wgpu::BufferAddress * PROBE_SIZE,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Never empty, so it can always be bound
+    fn probe_grids_buffer(device: &wgpu::Device, grid_count: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Probe Grids Buffer"),
+            size: grid_count.max(1) as wgpu::BufferAddress * GpuProbeGrid::SHADER_SIZE.get(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Never empty, so it can always be bound
+    fn lights_buffer(device: &wgpu::Device, light_count: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lights Buffer"),
+            size: light_count.max(1) as wgpu::BufferAddress * GpuLight::SHADER_SIZE.get(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Recreates the objects bind group, after any of its buffers were recreated
+    fn bind_objects(
+        &mut self,
+        device: &wgpu::Device,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        self.objects_bind_group = Self::create_objects_bind_group(
+            device,
+            objects_bind_group_layout,
+            [
+                &self.planes_buffer,
+                &self.baked_lighting_buffer,
+                &self.probes_buffer,
+                &self.probe_grids_buffer,
+                &self.bvh_buffers.nodes,
+                &self.bvh_buffers.plane_indices,
+                &self.textures.infos,
+                &self.textures.texels,
+                &self.lights_buffer,
+            ],
+        );
+    }
+
+    /// `buffers` are in the order of their bindings
+    fn create_objects_bind_group(
+        device: &wgpu::Device,
+        objects_bind_group_layout: &wgpu::BindGroupLayout,
+        buffers: [&wgpu::Buffer; 9],
+    ) -> wgpu::BindGroup {
+        let entries = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>();
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Objects Bind Group"),
+            layout: objects_bind_group_layout,
+            entries: &entries,
+        })
+    }
+}
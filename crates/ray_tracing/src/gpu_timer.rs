@@ -0,0 +1,75 @@
+use crate::readback::AsyncReadback;
+use eframe::wgpu;
+use std::time::Duration;
+
+/// A timestamp is a u64 of ticks
+const TIMESTAMP_SIZE: wgpu::BufferAddress = 8;
+
+/// Measures how long the gpu spends in a compute pass with timestamp queries, read back without stalling,
+/// only on devices with [`wgpu::Features::TIMESTAMP_QUERY`]
+pub(crate) struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback: AsyncReadback,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+}
+
+impl GpuTimer {
+    /// `None` if the device can't write timestamps
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        Some(Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Ray Tracing Timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Ray Tracing Timestamps Resolve Buffer"),
+                size: TIMESTAMP_SIZE * 2,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback: AsyncReadback::new(
+                device,
+                "Ray Tracing Timestamps Readback Buffer",
+                TIMESTAMP_SIZE * 2,
+            ),
+            period: queue.get_timestamp_period(),
+        })
+    }
+
+    /// Whether the last measurement was read back, so another pass can be measured
+    pub fn is_idle(&self) -> bool {
+        self.readback.is_idle()
+    }
+
+    /// For the descriptor of the pass to measure, only while [`GpuTimer::is_idle`]
+    pub fn timestamp_writes(&self) -> wgpu::ComputePassTimestampWrites<'_> {
+        wgpu::ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        }
+    }
+
+    /// Records reading back the timestamps of the measured pass, after it
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+        self.readback.copy_buffer(encoder, &self.resolve_buffer);
+    }
+
+    /// How long the measured pass took once its timestamps have been read back,
+    /// has to be called before the next frame's commands are recorded
+    pub fn poll(&mut self, device: &wgpu::Device) -> Option<Duration> {
+        let data = self.readback.poll(device)?;
+        let [start, end] =
+            [&data[..8], &data[8..16]].map(|bytes| u64::from_ne_bytes(bytes.try_into().unwrap()));
+        Some(Duration::from_nanos(
+            (end.saturating_sub(start) as f64 * self.period as f64) as u64,
+        ))
+    }
+}
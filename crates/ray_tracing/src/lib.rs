@@ -1,10 +1,53 @@
+use bvh::GpuBvhNode;
 use eframe::wgpu;
 use encase::{ShaderSize, ShaderType};
+use gpu_scene::{BAKED_CELL_SIZE, PROBE_SIZE};
+use gpu_timer::GpuTimer;
+use lights::GpuLight;
 use math::{Transform, Vector3};
+use probes::GpuProbeGrid;
+use readback::AsyncReadback;
+use shader_error::{create_pipeline, create_shader_module};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use textures::{GpuTexture, Textures};
 
+mod accumulation;
+mod aovs;
+mod bake;
+mod bvh;
 mod color;
+mod focus_peaking;
+mod frame_graph;
+mod gpu_scene;
+mod gpu_timer;
+mod light_groups;
+mod lights;
+mod overlay;
+mod probes;
+mod readback;
+mod shader_error;
+mod textures;
+mod tone_mapping;
+mod view;
+mod workgroup;
 
+pub use accumulation::*;
+pub use aovs::*;
+pub use bake::*;
 pub use color::*;
+pub use focus_peaking::*;
+pub use light_groups::*;
+pub use overlay::*;
+pub use probes::*;
+pub use readback::TextureRectReadback;
+pub use shader_error::*;
+pub use textures::{TextureError, TextureId, TextureImage};
+pub use tone_mapping::*;
+pub use view::*;
+pub use workgroup::*;
 
 #[derive(Debug, Clone, Copy, ShaderType)]
 pub struct GpuCamera {
@@ -16,21 +59,133 @@ pub struct GpuCamera {
     pub sun_size: f32,
     pub recursive_portal_count: u32,
     pub max_bounces: u32,
+    /// Primary rays ignore anything closer than this
+    pub near_plane: f32,
+    /// How far rays are pushed past a portal when traversing it
+    pub portal_epsilon: f32,
+    /// The total number of portals a single sample can traverse across all bounces
+    pub max_portal_traversals: u32,
+    /// How much wider the sun appears to paths that refracted after a diffuse bounce, in radians
+    pub caustic_regularization: f32,
+    /// The vertical field of view in radians
+    pub fov: f32,
+    /// The radius of the lens, 0 is a pinhole camera
+    pub aperture: f32,
+    /// How far along the camera's forward direction things are in focus
+    pub focus_distance: f32,
+    /// Camera rays don't go through portals they see smaller than this many pixels, 0 goes through every portal
+    pub portal_cull_pixels: f32,
+    /// What is seen on portals rays stop at instead of going through, one of the `PORTAL_FILL_*` constants
+    pub portal_fill: u32,
+    /// What [`PORTAL_FILL_COLOR`] fills portals with
+    pub portal_fill_color: Color,
+    /// How far rays go before [`RENDER_TYPE_PORTAL_DISTANCE`] reaches the end of its color ramp
+    pub portal_distance_range: f32,
+    /// Which way is up in the scene, the sky fades from its down color to its up color along it
+    pub up: Vector3,
 }
 
 pub const RENDER_TYPE_UNLIT: u32 = 0;
 pub const RENDER_TYPE_LIT: u32 = 1;
+/// Colors pixels by how far the camera ray went to what it hit, counting the distance before every portal it went through
+pub const RENDER_TYPE_PORTAL_DISTANCE: u32 = 2;
+
+/// The side of a plane its y axis points out of
+pub const EMISSIVE_FRONT: u32 = 1;
+pub const EMISSIVE_BACK: u32 = 2;
+
+pub const BACKGROUND_SKY: u32 = 0;
+pub const BACKGROUND_BLACK: u32 = 1;
+pub const BACKGROUND_TRANSPARENT: u32 = 2;
+
+/// Portals past the recursion limit show the plane they are on
+pub const PORTAL_FILL_NONE: u32 = 0;
+/// Portals past the recursion limit show [`GpuCamera::portal_fill_color`]
+pub const PORTAL_FILL_COLOR: u32 = 1;
+/// Portals past the recursion limit show the average color of the sky
+pub const PORTAL_FILL_SKY: u32 = 2;
+
+/// Features the renderer makes use of if the device has them,
+/// devices should be requested with the ones their adapter supports, `adapter.features() & OPTIONAL_FEATURES`
+pub const OPTIONAL_FEATURES: wgpu::Features =
+    wgpu::Features::SUBGROUP.union(wgpu::Features::TIMESTAMP_QUERY);
 
 #[derive(Debug, Clone, Copy, ShaderType)]
 pub struct GpuSceneInfo {
     pub camera: GpuCamera,
     pub aspect: f32,
     pub accumulated_frames: u32,
+    pub accumulated_samples: u32,
     pub random_seed: u32,
     pub render_type: u32,
     pub samples_per_pixel: u32,
     pub antialiasing: u32,
     pub plane_count: u32,
+    /// How many emissive planes are sampled directly, 0 if they aren't
+    pub light_count: u32,
+    pub furnace_test: u32,
+    pub spectral: u32,
+    pub background: u32,
+    pub crop_x: u32,
+    pub crop_y: u32,
+    pub crop_width: u32,
+    pub crop_height: u32,
+    pub blend_factor: f32,
+    pub light_group_intensities: LightGroupIntensities,
+    pub bake_cell_count: u32,
+    pub bake_accumulated_frames: u32,
+    pub bake_samples_per_cell: u32,
+    pub probe_grid_count: u32,
+    pub probe_count: u32,
+    pub probe_accumulated_frames: u32,
+    pub probe_samples_per_probe: u32,
+}
+
+#[derive(Debug, Clone, Copy, ShaderType)]
+pub struct GpuDisplayInfo {
+    pub encode_srgb: u32,
+    pub false_color: u32,
+    pub zebra_stripes: u32,
+    pub teleport_effect: f32,
+    pub tone_mapper: u32,
+    pub split_position: f32,
+    pub split_tone_mapper: u32,
+}
+
+pub const HISTOGRAM_BIN_COUNT: usize = 64;
+/// The luminance of the first histogram bin in stops
+pub const HISTOGRAM_MIN_EV: f32 = -8.0;
+/// The luminance of the end of the last histogram bin in stops
+pub const HISTOGRAM_MAX_EV: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct InspectedPixel {
+    pub x: u32,
+    pub y: u32,
+    /// Linear and premultiplied by alpha, exactly as it is stored in the accumulated image
+    pub color: [f32; 4],
+}
+
+/// How long the cpu spent in [`RayTracingRenderer::prepare_frame`] since the timings were last taken
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrepareTimings {
+    /// How many frames were prepared
+    pub frames: u32,
+    pub total: Duration,
+    /// The part of `total` spent serializing the scene and frame info into gpu buffers
+    pub encoding: Duration,
+    /// How long the gpu took to trace the latest frame whose timestamps were read back since the timings were last taken,
+    /// this lags a few frames behind, always `None` without [`wgpu::Features::TIMESTAMP_QUERY`]
+    pub gpu_trace: Option<Duration>,
+}
+
+/// A rectangle of texture pixels, row 0 is the bottom of the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
 }
 
 /// An XZ plane transformed by `transform`
@@ -45,6 +200,29 @@ pub struct GpuPlane {
     pub checker_darkness: f32,
     pub emissive_color: Color,
     pub emissive_checker_darkness: f32,
+    /// Which sides emit, [`EMISSIVE_FRONT`] and [`EMISSIVE_BACK`] combined
+    pub emissive_sides: u32,
+    /// The emission falls off with the cosine of the angle to the normal to this power, normalised so the plane
+    /// emits the same light in total, 0 is lambertian and equally bright from every angle
+    pub emission_exponent: f32,
+    pub transmission: f32,
+    pub ior: f32,
+    pub dispersion: f32,
+    /// From 0 for dielectrics to 1 for metals, which reflect in their color and don't scatter diffusely
+    pub metallic: f32,
+    /// How blurry specular reflections are, from 0 for a mirror to 1
+    pub roughness: f32,
+    /// Scales the Fresnel reflection of dielectrics, 0 has no specular lobe
+    pub specular: f32,
+    /// See [`LightGroup::index`]
+    pub light_group: u32,
+    /// Camera rays that hit the plane see the background with only the shadows and light the rest of the scene casts onto it,
+    /// the shadows are in the alpha so they can be composited onto a photograph
+    pub shadow_catcher: u32,
+    /// Where the baked lighting of the plane starts, see [`BakeFrame`], u32::MAX if it isn't baked
+    pub baked_offset: u32,
+    /// See [`TextureId::index`], multiplies the color instead of the checker pattern, u32::MAX for the checker pattern
+    pub texture: u32,
     pub front_portal: GpuPortalConnection,
     pub back_portal: GpuPortalConnection,
 }
@@ -53,427 +231,1301 @@ pub struct GpuPlane {
 pub struct GpuPortalConnection {
     /// u32::MAX is no connection
     pub other_index: u32,
-    // pub flip: u32,
+    /// How many times bigger anything that goes through comes out of the other plane
+    pub scale: f32,
+    /// Whether rays are mirrored off of the plane before going through, so they come out of the other side of the other plane
+    pub flip: u32,
 }
 
 pub struct RayTracingRenderer {
-    ray_tracing_texture: wgpu::Texture,
-    ray_tracing_texture_write_bind_group_layout: wgpu::BindGroupLayout,
-    ray_tracing_texture_sample_bind_group_layout: wgpu::BindGroupLayout,
-    ray_tracing_texture_write_bind_group: wgpu::BindGroup,
-    ray_tracing_texture_sample_bind_group: wgpu::BindGroup,
+    accumulations: Accumulations,
+    aov_pass: AovPass,
+    /// Every render target, see [`TargetImage::target`]
+    targets: HashMap<u64, RenderTarget>,
+    /// The target the texture methods act on, the last one selected or prepared
+    current_target: u64,
 
+    paint_target: PaintTarget,
     full_screen_quad_pipeline: wgpu::RenderPipeline,
+    focus_peaking_pass: FocusPeakingPass,
+    overlays: Overlays,
+    surface_is_srgb: bool,
+    display_info_buffer: wgpu::Buffer,
+    display_info_bind_group: wgpu::BindGroup,
 
+    scene_info_stride: wgpu::BufferAddress,
     scene_info_buffer: wgpu::Buffer,
+    scene_info_bind_group_layout: wgpu::BindGroupLayout,
     scene_info_bind_group: wgpu::BindGroup,
 
-    planes_buffer: wgpu::Buffer,
     objects_bind_group_layout: wgpu::BindGroupLayout,
-    objects_bind_group: wgpu::BindGroup,
+    textures: Textures,
+
+    histogram_pipeline: wgpu::ComputePipeline,
+    histogram_buffer: wgpu::Buffer,
+    histogram_bind_group: wgpu::BindGroup,
+    histogram_readback: AsyncReadback,
+    histogram: Vec<u32>,
+
+    pixel_readback: AsyncReadback,
+    /// The pixel the pending readback is for
+    pixel_readback_position: (u32, u32),
+    /// The precision of the image the pending readback is from
+    pixel_readback_precision: AccumulationPrecision,
+    inspected_pixel: Option<InspectedPixel>,
 
-    ray_tracing_pipeline: wgpu::ComputePipeline,
+    /// Measures the ray tracing pass, `None` if the device can't write timestamps
+    trace_timer: Option<GpuTimer>,
+    prepare_timings: PrepareTimings,
 }
 
 impl RayTracingRenderer {
+    /// Displays into a render pass without a depth buffer or multisampling, see [`RayTracingRenderer::new_for_target`]
     pub fn new(
         device: &wgpu::Device,
-        _queue: &wgpu::Queue,
+        queue: &wgpu::Queue,
         surface_format: wgpu::TextureFormat,
-    ) -> Self {
-        let full_screen_quad_shader = device.create_shader_module(wgpu::include_wgsl!(concat!(
-            env!("OUT_DIR"),
-            "/shaders/full_screen_quad.wgsl"
-        )));
-
-        let ray_tracing_shader = device.create_shader_module(wgpu::include_wgsl!(concat!(
-            env!("OUT_DIR"),
-            "/shaders/ray_tracing.wgsl"
-        )));
-
-        let ray_tracing_texture = Self::ray_tracing_texture(device, 1, 1);
-        let ray_tracing_texture_write_bind_group_layout =
+    ) -> Result<Self, ShaderError> {
+        Self::new_for_target(device, queue, PaintTarget::new(surface_format))
+    }
+
+    /// Displays into render passes like `paint_target`, which has to match egui's when used as a paint callback
+    pub fn new_for_target(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        paint_target: PaintTarget,
+    ) -> Result<Self, ShaderError> {
+        let full_screen_quad_shader = create_shader_module(
+            device,
+            "full_screen_quad.wgsl",
+            wgpu::include_wgsl!(concat!(env!("OUT_DIR"), "/shaders/full_screen_quad.wgsl")),
+        )?;
+
+        let subgroups = device.features().contains(wgpu::Features::SUBGROUP);
+        tracing::debug!(subgroups, "creating histogram shader");
+        let histogram_shader = if subgroups {
+            create_shader_module(
+                device,
+                "histogram_subgroups.wgsl",
+                wgpu::include_wgsl!(concat!(
+                    env!("OUT_DIR"),
+                    "/shaders/histogram_subgroups.wgsl"
+                )),
+            )?
+        } else {
+            create_shader_module(
+                device,
+                "histogram.wgsl",
+                wgpu::include_wgsl!(concat!(env!("OUT_DIR"), "/shaders/histogram.wgsl")),
+            )?
+        };
+
+        let scene_info_stride = GpuSceneInfo::SHADER_SIZE.get().next_multiple_of(
+            device.limits().min_uniform_buffer_offset_alignment as wgpu::BufferAddress,
+        );
+        let scene_info_buffer = Self::scene_info_buffer(device, scene_info_stride);
+        let scene_info_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Ray Tracing Texture Write Bind Group Layout"),
+                label: Some("Scene Info Bind Group Layout"),
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
                     visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::ReadWrite,
-                        format: wgpu::TextureFormat::Rgba32Float,
-                        view_dimension: wgpu::TextureViewDimension::D2,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(GpuSceneInfo::SHADER_SIZE),
                     },
                     count: None,
                 }],
             });
-        let ray_tracing_texture_sample_bind_group_layout =
+        let scene_info_bind_group =
+            Self::scene_info_bind_group(device, &scene_info_bind_group_layout, &scene_info_buffer);
+
+        let objects_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Ray Tracing Texture Sample Bind Group Layout"),
+                label: Some("Objects Bind Group Layout"),
                 entries: &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            multisampled: false,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(GpuPlane::SHADER_SIZE),
                         },
                         count: None,
                     },
                     wgpu::BindGroupLayoutEntry {
                         binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(BAKED_CELL_SIZE),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(PROBE_SIZE),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(GpuProbeGrid::SHADER_SIZE),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(GpuBvhNode::SHADER_SIZE),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(size_of::<u32>() as _),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(GpuTexture::SHADER_SIZE),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 7,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(size_of::<u32>() as _),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 8,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(GpuLight::SHADER_SIZE),
+                        },
                         count: None,
                     },
                 ],
             });
-        let (ray_tracing_texture_write_bind_group, ray_tracing_texture_sample_bind_group) =
-            Self::ray_tracing_texture_bind_groups(
-                device,
-                &ray_tracing_texture_write_bind_group_layout,
-                &ray_tracing_texture_sample_bind_group_layout,
-                &ray_tracing_texture,
-            );
 
-        let full_screen_quad_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Full Screen Quad Pipeline Layout"),
-                bind_group_layouts: &[&ray_tracing_texture_sample_bind_group_layout],
-                push_constant_ranges: &[],
-            });
-        let full_screen_quad_pipeline =
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Full Screen Quad Pipeline"),
-                layout: Some(&full_screen_quad_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &full_screen_quad_shader,
-                    entry_point: Some("vertex"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    buffers: &[],
-                },
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleStrip,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Cw,
-                    cull_mode: None,
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: 1,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &full_screen_quad_shader,
-                    entry_point: Some("fragment"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: surface_format,
-                        blend: None,
-                        write_mask: wgpu::ColorWrites::all(),
-                    })],
-                }),
-                multiview: None,
-                cache: None,
-            });
+        let workgroup_size = WorkgroupSize::for_limits(&device.limits());
+        tracing::debug!(?workgroup_size, "picked ray tracing workgroup size");
+        let accumulations = Accumulations::new(
+            device,
+            &scene_info_bind_group_layout,
+            &objects_bind_group_layout,
+            workgroup_size,
+        )?;
+        let full_precision = &accumulations.full_precision;
+        let aov_pass = AovPass::new(
+            device,
+            full_precision,
+            &scene_info_bind_group_layout,
+            &objects_bind_group_layout,
+        )?;
+        let textures = Textures::new(device);
+        let default_target = RenderTarget::new(
+            device,
+            full_precision,
+            AccumulationPrecision::Full,
+            &objects_bind_group_layout,
+            textures.buffers(),
+        );
 
-        let scene_info_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Scene Info Buffer"),
-            size: GpuSceneInfo::SHADER_SIZE.get(),
+        let display_info_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Display Info Buffer"),
+            size: GpuDisplayInfo::SHADER_SIZE.get(),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
-        let scene_info_bind_group_layout =
+        let display_info_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Scene Info Bind Group Layout"),
+                label: Some("Display Info Bind Group Layout"),
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size: Some(GpuSceneInfo::SHADER_SIZE),
+                        min_binding_size: Some(GpuDisplayInfo::SHADER_SIZE),
                     },
                     count: None,
                 }],
             });
-        let scene_info_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Scene Info Bind Group"),
-            layout: &scene_info_bind_group_layout,
+        let display_info_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Info Bind Group"),
+            layout: &display_info_bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
-                resource: scene_info_buffer.as_entire_binding(),
+                resource: display_info_buffer.as_entire_binding(),
             }],
         });
 
-        let planes_buffer = Self::planes_buffer(device, GpuPlane::SHADER_SIZE.get());
-        let objects_bind_group_layout =
+        let full_screen_quad_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Full Screen Quad Pipeline Layout"),
+                bind_group_layouts: &[
+                    full_precision
+                        .frame_graph
+                        .bind_group_layout(full_precision.full_screen_quad_pass),
+                    &display_info_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        let full_screen_quad_pipeline =
+            create_pipeline(device, "Full Screen Quad Pipeline", || {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Full Screen Quad Pipeline"),
+                    layout: Some(&full_screen_quad_pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &full_screen_quad_shader,
+                        entry_point: Some("vertex"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleStrip,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Cw,
+                        cull_mode: None,
+                        unclipped_depth: false,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        conservative: false,
+                    },
+                    depth_stencil: paint_target.depth_stencil(),
+                    multisample: paint_target.multisample(),
+                    fragment: Some(wgpu::FragmentState {
+                        module: &full_screen_quad_shader,
+                        entry_point: Some("fragment"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(paint_target.color_target(None))],
+                    }),
+                    multiview: None,
+                    cache: None,
+                })
+            })?;
+
+        let focus_peaking_pass = FocusPeakingPass::new(device, paint_target, &aov_pass)?;
+
+        let histogram_size =
+            (HISTOGRAM_BIN_COUNT * std::mem::size_of::<u32>()) as wgpu::BufferAddress;
+        let histogram_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Buffer"),
+            size: histogram_size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let histogram_readback =
+            AsyncReadback::new(device, "Histogram Readback Buffer", histogram_size);
+        let histogram_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Objects Bind Group Layout"),
+                label: Some("Histogram Bind Group Layout"),
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
                     visibility: wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
                         has_dynamic_offset: false,
-                        min_binding_size: Some(GpuPlane::SHADER_SIZE),
+                        min_binding_size: None,
                     },
                     count: None,
                 }],
             });
-        let objects_bind_group =
-            Self::objects_bind_group(device, &objects_bind_group_layout, &planes_buffer);
+        let histogram_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Histogram Bind Group"),
+            layout: &histogram_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: histogram_buffer.as_entire_binding(),
+            }],
+        });
 
-        let ray_tracing_pipeline_layout =
+        let histogram_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Ray Tracing Pipeline Layout"),
+                label: Some("Histogram Pipeline Layout"),
                 bind_group_layouts: &[
-                    &ray_tracing_texture_write_bind_group_layout,
-                    &scene_info_bind_group_layout,
-                    &objects_bind_group_layout,
+                    full_precision
+                        .frame_graph
+                        .bind_group_layout(full_precision.histogram_pass),
+                    &histogram_bind_group_layout,
                 ],
                 push_constant_ranges: &[],
             });
-        let ray_tracing_pipeline =
+        let histogram_pipeline = create_pipeline(device, "Histogram Pipeline", || {
             device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                label: Some("Ray Tracing Pipeline"),
-                layout: Some(&ray_tracing_pipeline_layout),
-                module: &ray_tracing_shader,
-                entry_point: Some("ray_trace"),
+                label: Some("Histogram Pipeline"),
+                layout: Some(&histogram_pipeline_layout),
+                module: &histogram_shader,
+                entry_point: Some("histogram"),
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
                 cache: None,
-            });
+            })
+        })?;
 
-        Self {
-            ray_tracing_texture,
-            ray_tracing_texture_write_bind_group_layout,
-            ray_tracing_texture_sample_bind_group_layout,
-            ray_tracing_texture_write_bind_group,
-            ray_tracing_texture_sample_bind_group,
+        Ok(Self {
+            accumulations,
+            aov_pass,
+            targets: HashMap::from([(0, default_target)]),
+            current_target: 0,
 
+            paint_target,
             full_screen_quad_pipeline,
+            focus_peaking_pass,
+            overlays: Overlays::default(),
+            surface_is_srgb: paint_target.color_format.is_srgb(),
+            display_info_buffer,
+            display_info_bind_group,
 
+            scene_info_stride,
             scene_info_buffer,
+            scene_info_bind_group_layout,
             scene_info_bind_group,
 
-            planes_buffer,
             objects_bind_group_layout,
-            objects_bind_group,
+            textures,
 
-            ray_tracing_pipeline,
-        }
-    }
+            histogram_pipeline,
+            histogram_buffer,
+            histogram_bind_group,
+            histogram_readback,
+            histogram: vec![0; HISTOGRAM_BIN_COUNT],
 
-    fn planes_buffer(device: &wgpu::Device, size: wgpu::BufferAddress) -> wgpu::Buffer {
-        device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Planes Buffer"),
-            size,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+            pixel_readback: AsyncReadback::new(
+                device,
+                "Pixel Readback Buffer",
+                std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+            ),
+            pixel_readback_position: (0, 0),
+            pixel_readback_precision: AccumulationPrecision::Full,
+            inspected_pixel: None,
+            trace_timer: GpuTimer::new(device, queue),
+            prepare_timings: PrepareTimings::default(),
         })
     }
 
-    fn objects_bind_group(
+    /// Uploads `image` for planes to show instead of their checker pattern, see [`GpuPlane::texture`]
+    pub fn add_texture(
+        &mut self,
         device: &wgpu::Device,
-        objects_bind_group_layout: &wgpu::BindGroupLayout,
-        planes_buffer: &wgpu::Buffer,
-    ) -> wgpu::BindGroup {
-        device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Objects Bind Group"),
-            layout: objects_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: planes_buffer.as_entire_binding(),
-            }],
-        })
+        queue: &wgpu::Queue,
+        image: TextureImage,
+    ) -> Result<TextureId, TextureError> {
+        self.textures.add(device, queue, image)
     }
 
-    fn ray_tracing_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
-        device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Ray Tracing Texture"),
-            size: wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba32Float,
-            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        })
+    /// Planes still showing the texture only show their color, returns whether there was a texture with that id
+    pub fn remove_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        id: TextureId,
+    ) -> bool {
+        self.textures.remove(device, queue, id)
     }
 
-    fn ray_tracing_texture_bind_groups(
-        device: &wgpu::Device,
-        ray_tracing_texture_write_bind_group_layout: &wgpu::BindGroupLayout,
-        ray_tracing_texture_sample_bind_group_layout: &wgpu::BindGroupLayout,
-        ray_tracing_texture: &wgpu::Texture,
-    ) -> (wgpu::BindGroup, wgpu::BindGroup) {
-        let ray_tracing_texture_view = ray_tracing_texture.create_view(&Default::default());
-        let ray_tracing_texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Ray Tracing Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+    /// What the render passes the image is displayed into are like, overlays have to create their pipelines for it
+    pub fn paint_target(&self) -> PaintTarget {
+        self.paint_target
+    }
 
-        let ray_tracing_texture_write_bind_group =
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Ray Tracing Texture Write Bind Group"),
-                layout: ray_tracing_texture_write_bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&ray_tracing_texture_view),
-                }],
-            });
-        let ray_tracing_texture_sample_bind_group =
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Ray Tracing Texture Sample Bind Group"),
-                layout: ray_tracing_texture_sample_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&ray_tracing_texture_view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&ray_tracing_texture_sampler),
-                    },
-                ],
-            });
-        (
-            ray_tracing_texture_write_bind_group,
-            ray_tracing_texture_sample_bind_group,
-        )
+    /// Draws `overlay` over every view after the traced image, on top of the overlays added before it
+    pub fn add_overlay(&mut self, overlay: impl Overlay) -> OverlayId {
+        self.overlays.add(Box::new(overlay))
     }
-}
 
-pub struct RayTracingPaintCallback {
-    pub width: u32,
-    pub height: u32,
-    pub camera: GpuCamera,
-    pub accumulated_frames: u32,
-    pub random_seed: u32,
-    pub render_type: u32,
-    pub samples_per_pixel: u32,
-    pub antialiasing: bool,
-    pub planes: Vec<GpuPlane>,
-}
+    pub fn remove_overlay(&mut self, id: OverlayId) -> Option<Box<dyn Overlay>> {
+        self.overlays.remove(id)
+    }
 
-impl eframe::egui_wgpu::CallbackTrait for RayTracingPaintCallback {
-    fn prepare(
-        &self,
+    /// Uploads the frame's scene and records the ray tracing dispatches,
+    /// this is what the egui callback uses but can also be used without a window
+    pub fn prepare_frame(
+        &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        _screen_descriptor: &eframe::egui_wgpu::ScreenDescriptor,
-        _egui_encoder: &mut wgpu::CommandEncoder,
-        callback_resources: &mut eframe::egui_wgpu::CallbackResources,
-    ) -> Vec<wgpu::CommandBuffer> {
-        let renderer: &mut RayTracingRenderer = callback_resources.get_mut().unwrap();
+        frame: &RayTracingPaintCallback,
+    ) -> wgpu::CommandBuffer {
+        let _span = tracing::trace_span!(
+            "prepare_frame",
+            width = frame.image.width,
+            height = frame.image.height
+        )
+        .entered();
+        let start_time = Instant::now();
+        let mut encoding = Duration::ZERO;
 
-        {
-            let ray_tracing_texture_size = renderer.ray_tracing_texture.size();
-            if self.width > 0
-                && self.height > 0
-                && (ray_tracing_texture_size.width != self.width
-                    || ray_tracing_texture_size.height != self.height)
-            {
-                renderer.ray_tracing_texture =
-                    RayTracingRenderer::ray_tracing_texture(device, self.width, self.height);
-                (
-                    renderer.ray_tracing_texture_write_bind_group,
-                    renderer.ray_tracing_texture_sample_bind_group,
-                ) = RayTracingRenderer::ray_tracing_texture_bind_groups(
-                    device,
-                    &renderer.ray_tracing_texture_write_bind_group_layout,
-                    &renderer.ray_tracing_texture_sample_bind_group_layout,
-                    &renderer.ray_tracing_texture,
-                );
-            }
+        self.select_target(device, frame.image.target);
+        self.set_precision(
+            device,
+            frame.accumulation_precision,
+            frame.light_groups.is_some(),
+        );
+        if frame.image.width > 0 && frame.image.height > 0 {
+            self.resize_texture(device, frame.image.width, frame.image.height);
         }
+        self.set_aovs(device, frame.aovs || frame.focus_peaking.is_some());
 
         {
-            let scene_info = GpuSceneInfo {
-                camera: self.camera,
-                aspect: self.width as f32 / self.height as f32,
-                accumulated_frames: self.accumulated_frames,
-                random_seed: self.random_seed,
-                render_type: self.render_type,
-                samples_per_pixel: self.samples_per_pixel,
-                antialiasing: self.antialiasing as u32,
-                plane_count: self.planes.len() as _,
+            let display_info = GpuDisplayInfo {
+                encode_srgb: (!self.surface_is_srgb && !frame.display_linear) as u32,
+                false_color: frame.false_color as u32,
+                zebra_stripes: frame.zebra_stripes as u32,
+                teleport_effect: frame.teleport_effect.clamp(0.0, 1.0),
+                tone_mapper: frame.tone_mapper.shader_value(),
+                split_position: frame
+                    .split_tone_mapper
+                    .map_or(1.0, |(_, position)| position.clamp(0.0, 1.0)),
+                split_tone_mapper: frame
+                    .split_tone_mapper
+                    .map_or(frame.tone_mapper, |(tone_mapper, _)| tone_mapper)
+                    .shader_value(),
             };
 
-            let mut scene_info_buffer = queue
-                .write_buffer_with(&renderer.scene_info_buffer, 0, GpuSceneInfo::SHADER_SIZE)
+            let encoding_start = Instant::now();
+            let mut display_info_buffer = queue
+                .write_buffer_with(&self.display_info_buffer, 0, GpuDisplayInfo::SHADER_SIZE)
                 .unwrap();
-            encase::UniformBuffer::new(&mut *scene_info_buffer)
-                .write(&scene_info)
+            encase::UniformBuffer::new(&mut *display_info_buffer)
+                .write(&display_info)
                 .unwrap();
+            if let Some(focus_peaking) = frame.focus_peaking {
+                self.focus_peaking_pass.prepare(
+                    queue,
+                    focus_peaking,
+                    &frame.camera,
+                    frame.image.width as f32 / frame.image.height as f32,
+                    !self.surface_is_srgb && !frame.display_linear,
+                );
+            }
+            encoding += encoding_start.elapsed();
         }
 
-        {
-            let mut should_recreate_objects_bind_group = false;
-
-            {
-                let size = self.planes.size();
+        let target = self.targets.get_mut(&self.current_target).unwrap();
+        target.scene.update_textures(
+            device,
+            &self.objects_bind_group_layout,
+            self.textures.buffers(),
+        );
+        encoding += target.scene.update(
+            device,
+            queue,
+            &self.objects_bind_group_layout,
+            &frame.planes,
+            frame.image.planes_version,
+            frame.dirty_planes.as_deref(),
+        );
+        if let Some(bake) = frame.bake {
+            target.scene.reserve_baked_cells(
+                device,
+                &self.objects_bind_group_layout,
+                bake.cell_count,
+            );
+        }
+        if let Some(probes) = &frame.probes {
+            encoding +=
+                target
+                    .scene
+                    .update_probes(device, queue, &self.objects_bind_group_layout, probes);
+        }
+        // the furnace test has no emission to sample
+        let light_count = if frame.sample_lights && !frame.furnace_test {
+            target.scene.light_count()
+        } else {
+            0
+        };
 
-                if size.get() > renderer.planes_buffer.size() {
-                    renderer.planes_buffer = RayTracingRenderer::planes_buffer(device, size.get());
-                    should_recreate_objects_bind_group = true;
-                }
+        let pass_count = frame.sample_passes().count() as wgpu::BufferAddress;
 
-                let mut planes_buffer = queue
-                    .write_buffer_with(&renderer.planes_buffer, 0, size)
-                    .unwrap();
-                encase::StorageBuffer::new(&mut *planes_buffer)
-                    .write(&self.planes)
-                    .unwrap();
+        let crop = {
+            let (width, height) = self.texture_size();
+            let crop = frame.crop.unwrap_or(CropRect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            });
+            let x = crop.x.min(width);
+            let y = crop.y.min(height);
+            CropRect {
+                x,
+                y,
+                width: crop.width.min(width - x),
+                height: crop.height.min(height - y),
             }
+        };
 
-            if should_recreate_objects_bind_group {
-                renderer.objects_bind_group = RayTracingRenderer::objects_bind_group(
+        {
+            let size = (pass_count * self.scene_info_stride).max(self.scene_info_stride);
+            if size > self.scene_info_buffer.size() {
+                self.scene_info_buffer = Self::scene_info_buffer(device, size);
+                self.scene_info_bind_group = Self::scene_info_bind_group(
                     device,
-                    &renderer.objects_bind_group_layout,
-                    &renderer.planes_buffer,
+                    &self.scene_info_bind_group_layout,
+                    &self.scene_info_buffer,
                 );
             }
+
+            let mut accumulated_samples = frame.image.accumulated_samples;
+            for (pass, samples_per_pixel) in frame.sample_passes().enumerate() {
+                let scene_info = GpuSceneInfo {
+                    camera: frame.camera,
+                    aspect: frame.image.width as f32 / frame.image.height as f32,
+                    accumulated_frames: frame.image.accumulated_frames + pass as u32,
+                    accumulated_samples: accumulated_samples.min(u32::MAX as u64) as u32,
+                    random_seed: frame
+                        .random_seed
+                        .wrapping_add((pass as u32).wrapping_mul(0x9E3779B9)),
+                    render_type: frame.render_type,
+                    samples_per_pixel,
+                    antialiasing: frame.antialiasing as u32,
+                    plane_count: frame.planes.len() as _,
+                    light_count,
+                    furnace_test: frame.furnace_test as u32,
+                    spectral: frame.spectral as u32,
+                    background: frame.background,
+                    crop_x: crop.x,
+                    crop_y: crop.y,
+                    crop_width: crop.width,
+                    crop_height: crop.height,
+                    blend_factor: frame.blend_factor.clamp(0.0, 1.0),
+                    light_group_intensities: frame.light_groups.unwrap_or_default(),
+                    bake_cell_count: frame.bake.map_or(0, |bake| bake.cell_count),
+                    bake_accumulated_frames: frame.bake.map_or(0, |bake| bake.accumulated_frames),
+                    bake_samples_per_cell: frame.bake.map_or(0, |bake| bake.samples_per_cell),
+                    probe_grid_count: frame
+                        .probes
+                        .as_ref()
+                        .map_or(0, |probes| probes.grids.len() as u32),
+                    probe_count: frame.probes.as_ref().map_or(0, ProbeFrame::probe_count),
+                    probe_accumulated_frames: frame
+                        .probes
+                        .as_ref()
+                        .map_or(0, |probes| probes.accumulated_frames),
+                    probe_samples_per_probe: frame
+                        .probes
+                        .as_ref()
+                        .map_or(0, |probes| probes.samples_per_probe),
+                };
+                accumulated_samples += samples_per_pixel as u64;
+
+                let encoding_start = Instant::now();
+                let mut scene_info_buffer = queue
+                    .write_buffer_with(
+                        &self.scene_info_buffer,
+                        pass as wgpu::BufferAddress * self.scene_info_stride,
+                        GpuSceneInfo::SHADER_SIZE,
+                    )
+                    .unwrap();
+                encase::UniformBuffer::new(&mut *scene_info_buffer)
+                    .write(&scene_info)
+                    .unwrap();
+                encoding += encoding_start.elapsed();
+            }
         }
 
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Ray Tracing Encoder"),
         });
 
+        if let Some(timer) = &mut self.trace_timer
+            && let Some(gpu_trace) = timer.poll(device)
+        {
+            self.prepare_timings.gpu_trace = Some(gpu_trace);
+        }
+        // only one measurement is read back at a time, frames in between aren't measured
+        let timed = self
+            .trace_timer
+            .as_ref()
+            .filter(|timer| timer.is_idle() && pass_count > 0);
         {
             let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some("Ray Tracing Compute Pass"),
-                timestamp_writes: None,
+                timestamp_writes: timed.map(GpuTimer::timestamp_writes),
             });
 
-            let ray_tracing_texture_size = renderer.ray_tracing_texture.size();
+            let target = self.target();
+            let accumulation = self.accumulations.for_target(target);
+            compute_pass.set_bind_group(
+                0,
+                target.resources.bind_group(accumulation.ray_tracing_pass),
+                &[],
+            );
+            compute_pass.set_bind_group(2, target.scene.objects_bind_group(), &[]);
+
+            // baked and updated before tracing so this frame already uses the new lighting
+            if let Some(bake) = frame.bake
+                && bake.samples_per_cell > 0
+                && bake.cell_count > 0
+                && pass_count > 0
+            {
+                let (x, y) = bake.dispatch_size(accumulation.workgroup_size);
+                tracing::trace!(x, y, cells = bake.cell_count, "dispatching light bake");
+                compute_pass.set_pipeline(&accumulation.bake_lighting_pipeline);
+                compute_pass.set_bind_group(1, &self.scene_info_bind_group, &[0]);
+                compute_pass.dispatch_workgroups(x, y, 1);
+            }
+            if let Some(probes) = &frame.probes
+                && probes.samples_per_probe > 0
+                && probes.probe_count() > 0
+                && pass_count > 0
+            {
+                let (x, y) = probes.dispatch_size(accumulation.workgroup_size);
+                tracing::trace!(
+                    x,
+                    y,
+                    probes = probes.probe_count(),
+                    "dispatching probe update"
+                );
+                compute_pass.set_pipeline(&accumulation.update_probes_pipeline);
+                compute_pass.set_bind_group(1, &self.scene_info_bind_group, &[0]);
+                compute_pass.dispatch_workgroups(x, y, 1);
+            }
+
+            let (x, y) = accumulation
+                .workgroup_size
+                .dispatch_size(crop.width, crop.height);
+            tracing::trace!(x, y, passes = pass_count, "dispatching ray tracing");
+            compute_pass.set_pipeline(&accumulation.ray_tracing_pipeline);
+            for pass in 0..pass_count {
+                compute_pass.set_bind_group(
+                    1,
+                    &self.scene_info_bind_group,
+                    &[(pass * self.scene_info_stride) as wgpu::DynamicOffset],
+                );
+                compute_pass.dispatch_workgroups(x, y, 1);
+            }
+
+            // the traced pixels are mixed already, this is for the rest of the image when the intensities change
+            if let Some(mix_light_groups_pipeline) = &accumulation.mix_light_groups_pipeline
+                && pass_count > 0
+            {
+                let (width, height) = target.resources.size();
+                let (x, y) = accumulation.workgroup_size.dispatch_size(width, height);
+                compute_pass.set_pipeline(mix_light_groups_pipeline);
+                compute_pass.set_bind_group(1, &self.scene_info_bind_group, &[0]);
+                compute_pass.dispatch_workgroups(x, y, 1);
+            }
+
+            if let Some(aovs) = &target.aovs
+                && pass_count > 0
+            {
+                let (x, y) = self
+                    .aov_pass
+                    .workgroup_size
+                    .dispatch_size(crop.width, crop.height);
+                compute_pass.set_pipeline(&self.aov_pass.pipeline);
+                compute_pass.set_bind_group(0, &self.aov_pass.empty_bind_group, &[]);
+                compute_pass.set_bind_group(1, &self.scene_info_bind_group, &[0]);
+                compute_pass.set_bind_group(3, aovs.bind_group(self.aov_pass.pass), &[]);
+                compute_pass.dispatch_workgroups(x, y, 1);
+            }
+        }
+        if timed.is_some()
+            && let Some(timer) = &mut self.trace_timer
+        {
+            timer.resolve(&mut encoder);
+        }
+
+        if frame.histogram {
+            if let Some(data) = self.histogram_readback.poll(device) {
+                self.histogram = data
+                    .chunks_exact(4)
+                    .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+                    .collect();
+            }
+
+            encoder.clear_buffer(&self.histogram_buffer, 0, None);
+            {
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Histogram Compute Pass"),
+                    timestamp_writes: None,
+                });
+
+                let (width, height) = self.texture_size();
 
-            compute_pass.set_pipeline(&renderer.ray_tracing_pipeline);
-            compute_pass.set_bind_group(0, &renderer.ray_tracing_texture_write_bind_group, &[]);
-            compute_pass.set_bind_group(1, &renderer.scene_info_bind_group, &[]);
-            compute_pass.set_bind_group(2, &renderer.objects_bind_group, &[]);
-            compute_pass.dispatch_workgroups(
-                ray_tracing_texture_size.width.div_ceil(16),
-                ray_tracing_texture_size.height.div_ceil(16),
-                1,
+                compute_pass.set_pipeline(&self.histogram_pipeline);
+                let target = self.target();
+                let accumulation = self.accumulations.for_target(target);
+                compute_pass.set_bind_group(
+                    0,
+                    target.resources.bind_group(accumulation.histogram_pass),
+                    &[],
+                );
+                compute_pass.set_bind_group(1, &self.histogram_bind_group, &[]);
+                compute_pass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
+            }
+
+            if self.histogram_readback.is_idle() {
+                self.histogram_readback
+                    .copy_buffer(&mut encoder, &self.histogram_buffer);
+            }
+        }
+
+        if let Some((x, y)) = frame.inspect_pixel {
+            if let Some(data) = self.pixel_readback.poll(device) {
+                let (x, y) = self.pixel_readback_position;
+                self.inspected_pixel = Some(InspectedPixel {
+                    x,
+                    y,
+                    color: self.pixel_readback_precision.decode(&data)[0],
+                });
+            }
+
+            let (width, height) = self.texture_size();
+            if self.pixel_readback.is_idle() && x < width && y < height {
+                let target = &self.targets[&self.current_target];
+                let texture = target
+                    .resources
+                    .texture(self.accumulations.for_target(target).ray_tracing_texture);
+                self.pixel_readback.copy_texel(&mut encoder, texture, x, y);
+                self.pixel_readback_position = (x, y);
+                self.pixel_readback_precision = target.precision;
+            }
+        }
+
+        self.prepare_timings.frames += 1;
+        self.prepare_timings.total += start_time.elapsed();
+        self.prepare_timings.encoding += encoding;
+
+        encoder.finish()
+    }
+
+    /// The timings of every frame prepared since this was last called, then starts them over
+    pub fn take_prepare_timings(&mut self) -> PrepareTimings {
+        std::mem::take(&mut self.prepare_timings)
+    }
+
+    /// The most recently read back pixel requested with [`RayTracingPaintCallback::inspect_pixel`],
+    /// this lags a few frames behind what is displayed
+    pub fn inspected_pixel(&self) -> Option<InspectedPixel> {
+        self.inspected_pixel
+    }
+
+    /// The most recently read back luminance histogram, see [`HISTOGRAM_MIN_EV`] and [`HISTOGRAM_MAX_EV`] for its range,
+    /// this lags a few frames behind what is displayed
+    pub fn histogram(&self) -> &[u32] {
+        &self.histogram
+    }
+
+    /// Makes the texture methods act on `target`, creating an empty image for it if it doesn't have one yet,
+    /// [`RayTracingRenderer::prepare_frame`] selects the frame's target itself
+    pub fn select_target(&mut self, device: &wgpu::Device, target: u64) {
+        if target != self.current_target {
+            // they were read back from the previous target's image
+            self.histogram.fill(0);
+            self.inspected_pixel = None;
+        }
+        self.current_target = target;
+        if !self.targets.contains_key(&target) {
+            tracing::debug!(target, "creating render target");
+            let render_target = RenderTarget::new(
+                device,
+                &self.accumulations.full_precision,
+                AccumulationPrecision::Full,
+                &self.objects_bind_group_layout,
+                self.textures.buffers(),
             );
+            self.targets.insert(target, render_target);
+        }
+    }
+
+    /// Recreates the current target's image at `precision` and with or without light groups
+    /// if it is different, which throws away what was accumulated
+    fn set_precision(
+        &mut self,
+        device: &wgpu::Device,
+        precision: AccumulationPrecision,
+        light_groups: bool,
+    ) {
+        let precision = if light_groups {
+            AccumulationPrecision::Full
+        } else {
+            precision
+        };
+        let target = self.targets.get_mut(&self.current_target).unwrap();
+        if target.precision == precision && target.light_groups == light_groups {
+            return;
+        }
+        tracing::debug!(
+            ?precision,
+            light_groups,
+            target = self.current_target,
+            "changing accumulation precision"
+        );
+        let accumulation = self.accumulations.get(precision, light_groups);
+        let (width, height) = target.resources.size();
+        target.resources = accumulation
+            .frame_graph
+            .create_resources(device, width, height);
+        target.precision = precision;
+        target.light_groups = light_groups;
+    }
+
+    /// Frees the accumulated image of `target`, rendering to it again starts from an empty image
+    pub fn remove_target(&mut self, device: &wgpu::Device, target: u64) {
+        self.targets.remove(&target);
+        if target == self.current_target {
+            self.select_target(device, target);
+        }
+    }
+
+    fn target(&self) -> &RenderTarget {
+        &self.targets[&self.current_target]
+    }
+
+    pub fn workgroup_size(&self) -> WorkgroupSize {
+        self.accumulations.workgroup_size()
+    }
+
+    /// Recreates the ray tracing pipelines with a different workgroup size,
+    /// [`RayTracingRenderer::new`] picks one with [`WorkgroupSize::for_limits`]
+    pub fn set_workgroup_size(
+        &mut self,
+        device: &wgpu::Device,
+        workgroup_size: WorkgroupSize,
+    ) -> Result<(), ShaderError> {
+        tracing::debug!(?workgroup_size, "changing ray tracing workgroup size");
+        self.accumulations
+            .set_workgroup_size(device, workgroup_size)?;
+        self.aov_pass.set_workgroup_size(device, workgroup_size)
+    }
+
+    pub fn texture_size(&self) -> (u32, u32) {
+        self.target().resources.size()
+    }
+
+    fn resize_texture(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.texture_size() == (width, height) {
+            return;
+        }
+        tracing::debug!(
+            width,
+            height,
+            target = self.current_target,
+            "resizing ray tracing texture"
+        );
+        let target = self.targets.get_mut(&self.current_target).unwrap();
+        let accumulation = self.accumulations.for_target(target);
+        target
+            .resources
+            .resize(&accumulation.frame_graph, device, width, height);
+        if let Some(aovs) = &mut target.aovs {
+            aovs.resize(&self.aov_pass.frame_graph, device, width, height);
         }
+    }
 
-        vec![encoder.finish()]
+    /// Creates or frees the current target's [`Aovs`] textures
+    fn set_aovs(&mut self, device: &wgpu::Device, enabled: bool) {
+        let target = self.targets.get_mut(&self.current_target).unwrap();
+        if target.aovs.is_some() == enabled {
+            return;
+        }
+        tracing::debug!(enabled, target = self.current_target, "changing aovs");
+        target.aovs = enabled.then(|| {
+            let (width, height) = target.resources.size();
+            self.aov_pass.create_resources(device, width, height)
+        });
+    }
+
+    /// Replaces the accumulated image, resizing it to `width` by `height`,
+    /// the inverse of [`RayTracingRenderer::read_texture`] so accumulation can be resumed later
+    pub fn write_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        pixels: &[[f32; 4]],
+    ) {
+        self.resize_texture(device, width, height);
+        self.write_texture_rect(
+            queue,
+            CropRect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            },
+            pixels,
+        );
+    }
+
+    /// Replaces the pixels inside `rect` of the accumulated image, which has to fit inside it,
+    /// with light groups all of the pixels go into [`LightGroup::Sky`]
+    pub fn write_texture_rect(&self, queue: &wgpu::Queue, rect: CropRect, pixels: &[[f32; 4]]) {
+        assert_eq!(pixels.len(), rect.width as usize * rect.height as usize);
+        let target = self.target();
+        let accumulation = self.accumulations.for_target(target);
+        let bytes_per_pixel = target.precision.bytes_per_pixel();
+        let write = |texture, data: &[u8]| {
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: target.resources.texture(texture),
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: rect.x,
+                        y: rect.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(rect.width * bytes_per_pixel),
+                    rows_per_image: Some(rect.height),
+                },
+                wgpu::Extent3d {
+                    width: rect.width,
+                    height: rect.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        };
+        let data = target.precision.encode(pixels);
+        write(accumulation.ray_tracing_texture, &data);
+        // the rounding error of what was there before doesn't belong to the new pixels
+        if let Some(compensation_texture) = accumulation.compensation_texture {
+            write(compensation_texture, &vec![0; data.len()]);
+        }
+        if let Some(light_group_textures) = accumulation.light_group_textures {
+            for group in LightGroup::ALL {
+                let texture = light_group_textures[group.index() as usize];
+                if group == LightGroup::Sky {
+                    write(texture, &data);
+                } else {
+                    write(texture, &vec![0; data.len()]);
+                }
+            }
+        }
+    }
+
+    /// Copies the accumulated image back to the cpu, blocking until the gpu is done,
+    /// the colors are linear and premultiplied by the alpha channel and the first row is the bottom of the image
+    pub fn read_texture(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<[f32; 4]> {
+        let (width, height) = self.texture_size();
+        self.read_texture_rect(
+            device,
+            queue,
+            CropRect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            },
+        )
+    }
+
+    /// Records a copy of the pixels inside `readback`'s rectangle of the accumulated image, which has to have the
+    /// current target's precision, the pixels are read back without stalling, see [`TextureRectReadback::poll`]
+    pub fn copy_texture_rect(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        readback: &mut TextureRectReadback,
+    ) {
+        let target = self.target();
+        let accumulation = self.accumulations.for_target(target);
+        readback.copy(
+            encoder,
+            target.resources.texture(accumulation.ray_tracing_texture),
+        );
+    }
+
+    /// The precision of the current target's accumulated image
+    pub fn accumulation_precision(&self) -> AccumulationPrecision {
+        self.target().precision
+    }
+
+    /// Copies the pixels inside `rect` of the accumulated image back to the cpu, blocking until the gpu is done
+    pub fn read_texture_rect(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rect: CropRect,
+    ) -> Vec<[f32; 4]> {
+        let target = self.target();
+        let accumulation = self.accumulations.for_target(target);
+        let data = Self::read_texels(
+            device,
+            queue,
+            target.resources.texture(accumulation.ray_tracing_texture),
+            rect,
+        );
+        target.precision.decode(&data)
+    }
+
+    /// Copies the current target's [`Aovs`] back to the cpu, blocking until the gpu is done,
+    /// `None` if the last frame of the target didn't have [`RayTracingPaintCallback::aovs`] set
+    pub fn read_aovs(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Aovs> {
+        let aovs = self.target().aovs.as_ref()?;
+        let (width, height) = aovs.size();
+        let rect = CropRect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        let data = self
+            .aov_pass
+            .textures
+            .map(|texture| Self::read_texels(device, queue, aovs.texture(texture), rect));
+        Some(AovPass::decode(width, height, data))
+    }
+
+    /// Copies the texels inside `rect` of `texture` back to the cpu without any row padding,
+    /// blocking until the gpu is done
+    fn read_texels(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        rect: CropRect,
+    ) -> Vec<u8> {
+        let size = wgpu::Extent3d {
+            width: rect.width,
+            height: rect.height,
+            depth_or_array_layers: 1,
+        };
+        let bytes_per_pixel = texture.format().block_copy_size(None).unwrap();
+        let bytes_per_row = size.width * bytes_per_pixel;
+        let padded_bytes_per_row =
+            bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ray Tracing Texture Readback Buffer"),
+            size: padded_bytes_per_row as wgpu::BufferAddress * size.height as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Ray Tracing Texture Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.x,
+                    y: rect.y,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        let submission_index = queue.submit([encoder.finish()]);
+
+        let slice = readback_buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device
+            .poll(wgpu::PollType::WaitForSubmissionIndex(submission_index))
+            .unwrap();
+
+        let data = slice.get_mapped_range();
+        let mut texels = Vec::with_capacity(bytes_per_row as usize * size.height as usize);
+        for row in data.chunks_exact(padded_bytes_per_row as usize) {
+            texels.extend_from_slice(&row[..bytes_per_row as usize]);
+        }
+        drop(data);
+        readback_buffer.unmap();
+
+        texels
+    }
+
+    fn scene_info_buffer(device: &wgpu::Device, size: wgpu::BufferAddress) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Scene Info Buffer"),
+            size,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn scene_info_bind_group(
+        device: &wgpu::Device,
+        scene_info_bind_group_layout: &wgpu::BindGroupLayout,
+        scene_info_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Scene Info Bind Group"),
+            layout: scene_info_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: scene_info_buffer,
+                    offset: 0,
+                    size: Some(GpuSceneInfo::SHADER_SIZE),
+                }),
+            }],
+        })
+    }
+}
+
+/// Which of the renderer's images a frame traces into, how big it is and how much it has accumulated so far,
+/// what a [`RayTracingView`] keeps track of from one frame to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetImage {
+    /// Which accumulated image to trace into and display, each target keeps its own image
+    /// so several views can accumulate independently, see [`RayTracingRenderer::remove_target`]
+    pub target: u64,
+    pub width: u32,
+    pub height: u32,
+    pub accumulated_frames: u32,
+    /// How many samples per pixel have already been accumulated
+    pub accumulated_samples: u64,
+    /// Should increase by 1 every frame, [`RayTracingPaintCallback::dirty_planes`] are relative to the previous version
+    pub planes_version: u64,
+}
+
+pub struct RayTracingPaintCallback {
+    pub image: TargetImage,
+    pub camera: GpuCamera,
+    /// The least the samples of this frame are weighted by when they are averaged into the accumulated image,
+    /// 0 weighs every sample equally, anything up to 1 fades out older samples so the image follows changes
+    /// without being reset, at the cost of never converging further than that
+    pub blend_factor: f32,
+    pub random_seed: u32,
+    pub render_type: u32,
+    /// What camera rays that escape the scene see, one of the `BACKGROUND_*` constants
+    pub background: u32,
+    /// The total samples per pixel to trace this frame
+    pub samples_per_pixel: u32,
+    /// The samples are split across multiple dispatches of at most this many samples each
+    pub max_samples_per_dispatch: u32,
+    pub antialiasing: bool,
+    /// Displays the raw linear colors without encoding them as sRGB, for debugging
+    pub display_linear: bool,
+    /// Displays bands of exposure instead of the image
+    pub false_color: bool,
+    /// Draws stripes over pixels that are clipped on display
+    pub zebra_stripes: bool,
+    /// How strongly the screen space effect shown after teleporting through a portal is drawn, from 0 to 1,
+    /// it is only applied on display so it doesn't affect what is accumulated
+    pub teleport_effect: f32,
+    pub tone_mapper: ToneMapper,
+    /// Tone maps the pixels right of this fraction of the width with another tone mapper, to compare them
+    pub split_tone_mapper: Option<(ToneMapper, f32)>,
+    /// Computes the luminance histogram of the accumulated image, see [`RayTracingRenderer::histogram`]
+    pub histogram: bool,
+    /// Reads back the pixel at these texture coordinates, row 0 is the bottom of the image,
+    /// see [`RayTracingRenderer::inspected_pixel`]
+    pub inspect_pixel: Option<(u32, u32)>,
+    /// Only traces the pixels inside this rectangle, every other pixel keeps what was accumulated before,
+    /// the image's `accumulated_frames` and `accumulated_samples` are then only for the pixels inside it
+    pub crop: Option<CropRect>,
+    /// Replaces the sky with a uniform white environment and every surface with a white diffuse one,
+    /// a correct path tracer converges to exactly 1 everywhere as long as paths can escape
+    pub furnace_test: bool,
+    /// Samples the emissive planes directly at every diffuse bounce instead of only finding them by chance,
+    /// weighted with multiple importance sampling so the image converges to the same result faster
+    pub sample_lights: bool,
+    /// Hero wavelength spectral rendering, needed for dispersion through refractive planes
+    pub spectral: bool,
+    /// What the target's image is accumulated in, changing it starts the image over
+    pub accumulation_precision: AccumulationPrecision,
+    /// Accumulates every [`LightGroup`] on its own and displays them added up with these intensities,
+    /// changing the intensities doesn't need the image to start over but turning light groups on or off does,
+    /// light groups are always accumulated at full precision
+    pub light_groups: Option<LightGroupIntensities>,
+    /// Traces the [`Aovs`] of the crop rectangle every frame, see [`RayTracingRenderer::read_aovs`]
+    pub aovs: bool,
+    /// Highlights what is in focus with the depth of the aovs, which are traced for it even if `aovs` isn't set
+    pub focus_peaking: Option<FocusPeaking>,
+    /// Bakes lighting into the checker cells of the planes before tracing, see [`BakeFrame`]
+    pub bake: Option<BakeFrame>,
+    /// Lights diffuse bounces past the first one from the camera with grids of probes, see [`ProbeFrame`],
+    /// the probes don't keep track of where their light came from so with light groups it is all in the sky's group
+    pub probes: Option<ProbeFrame>,
+    pub planes: Vec<GpuPlane>,
+    /// The indices of the planes that changed since the previous version, `None` if that isn't known,
+    /// only these planes get uploaded if the target rendered the previous version,
+    /// otherwise every plane is compared against what was uploaded before
+    pub dirty_planes: Option<Vec<usize>>,
+}
+
+impl RayTracingPaintCallback {
+    fn overlay_frame(&self) -> OverlayFrame {
+        OverlayFrame {
+            target: self.image.target,
+            width: self.image.width,
+            height: self.image.height,
+            camera: self.camera,
+        }
+    }
+
+    fn sample_passes(&self) -> impl Iterator<Item = u32> {
+        let max_samples_per_dispatch = self.max_samples_per_dispatch.max(1);
+        let pass_count = self.samples_per_pixel.div_ceil(max_samples_per_dispatch);
+        (0..pass_count).map(move |pass| {
+            (self.samples_per_pixel - pass * max_samples_per_dispatch).min(max_samples_per_dispatch)
+        })
+    }
+}
+
+impl eframe::egui_wgpu::CallbackTrait for RayTracingPaintCallback {
+    fn prepare(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _screen_descriptor: &eframe::egui_wgpu::ScreenDescriptor,
+        _egui_encoder: &mut wgpu::CommandEncoder,
+        callback_resources: &mut eframe::egui_wgpu::CallbackResources,
+    ) -> Vec<wgpu::CommandBuffer> {
+        // there is nothing to trace into, and the aspect ratio would be nan
+        if self.image.width == 0 || self.image.height == 0 {
+            tracing::trace!(
+                width = self.image.width,
+                height = self.image.height,
+                "skipping degenerate frame"
+            );
+            return vec![];
+        }
+        let renderer: &mut RayTracingRenderer = callback_resources.get_mut().unwrap();
+        let command_buffer = renderer.prepare_frame(device, queue, self);
+        renderer
+            .overlays
+            .prepare(device, queue, &self.overlay_frame());
+        vec![command_buffer]
     }
 
     fn paint(
@@ -483,9 +1535,33 @@ impl eframe::egui_wgpu::CallbackTrait for RayTracingPaintCallback {
         callback_resources: &eframe::egui_wgpu::CallbackResources,
     ) {
         let renderer: &RayTracingRenderer = callback_resources.get().unwrap();
+        if self.image.width == 0 || self.image.height == 0 {
+            return;
+        }
+        let Some(target) = renderer.targets.get(&self.image.target) else {
+            return;
+        };
 
         render_pass.set_pipeline(&renderer.full_screen_quad_pipeline);
-        render_pass.set_bind_group(0, &renderer.ray_tracing_texture_sample_bind_group, &[]);
+        let accumulation = renderer.accumulations.for_target(target);
+        render_pass.set_bind_group(
+            0,
+            target
+                .resources
+                .bind_group(accumulation.full_screen_quad_pass),
+            &[],
+        );
+        render_pass.set_bind_group(1, &renderer.display_info_bind_group, &[]);
         render_pass.draw(0..4, 0..1);
+
+        if self.focus_peaking.is_some()
+            && let Some(aovs) = &target.aovs
+        {
+            renderer
+                .focus_peaking_pass
+                .paint(render_pass, &renderer.aov_pass, aovs);
+        }
+
+        renderer.overlays.paint(render_pass, &self.overlay_frame());
     }
 }
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{Index, IndexMut};
+
+pub const LIGHT_GROUP_COUNT: usize = 4;
+
+/// Where the light reaching the camera came from, accumulating with [`crate::RayTracingPaintCallback::light_groups`]
+/// gives every group its own image so how bright they are relative to each other can be changed without tracing again
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LightGroup {
+    Sky,
+    Sun,
+    /// Emissive planes are in this group unless they are assigned to another one
+    #[default]
+    EmissionA,
+    EmissionB,
+}
+
+impl LightGroup {
+    pub const ALL: [Self; LIGHT_GROUP_COUNT] =
+        [Self::Sky, Self::Sun, Self::EmissionA, Self::EmissionB];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LightGroup::Sky => "Sky",
+            LightGroup::Sun => "Sun",
+            LightGroup::EmissionA => "Emission A",
+            LightGroup::EmissionB => "Emission B",
+        }
+    }
+
+    /// Which image of the light groups this group is accumulated in
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+}
+
+/// How bright every light group is when they are mixed into the displayed image
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LightGroupIntensities(pub [f32; LIGHT_GROUP_COUNT]);
+
+impl Default for LightGroupIntensities {
+    fn default() -> Self {
+        Self([1.0; LIGHT_GROUP_COUNT])
+    }
+}
+
+impl Index<LightGroup> for LightGroupIntensities {
+    type Output = f32;
+
+    fn index(&self, group: LightGroup) -> &Self::Output {
+        &self.0[group.index() as usize]
+    }
+}
+
+impl IndexMut<LightGroup> for LightGroupIntensities {
+    fn index_mut(&mut self, group: LightGroup) -> &mut Self::Output {
+        &mut self.0[group.index() as usize]
+    }
+}
+
+impl AsRef<[f32; LIGHT_GROUP_COUNT]> for LightGroupIntensities {
+    fn as_ref(&self) -> &[f32; LIGHT_GROUP_COUNT] {
+        &self.0
+    }
+}
+
+impl AsMut<[f32; LIGHT_GROUP_COUNT]> for LightGroupIntensities {
+    fn as_mut(&mut self) -> &mut [f32; LIGHT_GROUP_COUNT] {
+        &mut self.0
+    }
+}
+
+impl From<[f32; LIGHT_GROUP_COUNT]> for LightGroupIntensities {
+    fn from(intensities: [f32; LIGHT_GROUP_COUNT]) -> Self {
+        Self(intensities)
+    }
+}
+
+impl From<LightGroupIntensities> for [f32; LIGHT_GROUP_COUNT] {
+    fn from(LightGroupIntensities(intensities): LightGroupIntensities) -> Self {
+        intensities
+    }
+}
+
+encase::impl_vector!(4, LightGroupIntensities, f32; using AsRef AsMut From);
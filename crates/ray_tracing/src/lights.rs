@@ -0,0 +1,65 @@
+use crate::{EMISSIVE_BACK, EMISSIVE_FRONT, GpuPlane};
+use encase::ShaderType;
+
+/// An emissive plane the shader samples directly, picked in proportion to how much light it emits
+#[derive(Debug, Clone, Copy, ShaderType)]
+pub(crate) struct GpuLight {
+    pub plane_index: u32,
+    /// The chance of picking this light
+    pub probability: f32,
+    /// The chance of picking this light or any before it, the last light's is 1
+    pub cumulative: f32,
+}
+
+/// The emissive planes in the order of the planes, so the shader can find a plane's light by its index,
+/// `powers` are the [`emitted_power`] of every plane
+pub(crate) fn build_lights(powers: &[f32]) -> Vec<GpuLight> {
+    let powers = powers
+        .iter()
+        .enumerate()
+        .filter(|&(_, &power)| power > 0.0)
+        .map(|(index, &power)| (index as u32, power))
+        .collect::<Vec<_>>();
+    let total_power = powers.iter().map(|&(_, power)| power).sum::<f32>();
+
+    let mut cumulative = 0.0;
+    let mut lights = powers
+        .into_iter()
+        .map(|(plane_index, power)| {
+            let probability = power / total_power;
+            cumulative += probability;
+            GpuLight {
+                plane_index,
+                probability,
+                cumulative,
+            }
+        })
+        .collect::<Vec<_>>();
+    // so rounding never leaves a random number past the last light
+    if let Some(last) = lights.last_mut() {
+        last.cumulative = 1.0;
+    }
+    lights
+}
+
+/// Roughly how much light the plane emits, only from its emissive sides that aren't connected to portals
+pub(crate) fn emitted_power(plane: &GpuPlane) -> f32 {
+    let [r, g, b] = plane.emissive_color.into();
+    let luminance = r * 0.2126 + g * 0.7152 + b * 0.0722;
+    // half of the checker cells are darkened
+    let checker_average = (1.0 + plane.emissive_checker_darkness) * 0.5;
+    let emitting_sides = [
+        (EMISSIVE_FRONT, plane.front_portal),
+        (EMISSIVE_BACK, plane.back_portal),
+    ]
+    .iter()
+    .filter(|(side, portal)| plane.emissive_sides & side != 0 && portal.other_index == u32::MAX)
+    .count();
+    let power =
+        luminance * checker_average * (plane.width * plane.height).abs() * emitting_sides as f32;
+    if power.is_finite() {
+        power.max(0.0)
+    } else {
+        0.0
+    }
+}
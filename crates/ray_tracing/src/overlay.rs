@@ -0,0 +1,112 @@
+use crate::GpuCamera;
+use eframe::wgpu;
+
+/// The formats and sample count of the render pass egui paints into,
+/// every pipeline drawn from a paint callback has to be created to match it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaintTarget {
+    pub color_format: wgpu::TextureFormat,
+    /// The depth buffer egui was configured with, if any
+    pub depth_format: Option<wgpu::TextureFormat>,
+    /// More than 1 if egui was configured with multisampling, it resolves the samples itself
+    pub sample_count: u32,
+}
+
+impl PaintTarget {
+    /// Without a depth buffer or multisampling, which is what eframe uses unless configured otherwise
+    pub fn new(color_format: wgpu::TextureFormat) -> Self {
+        Self {
+            color_format,
+            depth_format: None,
+            sample_count: 1,
+        }
+    }
+
+    pub fn multisample(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: self.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        }
+    }
+
+    /// Leaves egui's depth buffer as it is, overlays are drawn in the order they were added
+    pub fn depth_stencil(&self) -> Option<wgpu::DepthStencilState> {
+        self.depth_format.map(|format| wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        })
+    }
+
+    pub fn color_target(&self, blend: Option<wgpu::BlendState>) -> wgpu::ColorTargetState {
+        wgpu::ColorTargetState {
+            format: self.color_format,
+            blend,
+            write_mask: wgpu::ColorWrites::all(),
+        }
+    }
+}
+
+/// What an [`Overlay`] is drawn over
+#[derive(Debug, Clone, Copy)]
+pub struct OverlayFrame {
+    /// See [`crate::TargetImage::target`]
+    pub target: u64,
+    /// The size of the traced image, the viewport of the render pass covers the view
+    pub width: u32,
+    pub height: u32,
+    pub camera: GpuCamera,
+}
+
+/// Something rasterized over the traced image of every view, like gizmos or outlines,
+/// its pipelines have to be created for [`crate::RayTracingRenderer::paint_target`]
+pub trait Overlay: Send + Sync + 'static {
+    /// Runs before egui's render pass every time a view is painted, to upload what is drawn
+    fn prepare(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue, _frame: &OverlayFrame) {}
+
+    /// Draws into egui's render pass after the traced image
+    fn paint(&self, render_pass: &mut wgpu::RenderPass<'static>, frame: &OverlayFrame);
+}
+
+/// Identifies an overlay added with [`crate::RayTracingRenderer::add_overlay`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OverlayId(u64);
+
+/// The overlays drawn over the views, in the order they were added
+#[derive(Default)]
+pub(crate) struct Overlays {
+    overlays: Vec<(OverlayId, Box<dyn Overlay>)>,
+    next_id: u64,
+}
+
+impl Overlays {
+    pub fn add(&mut self, overlay: Box<dyn Overlay>) -> OverlayId {
+        let id = OverlayId(self.next_id);
+        self.next_id += 1;
+        self.overlays.push((id, overlay));
+        id
+    }
+
+    pub fn remove(&mut self, id: OverlayId) -> Option<Box<dyn Overlay>> {
+        let index = self
+            .overlays
+            .iter()
+            .position(|&(overlay_id, _)| overlay_id == id)?;
+        Some(self.overlays.remove(index).1)
+    }
+
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: &OverlayFrame) {
+        for (_, overlay) in &mut self.overlays {
+            overlay.prepare(device, queue, frame);
+        }
+    }
+
+    pub fn paint(&self, render_pass: &mut wgpu::RenderPass<'static>, frame: &OverlayFrame) {
+        for (_, overlay) in &self.overlays {
+            overlay.paint(render_pass, frame);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use encase::ShaderType;
+use math::Vector3;
+
+/// How many probes are in a row of the update's dispatch, the same as in the ray tracing shader
+pub(crate) const PROBE_ROW_WIDTH: u32 = 256;
+
+/// A box of irradiance probes spaced evenly along every axis
+#[derive(Debug, Clone, Copy)]
+pub struct ProbeGrid {
+    /// The position of the first probe, the grid goes along the positive axes from it
+    pub origin: Vector3,
+    /// The distance between neighbouring probes
+    pub spacing: f32,
+    pub count_x: u32,
+    pub count_y: u32,
+    pub count_z: u32,
+}
+
+impl ProbeGrid {
+    pub fn probe_count(&self) -> u32 {
+        self.count_x
+            .saturating_mul(self.count_y)
+            .saturating_mul(self.count_z)
+    }
+
+    /// Every probe position, x first, then y, then z
+    pub fn probe_positions(&self) -> impl Iterator<Item = Vector3> + '_ {
+        (0..self.count_z).flat_map(move |z| {
+            (0..self.count_y).flat_map(move |y| {
+                (0..self.count_x).map(move |x| {
+                    self.origin
+                        + Vector3 {
+                            x: x as f32,
+                            y: y as f32,
+                            z: z as f32,
+                        } * self.spacing
+                })
+            })
+        })
+    }
+}
+
+/// Grids of irradiance probes, every frame each probe traces rays in random directions and accumulates
+/// the light arriving from them, rays going through portals so probes see what is on the other side,
+/// diffuse bounces past the first one from the camera inside a grid are then lit by the probes around them
+/// instead of tracing on, which is biased but has far less noise, the probes are kept in the render target between frames
+#[derive(Debug, Clone)]
+pub struct ProbeFrame {
+    /// Where grids overlap the first one is used
+    pub grids: Vec<ProbeGrid>,
+    /// How many frames of updates were accumulated before this one, 0 starts the probes over
+    pub accumulated_frames: u32,
+    /// How many rays every probe traces this frame, 0 only uses what was accumulated,
+    /// should stay the same while accumulating because every frame is weighted the same
+    pub samples_per_probe: u32,
+}
+
+impl ProbeFrame {
+    pub fn probe_count(&self) -> u32 {
+        self.grids.iter().fold(0, |count: u32, grid| {
+            count.saturating_add(grid.probe_count())
+        })
+    }
+
+    /// The workgroups of the update's dispatch, every thread updates a probe
+    pub(crate) fn dispatch_size(&self, workgroup_size: crate::WorkgroupSize) -> (u32, u32) {
+        workgroup_size.dispatch_size(
+            PROBE_ROW_WIDTH,
+            self.probe_count().div_ceil(PROBE_ROW_WIDTH),
+        )
+    }
+
+    /// The grids with where their probes start
+    pub(crate) fn gpu_grids(&self) -> Vec<GpuProbeGrid> {
+        let mut first_probe = 0u32;
+        self.grids
+            .iter()
+            .map(|grid| {
+                let gpu_grid = GpuProbeGrid {
+                    origin: grid.origin,
+                    spacing: grid.spacing,
+                    count_x: grid.count_x,
+                    count_y: grid.count_y,
+                    count_z: grid.count_z,
+                    first_probe,
+                };
+                first_probe = first_probe.saturating_add(grid.probe_count());
+                gpu_grid
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, ShaderType)]
+pub(crate) struct GpuProbeGrid {
+    pub origin: Vector3,
+    pub spacing: f32,
+    pub count_x: u32,
+    pub count_y: u32,
+    pub count_z: u32,
+    pub first_probe: u32,
+}
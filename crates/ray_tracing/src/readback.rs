@@ -0,0 +1,206 @@
+use crate::{AccumulationPrecision, CropRect};
+use eframe::wgpu;
+use std::sync::{
+    Arc,
+    atomic::{AtomicU8, Ordering},
+};
+
+const PENDING: u8 = 0;
+const MAPPED: u8 = 1;
+const FAILED: u8 = 2;
+
+enum ReadbackState {
+    Idle,
+    Copied,
+    Mapping(Arc<AtomicU8>),
+}
+
+/// Reads gpu data back without ever stalling, the copy is recorded in one frame
+/// and the buffer is only mapped once that frame has been submitted
+pub(crate) struct AsyncReadback {
+    buffer: wgpu::Buffer,
+    state: ReadbackState,
+}
+
+impl AsyncReadback {
+    pub fn new(device: &wgpu::Device, label: &str, size: wgpu::BufferAddress) -> Self {
+        Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            state: ReadbackState::Idle,
+        }
+    }
+
+    /// Whether a new copy can be recorded
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, ReadbackState::Idle)
+    }
+
+    pub fn copy_buffer(&mut self, encoder: &mut wgpu::CommandEncoder, source: &wgpu::Buffer) {
+        debug_assert!(self.is_idle());
+        encoder.copy_buffer_to_buffer(source, 0, &self.buffer, 0, self.buffer.size());
+        self.state = ReadbackState::Copied;
+    }
+
+    /// Copies a single texel, which has to fit in the readback buffer
+    pub fn copy_texel(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        x: u32,
+        y: u32,
+    ) {
+        debug_assert!(self.is_idle());
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: None,
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.state = ReadbackState::Copied;
+    }
+
+    /// Advances the readback, returning the data once the gpu has finished copying it,
+    /// has to be called before the next frame's commands are recorded
+    pub fn poll(&mut self, device: &wgpu::Device) -> Option<Vec<u8>> {
+        match &self.state {
+            ReadbackState::Idle => None,
+            // the copy was submitted along with the last frame, so it's safe to map now
+            ReadbackState::Copied => {
+                let state = Arc::new(AtomicU8::new(PENDING));
+                self.buffer.slice(..).map_async(wgpu::MapMode::Read, {
+                    let state = state.clone();
+                    move |result| {
+                        state.store(
+                            if result.is_ok() { MAPPED } else { FAILED },
+                            Ordering::Release,
+                        );
+                    }
+                });
+                self.state = ReadbackState::Mapping(state);
+                None
+            }
+            ReadbackState::Mapping(state) => {
+                _ = device.poll(wgpu::PollType::Poll);
+                match state.load(Ordering::Acquire) {
+                    MAPPED => {
+                        let data = self.buffer.slice(..).get_mapped_range().to_vec();
+                        self.buffer.unmap();
+                        self.state = ReadbackState::Idle;
+                        Some(data)
+                    }
+                    FAILED => {
+                        self.state = ReadbackState::Idle;
+                        None
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// A rectangle of an accumulated image read back without stalling, the copy is recorded by
+/// [`RayTracingRenderer::copy_texture_rect`](crate::RayTracingRenderer::copy_texture_rect)
+/// and the pixels are returned by [`TextureRectReadback::poll`] a few frames later
+pub struct TextureRectReadback {
+    readback: AsyncReadback,
+    rect: CropRect,
+    /// What the copied texels were stored as
+    precision: AccumulationPrecision,
+}
+
+impl TextureRectReadback {
+    pub fn new(device: &wgpu::Device, rect: CropRect, precision: AccumulationPrecision) -> Self {
+        let size = Self::padded_bytes_per_row(rect, precision) as wgpu::BufferAddress
+            * rect.height as wgpu::BufferAddress;
+        Self {
+            readback: AsyncReadback::new(device, "Texture Rect Readback Buffer", size),
+            rect,
+            precision,
+        }
+    }
+
+    pub fn rect(&self) -> CropRect {
+        self.rect
+    }
+
+    pub fn precision(&self) -> AccumulationPrecision {
+        self.precision
+    }
+
+    /// Whether a new copy can be recorded
+    pub fn is_idle(&self) -> bool {
+        self.readback.is_idle()
+    }
+
+    fn padded_bytes_per_row(rect: CropRect, precision: AccumulationPrecision) -> u32 {
+        (rect.width * precision.bytes_per_pixel())
+            .next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+    }
+
+    /// `texture` has to be stored with the readback's precision
+    pub(crate) fn copy(&mut self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        debug_assert!(self.is_idle());
+        debug_assert_eq!(texture.format(), self.precision.texture_format());
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: self.rect.x,
+                    y: self.rect.y,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback.buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(Self::padded_bytes_per_row(self.rect, self.precision)),
+                    rows_per_image: Some(self.rect.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.rect.width,
+                height: self.rect.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.readback.state = ReadbackState::Copied;
+    }
+
+    /// Advances the readback like [`AsyncReadback::poll`], returning the pixels inside the rectangle once the gpu
+    /// has finished copying them, linear, premultiplied by alpha and bottom row first
+    pub fn poll(&mut self, device: &wgpu::Device) -> Option<Vec<[f32; 4]>> {
+        let data = self.readback.poll(device)?;
+        let bytes_per_row = (self.rect.width * self.precision.bytes_per_pixel()) as usize;
+        let padded_bytes_per_row = Self::padded_bytes_per_row(self.rect, self.precision) as usize;
+        let texels = data
+            .chunks_exact(padded_bytes_per_row)
+            .flat_map(|row| &row[..bytes_per_row])
+            .copied()
+            .collect::<Vec<_>>();
+        Some(self.precision.decode(&texels))
+    }
+}
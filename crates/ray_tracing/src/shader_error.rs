@@ -0,0 +1,93 @@
+use eframe::wgpu;
+
+#[derive(Debug, Clone)]
+pub struct ShaderErrorMessage {
+    /// 1-based, in the generated wgsl
+    pub line: Option<u32>,
+    /// 1-based, in bytes
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+/// A shader that failed to compile, or a pipeline that failed to be created from it
+#[derive(Debug, Clone)]
+pub struct ShaderError {
+    /// The generated wgsl file, or the pipeline if the shader itself compiled
+    pub file: String,
+    pub messages: Vec<ShaderErrorMessage>,
+}
+
+impl std::fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for message in &self.messages {
+            write!(f, "{}", self.file)?;
+            if let Some(line) = message.line {
+                write!(f, ":{line}")?;
+            }
+            if let Some(column) = message.column {
+                write!(f, ":{column}")?;
+            }
+            writeln!(f, ": {}", message.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Creates a shader module, catching its validation errors instead of letting the device panic
+pub(crate) fn create_shader_module(
+    device: &wgpu::Device,
+    file: &str,
+    descriptor: wgpu::ShaderModuleDescriptor<'_>,
+) -> Result<wgpu::ShaderModule, ShaderError> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.create_shader_module(descriptor);
+    let Some(error) = pollster::block_on(device.pop_error_scope()) else {
+        return Ok(module);
+    };
+
+    // the compilation info has the locations, the error itself only has them baked into its text
+    let mut messages = pollster::block_on(module.get_compilation_info())
+        .messages
+        .into_iter()
+        .filter(|message| message.message_type == wgpu::CompilationMessageType::Error)
+        .map(|message| ShaderErrorMessage {
+            line: message.location.map(|location| location.line_number),
+            column: message.location.map(|location| location.line_position),
+            message: message.message,
+        })
+        .collect::<Vec<_>>();
+    if messages.is_empty() {
+        messages.push(ShaderErrorMessage {
+            line: None,
+            column: None,
+            message: error.to_string(),
+        });
+    }
+    Err(ShaderError {
+        file: file.into(),
+        messages,
+    })
+}
+
+/// Runs `create`, catching the validation errors of the pipeline it creates
+pub(crate) fn create_pipeline<T>(
+    device: &wgpu::Device,
+    label: &str,
+    create: impl FnOnce() -> T,
+) -> Result<T, ShaderError> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let pipeline = create();
+    match pollster::block_on(device.pop_error_scope()) {
+        None => Ok(pipeline),
+        Some(error) => Err(ShaderError {
+            file: label.into(),
+            messages: vec![ShaderErrorMessage {
+                line: None,
+                column: None,
+                message: error.to_string(),
+            }],
+        }),
+    }
+}
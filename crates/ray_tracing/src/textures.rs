@@ -0,0 +1,216 @@
+use eframe::wgpu;
+use encase::{ShaderSize, ShaderType};
+
+/// Identifies a texture added with [`crate::RayTracingRenderer::add_texture`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureId(u32);
+
+impl TextureId {
+    /// What [`crate::GpuPlane::texture`] is set to for the planes showing this texture
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// An image in srgb with 8 bits per channel, row by row from the top, the alpha is ignored
+#[derive(Debug, Clone)]
+pub struct TextureImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+    /// The image has no pixels, or fewer than its size says it has
+    Empty,
+    /// All the textures together wouldn't fit in the storage buffer they are read from
+    TooLarge { bytes: u64, max_bytes: u64 },
+}
+
+impl std::fmt::Display for TextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureError::Empty => write!(f, "the image is empty"),
+            TextureError::TooLarge { bytes, max_bytes } => write!(
+                f,
+                "the textures would take {} MiB, but the gpu can only read {} MiB of them",
+                bytes.div_ceil(1 << 20),
+                max_bytes >> 20
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+/// Where a texture's texels are in the texels buffer
+#[derive(Debug, Clone, Copy, ShaderType)]
+pub(crate) struct GpuTexture {
+    pub offset: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The buffers every render target binds the textures from, see [`Textures`]
+#[derive(Clone)]
+pub(crate) struct TextureBuffers {
+    /// Changes whenever the buffers are recreated, so the render targets know to bind them again
+    pub generation: u64,
+    pub infos: wgpu::Buffer,
+    /// The texels of every texture one after another, packed into a u32 each
+    pub texels: wgpu::Buffer,
+}
+
+/// Every texture of the planes in one atlas of texels, shared by the render targets
+pub(crate) struct Textures {
+    /// Removed textures leave their slot empty, so the ids of the others stay the same
+    images: Vec<Option<TextureImage>>,
+    buffers: TextureBuffers,
+}
+
+impl Textures {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            images: vec![],
+            buffers: Self::create_buffers(device, 0, 1, 1),
+        }
+    }
+
+    pub fn buffers(&self) -> &TextureBuffers {
+        &self.buffers
+    }
+
+    pub fn add(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: TextureImage,
+    ) -> Result<TextureId, TextureError> {
+        if image.width == 0
+            || image.height == 0
+            || image.pixels.len() < image.width as usize * image.height as usize
+        {
+            return Err(TextureError::Empty);
+        }
+
+        let bytes = (self.texel_count() + image.width as u64 * image.height as u64)
+            * size_of::<u32>() as u64;
+        let limits = device.limits();
+        let max_bytes = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        if bytes > max_bytes {
+            return Err(TextureError::TooLarge { bytes, max_bytes });
+        }
+
+        let index = match self.images.iter().position(Option::is_none) {
+            Some(index) => {
+                self.images[index] = Some(image);
+                index
+            }
+            None => {
+                self.images.push(Some(image));
+                self.images.len() - 1
+            }
+        };
+        self.upload(device, queue);
+        Ok(TextureId(index as u32))
+    }
+
+    /// Returns whether there was a texture with that id
+    pub fn remove(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, id: TextureId) -> bool {
+        let Some(image) = self.images.get_mut(id.0 as usize) else {
+            return false;
+        };
+        if image.take().is_none() {
+            return false;
+        }
+        while self.images.last().is_some_and(Option::is_none) {
+            self.images.pop();
+        }
+        self.upload(device, queue);
+        true
+    }
+
+    fn texel_count(&self) -> u64 {
+        self.images
+            .iter()
+            .flatten()
+            .map(|image| image.width as u64 * image.height as u64)
+            .sum()
+    }
+
+    /// Recreates the buffers with every texture in them, textures are added rarely enough that packing them again is fine
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mut infos = Vec::with_capacity(self.images.len());
+        let mut texels = Vec::with_capacity(self.texel_count() as usize);
+        for image in &self.images {
+            let Some(image) = image else {
+                infos.push(GpuTexture {
+                    offset: 0,
+                    width: 0,
+                    height: 0,
+                });
+                continue;
+            };
+            infos.push(GpuTexture {
+                offset: texels.len() as u32,
+                width: image.width,
+                height: image.height,
+            });
+            let pixel_count = image.width as usize * image.height as usize;
+            texels.extend(
+                image.pixels[..pixel_count]
+                    .iter()
+                    .map(|&pixel| u32::from_le_bytes(pixel)),
+            );
+        }
+        tracing::trace!(
+            textures = infos.len(),
+            texels = texels.len(),
+            "uploading textures"
+        );
+
+        self.buffers = Self::create_buffers(
+            device,
+            self.buffers.generation + 1,
+            infos.len(),
+            texels.len(),
+        );
+        if !infos.is_empty() {
+            let mut encoded = encase::StorageBuffer::new(vec![]);
+            encoded.write(&infos).unwrap();
+            queue.write_buffer(&self.buffers.infos, 0, &encoded.into_inner());
+        }
+        if !texels.is_empty() {
+            queue.write_buffer(&self.buffers.texels, 0, bytemuck::cast_slice(&texels));
+        }
+    }
+
+    /// Never empty, so they can always be bound
+    fn create_buffers(
+        device: &wgpu::Device,
+        generation: u64,
+        texture_count: usize,
+        texel_count: usize,
+    ) -> TextureBuffers {
+        let buffer = |label, size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        TextureBuffers {
+            generation,
+            infos: buffer(
+                "Texture Infos Buffer",
+                texture_count.max(1) as wgpu::BufferAddress * GpuTexture::SHADER_SIZE.get(),
+            ),
+            texels: buffer(
+                "Texels Buffer",
+                texel_count.max(1) as wgpu::BufferAddress * size_of::<u32>() as wgpu::BufferAddress,
+            ),
+        }
+    }
+}
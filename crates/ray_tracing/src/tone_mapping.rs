@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+/// How colors brighter than the display can show are brought into range when the image is displayed,
+/// it is only applied on display so it doesn't affect what is accumulated
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToneMapper {
+    /// Clips every channel at 1
+    #[default]
+    None,
+    /// Reinhard on luminance blended towards Reinhard per channel as the channels saturate,
+    /// keeps the hue of dark colors while bright colors still desaturate towards white
+    ReinhardJodie,
+    /// Compresses in a log space with a sigmoid, bright colors desaturate smoothly like film
+    AgX,
+}
+
+impl ToneMapper {
+    pub const ALL: [Self; 3] = [Self::None, Self::ReinhardJodie, Self::AgX];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ToneMapper::None => "None",
+            ToneMapper::ReinhardJodie => "Reinhard-Jodie",
+            ToneMapper::AgX => "AgX",
+        }
+    }
+
+    /// What the display shader's `tone_map` takes
+    pub(crate) fn shader_value(self) -> u32 {
+        match self {
+            ToneMapper::None => 0,
+            ToneMapper::ReinhardJodie => 1,
+            ToneMapper::AgX => 2,
+        }
+    }
+
+    /// The same curve the display shader applies, for when the image leaves the gpu,
+    /// takes and returns linear colors
+    pub fn apply(self, [r, g, b]: [f32; 3]) -> [f32; 3] {
+        match self {
+            ToneMapper::None => [r, g, b],
+            ToneMapper::ReinhardJodie => {
+                let luminance = r * 0.2126 + g * 0.7152 + b * 0.0722;
+                let channel = |value: f32| {
+                    let per_channel = value / (1.0 + value);
+                    let by_luminance = value / (1.0 + luminance);
+                    by_luminance + (per_channel - by_luminance) * per_channel
+                };
+                [channel(r), channel(g), channel(b)]
+            }
+            ToneMapper::AgX => {
+                const MIN_EV: f32 = -12.47393;
+                const MAX_EV: f32 = 4.026069;
+
+                let inset = [
+                    0.8424791 * r + 0.0784336 * g + 0.07922375 * b,
+                    0.04232824 * r + 0.8784686 * g + 0.07916613 * b,
+                    0.04237565 * r + 0.0784336 * g + 0.879143 * b,
+                ];
+                let [r, g, b] = inset.map(|value| {
+                    let x = (value.max(1e-10).log2().clamp(MIN_EV, MAX_EV) - MIN_EV)
+                        / (MAX_EV - MIN_EV);
+                    // polynomial fit of the default AgX contrast curve
+                    let x2 = x * x;
+                    let x4 = x2 * x2;
+                    15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x
+                        + 0.4298 * x2
+                        + 0.1191 * x
+                        - 0.00232
+                });
+                let outset = [
+                    1.196879 * r - 0.09802088 * g - 0.09902974 * b,
+                    -0.05289685 * r + 1.151903 * g - 0.09896118 * b,
+                    -0.05297164 * r - 0.09804345 * g + 1.151074 * b,
+                ];
+                // the curve is made for a 2.2 gamma display, this goes back to linear for the sRGB encoding
+                outset.map(|value| value.max(0.0).powf(2.2))
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{PaintTarget, RayTracingPaintCallback, RayTracingRenderer, ShaderError, TargetImage};
+use eframe::{egui, egui_wgpu};
+
+/// Views smaller than this in either direction aren't traced, they show a placeholder instead
+pub const MIN_VIEW_SIZE: f32 = 1.0;
+
+/// A viewport that traces a scene into the space it is given,
+/// it keeps accumulating into its own render target until [`RayTracingView::reset`] is called,
+/// embedding it takes [`RayTracingView::install`] once and [`RayTracingView::show`] every frame,
+/// the view only keeps its [`TargetImage`] between frames and everything else is given to it each frame
+#[derive(Debug, Clone)]
+pub struct RayTracingView {
+    target: u64,
+    accumulated_frames: u32,
+    accumulated_samples: u64,
+    /// See [`TargetImage::planes_version`]
+    planes_version: u64,
+    /// The size of the traced image relative to the space the view is given, it is stretched to fill it
+    render_scale: f32,
+    /// How many physical pixels of the screen a point of the ui covers
+    pixels_per_point: f32,
+}
+
+impl RayTracingView {
+    /// Every view that is shown at the same time needs a different `target`, see [`TargetImage::target`]
+    pub fn new(target: u64) -> Self {
+        Self {
+            target,
+            accumulated_frames: 0,
+            accumulated_samples: 0,
+            planes_version: 0,
+            render_scale: 1.0,
+            pixels_per_point: 1.0,
+        }
+    }
+
+    /// Creates the renderer the views draw with, unless there already is one,
+    /// this only has to be done once per app, see [`RayTracingView::install_for_target`]
+    pub fn install(render_state: &egui_wgpu::RenderState) -> Result<(), ShaderError> {
+        Self::install_for_target(render_state, PaintTarget::new(render_state.target_format))
+    }
+
+    /// Like [`RayTracingView::install`] for when eframe was configured with a depth buffer or multisampling,
+    /// which egui's render state doesn't say
+    pub fn install_for_target(
+        render_state: &egui_wgpu::RenderState,
+        paint_target: PaintTarget,
+    ) -> Result<(), ShaderError> {
+        let mut renderer = render_state.renderer.write();
+        if !renderer.callback_resources.contains::<RayTracingRenderer>() {
+            let ray_tracer = RayTracingRenderer::new_for_target(
+                &render_state.device,
+                &render_state.queue,
+                paint_target,
+            )?;
+            renderer.callback_resources.insert(ray_tracer);
+        }
+        Ok(())
+    }
+
+    pub fn target(&self) -> u64 {
+        self.target
+    }
+
+    pub fn accumulated_frames(&self) -> u32 {
+        self.accumulated_frames
+    }
+
+    pub fn accumulated_samples(&self) -> u64 {
+        self.accumulated_samples
+    }
+
+    /// Throws away what was accumulated, this has to be called whenever anything that changes the image does
+    pub fn reset(&mut self) {
+        self.accumulated_frames = 0;
+        self.accumulated_samples = 0;
+    }
+
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Changing the scale resizes the image, so this starts accumulating from scratch if it changed
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        let render_scale = render_scale.clamp(0.01, 1.0);
+        if render_scale != self.render_scale {
+            self.render_scale = render_scale;
+            self.reset();
+        }
+    }
+
+    /// Whether `rect` is big enough to trace into, see [`MIN_VIEW_SIZE`]
+    pub fn can_trace(rect: egui::Rect) -> bool {
+        rect.width() >= MIN_VIEW_SIZE && rect.height() >= MIN_VIEW_SIZE
+    }
+
+    /// What is shown instead of the image when the view is too small, throws away what was accumulated
+    /// so tracing starts over once it is big enough again
+    pub fn show_placeholder(&mut self, painter: &egui::Painter, rect: egui::Rect) {
+        self.reset();
+        painter.with_clip_rect(rect).text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "Viewport Too Small",
+            egui::FontId::proportional(12.0),
+            egui::Color32::GRAY,
+        );
+    }
+
+    /// Traces at the physical resolution of the screen, which is more pixels than the ui has points on high dpi displays,
+    /// changing it resizes the image so this starts accumulating from scratch if it changed
+    pub fn set_pixels_per_point(&mut self, pixels_per_point: f32) {
+        if pixels_per_point != self.pixels_per_point {
+            self.pixels_per_point = pixels_per_point;
+            self.reset();
+        }
+    }
+
+    /// How many pixels of the traced image a point of the ui covers
+    pub fn pixel_scale(&self) -> f32 {
+        self.pixels_per_point * self.render_scale
+    }
+
+    /// The size of the traced image when the view is given `rect`
+    pub fn image_size(&self, rect: egui::Rect) -> (u32, u32) {
+        (
+            ((rect.width() * self.pixel_scale()) as u32).max(1),
+            ((rect.height() * self.pixel_scale()) as u32).max(1),
+        )
+    }
+
+    /// Continues from an image written with [`RayTracingRenderer::write_texture`]
+    pub fn resume(&mut self, accumulated_frames: u32, accumulated_samples: u64) {
+        self.accumulated_frames = accumulated_frames;
+        self.accumulated_samples = accumulated_samples;
+    }
+
+    /// Fills all of the available space with the traced image, see [`RayTracingView::paint`],
+    /// `frame` builds what to trace this frame around the view's image,
+    /// the response senses clicks and drags so the app can use it to move the camera
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        frame: impl FnOnce(TargetImage) -> RayTracingPaintCallback,
+    ) -> egui::Response {
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        self.set_pixels_per_point(ui.ctx().pixels_per_point());
+        if !Self::can_trace(rect) {
+            self.show_placeholder(ui.painter(), rect);
+            return response;
+        }
+        let frame = frame(self.image(rect));
+        self.paint(ui.painter(), rect, frame);
+        response
+    }
+
+    /// The image the view traces into when it is given `rect`, with what it has accumulated so far,
+    /// for building a frame to give to [`RayTracingView::paint`]
+    pub fn image(&self, rect: egui::Rect) -> TargetImage {
+        let (width, height) = self.image_size(rect);
+        TargetImage {
+            target: self.target,
+            width,
+            height,
+            accumulated_frames: self.accumulated_frames,
+            accumulated_samples: self.accumulated_samples,
+            planes_version: self.planes_version,
+        }
+    }
+
+    /// Traces `frame` into `rect` and counts its samples as accumulated, its image should come from [`RayTracingView::image`]
+    pub fn paint(
+        &mut self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        frame: RayTracingPaintCallback,
+    ) {
+        self.accumulated_frames += 1;
+        self.accumulated_samples += frame.samples_per_pixel as u64;
+        self.planes_version += 1;
+        painter.add(egui_wgpu::Callback::new_paint_callback(rect, frame));
+    }
+
+    /// The texture coordinates of the pixel under `position`, row 0 is the bottom of the image,
+    /// for [`RayTracingPaintCallback::inspect_pixel`]
+    pub fn texel_at(&self, rect: egui::Rect, position: egui::Pos2) -> Option<(u32, u32)> {
+        let (width, height) = self.image_size(rect);
+        Some(position - rect.min)
+            .filter(|offset| offset.x >= 0.0 && offset.y >= 0.0)
+            .map(|offset| {
+                (
+                    (offset.x * self.pixel_scale()) as u32,
+                    (offset.y * self.pixel_scale()) as u32,
+                )
+            })
+            .filter(|&(x, y)| x < width && y < height)
+            .map(|(x, y)| (x, height - 1 - y))
+    }
+}
@@ -0,0 +1,69 @@
+use eframe::wgpu;
+use serde::{Deserialize, Serialize};
+
+/// The shape of the ray tracing workgroups, which one is fastest depends on the hardware
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkgroupSize {
+    /// 8x8, for hardware with narrow subgroups or small workgroup limits
+    Square8,
+    /// 16x16
+    Square16,
+    /// 32x8, every row fills a whole 32 wide subgroup so neighbouring rays run together
+    Wide32x8,
+}
+
+impl WorkgroupSize {
+    pub const ALL: [Self; 3] = [Self::Square8, Self::Square16, Self::Wide32x8];
+
+    pub fn dimensions(self) -> (u32, u32) {
+        match self {
+            WorkgroupSize::Square8 => (8, 8),
+            WorkgroupSize::Square16 => (16, 16),
+            WorkgroupSize::Wide32x8 => (32, 8),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WorkgroupSize::Square8 => "8x8",
+            WorkgroupSize::Square16 => "16x16",
+            WorkgroupSize::Wide32x8 => "32x8",
+        }
+    }
+
+    /// Picks a size from the subgroup sizes and workgroup limits of a device,
+    /// devices are requested with their adapter's limits so these are what the hardware reports
+    pub fn for_limits(limits: &wgpu::Limits) -> Self {
+        let fits = |size: Self| {
+            let (x, y) = size.dimensions();
+            x * y <= limits.max_compute_invocations_per_workgroup
+                && x <= limits.max_compute_workgroup_size_x
+                && y <= limits.max_compute_workgroup_size_y
+        };
+        // the subgroup sizes are 0 if the backend doesn't report them
+        let preferred = if limits.min_subgroup_size == 32 && limits.max_subgroup_size == 32 {
+            WorkgroupSize::Wide32x8
+        } else if limits.max_subgroup_size != 0 && limits.max_subgroup_size < 16 {
+            WorkgroupSize::Square8
+        } else {
+            WorkgroupSize::Square16
+        };
+        if fits(preferred) {
+            preferred
+        } else {
+            WorkgroupSize::Square8
+        }
+    }
+
+    /// How many workgroups cover `width` by `height` pixels
+    pub(crate) fn dispatch_size(self, width: u32, height: u32) -> (u32, u32) {
+        let (x, y) = self.dimensions();
+        (width.div_ceil(x), height.div_ceil(y))
+    }
+
+    /// The values of the shader's workgroup size overrides
+    pub(crate) fn pipeline_constants(self) -> [(&'static str, f64); 2] {
+        let (x, y) = self.dimensions();
+        [("0", x as f64), ("1", y as f64)]
+    }
+}
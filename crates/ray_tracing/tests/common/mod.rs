@@ -0,0 +1,36 @@
+//! Shared by the gpu integration tests
+
+use eframe::wgpu;
+
+/// Set to skip the tests instead of failing them on machines without a gpu adapter
+const SKIP_GPU_TESTS_VAR: &str = "PORTALS_SKIP_GPU_TESTS";
+
+/// Panics if there is no adapter available, returns `None` instead when [`SKIP_GPU_TESTS_VAR`] is set
+pub fn create_device(test: &str) -> Option<(wgpu::Device, wgpu::Queue)> {
+    let device = pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .map_err(|error| error.to_string())?;
+        adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                    | (adapter.features() & ray_tracing::OPTIONAL_FEATURES),
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|error| error.to_string())
+    });
+    match device {
+        Ok(device) => Some(device),
+        Err(error) if std::env::var_os(SKIP_GPU_TESTS_VAR).is_some() => {
+            eprintln!("skipping {test}, no gpu device available: {error}");
+            None
+        }
+        Err(error) => panic!(
+            "no gpu device available to run {test} on: {error}, set {SKIP_GPU_TESTS_VAR} to skip the gpu tests"
+        ),
+    }
+}
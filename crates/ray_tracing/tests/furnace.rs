@@ -0,0 +1,212 @@
+//! White furnace tests, with every surface perfectly white and the sky emitting exactly 1
+//! every pixel should converge to exactly 1, anything else means energy is being gained or lost
+
+mod common;
+
+use eframe::wgpu;
+use math::{Transform, Vector3};
+use ray_tracing::{
+    AccumulationPrecision, BACKGROUND_SKY, Color, EMISSIVE_BACK, EMISSIVE_FRONT, GpuCamera,
+    GpuPlane, GpuPortalConnection, PORTAL_FILL_NONE, RENDER_TYPE_LIT, RayTracingPaintCallback,
+    RayTracingRenderer, TargetImage, ToneMapper,
+};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+const TOLERANCE: f32 = 1e-4;
+
+const NO_PORTAL: GpuPortalConnection = GpuPortalConnection {
+    other_index: u32::MAX,
+    scale: 1.0,
+    flip: 0,
+};
+
+/// A pure white lambertian plane, with nothing that could add or remove light apart from the lobe a test turns on
+fn plane(transform: Transform, front_portal: GpuPortalConnection) -> GpuPlane {
+    GpuPlane {
+        transform,
+        width: 10.0,
+        height: 10.0,
+        checker_count_x: 4,
+        checker_count_z: 4,
+        color: Color {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+        },
+        checker_darkness: 1.0,
+        emissive_color: Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+        },
+        emissive_checker_darkness: 1.0,
+        emissive_sides: EMISSIVE_FRONT | EMISSIVE_BACK,
+        emission_exponent: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        dispersion: 0.0,
+        metallic: 0.0,
+        roughness: 1.0,
+        specular: 0.0,
+        light_group: 0,
+        shadow_catcher: 0,
+        baked_offset: u32::MAX,
+        texture: u32::MAX,
+        front_portal,
+        back_portal: NO_PORTAL,
+    }
+}
+
+fn transmissive_plane(transmission: f32, ior: f32) -> GpuPlane {
+    GpuPlane {
+        transmission,
+        ior,
+        ..plane(Transform::IDENTITY, NO_PORTAL)
+    }
+}
+
+fn assert_furnace(name: &str, planes: Vec<GpuPlane>) {
+    let Some((device, queue)) = common::create_device(&format!("furnace test '{name}'")) else {
+        return;
+    };
+
+    let mut renderer =
+        RayTracingRenderer::new(&device, &queue, wgpu::TextureFormat::Rgba8Unorm).unwrap();
+    let frame = RayTracingPaintCallback {
+        image: TargetImage {
+            target: 0,
+            width: WIDTH,
+            height: HEIGHT,
+            accumulated_frames: 0,
+            accumulated_samples: 0,
+            planes_version: 0,
+        },
+        camera: GpuCamera {
+            transform: Transform::translation(Vector3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            })
+            .then(Transform::rotation_xy(-0.3)),
+            up_sky_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            down_sky_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            sun_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            sun_direction: Vector3::UP,
+            sun_size: 0.0,
+            recursive_portal_count: 8,
+            max_bounces: 8,
+            near_plane: 0.0,
+            portal_epsilon: 0.001,
+            max_portal_traversals: u32::MAX,
+            caustic_regularization: 0.0,
+            fov: std::f32::consts::FRAC_PI_2,
+            aperture: 0.0,
+            focus_distance: 1.0,
+            portal_cull_pixels: 0.0,
+            portal_fill: PORTAL_FILL_NONE,
+            portal_fill_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            portal_distance_range: 1.0,
+            up: Vector3::UP,
+        },
+        blend_factor: 0.0,
+        random_seed: 0,
+        render_type: RENDER_TYPE_LIT,
+        background: BACKGROUND_SKY,
+        samples_per_pixel: 4,
+        max_samples_per_dispatch: 4,
+        antialiasing: true,
+        display_linear: false,
+        false_color: false,
+        zebra_stripes: false,
+        teleport_effect: 0.0,
+        tone_mapper: ToneMapper::None,
+        split_tone_mapper: None,
+        histogram: false,
+        inspect_pixel: None,
+        crop: None,
+        furnace_test: true,
+        sample_lights: true,
+        spectral: false,
+        accumulation_precision: AccumulationPrecision::Full,
+        light_groups: None,
+        aovs: false,
+        focus_peaking: None,
+        bake: None,
+        probes: None,
+        planes,
+        dirty_planes: None,
+    };
+    queue.submit([renderer.prepare_frame(&device, &queue, &frame)]);
+
+    let pixels = renderer.read_texture(&device, &queue);
+    assert_eq!(pixels.len(), (WIDTH * HEIGHT) as usize);
+    for (index, pixel) in pixels.iter().enumerate() {
+        let (x, y) = (index as u32 % WIDTH, index as u32 / WIDTH);
+        for &channel in &pixel[..3] {
+            assert!(
+                (channel - 1.0).abs() <= TOLERANCE,
+                "{name}: pixel ({x}, {y}) is {pixel:?}, expected 1.0 in every channel"
+            );
+        }
+    }
+}
+
+#[test]
+fn furnace_single_plane() {
+    assert_furnace("single plane", vec![plane(Transform::IDENTITY, NO_PORTAL)]);
+}
+
+#[test]
+fn furnace_through_portal() {
+    assert_furnace(
+        "through portal",
+        vec![
+            plane(
+                Transform::IDENTITY,
+                GpuPortalConnection {
+                    other_index: 1,
+                    scale: 1.0,
+                    flip: 0,
+                },
+            ),
+            plane(
+                Transform::translation(Vector3 {
+                    x: 1000.0,
+                    y: 0.0,
+                    z: 0.0,
+                }),
+                NO_PORTAL,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn furnace_transmissive_plane() {
+    assert_furnace("transmissive plane", vec![transmissive_plane(1.0, 1.5)]);
+}
+
+#[test]
+fn furnace_partly_transmissive_plane() {
+    assert_furnace(
+        "partly transmissive plane",
+        vec![transmissive_plane(0.5, 1.33)],
+    );
+}
@@ -0,0 +1,199 @@
+//! Looks straight down through a portal whose other plane has a red plane just behind it and a green floor in front,
+//! rays coming out of the other plane must only ever see the floor
+
+mod common;
+
+use eframe::wgpu;
+use math::{Transform, Vector3};
+use ray_tracing::{
+    AccumulationPrecision, BACKGROUND_BLACK, Color, EMISSIVE_FRONT, GpuCamera, GpuPlane,
+    GpuPortalConnection, PORTAL_FILL_NONE, RENDER_TYPE_UNLIT, RayTracingPaintCallback,
+    RayTracingRenderer, TargetImage, ToneMapper,
+};
+
+const WIDTH: u32 = 32;
+const HEIGHT: u32 = 32;
+
+const NO_PORTAL: GpuPortalConnection = GpuPortalConnection {
+    other_index: u32::MAX,
+    scale: 1.0,
+    flip: 0,
+};
+
+const RED: Color = Color {
+    r: 1.0,
+    g: 0.0,
+    b: 0.0,
+};
+const GREEN: Color = Color {
+    r: 0.0,
+    g: 1.0,
+    b: 0.0,
+};
+
+fn plane(y: f32, size: f32, color: Color, front_portal: GpuPortalConnection) -> GpuPlane {
+    GpuPlane {
+        // far enough from the camera that only the portal can see it
+        transform: Transform::translation(Vector3 {
+            x: if front_portal.other_index == u32::MAX {
+                1000.0
+            } else {
+                0.0
+            },
+            y,
+            z: 0.0,
+        }),
+        width: size,
+        height: size,
+        checker_count_x: 1,
+        checker_count_z: 1,
+        color,
+        checker_darkness: 1.0,
+        emissive_color: Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+        },
+        emissive_checker_darkness: 1.0,
+        emissive_sides: EMISSIVE_FRONT,
+        emission_exponent: 0.0,
+        transmission: 0.0,
+        ior: 1.5,
+        dispersion: 0.0,
+        metallic: 0.0,
+        roughness: 1.0,
+        specular: 0.0,
+        light_group: 0,
+        shadow_catcher: 0,
+        baked_offset: u32::MAX,
+        texture: u32::MAX,
+        front_portal,
+        back_portal: NO_PORTAL,
+    }
+}
+
+/// `behind` and `floor` are the heights of the red and green planes around the other plane, which is at 0,
+/// the camera looks down onto the front of the portal from above
+fn assert_only_floor_seen(name: &str, flip: bool, behind: f32, floor: f32) {
+    let Some((device, queue)) = common::create_device(&format!("portal clip test '{name}'")) else {
+        return;
+    };
+
+    let portal = GpuPortalConnection {
+        other_index: 1,
+        scale: 1.0,
+        flip: flip as u32,
+    };
+    let planes = vec![
+        plane(0.0, 10.0, RED, portal),
+        plane(0.0, 10.0, RED, NO_PORTAL),
+        plane(behind, 10.0, RED, NO_PORTAL),
+        plane(floor, 100.0, GREEN, NO_PORTAL),
+    ];
+
+    let mut renderer =
+        RayTracingRenderer::new(&device, &queue, wgpu::TextureFormat::Rgba8Unorm).unwrap();
+    let frame = RayTracingPaintCallback {
+        image: TargetImage {
+            target: 0,
+            width: WIDTH,
+            height: HEIGHT,
+            accumulated_frames: 0,
+            accumulated_samples: 0,
+            planes_version: 0,
+        },
+        camera: GpuCamera {
+            transform: Transform::translation(Vector3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            })
+            .then(Transform::rotation_xy(-std::f32::consts::FRAC_PI_2)),
+            up_sky_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            down_sky_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            sun_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            sun_direction: Vector3::UP,
+            sun_size: 0.0,
+            recursive_portal_count: 1,
+            max_bounces: 1,
+            near_plane: 0.0,
+            portal_epsilon: 0.001,
+            max_portal_traversals: u32::MAX,
+            caustic_regularization: 0.0,
+            fov: std::f32::consts::FRAC_PI_2,
+            aperture: 0.0,
+            focus_distance: 1.0,
+            portal_cull_pixels: 0.0,
+            portal_fill: PORTAL_FILL_NONE,
+            portal_fill_color: Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            portal_distance_range: 1.0,
+            up: Vector3::UP,
+        },
+        blend_factor: 0.0,
+        random_seed: 0,
+        render_type: RENDER_TYPE_UNLIT,
+        background: BACKGROUND_BLACK,
+        samples_per_pixel: 1,
+        max_samples_per_dispatch: 1,
+        antialiasing: false,
+        display_linear: false,
+        false_color: false,
+        zebra_stripes: false,
+        teleport_effect: 0.0,
+        tone_mapper: ToneMapper::None,
+        split_tone_mapper: None,
+        histogram: false,
+        inspect_pixel: None,
+        crop: None,
+        furnace_test: false,
+        sample_lights: false,
+        spectral: false,
+        accumulation_precision: AccumulationPrecision::Full,
+        light_groups: None,
+        aovs: false,
+        focus_peaking: None,
+        bake: None,
+        probes: None,
+        planes,
+        dirty_planes: None,
+    };
+    queue.submit([renderer.prepare_frame(&device, &queue, &frame)]);
+
+    let pixels = renderer.read_texture(&device, &queue);
+    assert_eq!(pixels.len(), (WIDTH * HEIGHT) as usize);
+    for (index, pixel) in pixels.iter().enumerate() {
+        let (x, y) = (index as u32 % WIDTH, index as u32 / WIDTH);
+        assert!(
+            pixel[0] == 0.0 && pixel[1] > 0.5,
+            "{name}: pixel ({x}, {y}) is {pixel:?}, expected only the green floor"
+        );
+    }
+}
+
+/// Going into the front of the portal comes out of the back of the other plane, below it
+#[test]
+fn portal_clip_behind_exit() {
+    assert_only_floor_seen("behind exit", false, 0.0005, -3.0);
+}
+
+/// Mirrored portals turn the ray around, so it comes out of the front of the other plane, above it
+#[test]
+fn portal_clip_behind_flipped_exit() {
+    assert_only_floor_seen("behind flipped exit", true, -0.0005, 3.0);
+}